@@ -0,0 +1,178 @@
+//! Interpolation of angles such as compass headings or phases, which wrap
+//! around at the end of their range.
+//!
+//! Interpolating the raw values of a heading passing north, e.g. 350° then
+//! 10°, swings the wrong way round through 180°. The angles are unwrapped
+//! first, each shifted by whole turns to within half a turn of its
+//! predecessor, then interpolated, and evaluated values are wrapped back into
+//! the range.
+
+use crate::{check_increasing, eval, splinterpol_slices, Boundary, Error, Extrapolate, Solver};
+
+/// Range of angles, `[start, start + period)`.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Wrap {
+    /// Smallest angle of the range
+    pub start: f32,
+    /// Length of the range, one full turn, strictly positive
+    pub period: f32,
+}
+
+impl Wrap {
+    /// Radians in `[-π, π)`
+    pub const RADIANS: Wrap = Wrap {
+        start: -core::f32::consts::PI,
+        period: core::f32::consts::TAU,
+    };
+    /// Degrees in `[0, 360)`, as for compass headings
+    pub const DEGREES: Wrap = Wrap {
+        start: 0f32,
+        period: 360f32,
+    };
+
+    /// The angle shifted by whole turns into the range.
+    ///
+    /// ```
+    /// use cubic_splinterpol::angles::Wrap;
+    ///
+    /// assert_eq!(350.0, Wrap::DEGREES.wrap(-10.0));
+    /// assert_eq!(0.0, Wrap::DEGREES.wrap(720.0));
+    /// ```
+    pub fn wrap(&self, angle: f32) -> f32 {
+        let turns = libm::floorf((angle - self.start) / self.period);
+        let wrapped = angle - turns * self.period;
+        // Rounding may land just on the excluded end
+        if wrapped >= self.start + self.period {
+            self.start
+        } else {
+            wrapped
+        }
+    }
+}
+
+/// Unwrap `angles` in place: shift every angle by whole turns of `period` to
+/// within half a turn of its predecessor, so that the sequence has no jumps
+/// at the end of the range. The first angle stays as it is.
+///
+/// ```
+/// use cubic_splinterpol::angles::unwrap;
+///
+/// let mut headings = [340f32, 355.0, 10.0, 20.0];
+/// unwrap(&mut headings, 360.0).unwrap();
+/// assert_eq!([340.0, 355.0, 370.0, 380.0], headings);
+/// ```
+pub fn unwrap(angles: &mut [f32], period: f32) -> Result<(), Error> {
+    if period.is_nan() || period <= 0f32 {
+        return Err(Error::InvalidLimit);
+    }
+    for i in 1..angles.len() {
+        let mut delta = angles[i] - angles[i - 1];
+        delta -= period * libm::roundf(delta / period);
+        angles[i] = angles[i - 1] + delta;
+    }
+    Ok(())
+}
+
+/// A natural spline through `N` angles, interpolated the short way round
+/// between neighbouring ones.
+///
+/// ```
+/// use cubic_splinterpol::angles::{AngleSpline, Wrap};
+/// use cubic_splinterpol::Extrapolate;
+///
+/// // A heading passing north
+/// let xs = [0f32, 1.0, 2.0, 3.0];
+/// let headings = [330f32, 350.0, 10.0, 30.0];
+/// let spline = AngleSpline::new(&xs, &headings, Wrap::DEGREES).unwrap();
+/// let heading = spline.eval(1.5, Extrapolate::Error).unwrap();
+/// assert!(heading < 1e-3 || heading > 360.0 - 1e-3);
+/// assert!((spline.eval(2.5, Extrapolate::Error).unwrap() - 20.0).abs() < 1e-3);
+/// ```
+#[derive(Debug, Clone)]
+pub struct AngleSpline<const N: usize> {
+    xs: [f32; N],
+    /// N-1 of N used, of the unwrapped angles
+    coefficients: [(f32, f32, f32, f32); N],
+    wrap: Wrap,
+}
+
+impl<const N: usize> AngleSpline<N> {
+    /// A spline through the `angles` at the strictly increasing `xs`, which
+    /// evaluates to angles in the range `wrap`. At least 3 angles are needed,
+    /// and neighbouring ones must be less than half a turn apart.
+    pub fn new(xs: &[f32; N], angles: &[f32; N], wrap: Wrap) -> Result<Self, Error> {
+        if N < 3 {
+            return Err(Error::InvalidSliceLength);
+        }
+        check_increasing(xs)?;
+        let mut ys = *angles;
+        unwrap(&mut ys, wrap.period)?;
+        let mut coefficients = [(0f32, 0f32, 0f32, 0f32); N];
+        splinterpol_slices::<N>(
+            xs,
+            &ys,
+            &mut coefficients,
+            Boundary::Natural,
+            Solver::Thomas,
+        )?;
+        Ok(Self {
+            xs: *xs,
+            coefficients,
+            wrap,
+        })
+    }
+
+    /// The angle at `x`, wrapped into the range, with `extrapolate` deciding
+    /// about positions outside of the knots.
+    pub fn eval(&self, x: f32, extrapolate: Extrapolate) -> Result<f32, Error> {
+        self.eval_unwrapped(x, extrapolate)
+            .map(|angle| self.wrap.wrap(angle))
+    }
+
+    /// The angle at `x` before wrapping, continuous across the end of the
+    /// range, e.g. to count full turns.
+    pub fn eval_unwrapped(&self, x: f32, extrapolate: Extrapolate) -> Result<f32, Error> {
+        eval(&self.coefficients[..N - 1], &self.xs, x, extrapolate)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use core::f32::consts::PI;
+
+    #[test]
+    fn wrap_and_unwrap() {
+        assert_eq!(-PI, Wrap::RADIANS.wrap(PI));
+        assert!((Wrap::RADIANS.wrap(3f32 * PI / 2f32) + PI / 2f32).abs() < 1e-6);
+        assert_eq!(359.5, Wrap::DEGREES.wrap(-0.5));
+        let mut phases = [3f32, -3.0, 2.9, -3.1];
+        unwrap(&mut phases, 2f32 * PI).unwrap();
+        for pair in phases.windows(2) {
+            assert!((pair[1] - pair[0]).abs() < PI);
+        }
+        assert!((phases[1] - (2f32 * PI - 3f32)).abs() < 1e-6);
+        assert_eq!(Err(Error::InvalidLimit), unwrap(&mut phases, 0.0));
+    }
+
+    #[test]
+    fn interpolates_short_way_round() {
+        let xs = [0f32, 1.0, 2.0, 3.0, 4.0];
+        let phases = [2.5f32, 3.0, -3.0, -2.5, -2.0];
+        let spline = AngleSpline::new(&xs, &phases, Wrap::RADIANS).unwrap();
+        for (x, phase) in xs.iter().zip(&phases) {
+            let value = spline.eval(*x, Extrapolate::Error).unwrap();
+            assert!((value - phase).abs() < 1e-5);
+        }
+        for i in 0..=40 {
+            let x = i as f32 / 10f32;
+            let value = spline.eval(x, Extrapolate::Error).unwrap();
+            assert!((-PI..PI).contains(&value));
+            // Never near zero, the long way round
+            assert!(value.abs() > 1.9);
+        }
+        let unwrapped = spline.eval_unwrapped(4.0, Extrapolate::Error).unwrap();
+        assert!((unwrapped - (2f32 * PI - 2f32)).abs() < 1e-5);
+        assert_eq!(Err(Error::OutOfRange), spline.eval(5.0, Extrapolate::Error));
+    }
+}
@@ -0,0 +1,188 @@
+//! Keyframe animation of several channels on a common clock, e.g. the servo
+//! angles, LED brightness and speaker volume of an animatronic figure.
+
+use crate::{
+    check_increasing, eval_segment, find_segment, splinterpol_slices, Boundary, Error, Solver,
+};
+
+/// One animated value through `K` keyframes `(time, value)`, a spline which
+/// holds the first value before the first keyframe and the last value after
+/// the last one.
+///
+/// ```
+/// use cubic_splinterpol::anim::Channel;
+///
+/// let jaw = Channel::new(&[(0f32, 10f32), (0.5, 45.0), (1.0, 10.0)]).unwrap();
+/// assert_eq!(45.0, jaw.value(0.5));
+/// assert_eq!(10.0, jaw.value(3.0));
+/// ```
+#[derive(Debug, Clone)]
+pub struct Channel<const K: usize> {
+    times: [f32; K],
+    values: [f32; K],
+    /// K-1 of K used
+    coefficients: [(f32, f32, f32, f32); K],
+}
+
+impl<const K: usize> Channel<K> {
+    /// A natural spline through the keyframes `(time, value)`, with strictly
+    /// increasing time. Two keyframes give a straight line.
+    pub fn new(keyframes: &[(f32, f32); K]) -> Result<Self, Error> {
+        Self::with_boundary(keyframes, Boundary::Natural)
+    }
+
+    /// Like [`new`](Channel::new), with the given boundary condition, e.g.
+    /// `Clamped { start: 0.0, end: 0.0 }` to start and stop at rest.
+    pub fn with_boundary(keyframes: &[(f32, f32); K], boundary: Boundary) -> Result<Self, Error> {
+        if K < 2 {
+            return Err(Error::InvalidSliceLength);
+        }
+        let mut times = [0f32; K];
+        let mut values = [0f32; K];
+        for (i, (time, value)) in keyframes.iter().enumerate() {
+            times[i] = *time;
+            values[i] = *value;
+        }
+        check_increasing(&times)?;
+        let mut coefficients = [(0f32, 0f32, 0f32, 0f32); K];
+        if K == 2 {
+            let slope = (values[1] - values[0]) / (times[1] - times[0]);
+            coefficients[0] = (values[0], slope, 0f32, 0f32);
+        } else {
+            splinterpol_slices::<K>(&times, &values, &mut coefficients, boundary, Solver::Thomas)?;
+        }
+        Ok(Self {
+            times,
+            values,
+            coefficients,
+        })
+    }
+
+    /// Time of the first keyframe.
+    pub fn start(&self) -> f32 {
+        self.times[0]
+    }
+
+    /// Time of the last keyframe.
+    pub fn end(&self) -> f32 {
+        self.times[K - 1]
+    }
+
+    /// The value at `time`, exactly that of the keyframe at the first and
+    /// last one. A NaN time gives the first value.
+    pub fn value(&self, time: f32) -> f32 {
+        if time.is_nan() || time <= self.times[0] {
+            return self.values[0];
+        }
+        if time >= self.times[K - 1] {
+            return self.values[K - 1];
+        }
+        let segment = find_segment(&self.times, time);
+        eval_segment(self.coefficients[segment], time - self.times[segment])
+    }
+}
+
+/// `C` channels of `K` keyframes each, evaluated together on one clock.
+///
+/// ```
+/// use cubic_splinterpol::anim::{Channel, Timeline};
+///
+/// let servo = Channel::new(&[(0f32, 0f32), (1.0, 90.0), (2.0, 45.0)]).unwrap();
+/// let led = Channel::new(&[(0f32, 0f32), (0.5, 255.0), (4.0, 0.0)]).unwrap();
+/// let timeline = Timeline::new([servo, led]);
+/// assert_eq!(4.0, timeline.duration());
+///
+/// let [angle, brightness] = timeline.eval(0.5);
+/// assert_eq!(255.0, brightness);
+/// assert!(angle > 0.0 && angle < 90.0);
+/// assert_eq!([45.0, 0.0], timeline.eval(10.0));
+/// assert_eq!(timeline.eval(0.5), timeline.eval_looped(4.5));
+/// ```
+#[derive(Debug, Clone)]
+pub struct Timeline<const C: usize, const K: usize> {
+    channels: [Channel<K>; C],
+}
+
+impl<const C: usize, const K: usize> Timeline<C, K> {
+    /// A timeline playing the `channels`, which start at time 0.
+    pub fn new(channels: [Channel<K>; C]) -> Self {
+        Self { channels }
+    }
+
+    /// The channels of the timeline.
+    pub fn channels(&self) -> &[Channel<K>; C] {
+        &self.channels
+    }
+
+    /// Time of the last keyframe of any channel, after which all values
+    /// hold.
+    pub fn duration(&self) -> f32 {
+        self.channels.iter().map(Channel::end).fold(0f32, f32::max)
+    }
+
+    /// The values of all channels at `time`.
+    pub fn eval(&self, time: f32) -> [f32; C] {
+        let mut values = [0f32; C];
+        for (value, channel) in values.iter_mut().zip(&self.channels) {
+            *value = channel.value(time);
+        }
+        values
+    }
+
+    /// Like [`eval`](Timeline::eval), playing the timeline in a loop, so that
+    /// `time` wraps around at the [`duration`](Timeline::duration).
+    pub fn eval_looped(&self, time: f32) -> [f32; C] {
+        let duration = self.duration();
+        if duration <= 0f32 {
+            return self.eval(time);
+        }
+        let wrapped = time - libm::floorf(time / duration) * duration;
+        self.eval(wrapped)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn channels_hold_outside_keyframes() {
+        let channel = Channel::new(&[(1f32, 2f32), (2.0, 5.0), (4.0, 3.0), (5.0, 3.5)]).unwrap();
+        assert_eq!(2.0, channel.value(0.0));
+        assert_eq!(2.0, channel.value(1.0));
+        assert_eq!(5.0, channel.value(2.0));
+        assert_eq!(3.5, channel.value(5.0));
+        assert_eq!(3.5, channel.value(9.0));
+        assert_eq!(2.0, channel.value(f32::NAN));
+        assert_eq!((1.0, 5.0), (channel.start(), channel.end()));
+
+        let line = Channel::new(&[(0f32, 0f32), (2.0, 1.0)]).unwrap();
+        assert_eq!(0.25, line.value(0.5));
+        let rest = Channel::with_boundary(
+            &[(0f32, 0f32), (1.0, 1.0), (2.0, 0.0)],
+            Boundary::Clamped {
+                start: 0.0,
+                end: 0.0,
+            },
+        )
+        .unwrap();
+        assert!((rest.value(0.01) - rest.value(0.0)).abs() < 1e-3);
+        assert_eq!(
+            Err(Error::NonIncreasingKnots { index: 1 }),
+            Channel::new(&[(1f32, 0f32), (1.0, 1.0)]).map(|_| ())
+        );
+    }
+
+    #[test]
+    fn timeline_on_common_clock() {
+        let a = Channel::new(&[(0f32, 0f32), (1.0, 1.0), (2.0, 0.0)]).unwrap();
+        let b = Channel::new(&[(0.5f32, 3f32), (1.0, 2.0), (1.5, 1.0)]).unwrap();
+        let timeline = Timeline::new([a, b]);
+        assert_eq!(2.0, timeline.duration());
+        assert_eq!([1.0, 2.0], timeline.eval(1.0));
+        assert_eq!([0.0, 3.0], timeline.eval(0.0));
+        assert_eq!(timeline.eval(1.0), timeline.eval_looped(5.0));
+        assert_eq!(timeline.eval(0.5), timeline.eval_looped(-1.5));
+        assert_eq!(2, timeline.channels().len());
+    }
+}
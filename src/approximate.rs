@@ -0,0 +1,205 @@
+use crate::{
+    check_increasing, check_range, check_spline, eval_segment, splinterpol_slices, Boundary, Error,
+    Solver,
+};
+
+/// Number of positions per segment at which [`approximate`] compares the
+/// spline to the function to estimate the error.
+const PROBES: usize = 3;
+
+/// Placement of the knots of [`approximate`] within the interval.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum Nodes {
+    /// Equally spaced, for the fastest lookup
+    #[default]
+    Uniform,
+    /// Chebyshev-Lobatto points, denser towards both ends, which reduces the
+    /// error at the ends for functions steep there
+    Chebyshev,
+}
+
+/// Replace the function `f` on `[a, b]` by a spline through its values at `N`
+/// knots placed according to `nodes`, e.g. to turn an expensive `sin` or
+/// `log` into a lookup on parts without an FPU.
+///
+/// Writes the knots to `xs` and the coefficients to the first `N - 1`
+/// elements of `coefficients`. The spline is not-a-knot for at least 4 knots,
+/// which approximates smooth functions to fourth order up to the ends, and
+/// natural for 3.
+///
+/// Returns the largest deviation `|f(x) - s(x)|` found at a few positions
+/// within every segment, an estimate of the maximum error.
+///
+/// ```
+/// use cubic_splinterpol::{approximate, eval, Extrapolate, Nodes};
+///
+/// let mut xs = [0f32; 17];
+/// let mut coefficients = [(0f32, 0f32, 0f32, 0f32); 16];
+/// let f = |x: f32| (x * 1.7).sin();
+/// let error = approximate(f, 0.0, 3.0, Nodes::Uniform, &mut xs, &mut coefficients).unwrap();
+/// assert!(error < 1e-3);
+///
+/// let y = eval(&coefficients, &xs, 1.0, Extrapolate::Error).unwrap();
+/// assert!((y - f(1.0)).abs() <= error * 1.5);
+/// ```
+pub fn approximate<const N: usize>(
+    f: impl Fn(f32) -> f32,
+    a: f32,
+    b: f32,
+    nodes: Nodes,
+    xs: &mut [f32; N],
+    coefficients: &mut [(f32, f32, f32, f32)],
+) -> Result<f32, Error> {
+    if N < 3 || coefficients.len() < N - 1 {
+        return Err(Error::InvalidSliceLength);
+    }
+    check_range(a, b)?;
+    let last = (N - 1) as f32;
+    for (i, x) in xs.iter_mut().enumerate() {
+        let fraction = match nodes {
+            Nodes::Uniform => i as f32 / last,
+            Nodes::Chebyshev => {
+                0.5f32 - 0.5f32 * libm::cosf(core::f32::consts::PI * i as f32 / last)
+            }
+        };
+        *x = a + (b - a) * fraction;
+    }
+    // Exact ends, free of rounding in the node formulas
+    xs[0] = a;
+    xs[N - 1] = b;
+    check_increasing(xs)?;
+
+    let mut ys = [0f32; N];
+    for (y, x) in ys.iter_mut().zip(xs.iter()) {
+        *y = f(*x);
+    }
+    let boundary = if N >= 4 {
+        Boundary::NotAKnot
+    } else {
+        Boundary::Natural
+    };
+    splinterpol_slices::<N>(xs, &ys, coefficients, boundary, Solver::Thomas)?;
+    max_error(f, &coefficients[..N - 1], xs, PROBES)
+}
+
+/// Estimate the largest deviation `|f(x) - s(x)|` between the function `f`
+/// and the spline given by `coefficients` and the knots `xs`, from the
+/// deviation at every knot and at `probes_per_segment` equally spaced
+/// positions within every segment.
+///
+/// The estimate is a lower bound of the true maximum which converges as the
+/// probes get denser, e.g. to decide at build time how many knots a
+/// replacement of a function needs. A deviation of NaN, e.g. where `f` is not
+/// defined, is returned as NaN.
+///
+/// ```
+/// use cubic_splinterpol::{max_error, splinterpol};
+///
+/// let xs = [0f32, 0.5, 1.0, 1.5, 2.0];
+/// let mut ys = [0f32; 5];
+/// for (y, x) in ys.iter_mut().zip(&xs) {
+///     *y = x.exp();
+/// }
+/// let mut coefficients = [(0f32, 0f32, 0f32, 0f32); 4];
+/// splinterpol(&xs, &ys, &mut coefficients).unwrap();
+/// let error = max_error(|x: f32| x.exp(), &coefficients, &xs, 16).unwrap();
+/// assert!(error > 0.01 && error < 0.1);
+/// ```
+pub fn max_error(
+    f: impl Fn(f32) -> f32,
+    coefficients: &[(f32, f32, f32, f32)],
+    xs: &[f32],
+    probes_per_segment: usize,
+) -> Result<f32, Error> {
+    check_spline(coefficients, xs)?;
+    if probes_per_segment == 0 {
+        return Err(Error::InvalidLimit);
+    }
+    let segments = coefficients.len();
+    let mut error = 0f32;
+    let mut update = |deviation: f32| {
+        // NaN compares false, but the error must not hide it
+        if deviation.is_nan() || deviation > error {
+            error = deviation;
+        }
+    };
+    for (segment, polynomial) in coefficients.iter().enumerate() {
+        let h = xs[segment + 1] - xs[segment];
+        for probe in 0..=probes_per_segment {
+            let t = h * probe as f32 / (probes_per_segment + 1) as f32;
+            update((f(xs[segment] + t) - eval_segment(*polynomial, t)).abs());
+        }
+    }
+    let h = xs[segments] - xs[segments - 1];
+    update((f(xs[segments]) - eval_segment(coefficients[segments - 1], h)).abs());
+    Ok(error)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn reproduces_cubics_exactly() {
+        let f = |x: f32| 2f32 - x + 0.5 * x * x * x;
+        let mut xs = [0f32; 5];
+        let mut coeffs = [(0f32, 0f32, 0f32, 0f32); 4];
+        let error = approximate(f, -1.0, 2.0, Nodes::Uniform, &mut xs, &mut coeffs).unwrap();
+        assert!(error < 1e-5);
+        assert_eq!([-1.0, -0.25, 0.5, 1.25, 2.0], xs);
+    }
+
+    #[test]
+    fn chebyshev_nodes() {
+        let mut xs = [0f32; 5];
+        let mut coeffs = [(0f32, 0f32, 0f32, 0f32); 4];
+        let sqrt = |x: f32| libm::sqrtf(x);
+        let chebyshev =
+            approximate(sqrt, 0.0, 1.0, Nodes::Chebyshev, &mut xs, &mut coeffs).unwrap();
+        assert_eq!(0.0, xs[0]);
+        assert!((xs[1] - 0.146_446_6).abs() < 1e-6);
+        assert!((xs[2] - 0.5).abs() < 1e-6);
+        assert_eq!(1.0, xs[4]);
+        // Steep at 0, where Chebyshev nodes are denser
+        let uniform = approximate(sqrt, 0.0, 1.0, Nodes::Uniform, &mut xs, &mut coeffs).unwrap();
+        assert!(chebyshev < uniform);
+    }
+
+    #[test]
+    fn reports_nan_and_invalid_input() {
+        let mut xs = [0f32; 4];
+        let mut coeffs = [(0f32, 0f32, 0f32, 0f32); 3];
+        let f = |x: f32| if x > 0.4 && x < 0.6 { f32::NAN } else { x };
+        let error = approximate(f, 0.0, 3.0, Nodes::Uniform, &mut xs, &mut coeffs).unwrap();
+        assert!(error.is_nan());
+        assert_eq!(
+            Err(Error::InvalidRange),
+            approximate(|x| x, 1.0, 1.0, Nodes::Uniform, &mut xs, &mut coeffs)
+        );
+        assert_eq!(
+            Err(Error::InvalidSliceLength),
+            approximate(|x| x, 0.0, 1.0, Nodes::Uniform, &mut xs, &mut coeffs[..2])
+        );
+    }
+
+    #[test]
+    fn max_error_converges() {
+        let xs = [0f32, 1.0, 2.0];
+        let coeffs = [(0f32, 1f32, 0f32, 0f32), (1.0, 1.0, 0.0, 0.0)];
+        // s(x) = x, so |x^2 - x| is largest at the last knot
+        let f = |x: f32| x * x;
+        assert_eq!(Ok(2.0), max_error(f, &coeffs, &xs, 1));
+        assert_eq!(Ok(0.0), max_error(|x| x, &coeffs, &xs, 7));
+        // |g - s| = |x (x - 1) (2 - x)| peaks inside both segments
+        let g = |x: f32| x + x * (x - 1f32) * (2f32 - x);
+        let coarse = max_error(g, &coeffs, &xs, 2).unwrap();
+        let fine = max_error(g, &coeffs, &xs, 64).unwrap();
+        assert!(coarse <= fine);
+        assert!((fine - 2f32 / (3f32 * libm::sqrtf(3f32))).abs() < 1e-3);
+        assert_eq!(Err(Error::InvalidLimit), max_error(f, &coeffs, &xs, 0));
+        assert_eq!(
+            Err(Error::InvalidSliceLength),
+            max_error(f, &coeffs, &xs[..2], 4)
+        );
+    }
+}
@@ -0,0 +1,146 @@
+//! Arithmetic on splines defined on the same knots, e.g. to apply a per-unit
+//! correction to a baseline calibration curve.
+//!
+//! The coefficients of a segment are linear in the values the spline
+//! interpolates, so combining the coefficient tables segment by segment gives
+//! exactly the spline through the combined values. None of the functions need
+//! the knots themselves, but both operands must refer to the same knots.
+
+use crate::Error;
+
+/// Coefficients of `lhs + rhs`, written to `out`.
+pub fn add(
+    lhs: &[(f32, f32, f32, f32)],
+    rhs: &[(f32, f32, f32, f32)],
+    out: &mut [(f32, f32, f32, f32)],
+) -> Result<(), Error> {
+    combine(lhs, rhs, out, |l, r| l + r)
+}
+
+/// Coefficients of `lhs - rhs`, written to `out`.
+pub fn sub(
+    lhs: &[(f32, f32, f32, f32)],
+    rhs: &[(f32, f32, f32, f32)],
+    out: &mut [(f32, f32, f32, f32)],
+) -> Result<(), Error> {
+    combine(lhs, rhs, out, |l, r| l - r)
+}
+
+/// Coefficients of `factor * coefficients`, written to `out`.
+pub fn scale(
+    coefficients: &[(f32, f32, f32, f32)],
+    factor: f32,
+    out: &mut [(f32, f32, f32, f32)],
+) -> Result<(), Error> {
+    check_lengths(coefficients, out)?;
+    for ((a, b, c, d), elem) in coefficients.iter().zip(out) {
+        *elem = (a * factor, b * factor, c * factor, d * factor);
+    }
+    Ok(())
+}
+
+/// Coefficients of `coefficients + value`, the spline shifted vertically,
+/// written to `out`.
+pub fn offset(
+    coefficients: &[(f32, f32, f32, f32)],
+    value: f32,
+    out: &mut [(f32, f32, f32, f32)],
+) -> Result<(), Error> {
+    check_lengths(coefficients, out)?;
+    for ((a, b, c, d), elem) in coefficients.iter().zip(out) {
+        *elem = (a + value, *b, *c, *d);
+    }
+    Ok(())
+}
+
+fn combine(
+    lhs: &[(f32, f32, f32, f32)],
+    rhs: &[(f32, f32, f32, f32)],
+    out: &mut [(f32, f32, f32, f32)],
+    op: impl Fn(f32, f32) -> f32,
+) -> Result<(), Error> {
+    check_lengths(lhs, out)?;
+    check_lengths(rhs, out)?;
+    for ((l, r), elem) in lhs.iter().zip(rhs).zip(out) {
+        *elem = (op(l.0, r.0), op(l.1, r.1), op(l.2, r.2), op(l.3, r.3));
+    }
+    Ok(())
+}
+
+fn check_lengths(
+    coefficients: &[(f32, f32, f32, f32)],
+    out: &[(f32, f32, f32, f32)],
+) -> Result<(), Error> {
+    if coefficients.is_empty() {
+        return Err(Error::EmptyInput);
+    }
+    if coefficients.len() != out.len() {
+        return Err(Error::InvalidSliceLength);
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::fixture::{spline_through, YS};
+
+    fn assert_close(lhs: &[(f32, f32, f32, f32)], rhs: &[(f32, f32, f32, f32)]) {
+        for (l, r) in lhs.iter().zip(rhs) {
+            for (l, r) in [(l.0, r.0), (l.1, r.1), (l.2, r.2), (l.3, r.3)] {
+                assert!((l - r).abs() < 1e-5, "{:?} vs {:?}", lhs, rhs);
+            }
+        }
+    }
+
+    #[test]
+    fn operations_match_spline_of_combined_values() {
+        let baseline = YS;
+        let correction = [0.1f32, -0.2, 0.0, 0.3, -0.1];
+        let mut sum = [0f32; 5];
+        let mut difference = [0f32; 5];
+        let mut scaled = [0f32; 5];
+        let mut shifted = [0f32; 5];
+        for i in 0..5 {
+            sum[i] = baseline[i] + correction[i];
+            difference[i] = baseline[i] - correction[i];
+            scaled[i] = baseline[i] * 2.5;
+            shifted[i] = baseline[i] - 4.0;
+        }
+
+        let mut out = [(0f32, 0f32, 0f32, 0f32); 4];
+        add(
+            &spline_through(&baseline),
+            &spline_through(&correction),
+            &mut out,
+        )
+        .unwrap();
+        assert_close(&spline_through(&sum), &out);
+        sub(
+            &spline_through(&baseline),
+            &spline_through(&correction),
+            &mut out,
+        )
+        .unwrap();
+        assert_close(&spline_through(&difference), &out);
+        scale(&spline_through(&baseline), 2.5, &mut out).unwrap();
+        assert_close(&spline_through(&scaled), &out);
+        offset(&spline_through(&baseline), -4.0, &mut out).unwrap();
+        assert_close(&spline_through(&shifted), &out);
+    }
+
+    #[test]
+    fn rejects_mismatched_lengths() {
+        let coeffs = [(0f32, 1f32, 0f32, 0f32); 3];
+        let mut out = [(0f32, 0f32, 0f32, 0f32); 2];
+        assert_eq!(
+            Err(Error::InvalidSliceLength),
+            add(&coeffs, &coeffs, &mut out)
+        );
+        assert_eq!(
+            Err(Error::InvalidSliceLength),
+            add(&coeffs[..2], &coeffs, &mut out)
+        );
+        assert_eq!(Err(Error::EmptyInput), scale(&[], 1.0, &mut []));
+    }
+}
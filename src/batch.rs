@@ -0,0 +1,257 @@
+use crate::extrapolate::locate;
+use crate::{check_spline, eval_segment, Error, Extrapolate};
+
+/// Number of samples [`eval_batch`] evaluates per iteration.
+pub const LANES: usize = 8;
+
+/// Evaluate `LANES` polynomials at once. The loops work on plain arrays of
+/// fixed length without dependencies between lanes, so the compiler lowers
+/// them to SIMD instructions where the target has them (SSE/AVX, NEON,
+/// wasm simd128). This keeps the crate on stable Rust, where `core::simd` is
+/// not yet available.
+#[inline]
+fn horner_lanes(
+    a: &[f32; LANES],
+    b: &[f32; LANES],
+    c: &[f32; LANES],
+    d: &[f32; LANES],
+    t: &[f32; LANES],
+    out: &mut [f32],
+) {
+    let mut acc = [0f32; LANES];
+    for lane in 0..LANES {
+        acc[lane] = ((d[lane] * t[lane] + c[lane]) * t[lane] + b[lane]) * t[lane] + a[lane];
+    }
+    out.copy_from_slice(&acc);
+}
+
+/// Evaluate the spline at every position in `queries`, writing the results to
+/// the corresponding elements of `out`.
+///
+/// Samples are processed in blocks of [`LANES`]: segment lookup is done per
+/// sample, then the polynomials of the whole block are evaluated together in
+/// a form amenable to vectorization. The remainder which does not fill a
+/// block is evaluated one sample at a time. Positions outside of the knots
+/// are evaluated on the first or last polynomial.
+pub fn eval_batch(
+    coefficients: &[(f32, f32, f32, f32)],
+    xs: &[f32],
+    queries: &[f32],
+    out: &mut [f32],
+) -> Result<(), Error> {
+    eval_batch_extrapolated(coefficients, xs, queries, out, Extrapolate::ExtendCubic)
+}
+
+/// Like [`eval_batch`], but positions outside of the knots are extrapolated
+/// according to `extrapolate`. With [`Extrapolate::Error`], the first such
+/// position fails the whole batch.
+pub fn eval_batch_extrapolated(
+    coefficients: &[(f32, f32, f32, f32)],
+    xs: &[f32],
+    queries: &[f32],
+    out: &mut [f32],
+    extrapolate: Extrapolate,
+) -> Result<(), Error> {
+    check_spline(coefficients, xs)?;
+    eval_batch_with(
+        |segment| coefficients[segment],
+        xs,
+        queries,
+        out,
+        extrapolate,
+    )
+}
+
+/// Like [`eval_batch_extrapolated`], for `queries` sorted in non-decreasing
+/// order, e.g. the output positions of a resampler.
+///
+/// Instead of a segment lookup per query, the segments and queries are walked
+/// together in a single pass, in time linear in the number of both. Fails
+/// with [`Error::InvalidRange`] if a query is smaller than its predecessor or
+/// NaN, leaving the results up to there in `out`.
+///
+/// ```
+/// use cubic_splinterpol::{eval_sorted, splinterpol, Extrapolate};
+///
+/// let xs = [0f32, 1.0, 2.0, 3.0];
+/// let ys = [0f32, 1.0, 0.5, 2.0];
+/// let mut coefficients = [(0f32, 0f32, 0f32, 0f32); 3];
+/// splinterpol(&xs, &ys, &mut coefficients).unwrap();
+///
+/// let queries = [0.0, 0.5, 1.0, 2.5, 3.0];
+/// let mut out = [0f32; 5];
+/// eval_sorted(&coefficients, &xs, &queries, &mut out, Extrapolate::Error).unwrap();
+/// assert_eq!([0.0, 1.0, 2.0], [out[0], out[2], out[4]]);
+/// ```
+pub fn eval_sorted(
+    coefficients: &[(f32, f32, f32, f32)],
+    xs: &[f32],
+    queries: &[f32],
+    out: &mut [f32],
+    extrapolate: Extrapolate,
+) -> Result<(), Error> {
+    check_spline(coefficients, xs)?;
+    if queries.len() != out.len() {
+        return Err(Error::InvalidSliceLength);
+    }
+    let last = coefficients.len() - 1;
+    let (first_knot, last_knot) = (xs[0], xs[last + 1]);
+    let mut segment = 0;
+    let mut previous = f32::NEG_INFINITY;
+    for (x, elem) in queries.iter().zip(out.iter_mut()) {
+        if x.is_nan() || *x < previous {
+            return Err(Error::InvalidRange);
+        }
+        previous = *x;
+        let inside = *x >= first_knot && *x <= last_knot;
+        if !inside && extrapolate != Extrapolate::ExtendCubic {
+            let (polynomial, t) = locate(|segment| coefficients[segment], xs, *x, extrapolate)?;
+            *elem = eval_segment(polynomial, t);
+            continue;
+        }
+        while segment < last && xs[segment + 1] <= *x {
+            segment += 1;
+        }
+        *elem = eval_segment(coefficients[segment], x - xs[segment]);
+    }
+    Ok(())
+}
+
+/// Batch evaluation over any coefficient storage, with `coefficients`
+/// returning the coefficients of the segment with the given index. The knots must
+/// already have been checked against the storage.
+pub(crate) fn eval_batch_with(
+    coefficients: impl Fn(usize) -> (f32, f32, f32, f32),
+    xs: &[f32],
+    queries: &[f32],
+    out: &mut [f32],
+    extrapolate: Extrapolate,
+) -> Result<(), Error> {
+    if queries.len() != out.len() {
+        return Err(Error::InvalidSliceLength);
+    }
+    let mut query_blocks = queries.chunks_exact(LANES);
+    let mut out_blocks = out.chunks_exact_mut(LANES);
+    for (query_block, out_block) in (&mut query_blocks).zip(&mut out_blocks) {
+        let mut a = [0f32; LANES];
+        let mut b = [0f32; LANES];
+        let mut c = [0f32; LANES];
+        let mut d = [0f32; LANES];
+        let mut t = [0f32; LANES];
+        for (lane, x) in query_block.iter().enumerate() {
+            let (coeffs, position) = locate(&coefficients, xs, *x, extrapolate)?;
+            a[lane] = coeffs.0;
+            b[lane] = coeffs.1;
+            c[lane] = coeffs.2;
+            d[lane] = coeffs.3;
+            t[lane] = position;
+        }
+        horner_lanes(&a, &b, &c, &d, &t, out_block);
+    }
+    for (x, elem) in query_blocks
+        .remainder()
+        .iter()
+        .zip(out_blocks.into_remainder())
+    {
+        let (coeffs, position) = locate(&coefficients, xs, *x, extrapolate)?;
+        *elem = eval_segment(coeffs, position);
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::find_segment;
+
+    #[test]
+    fn batch_matches_scalar() {
+        let xs = [0.5f32, 1f32, 2f32, 3f32, 4.5f32, 5f32, 6f32, 7f32];
+        let ys = [0f32, 0f32, 1f32, 2f32, 4f32, 7f32, 9f32, 10f32];
+        let mut coeffs = [(0f32, 0f32, 0f32, 0f32); 7];
+        crate::splinterpol::<8>(&xs, &ys, &mut coeffs).unwrap();
+
+        let mut queries = [0f32; 37];
+        for (i, q) in queries.iter_mut().enumerate() {
+            *q = 0.25 + i as f32 * 0.2;
+        }
+        let mut out = [0f32; 37];
+        eval_batch(&coeffs, &xs, &queries, &mut out).unwrap();
+        for (x, y) in queries.iter().zip(&out) {
+            let segment = find_segment(&xs, *x);
+            assert_eq!(eval_segment(coeffs[segment], x - xs[segment]), *y);
+        }
+    }
+
+    #[test]
+    fn batch_checks_lengths() {
+        let xs = [0f32, 1f32];
+        let coeffs = [(0f32, 1f32, 0f32, 0f32)];
+        let mut out = [0f32; 2];
+        assert_eq!(
+            Err(Error::InvalidSliceLength),
+            eval_batch(&coeffs, &xs, &[0.5], &mut out)
+        );
+    }
+
+    #[test]
+    fn batch_extrapolates() {
+        let xs = [0f32, 1f32, 2f32];
+        let coeffs = [(0f32, 1f32, 0f32, 0f32), (1f32, 1f32, 0f32, 0f32)];
+        let queries = [-2f32, -1.0, 0.5, 1.5, 3.0, 4.0, 0.0, 1.0, 2.0, 5.0];
+        let mut out = [0f32; 10];
+        eval_batch_extrapolated(
+            &coeffs,
+            &xs,
+            &queries,
+            &mut out,
+            Extrapolate::ClampToEndValue,
+        )
+        .unwrap();
+        assert_eq!([0.0, 0.0, 0.5, 1.5, 2.0, 2.0, 0.0, 1.0, 2.0, 2.0], out);
+        assert_eq!(
+            Err(Error::OutOfRange),
+            eval_batch_extrapolated(&coeffs, &xs, &queries, &mut out, Extrapolate::Error)
+        );
+    }
+
+    #[test]
+    fn sorted_matches_batch() {
+        let xs = [0.5f32, 1f32, 2f32, 3f32, 4.5f32, 5f32, 6f32, 7f32];
+        let ys = [0f32, 0f32, 1f32, 2f32, 4f32, 7f32, 9f32, 10f32];
+        let mut coeffs = [(0f32, 0f32, 0f32, 0f32); 7];
+        crate::splinterpol::<8>(&xs, &ys, &mut coeffs).unwrap();
+
+        let mut queries = [0f32; 45];
+        for (i, q) in queries.iter_mut().enumerate() {
+            // Repeated positions, knots and both sides of the knots
+            *q = -0.5 + (i / 2) as f32 * 0.4;
+        }
+        for extrapolate in [
+            Extrapolate::ExtendCubic,
+            Extrapolate::ClampToEndValue,
+            Extrapolate::LinearFromEndSlope,
+        ] {
+            let mut expected = [0f32; 45];
+            eval_batch_extrapolated(&coeffs, &xs, &queries, &mut expected, extrapolate).unwrap();
+            let mut out = [0f32; 45];
+            eval_sorted(&coeffs, &xs, &queries, &mut out, extrapolate).unwrap();
+            assert_eq!(expected, out);
+        }
+        let mut out = [0f32; 3];
+        assert_eq!(
+            Err(Error::InvalidRange),
+            eval_sorted(
+                &coeffs,
+                &xs,
+                &[1.0, 2.0, 1.5],
+                &mut out,
+                Extrapolate::ExtendCubic
+            )
+        );
+        assert_eq!(
+            Err(Error::OutOfRange),
+            eval_sorted(&coeffs, &xs, &[1.0, 2.0, 7.5], &mut out, Extrapolate::Error)
+        );
+    }
+}
@@ -0,0 +1,220 @@
+use crate::{check_range, check_spline, eval, Error, Extrapolate};
+
+/// Weight of the second spline in a [`Blend`] at every position, 0 for only
+/// the first and 1 for only the second.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum BlendWeight<'a> {
+    /// The same weight everywhere, in `[0, 1]`
+    Constant(f32),
+    /// 0 up to `start`, 1 from `end` on, and rising smoothly in between with
+    /// zero slope at both ends, so that the blend is as smooth as the splines
+    Transition {
+        /// Start of the transition
+        start: f32,
+        /// End of the transition
+        end: f32,
+    },
+    /// The spline given by `coefficients` and the knots `xs`, clamped to
+    /// `[0, 1]` and extending its end values beyond the knots
+    Spline {
+        /// Coefficients of the weight
+        coefficients: &'a [(f32, f32, f32, f32)],
+        /// Knots of the weight
+        xs: &'a [f32],
+    },
+}
+
+/// Cross-fade between two splines, e.g. from the old trajectory to a
+/// re-planned one without a jump in the setpoint.
+///
+/// The blend at x is `(1 - w) * first(x) + w * second(x)` for the weight w of
+/// [`BlendWeight`]. The splines may have different knots, and are evaluated
+/// beyond them on their first or last polynomial; a spline with weight 0 is
+/// not evaluated at all.
+///
+/// ```
+/// use cubic_splinterpol::{splinterpol, Blend, BlendWeight};
+///
+/// let xs = [0f32, 1.0, 2.0, 3.0];
+/// let mut old = [(0f32, 0f32, 0f32, 0f32); 3];
+/// splinterpol(&xs, &[0.0, 1.0, 2.0, 3.0], &mut old).unwrap();
+/// let mut replanned = [(0f32, 0f32, 0f32, 0f32); 3];
+/// splinterpol(&xs, &[0.0, 1.0, 1.0, 1.0], &mut replanned).unwrap();
+///
+/// let weight = BlendWeight::Transition { start: 1.0, end: 2.0 };
+/// let blend = Blend::new(&old, &xs, &replanned, &xs, weight).unwrap();
+/// assert_eq!(0.5, blend.eval(0.5));
+/// assert_eq!(0.5, blend.weight(1.5));
+/// assert_eq!(1.0, blend.eval(3.0));
+/// ```
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Blend<'a> {
+    first_coefficients: &'a [(f32, f32, f32, f32)],
+    first_xs: &'a [f32],
+    second_coefficients: &'a [(f32, f32, f32, f32)],
+    second_xs: &'a [f32],
+    weight: BlendWeight<'a>,
+}
+
+impl<'a> Blend<'a> {
+    /// A blend from the spline given by `first_coefficients` and the knots
+    /// `first_xs` to the one given by `second_coefficients` and
+    /// `second_xs`. Fails with [`Error::InvalidLimit`] for a constant weight
+    /// outside of `[0, 1]` and [`Error::InvalidRange`] for a transition which
+    /// does not end after it starts.
+    pub fn new(
+        first_coefficients: &'a [(f32, f32, f32, f32)],
+        first_xs: &'a [f32],
+        second_coefficients: &'a [(f32, f32, f32, f32)],
+        second_xs: &'a [f32],
+        weight: BlendWeight<'a>,
+    ) -> Result<Self, Error> {
+        check_spline(first_coefficients, first_xs)?;
+        check_spline(second_coefficients, second_xs)?;
+        match weight {
+            BlendWeight::Constant(weight) => {
+                if !(0f32..=1f32).contains(&weight) {
+                    return Err(Error::InvalidLimit);
+                }
+            }
+            BlendWeight::Transition { start, end } => check_range(start, end)?,
+            BlendWeight::Spline { coefficients, xs } => check_spline(coefficients, xs)?,
+        }
+        Ok(Self {
+            first_coefficients,
+            first_xs,
+            second_coefficients,
+            second_xs,
+            weight,
+        })
+    }
+
+    /// The weight of the second spline at x.
+    pub fn weight(&self, x: f32) -> f32 {
+        match self.weight {
+            BlendWeight::Constant(weight) => weight,
+            BlendWeight::Transition { start, end } => {
+                let s = ((x - start) / (end - start)).clamp(0f32, 1f32);
+                s * s * (3f32 - 2f32 * s)
+            }
+            BlendWeight::Spline { coefficients, xs } => {
+                let weight = eval(coefficients, xs, x, Extrapolate::ClampToEndValue);
+                // The spline was checked on construction
+                weight.unwrap_or(0f32).clamp(0f32, 1f32)
+            }
+        }
+    }
+
+    /// Evaluate the blend at global position x.
+    pub fn eval(&self, x: f32) -> f32 {
+        let weight = self.weight(x);
+        // Both were checked on construction, and extending never fails
+        let first = || {
+            eval(
+                self.first_coefficients,
+                self.first_xs,
+                x,
+                Extrapolate::ExtendCubic,
+            )
+            .unwrap_or(f32::NAN)
+        };
+        let second = || {
+            eval(
+                self.second_coefficients,
+                self.second_xs,
+                x,
+                Extrapolate::ExtendCubic,
+            )
+            .unwrap_or(f32::NAN)
+        };
+        if weight == 0f32 {
+            first()
+        } else if weight == 1f32 {
+            second()
+        } else {
+            (1f32 - weight) * first() + weight * second()
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const SECOND: [(f32, f32, f32, f32); 3] = [(1f32, 0f32, 0f32, 0f32); 3];
+
+    fn first() -> [(f32, f32, f32, f32); 2] {
+        let mut first = [(0f32, 0f32, 0f32, 0f32); 2];
+        crate::splinterpol(&[0f32, 2.0, 4.0], &[0f32, 2.0, 0.0], &mut first).unwrap();
+        first
+    }
+
+    #[test]
+    fn transition_is_continuous() {
+        let (first, second) = (first(), SECOND);
+        let (first_xs, second_xs) = ([0f32, 2.0, 4.0], [1f32, 2.0, 3.0, 5.0]);
+        let weight = BlendWeight::Transition {
+            start: 1.0,
+            end: 3.0,
+        };
+        let blend = Blend::new(&first, &first_xs, &second, &second_xs, weight).unwrap();
+        for i in 0..=40 {
+            let x = i as f32 * 0.1;
+            let old = eval(&first, &first_xs, x, Extrapolate::ExtendCubic).unwrap();
+            if x <= 1.0 {
+                assert_eq!(old, blend.eval(x));
+            } else if x >= 3.0 {
+                assert_eq!(1.0, blend.eval(x));
+            }
+            // No jumps
+            assert!((blend.eval(x + 0.01) - blend.eval(x)).abs() < 0.03);
+        }
+        assert_eq!(0.5, blend.weight(2.0));
+        // Zero slope of the weight at both ends
+        assert!(blend.weight(1.01) < 1e-3 && blend.weight(2.99) > 1f32 - 1e-3);
+    }
+
+    #[test]
+    fn constant_and_spline_weights() {
+        let (first, second) = (first(), SECOND);
+        let xs = [0f32, 2.0, 4.0];
+        let second_xs = [0f32, 1.0, 2.0, 4.0];
+        let blend = Blend::new(
+            &first,
+            &xs,
+            &second,
+            &second_xs,
+            BlendWeight::Constant(0.25),
+        );
+        let blend = blend.unwrap();
+        assert_eq!(0.25 * 1.0 + 0.75 * 2.0, blend.eval(2.0));
+
+        // Ramp from 0 to 1 and beyond, clamped
+        let ramp = [(0f32, 0.5f32, 0f32, 0f32), (1.0, 0.5, 0.0, 0.0)];
+        let weight = BlendWeight::Spline {
+            coefficients: &ramp,
+            xs: &xs,
+        };
+        let blend = Blend::new(&first, &xs, &second, &second_xs, weight).unwrap();
+        assert_eq!(0.0, blend.weight(-1.0));
+        assert_eq!(0.5, blend.weight(1.0));
+        assert_eq!(1.0, blend.weight(4.0));
+
+        assert_eq!(
+            Err(Error::InvalidLimit),
+            Blend::new(&first, &xs, &second, &second_xs, BlendWeight::Constant(1.5))
+        );
+        let backwards = BlendWeight::Transition {
+            start: 2.0,
+            end: 1.0,
+        };
+        assert_eq!(
+            Err(Error::InvalidRange),
+            Blend::new(&first, &xs, &second, &second_xs, backwards)
+        );
+        assert_eq!(
+            Err(Error::InvalidSliceLength),
+            Blend::new(&first, &xs, &second, &xs, backwards)
+        );
+    }
+}
@@ -0,0 +1,195 @@
+//! Conversion between the per-segment power basis coefficients of this crate
+//! and the control points of a cubic B-spline, e.g. to exchange curves with
+//! CAD or graphics tools.
+//!
+//! A spline on the `n` knots `xs` corresponds to the clamped cubic B-spline
+//! with the `n + 6` knots of [`knot_vector`], the first and last knot repeated
+//! four times, and `n + 2` control points. The first and last control point are
+//! the values at the ends of the spline.
+//!
+//! The conversions use the polar form (blossom) of the segment polynomials: a
+//! control point is the blossom of any polynomial piece in its support at the
+//! three knots following it, and the Bézier points of a segment, from which its
+//! power basis coefficients follow, are blossoms at its end points.
+
+use crate::{check_spline, Error};
+
+/// Write the knot vector of the clamped B-spline for the knots `xs` to
+/// `knots`, which must hold `xs.len() + 6` elements.
+pub fn knot_vector(xs: &[f32], knots: &mut [f32]) -> Result<(), Error> {
+    if xs.len() < 2 || knots.len() != xs.len() + 6 {
+        return Err(Error::InvalidSliceLength);
+    }
+    for (j, knot) in knots.iter_mut().enumerate() {
+        *knot = knot_at(xs, j);
+    }
+    Ok(())
+}
+
+/// Write the control points of the B-spline equal to the spline given by
+/// `coefficients` and the knots `xs` to `control_points`, which must hold
+/// `xs.len() + 2` elements.
+pub fn from_coefficients(
+    coefficients: &[(f32, f32, f32, f32)],
+    xs: &[f32],
+    control_points: &mut [f32],
+) -> Result<(), Error> {
+    check_spline(coefficients, xs)?;
+    let n = xs.len();
+    if control_points.len() != n + 2 {
+        return Err(Error::InvalidSliceLength);
+    }
+    for (j, point) in control_points.iter_mut().enumerate() {
+        // Leftmost non-empty knot interval in the support of B_j
+        let segment = j.clamp(3, n + 1) - 3;
+        let (a, b, c, d) = coefficients[segment];
+        let origin = xs[segment];
+        let u1 = knot_at(xs, j + 1) - origin;
+        let u2 = knot_at(xs, j + 2) - origin;
+        let u3 = knot_at(xs, j + 3) - origin;
+        *point = a
+            + b * (u1 + u2 + u3) / 3f32
+            + c * (u1 * u2 + u1 * u3 + u2 * u3) / 3f32
+            + d * u1 * u2 * u3;
+    }
+    Ok(())
+}
+
+/// Write the coefficients of the B-spline with `control_points` on the knots
+/// `xs` to `coefficients`. `control_points` must hold `xs.len() + 2` elements
+/// and `coefficients` `xs.len() - 1`.
+pub fn to_coefficients(
+    control_points: &[f32],
+    xs: &[f32],
+    coefficients: &mut [(f32, f32, f32, f32)],
+) -> Result<(), Error> {
+    check_spline(coefficients, xs)?;
+    if control_points.len() != xs.len() + 2 {
+        return Err(Error::InvalidSliceLength);
+    }
+    for (segment, elem) in coefficients.iter_mut().enumerate() {
+        let points = [
+            control_points[segment],
+            control_points[segment + 1],
+            control_points[segment + 2],
+            control_points[segment + 3],
+        ];
+        let start = xs[segment];
+        let end = xs[segment + 1];
+        let h = end - start;
+        let b0 = blossom(xs, segment, points, [start, start, start]);
+        let b1 = blossom(xs, segment, points, [start, start, end]);
+        let b2 = blossom(xs, segment, points, [start, end, end]);
+        let b3 = blossom(xs, segment, points, [end, end, end]);
+        *elem = (
+            b0,
+            3f32 * (b1 - b0) / h,
+            3f32 * (b2 - 2f32 * b1 + b0) / (h * h),
+            (b3 - 3f32 * b2 + 3f32 * b1 - b0) / (h * h * h),
+        );
+    }
+    Ok(())
+}
+
+/// Knot j of the clamped knot vector for `xs`.
+fn knot_at(xs: &[f32], j: usize) -> f32 {
+    let n = xs.len();
+    xs[j.clamp(3, n + 2) - 3]
+}
+
+/// Blossom of the polynomial piece on `segment` at `u`, from the four control
+/// points whose support contains the segment, by de Boor's algorithm with a
+/// different argument on every level.
+fn blossom(xs: &[f32], segment: usize, mut points: [f32; 4], u: [f32; 3]) -> f32 {
+    // Knot interval [t_k, t_k+1] is the segment, control points d_k-3..d_k
+    let k = segment + 3;
+    for (r, u) in (1..=3).zip(u) {
+        for i in (r..4).rev() {
+            let j = k - 3 + i;
+            let left = knot_at(xs, j);
+            let alpha = (u - left) / (knot_at(xs, j + 4 - r) - left);
+            points[i] = (1f32 - alpha) * points[i - 1] + alpha * points[i];
+        }
+    }
+    points[3]
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const XS: [f32; 6] = [0f32, 1.0, 2.5, 3.0, 4.0, 5.5];
+
+    #[test]
+    fn roundtrip() {
+        let ys = [1f32, 2.0, 0.5, 3.0, 2.0, 2.5];
+        let mut coeffs = [(0f32, 0f32, 0f32, 0f32); 5];
+        crate::splinterpol(&XS, &ys, &mut coeffs).unwrap();
+
+        let mut control_points = [0f32; 8];
+        from_coefficients(&coeffs, &XS, &mut control_points).unwrap();
+        assert_eq!(ys[0], control_points[0]);
+        assert!((ys[5] - control_points[7]).abs() < 1e-5);
+
+        let mut back = [(0f32, 0f32, 0f32, 0f32); 5];
+        to_coefficients(&control_points, &XS, &mut back).unwrap();
+        for (lhs, rhs) in coeffs.iter().zip(&back) {
+            for (l, r) in [
+                (lhs.0, rhs.0),
+                (lhs.1, rhs.1),
+                (lhs.2, rhs.2),
+                (lhs.3, rhs.3),
+            ] {
+                assert!((l - r).abs() < 1e-4, "{:?} vs {:?}", coeffs, back);
+            }
+        }
+    }
+
+    #[test]
+    fn linear_function_has_greville_control_points() {
+        // f(x) = x on every segment
+        let mut coeffs = [(0f32, 1f32, 0f32, 0f32); 5];
+        for (segment, x) in coeffs.iter_mut().zip(&XS) {
+            segment.0 = *x;
+        }
+        let mut control_points = [0f32; 8];
+        from_coefficients(&coeffs, &XS, &mut control_points).unwrap();
+        let mut knots = [0f32; 12];
+        knot_vector(&XS, &mut knots).unwrap();
+        assert_eq!(
+            [0.0, 0.0, 0.0, 0.0, 1.0, 2.5, 3.0, 4.0, 5.5, 5.5, 5.5, 5.5],
+            knots
+        );
+        for (j, point) in control_points.iter().enumerate() {
+            let greville = (knots[j + 1] + knots[j + 2] + knots[j + 3]) / 3f32;
+            assert!((point - greville).abs() < 1e-5);
+        }
+
+        // Constant control points give a constant spline
+        let mut constant = [(0f32, 0f32, 0f32, 0f32); 5];
+        to_coefficients(&[2f32; 8], &XS, &mut constant).unwrap();
+        for segment in constant {
+            assert!((segment.0 - 2.0).abs() < 1e-6);
+            assert!(segment.1.abs() < 1e-5 && segment.2.abs() < 1e-5 && segment.3.abs() < 1e-5);
+        }
+    }
+
+    #[test]
+    fn rejects_invalid_lengths() {
+        let coeffs = [(0f32, 0f32, 0f32, 0f32); 5];
+        let mut control_points = [0f32; 7];
+        assert_eq!(
+            Err(Error::InvalidSliceLength),
+            from_coefficients(&coeffs, &XS, &mut control_points)
+        );
+        let mut out = [(0f32, 0f32, 0f32, 0f32); 5];
+        assert_eq!(
+            Err(Error::InvalidSliceLength),
+            to_coefficients(&control_points, &XS, &mut out)
+        );
+        assert_eq!(
+            Err(Error::InvalidSliceLength),
+            knot_vector(&XS, &mut [0f32; 11])
+        );
+    }
+}
@@ -0,0 +1,395 @@
+use crate::{check_increasing, splinterpol_slices, Boundary, Duplicates, Error, Solver};
+
+/// Accumulates up to `CAP` points one at a time, e.g. from an interrupt or DMA
+/// stream, and computes the spline through however many points were pushed.
+///
+/// The boundary condition and the solver are configurable, and default to a
+/// natural spline solved with the Thomas algorithm:
+///
+/// ```
+/// use cubic_splinterpol::{Boundary, SplineBuilder};
+///
+/// let mut builder = SplineBuilder::<8>::new().with_boundary(Boundary::Clamped {
+///     start: 0.0,
+///     end: 0.0,
+/// });
+/// for (x, y) in [(0.0, 0.0), (1.0, 2.0), (2.0, 1.0), (3.0, 3.0)] {
+///     builder.push(x, y).unwrap();
+/// }
+/// let mut coefficients = [(0f32, 0f32, 0f32, 0f32); 7];
+/// assert_eq!(Ok(3), builder.finish(&mut coefficients));
+/// ```
+#[derive(Debug, Clone)]
+pub struct SplineBuilder<const CAP: usize> {
+    xs: [f32; CAP],
+    ys: [f32; CAP],
+    len: usize,
+    boundary: Boundary,
+    solver: Solver,
+    duplicates: Duplicates,
+    epsilon: f32,
+    /// First x and number of the points merged into the last knot
+    run: (f32, usize),
+}
+
+impl<const CAP: usize> Default for SplineBuilder<CAP> {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl<const CAP: usize> SplineBuilder<CAP> {
+    /// A builder without any points.
+    pub fn new() -> Self {
+        Self {
+            xs: [0f32; CAP],
+            ys: [0f32; CAP],
+            len: 0,
+            boundary: Boundary::Natural,
+            solver: Solver::Thomas,
+            duplicates: Duplicates::Error,
+            epsilon: 0f32,
+            run: (0f32, 0),
+        }
+    }
+
+    /// A builder with the points from `points`, which are sorted by x in
+    /// place first, e.g. calibration points collected interactively in any
+    /// order. Fails with [`Error::NonIncreasingKnots`] for two points with the
+    /// same x, at the index of the second in sorted order, and with
+    /// [`Error::CapacityExceeded`] for more than `CAP` points. To merge
+    /// duplicates instead, see [`extend_unsorted`](Self::extend_unsorted).
+    ///
+    /// ```
+    /// use cubic_splinterpol::SplineBuilder;
+    ///
+    /// let mut points = [(2.0, 1.0), (0.0, 0.0), (3.0, 3.0), (1.0, 2.0)];
+    /// let builder = SplineBuilder::<8>::from_unsorted(&mut points).unwrap();
+    /// assert_eq!([0.0, 1.0, 2.0, 3.0], builder.xs());
+    /// assert_eq!((1.0, 2.0), points[1]);
+    /// ```
+    pub fn from_unsorted(points: &mut [(f32, f32)]) -> Result<Self, Error> {
+        if points.len() > CAP {
+            return Err(Error::CapacityExceeded);
+        }
+        let mut builder = Self::new();
+        builder.extend_unsorted(points)?;
+        Ok(builder)
+    }
+
+    /// Sort `points` by x in place and [`push`](Self::push) them in that
+    /// order, e.g. after [`with_duplicates`](Self::with_duplicates) to merge
+    /// repeated measurements at the same position. All of them must come
+    /// after the points pushed before.
+    pub fn extend_unsorted(&mut self, points: &mut [(f32, f32)]) -> Result<(), Error> {
+        // Insertion sort: no allocation, and fast for the few, often almost
+        // sorted points this is meant for
+        for i in 1..points.len() {
+            let mut j = i;
+            while j > 0 && points[j - 1].0 > points[j].0 {
+                points.swap(j - 1, j);
+                j -= 1;
+            }
+        }
+        for (x, y) in points.iter() {
+            self.push(*x, *y)?;
+        }
+        Ok(())
+    }
+
+    /// Use the given boundary condition.
+    pub fn with_boundary(mut self, boundary: Boundary) -> Self {
+        self.boundary = boundary;
+        self
+    }
+
+    /// Use the given solver for the tridiagonal system.
+    pub fn with_solver(mut self, solver: Solver) -> Self {
+        self.solver = solver;
+        self
+    }
+
+    /// Handle points whose x is at most `epsilon` above the first of the
+    /// points merged into the last knot as given by `duplicates`, instead of
+    /// rejecting equal knots. A negative or NaN epsilon rejects them as well.
+    ///
+    /// ```
+    /// use cubic_splinterpol::{Duplicates, SplineBuilder};
+    ///
+    /// let mut builder = SplineBuilder::<8>::new().with_duplicates(Duplicates::Average, 0.01);
+    /// for (x, y) in [(0.0, 0.0), (1.0, 2.0), (1.005, 3.0), (2.0, 1.0)] {
+    ///     builder.push(x, y).unwrap();
+    /// }
+    /// assert_eq!([0.0, 2.5, 1.0], builder.ys());
+    /// ```
+    pub fn with_duplicates(mut self, duplicates: Duplicates, epsilon: f32) -> Self {
+        self.duplicates = duplicates;
+        self.epsilon = epsilon;
+        self
+    }
+
+    /// Append a point. x must be greater than the x of the previous point,
+    /// unless it is a duplicate merged as configured by
+    /// [`with_duplicates`](Self::with_duplicates).
+    pub fn push(&mut self, x: f32, y: f32) -> Result<(), Error> {
+        if self.len > 0 {
+            let last = self.len - 1;
+            let (run_start, run_len) = self.run;
+            if x >= run_start && x - run_start <= self.epsilon {
+                let merged = (self.xs[last], self.ys[last]);
+                let (x, y) = self
+                    .duplicates
+                    .merge(merged, run_len, (x, y))
+                    .ok_or(Error::NonIncreasingKnots { index: self.len })?;
+                self.xs[last] = x;
+                self.ys[last] = y;
+                self.run.1 += 1;
+                return Ok(());
+            }
+        }
+        if self.len == CAP {
+            return Err(Error::CapacityExceeded);
+        }
+        if self.len > 0 && check_increasing(&[self.xs[self.len - 1], x]).is_err() {
+            return Err(Error::NonIncreasingKnots { index: self.len });
+        }
+        self.xs[self.len] = x;
+        self.ys[self.len] = y;
+        self.len += 1;
+        self.run = (x, 1);
+        Ok(())
+    }
+
+    /// Number of points pushed so far.
+    pub fn len(&self) -> usize {
+        self.len
+    }
+
+    /// Whether no points were pushed yet.
+    pub fn is_empty(&self) -> bool {
+        self.len == 0
+    }
+
+    /// Remove all points.
+    pub fn clear(&mut self) {
+        self.len = 0;
+    }
+
+    /// The knots pushed so far.
+    pub fn xs(&self) -> &[f32] {
+        &self.xs[..self.len]
+    }
+
+    /// The values pushed so far.
+    pub fn ys(&self) -> &[f32] {
+        &self.ys[..self.len]
+    }
+
+    /// Calculate the coefficients of the spline through the points pushed so
+    /// far. Writes len() - 1 segments to the front of coefficients and returns
+    /// that number.
+    pub fn finish(&self, coefficients: &mut [(f32, f32, f32, f32)]) -> Result<usize, Error> {
+        splinterpol_slices::<CAP>(
+            self.xs(),
+            self.ys(),
+            coefficients,
+            self.boundary,
+            self.solver,
+        )?;
+        Ok(self.len - 1)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn builder_matches_splinterpol() {
+        let xs = [0.5f32, 1f32, 2f32, 3f32, 4.5f32, 5f32, 6f32, 7f32];
+        let ys = [0f32, 0f32, 1f32, 2f32, 4f32, 7f32, 9f32, 10f32];
+        let mut expected = [(0f32, 0f32, 0f32, 0f32); 7];
+        crate::splinterpol::<8>(&xs, &ys, &mut expected).unwrap();
+
+        let mut builder = SplineBuilder::<16>::new();
+        for (x, y) in xs.iter().zip(&ys) {
+            builder.push(*x, *y).unwrap();
+        }
+        assert_eq!(8, builder.len());
+        let mut coeffs = [(0f32, 0f32, 0f32, 0f32); 15];
+        assert_eq!(Ok(7), builder.finish(&mut coeffs));
+        assert_eq!(expected, coeffs[..7]);
+    }
+
+    #[test]
+    fn builder_rejects_invalid_points() {
+        let mut builder = SplineBuilder::<2>::new();
+        builder.push(1.0, 0.0).unwrap();
+        assert_eq!(
+            Err(Error::NonIncreasingKnots { index: 1 }),
+            builder.push(1.0, 0.0)
+        );
+        builder.push(2.0, 0.0).unwrap();
+        assert_eq!(Err(Error::CapacityExceeded), builder.push(3.0, 0.0));
+        builder.clear();
+        assert!(builder.is_empty());
+    }
+
+    #[test]
+    fn builder_sorts_unsorted_points() {
+        let mut points = [(3f32, 2.0), (0.5, 0.0), (2.0, 1.0), (1.0, 0.0)];
+        let builder = SplineBuilder::<4>::from_unsorted(&mut points).unwrap();
+        assert_eq!([0.5, 1.0, 2.0, 3.0], builder.xs());
+        assert_eq!([0.0, 0.0, 1.0, 2.0], builder.ys());
+        let mut coeffs = [(0f32, 0f32, 0f32, 0f32); 3];
+        let mut expected = [(0f32, 0f32, 0f32, 0f32); 3];
+        crate::splinterpol(&[0.5, 1.0, 2.0, 3.0], &[0.0, 0.0, 1.0, 2.0], &mut expected).unwrap();
+        assert_eq!(Ok(3), builder.finish(&mut coeffs));
+        assert_eq!(expected, coeffs);
+
+        let mut duplicate = [(1f32, 0.0), (0.0, 0.0), (1.0, 1.0)];
+        assert_eq!(
+            Err(Error::NonIncreasingKnots { index: 2 }),
+            SplineBuilder::<4>::from_unsorted(&mut duplicate).map(|_| ())
+        );
+        assert_eq!(
+            Err(Error::CapacityExceeded),
+            SplineBuilder::<2>::from_unsorted(&mut points).map(|_| ())
+        );
+    }
+
+    #[test]
+    fn builder_merges_duplicates() {
+        let mut points = [(1f32, 4.0), (0.0, 0.0), (1.0, 2.0), (2.0, 1.0), (2.0, 5.0)];
+        let mut builder = SplineBuilder::<3>::new().with_duplicates(Duplicates::KeepLast, 0.0);
+        builder.extend_unsorted(&mut points).unwrap();
+        assert_eq!([0.0, 1.0, 2.0], builder.xs());
+        assert_eq!([0.0, 2.0, 5.0], builder.ys());
+        // A full builder still merges duplicates of its last knot
+        builder.push(2.0, 3.0).unwrap();
+        assert_eq!(3.0, builder.ys()[2]);
+        assert_eq!(Err(Error::CapacityExceeded), builder.push(3.0, 0.0));
+
+        let mut builder = SplineBuilder::<4>::new().with_duplicates(Duplicates::Average, 0.15);
+        for (x, y) in [
+            (0f32, 0f32),
+            (1.0, 1.0),
+            (1.05, 2.0),
+            (1.1, 6.0),
+            (1.2, 0.0),
+        ] {
+            builder.push(x, y).unwrap();
+        }
+        assert_eq!(3, builder.len());
+        assert!((builder.xs()[1] - 1.05).abs() < 1e-6);
+        assert!((builder.ys()[1] - 3.0).abs() < 1e-6);
+        assert_eq!(
+            Err(Error::NonIncreasingKnots { index: 3 }),
+            builder.push(1.1, 0.0)
+        );
+        let mut builder = SplineBuilder::<4>::new().with_duplicates(Duplicates::Error, 0.1);
+        builder.push(0.0, 0.0).unwrap();
+        assert_eq!(
+            Err(Error::NonIncreasingKnots { index: 1 }),
+            builder.push(0.05, 0.0)
+        );
+    }
+
+    fn build<const CAP: usize>(
+        builder: SplineBuilder<CAP>,
+        xs: &[f32],
+        f: impl Fn(f32) -> f32,
+        coefficients: &mut [(f32, f32, f32, f32)],
+    ) {
+        let mut builder = builder;
+        for x in xs {
+            builder.push(*x, f(*x)).unwrap();
+        }
+        builder.finish(coefficients).unwrap();
+    }
+
+    fn assert_reproduces(
+        coefficients: &[(f32, f32, f32, f32)],
+        xs: &[f32],
+        f: impl Fn(f32) -> f32,
+    ) {
+        for i in 0..coefficients.len() {
+            for step in 0..10 {
+                let t = (xs[i + 1] - xs[i]) * step as f32 / 10f32;
+                let y = crate::eval_segment(coefficients[i], t);
+                assert!(
+                    (y - f(xs[i] + t)).abs() < 0.001,
+                    "{} vs {}",
+                    y,
+                    f(xs[i] + t)
+                );
+            }
+        }
+    }
+
+    #[test]
+    fn clamped_reproduces_parabola() {
+        let xs = [0f32, 0.5, 2.0, 3.0, 4.5];
+        let mut coeffs = [(0f32, 0f32, 0f32, 0f32); 4];
+        let builder = SplineBuilder::<5>::new().with_boundary(Boundary::Clamped {
+            start: 0.0,
+            end: 9.0,
+        });
+        build(builder, &xs, |x| x * x, &mut coeffs);
+        assert_reproduces(&coeffs, &xs, |x| x * x);
+    }
+
+    #[test]
+    fn second_derivative_reproduces_parabola() {
+        let xs = [0f32, 0.5, 2.0, 3.0, 4.5, 5.0];
+        let mut coeffs = [(0f32, 0f32, 0f32, 0f32); 5];
+        let builder = SplineBuilder::<6>::new().with_boundary(Boundary::SecondDerivative {
+            start: 2.0,
+            end: 2.0,
+        });
+        build(builder, &xs, |x| x * x, &mut coeffs);
+        assert_reproduces(&coeffs, &xs, |x| x * x);
+    }
+
+    #[test]
+    fn not_a_knot_reproduces_cubic() {
+        let xs = [0f32, 0.5, 1.5, 2.0, 3.0, 3.5];
+        let cubic = |x: f32| x * x * x - 2f32 * x;
+        for solver in [Solver::Thomas, Solver::PartialPivoting] {
+            let mut coeffs = [(0f32, 0f32, 0f32, 0f32); 5];
+            let builder = SplineBuilder::<6>::new()
+                .with_boundary(Boundary::NotAKnot)
+                .with_solver(solver);
+            build(builder, &xs, cubic, &mut coeffs);
+            assert_reproduces(&coeffs, &xs, cubic);
+        }
+        let mut coeffs = [(0f32, 0f32, 0f32, 0f32); 3];
+        let builder = SplineBuilder::<4>::new().with_boundary(Boundary::NotAKnot);
+        build(builder, &xs[..4], cubic, &mut coeffs);
+        assert_reproduces(&coeffs, &xs[..4], cubic);
+    }
+
+    #[test]
+    fn periodic_wraps_smoothly() {
+        let xs = [0f32, 1.0, 2.5, 3.0, 4.0, 5.5, 6.0];
+        let ys = [0f32, 1.0, -1.0, 2.0, 0.5, -0.5, 0.0];
+        let mut builder = SplineBuilder::<7>::new().with_boundary(Boundary::Periodic);
+        for (x, y) in xs.iter().zip(&ys) {
+            builder.push(*x, *y).unwrap();
+        }
+        let mut coeffs = [(0f32, 0f32, 0f32, 0f32); 6];
+        builder.finish(&mut coeffs).unwrap();
+
+        let (a, b, c, d) = coeffs[5];
+        let h = xs[6] - xs[5];
+        assert!((a + b * h + c * h * h + d * h * h * h - ys[0]).abs() < 0.0001);
+        assert!((b + 2f32 * c * h + 3f32 * d * h * h - coeffs[0].1).abs() < 0.0001);
+        assert!((2f32 * c + 6f32 * d * h - 2f32 * coeffs[0].2).abs() < 0.0001);
+
+        builder.clear();
+        for (x, y) in xs.iter().zip(&[1f32, 0.0, 0.0, 0.0, 0.0, 0.0, 0.0]) {
+            builder.push(*x, *y).unwrap();
+        }
+        assert_eq!(Err(Error::NotPeriodic), builder.finish(&mut coeffs));
+    }
+}
@@ -0,0 +1,147 @@
+use crate::extrapolate::locate;
+use crate::thomas_algorithm::thomas_algorithm_symmetric;
+use crate::{check_increasing, check_spline, eval_segment, find_segment, Error, Extrapolate};
+
+/// Precomputed per-segment data for a fixed set of `N` knots: the segment
+/// start positions and the reciprocals `1/h_i` of the segment lengths.
+///
+/// Computing coefficients through the cache replaces every division by a
+/// segment length with a multiplication, which pays off on cores where
+/// floating point division takes many cycles and the same knots are
+/// interpolated repeatedly.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct SplineCache<const N: usize> {
+    xs: [f32; N],
+    inv_h: [f32; N],
+}
+
+impl<const N: usize> SplineCache<N> {
+    /// Build the cache for knots `xs`, which must be strictly increasing.
+    pub fn new(xs: &[f32; N]) -> Result<Self, Error> {
+        if N < 2 {
+            return Err(Error::InvalidSliceLength);
+        }
+        check_increasing(xs)?;
+        let mut inv_h = [0f32; N];
+        for i in 0..N - 1 {
+            inv_h[i] = 1f32 / (xs[i + 1] - xs[i]);
+        }
+        Ok(Self { xs: *xs, inv_h })
+    }
+
+    /// The knots this cache was built for.
+    pub fn xs(&self) -> &[f32; N] {
+        &self.xs
+    }
+
+    /// Reciprocal of the length of segment i.
+    pub fn inv_h(&self, i: usize) -> f32 {
+        self.inv_h[i]
+    }
+
+    /// Calculate the coefficients of the natural spline through the cached
+    /// knots and ys, like [`splinterpol`](crate::splinterpol).
+    pub fn splinterpol(
+        &self,
+        ys: &[f32; N],
+        coefficients: &mut [(f32, f32, f32, f32)],
+    ) -> Result<(), Error> {
+        if N < 3 || coefficients.len() != N - 1 {
+            return Err(Error::InvalidSliceLength);
+        }
+        let xs = &self.xs;
+        let h = |i: usize| xs[i + 1] - xs[i];
+        let slope = |i: usize| (ys[i + 1] - ys[i]) * self.inv_h[i];
+        const THIRD: f32 = 1f32 / 3f32;
+
+        // Array size const expression workaround
+        let mut diagonal = [0f32; N];
+        let diagonal = &mut diagonal[0..N - 2];
+        let mut r = [0f32; N];
+        let r = &mut r[0..N - 2];
+        let mut sub_diagonal = [0f32; N];
+        let sub_diagonal = &mut sub_diagonal[0..N - 3];
+        for i in 0..N - 2 {
+            diagonal[i] = 2f32 * (h(i) + h(i + 1));
+            r[i] = 3f32 * (slope(i + 1) - slope(i));
+        }
+        for (i, elem) in sub_diagonal.iter_mut().enumerate() {
+            *elem = h(i + 1);
+        }
+
+        let mut c = [0f32; N];
+        thomas_algorithm_symmetric(sub_diagonal, diagonal, r, &mut c[1..N - 1])?;
+
+        for (i, elem) in coefficients.iter_mut().enumerate() {
+            let b = slope(i) - (2f32 * c[i] + c[i + 1]) * THIRD * h(i);
+            let d = (c[i + 1] - c[i]) * THIRD * self.inv_h[i];
+            *elem = (ys[i], b, c[i], d);
+        }
+        Ok(())
+    }
+
+    /// Evaluate the spline with the given coefficients over the cached knots
+    /// at global position x. Positions outside of the knots are evaluated on
+    /// the first or last polynomial.
+    pub fn eval(&self, coefficients: &[(f32, f32, f32, f32)], x: f32) -> Result<f32, Error> {
+        self.eval_extrapolated(coefficients, x, Extrapolate::ExtendCubic)
+    }
+
+    /// Like [`eval`](SplineCache::eval), but positions outside of the knots
+    /// are extrapolated according to `extrapolate`.
+    pub fn eval_extrapolated(
+        &self,
+        coefficients: &[(f32, f32, f32, f32)],
+        x: f32,
+        extrapolate: Extrapolate,
+    ) -> Result<f32, Error> {
+        check_spline(coefficients, &self.xs)?;
+        let (polynomial, t) = locate(|segment| coefficients[segment], &self.xs, x, extrapolate)?;
+        Ok(eval_segment(polynomial, t))
+    }
+
+    /// Segment containing x and the position of x within it, normalized to
+    /// `[0, 1]` for positions between the knots.
+    pub fn locate(&self, x: f32) -> (usize, f32) {
+        let segment = find_segment(&self.xs, x);
+        (segment, (x - self.xs[segment]) * self.inv_h[segment])
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn cached_splinterpol_matches() {
+        let xs = [
+            0.5f32, 1f32, 2f32, 3f32, 4.5f32, 5f32, 6f32, 7f32, 8f32, 9f32, 10f32, 11.5f32, 12f32,
+            13f32, 14f32, 15f32,
+        ];
+        let ys = [
+            0f32, 0f32, 1f32, 2f32, 4f32, 7f32, 9f32, 10f32, 8f32, 6f32, 3f32, 2f32, 2f32, 1f32,
+            1f32, 0f32,
+        ];
+        let mut expected = [(0f32, 0f32, 0f32, 0f32); 15];
+        crate::splinterpol::<16>(&xs, &ys, &mut expected).unwrap();
+
+        let cache = SplineCache::new(&xs).unwrap();
+        let mut coeffs = [(0f32, 0f32, 0f32, 0f32); 15];
+        cache.splinterpol(&ys, &mut coeffs).unwrap();
+        for (c, e) in coeffs.iter().zip(&expected) {
+            assert!((c.1 - e.1).abs() < 0.0001);
+            assert!((c.2 - e.2).abs() < 0.0001);
+            assert!((c.3 - e.3).abs() < 0.0001);
+        }
+        assert!((cache.eval(&coeffs, 4.5).unwrap() - 4.0).abs() < 0.0001);
+        assert_eq!((4, 0.5), cache.locate(4.75));
+    }
+
+    #[test]
+    fn cache_rejects_unsorted_knots() {
+        assert_eq!(
+            Err(Error::NonIncreasingKnots { index: 2 }),
+            SplineCache::new(&[0f32, 1f32, 1f32, 2f32])
+        );
+    }
+}
@@ -0,0 +1,150 @@
+use crate::{check_spline, h, hermite, splinterpol_slices, Boundary, Error, Solver};
+
+/// Write the coefficients of the derivative of the spline given by
+/// `coefficients` and the knots `xs` into `out_coefficients`.
+///
+/// The derivative of a cubic segment is quadratic, so the cubic coefficient of
+/// every output segment is zero. The result is a spline on the same knots like
+/// any other, and can be evaluated, plotted, searched for roots or quantized
+/// with the same functions, e.g. to inspect the velocity along a position
+/// profile.
+///
+/// ```
+/// use cubic_splinterpol::differentiate;
+///
+/// let xs = [0f32, 1.0];
+/// let coefficients = [(1f32, 2f32, 3f32, 4f32)];
+/// let mut derivative = [(0f32, 0f32, 0f32, 0f32)];
+/// differentiate(&coefficients, &xs, &mut derivative).unwrap();
+/// assert_eq!([(2.0, 6.0, 12.0, 0.0)], derivative);
+/// ```
+pub fn differentiate(
+    coefficients: &[(f32, f32, f32, f32)],
+    xs: &[f32],
+    out_coefficients: &mut [(f32, f32, f32, f32)],
+) -> Result<(), Error> {
+    check_spline(coefficients, xs)?;
+    if out_coefficients.len() != coefficients.len() {
+        return Err(Error::InvalidSliceLength);
+    }
+    for ((_, b, c, d), out) in coefficients.iter().zip(out_coefficients) {
+        *out = (*b, 2f32 * c, 3f32 * d, 0f32);
+    }
+    Ok(())
+}
+
+/// Calculate the coefficients of the integral of a rate sampled at the knots
+/// `xs`, starting at `initial` at the first knot, e.g. an angle from the
+/// samples of a gyro.
+///
+/// The rates are interpolated by the not-a-knot spline, or the natural one for
+/// 3 knots, whose integral over every segment is exact; the result is the
+/// cubic through these integrals with the sampled `rates` as its derivatives,
+/// C1 everywhere. Like [`splinterpol`](crate::splinterpol), it needs at least
+/// 3 knots and writes the first `N - 1` elements of `coefficients`.
+///
+/// ```
+/// use cubic_splinterpol::splinterpol_from_derivative;
+///
+/// // Turning at a constant 2 rad/s for 2 s
+/// let xs = [0f32, 0.5, 1.0, 2.0];
+/// let mut coefficients = [(0f32, 0f32, 0f32, 0f32); 3];
+/// splinterpol_from_derivative(&xs, &[2.0; 4], 1.0, &mut coefficients).unwrap();
+/// assert_eq!((1.0, 2.0, 0.0, 0.0), coefficients[0]);
+/// assert_eq!((3.0, 2.0, 0.0, 0.0), coefficients[2]);
+/// ```
+pub fn splinterpol_from_derivative<const N: usize>(
+    xs: &[f32; N],
+    rates: &[f32; N],
+    initial: f32,
+    coefficients: &mut [(f32, f32, f32, f32)],
+) -> Result<(), Error> {
+    // The spline of the rates first, replaced segment by segment below
+    let boundary = if N >= 4 {
+        Boundary::NotAKnot
+    } else {
+        Boundary::Natural
+    };
+    splinterpol_slices::<N>(xs, rates, coefficients, boundary, Solver::Thomas)?;
+    let mut value = initial;
+    for (i, elem) in coefficients[..N - 1].iter_mut().enumerate() {
+        let (a, b, c, d) = *elem;
+        let h = h(i, xs);
+        let integral = h * (a + h * (b / 2f32 + h * (c / 3f32 + h * d / 4f32)));
+        *elem = hermite(value, value + integral, rates[i], rates[i + 1], h);
+        value += integral;
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{eval, eval_segment, Extrapolate};
+
+    #[test]
+    fn derivative_matches_finite_differences() {
+        let xs = [0f32, 1.0, 2.5, 3.0, 4.0];
+        let ys = [1f32, 2.0, 0.5, 3.0, 2.0];
+        let mut coeffs = [(0f32, 0f32, 0f32, 0f32); 4];
+        crate::splinterpol(&xs, &ys, &mut coeffs).unwrap();
+        let mut derivative = [(0f32, 0f32, 0f32, 0f32); 4];
+        differentiate(&coeffs, &xs, &mut derivative).unwrap();
+
+        let dx = 1e-3;
+        for i in 1..40 {
+            let x = i as f32 / 10f32;
+            let y = |x| eval(&coeffs, &xs, x, Extrapolate::ExtendCubic).unwrap();
+            let slope = (y(x + dx) - y(x - dx)) / (2f32 * dx);
+            let value = eval(&derivative, &xs, x, Extrapolate::ExtendCubic).unwrap();
+            assert!((slope - value).abs() < 0.01, "{} vs {}", slope, value);
+        }
+        // The derivative of a spline is continuous
+        for i in 0..3 {
+            let end = eval_segment(derivative[i], xs[i + 1] - xs[i]);
+            assert!((end - derivative[i + 1].0).abs() < 1e-5);
+        }
+    }
+
+    #[test]
+    fn differentiate_checks_lengths() {
+        let coeffs = [(0f32, 1f32, 0f32, 0f32); 2];
+        let mut out = [(0f32, 0f32, 0f32, 0f32); 1];
+        assert_eq!(
+            Err(Error::InvalidSliceLength),
+            differentiate(&coeffs, &[0f32, 1.0, 2.0], &mut out)
+        );
+        assert_eq!(
+            Err(Error::InvalidSliceLength),
+            differentiate(&coeffs, &[0f32, 1.0], &mut out)
+        );
+    }
+
+    #[test]
+    fn integral_of_sampled_rates() {
+        let mut xs = [0f32; 9];
+        let mut rates = [0f32; 9];
+        for i in 0..9 {
+            xs[i] = i as f32 * 0.4;
+            rates[i] = libm::cosf(xs[i]);
+        }
+        let mut coeffs = [(0f32, 0f32, 0f32, 0f32); 8];
+        splinterpol_from_derivative(&xs, &rates, 0.5, &mut coeffs).unwrap();
+        for i in 0..8 {
+            assert!((coeffs[i].0 - 0.5 - libm::sinf(xs[i])).abs() < 1e-3);
+            assert_eq!(rates[i], coeffs[i].1);
+            let h = xs[i + 1] - xs[i];
+            let slope = crate::eval_segment_derivative(coeffs[i], h);
+            assert!((slope - rates[i + 1]).abs() < 1e-5);
+        }
+        for i in 0..=32 {
+            let x = i as f32 * 0.1;
+            let y = eval(&coeffs, &xs, x, Extrapolate::Error).unwrap();
+            assert!((y - 0.5 - libm::sinf(x)).abs() < 1e-3, "{} at {}", y, x);
+        }
+        assert_eq!(
+            Err(Error::InvalidSliceLength),
+            splinterpol_from_derivative(&[0f32, 1.0], &[1f32, 1.0], 0.0, &mut coeffs)
+        );
+    }
+}
@@ -0,0 +1,202 @@
+//! Calibration curves mapping raw ADC codes to a physical quantity, e.g. the
+//! temperature of an NTC thermistor in a voltage divider, and back.
+//!
+//! Instead of evaluating the Steinhart–Hart equation at runtime, the curve is
+//! interpolated through a table of `(code, value)` pairs, taken from the
+//! thermistor datasheet or measured against a reference. The interpolant is a
+//! monotone cubic, so the curve has no spurious wiggles between the table
+//! entries and every value belongs to exactly one code.
+
+use core::convert::TryFrom;
+
+use crate::monotone::monotone_slices;
+use crate::{eval_segment, find_segment, inverse_eval, Error};
+
+/// A calibration curve through `N` table entries.
+///
+/// ```
+/// use cubic_splinterpol::calibration::Calibration;
+///
+/// // 10k NTC, B = 3950, below a 10k resistor on a 12 bit ADC: °C per code
+/// let ntc = Calibration::new(&[
+///     (311u16, 100f32),
+///     (609, 75.0),
+///     (1146, 50.0),
+///     (2048, 25.0),
+///     (3063, 0.0),
+///     (3724, -25.0),
+/// ])
+/// .unwrap();
+/// assert_eq!(Ok(25.0), ntc.value(2048));
+/// // Centidegrees as Q0 and Q16.16 fixed-point
+/// assert_eq!(Ok(2500), ntc.value_scaled(2048, 100));
+/// assert_eq!(Ok(25 << 16), ntc.value_fixed::<16>(2048));
+/// assert_eq!(Ok(1146), ntc.code(50.0));
+/// // Open or shorted sensor
+/// assert!(ntc.value(4095).is_err());
+/// ```
+#[derive(Debug, Clone)]
+pub struct Calibration<const N: usize> {
+    codes: [f32; N],
+    values: [f32; N],
+    /// N-1 of N used
+    coefficients: [(f32, f32, f32, f32); N],
+}
+
+impl<const N: usize> Calibration<N> {
+    /// A curve through the table `(code, value)`, with strictly increasing
+    /// codes and strictly increasing or strictly decreasing values, as for the
+    /// falling curve of an NTC. At least 2 entries are needed.
+    ///
+    /// Fails with [`Error::InvalidRange`] if the values are not strictly
+    /// monotone.
+    pub fn new(table: &[(u16, f32); N]) -> Result<Self, Error> {
+        if N < 2 {
+            return Err(Error::InvalidSliceLength);
+        }
+        let mut codes = [0f32; N];
+        let mut values = [0f32; N];
+        for (i, (code, value)) in table.iter().enumerate() {
+            codes[i] = f32::from(*code);
+            values[i] = *value;
+        }
+        let increasing = values.windows(2).all(|pair| pair[0] < pair[1]);
+        let decreasing = values.windows(2).all(|pair| pair[0] > pair[1]);
+        if !increasing && !decreasing {
+            return Err(Error::InvalidRange);
+        }
+        let mut coefficients = [(0f32, 0f32, 0f32, 0f32); N];
+        monotone_slices(&codes, &values, &mut coefficients[..N - 1])?;
+        Ok(Self {
+            codes,
+            values,
+            coefficients,
+        })
+    }
+
+    /// Value at `code`. Codes outside of the table fail with
+    /// [`Error::OutOfRange`], which usually means a broken sensor or wire.
+    pub fn value(&self, code: u16) -> Result<f32, Error> {
+        let x = f32::from(code);
+        if x < self.codes[0] || x > self.codes[N - 1] {
+            return Err(Error::OutOfRange);
+        }
+        let segment = find_segment(&self.codes, x);
+        Ok(eval_segment(
+            self.coefficients[segment],
+            x - self.codes[segment],
+        ))
+    }
+
+    /// Value at `code` multiplied by `scale` and rounded, e.g. with a scale
+    /// of 100 for a temperature in centidegrees. Fails with
+    /// [`Error::Overflow`] if the result does not fit into an `i32`.
+    pub fn value_scaled(&self, code: u16, scale: i32) -> Result<i32, Error> {
+        to_i32(self.value(code)? * scale as f32)
+    }
+
+    /// Value at `code` in signed fixed-point with `FRAC` fractional bits, e.g.
+    /// `value_fixed::<16>` for Q16.16. Fails with [`Error::Overflow`] if the
+    /// result does not fit into an `i32`.
+    pub fn value_fixed<const FRAC: u32>(&self, code: u16) -> Result<i32, Error> {
+        let one = libm::ldexpf(1f32, i32::try_from(FRAC).map_err(|_| Error::Overflow)?);
+        to_i32(self.value(code)? * one)
+    }
+
+    /// Code closest to where the curve takes `value`, e.g. the ADC threshold
+    /// of an over-temperature comparator. Values outside of the table fail
+    /// with [`Error::OutOfRange`].
+    pub fn code(&self, value: f32) -> Result<u16, Error> {
+        let (first, last) = (self.values[0], self.values[N - 1]);
+        let (low, high) = if first < last {
+            (first, last)
+        } else {
+            (last, first)
+        };
+        if value.is_nan() || value < low || value > high {
+            return Err(Error::OutOfRange);
+        }
+        // The end of the last segment may be off from the table by rounding
+        let end = eval_segment(
+            self.coefficients[N - 2],
+            self.codes[N - 1] - self.codes[N - 2],
+        );
+        let value = value.clamp(first.min(end), first.max(end));
+        let x = inverse_eval(&self.coefficients[..N - 1], &self.codes, value)
+            .ok_or(Error::OutOfRange)?;
+        // Within the codes of the table, so a valid u16
+        Ok(libm::roundf(x).clamp(self.codes[0], self.codes[N - 1]) as u16)
+    }
+}
+
+/// Round to the nearest i32, failing on NaN and values out of its range.
+fn to_i32(value: f32) -> Result<i32, Error> {
+    let rounded = libm::roundf(value);
+    // i32::MAX is not representable, i32::MIN is
+    if (-2_147_483_648f32..2_147_483_648f32).contains(&rounded) {
+        Ok(rounded as i32)
+    } else {
+        Err(Error::Overflow)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const NTC: [(u16, f32); 6] = [
+        (311, 100.0),
+        (609, 75.0),
+        (1146, 50.0),
+        (2048, 25.0),
+        (3063, 0.0),
+        (3724, -25.0),
+    ];
+
+    #[test]
+    fn forward_and_inverse() {
+        let ntc = Calibration::new(&NTC).unwrap();
+        for (code, value) in NTC {
+            assert!((ntc.value(code).unwrap() - value).abs() < 1e-4);
+            assert_eq!(Ok(code), ntc.code(value));
+        }
+        let mut previous = f32::INFINITY;
+        for code in 311..=3724 {
+            let value = ntc.value(code).unwrap();
+            assert!(value < previous);
+            previous = value;
+            let back = ntc.code(value).unwrap();
+            assert!((i32::from(back) - i32::from(code)).abs() <= 1);
+        }
+        assert_eq!(Err(Error::OutOfRange), ntc.value(310));
+        assert_eq!(Err(Error::OutOfRange), ntc.code(101.0));
+        assert_eq!(Err(Error::OutOfRange), ntc.code(f32::NAN));
+    }
+
+    #[test]
+    fn fixed_point_output() {
+        let ntc = Calibration::new(&NTC).unwrap();
+        assert_eq!(Ok(-2500), ntc.value_scaled(3724, 100));
+        assert_eq!(Ok(0), ntc.value_fixed::<16>(3063));
+        assert_eq!(Ok(100), ntc.value_fixed::<0>(311));
+        let value = ntc.value(1500).unwrap();
+        assert_eq!(
+            Ok(libm::roundf(value * 1024f32) as i32),
+            ntc.value_fixed::<10>(1500)
+        );
+        assert_eq!(Err(Error::Overflow), ntc.value_fixed::<25>(311));
+        assert_eq!(Err(Error::Overflow), ntc.value_scaled(311, i32::MAX));
+    }
+
+    #[test]
+    fn rejects_non_monotone_table() {
+        assert_eq!(
+            Err(Error::InvalidRange),
+            Calibration::new(&[(0u16, 0f32), (10, 5.0), (20, 5.0)]).map(|_| ())
+        );
+        assert_eq!(
+            Err(Error::NonIncreasingKnots { index: 1 }),
+            Calibration::new(&[(10u16, 0f32), (10, 5.0)]).map(|_| ())
+        );
+    }
+}
@@ -0,0 +1,102 @@
+/// Compute the coefficients of the natural spline through xs and ys at
+/// compile time, so that a spline through known calibration points can be
+/// stored as a `static` coefficient table in flash:
+///
+/// ```
+/// use cubic_splinterpol::splinterpol_const;
+///
+/// static XS: [f32; 6] = [0.0, 1.0, 2.0, 3.0, 4.0, 5.0];
+/// static COEFFICIENTS: [(f32, f32, f32, f32); 5] =
+///     splinterpol_const(&XS, &[0.0, 1.0, 4.0, 9.0, 16.0, 25.0]);
+/// ```
+///
+/// `M` must be `N - 1` and the knots must be strictly increasing; otherwise
+/// this panics, which in a const context is a compile error. The arithmetic
+/// is the same as in [`splinterpol`](crate::splinterpol), so both produce
+/// identical tables.
+pub const fn splinterpol_const<const N: usize, const M: usize>(
+    xs: &[f32; N],
+    ys: &[f32; N],
+) -> [(f32, f32, f32, f32); M] {
+    assert!(N >= 3, "at least three points are needed");
+    assert!(M + 1 == N, "M must be N - 1");
+    let mut i = 0;
+    while i < M {
+        assert!(xs[i] < xs[i + 1], "knots must be strictly increasing");
+        i += 1;
+    }
+
+    // Interior system for c[1..N - 1]
+    let n = N - 2;
+    let mut main = [0f32; N];
+    let mut r = [0f32; N];
+    let mut i = 0;
+    while i < n {
+        main[i] = 2f32 * ((xs[i + 1] - xs[i]) + (xs[i + 2] - xs[i + 1]));
+        let div1 = (ys[i + 2] - ys[i + 1]) / (xs[i + 2] - xs[i + 1]);
+        let div2 = (ys[i + 1] - ys[i]) / (xs[i + 1] - xs[i]);
+        r[i] = 3f32 * (div1 - div2);
+        i += 1;
+    }
+
+    // Thomas algorithm, sub-diagonal entry i is h(i + 1)
+    let mut i = 1;
+    while i < n {
+        let sub = xs[i + 1] - xs[i];
+        let mc = sub / main[i - 1];
+        main[i] -= mc * sub;
+        r[i] -= mc * r[i - 1];
+        i += 1;
+    }
+    let mut c = [0f32; N];
+    c[n] = r[n - 1] / main[n - 1];
+    let mut i = n - 1;
+    while i > 0 {
+        let sub = xs[i + 1] - xs[i];
+        c[i] = (r[i - 1] - sub * c[i + 1]) / main[i - 1];
+        i -= 1;
+    }
+
+    let mut coefficients = [(0f32, 0f32, 0f32, 0f32); M];
+    let mut i = 0;
+    while i < M {
+        let h = xs[i + 1] - xs[i];
+        let div_1 = (ys[i + 1] - ys[i]) / h;
+        let div_2 = (2f32 * c[i] + c[i + 1]) / 3f32;
+        let b = div_1 - div_2 * h;
+        let d = (c[i + 1] - c[i]) / (3f32 * h);
+        coefficients[i] = (ys[i], b, c[i], d);
+        i += 1;
+    }
+    coefficients
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const XS: [f32; 16] = [
+        0.5f32, 1f32, 2f32, 3f32, 4.5f32, 5f32, 6f32, 7f32, 8f32, 9f32, 10f32, 11.5f32, 12f32,
+        13f32, 14f32, 15f32,
+    ];
+    const YS: [f32; 16] = [
+        0f32, 0f32, 1f32, 2f32, 4f32, 7f32, 9f32, 10f32, 8f32, 6f32, 3f32, 2f32, 2f32, 1f32, 1f32,
+        0f32,
+    ];
+    static COEFFICIENTS: [(f32, f32, f32, f32); 15] = splinterpol_const(&XS, &YS);
+
+    #[test]
+    fn const_matches_runtime() {
+        let mut expected = [(0f32, 0f32, 0f32, 0f32); 15];
+        crate::splinterpol::<16>(&XS, &YS, &mut expected).unwrap();
+        assert_eq!(expected, COEFFICIENTS);
+    }
+
+    #[test]
+    fn const_three_points() {
+        const C: [(f32, f32, f32, f32); 2] =
+            splinterpol_const(&[0f32, 1f32, 2f32], &[0f32, 1f32, 0f32]);
+        assert_eq!(0f32, C[0].2);
+        assert!((crate::eval_segment(C[1], 1.0)).abs() < 0.0001);
+    }
+}
@@ -0,0 +1,223 @@
+use crate::thomas_algorithm::thomas_algorithm;
+use crate::{check_increasing, h, hermite, splinterpol_slices, Boundary, Error, Solver};
+
+/// Calculate the coefficients of the spline through xs and ys with the first
+/// derivative prescribed at selected knots, e.g. zero slope on a known plateau.
+///
+/// `slopes` holds pairs `(index, slope)` of a knot index and its derivative,
+/// in any order; if an index appears more than once, the last slope counts.
+/// A constraint at the first or last knot clamps that end, the other ends are
+/// natural. The spline is C2 at every free interior knot and C1 at the
+/// constrained ones, where the sections on either side are independent.
+///
+/// ```
+/// use cubic_splinterpol::splinterpol_with_slopes;
+///
+/// let xs = [0f32, 1.0, 2.0, 3.0, 4.0];
+/// let ys = [0f32, 2.0, 2.5, 1.0, 0.0];
+/// let mut coefficients = [(0f32, 0f32, 0f32, 0f32); 4];
+/// // Flat at the top, starting at rest
+/// splinterpol_with_slopes(&xs, &ys, &mut coefficients, &[(2, 0.0), (0, 0.0)]).unwrap();
+/// assert_eq!(0.0, coefficients[0].1);
+/// assert_eq!(0.0, coefficients[2].1);
+/// ```
+pub fn splinterpol_with_slopes<const N: usize>(
+    xs: &[f32; N],
+    ys: &[f32; N],
+    coefficients: &mut [(f32, f32, f32, f32)],
+    slopes: &[(usize, f32)],
+) -> Result<(), Error> {
+    if N < 2 || coefficients.len() < N - 1 {
+        return Err(Error::InvalidSliceLength);
+    }
+    check_increasing(xs)?;
+    let secant = |i: usize| (ys[i + 1] - ys[i]) / h(i, xs);
+
+    // Tridiagonal system for the derivatives m at the knots: continuity of the
+    // second derivative at interior knots, zero second derivative at the ends
+    let mut lower = [0f32; N];
+    let mut main = [0f32; N];
+    let mut upper = [0f32; N];
+    let mut r = [0f32; N];
+    main[0] = 2f32;
+    upper[0] = 1f32;
+    r[0] = 3f32 * secant(0);
+    for i in 1..N - 1 {
+        let (before, after) = (h(i - 1, xs), h(i, xs));
+        lower[i - 1] = after;
+        main[i] = 2f32 * (before + after);
+        upper[i] = before;
+        r[i] = 3f32 * (after * secant(i - 1) + before * secant(i));
+    }
+    lower[N - 2] = 1f32;
+    main[N - 1] = 2f32;
+    r[N - 1] = 3f32 * secant(N - 2);
+
+    // Constrained rows become m_i = slope, which splits the system
+    for (index, slope) in slopes {
+        let i = *index;
+        if i >= N {
+            return Err(Error::OutOfRange);
+        }
+        if i > 0 {
+            lower[i - 1] = 0f32;
+        }
+        main[i] = 1f32;
+        if i < N - 1 {
+            upper[i] = 0f32;
+        }
+        r[i] = *slope;
+    }
+
+    let mut m = [0f32; N];
+    thomas_algorithm(&lower[..N - 1], &mut main, &upper[..N - 1], &mut r, &mut m)?;
+    for (i, elem) in coefficients[..N - 1].iter_mut().enumerate() {
+        *elem = hermite(ys[i], ys[i + 1], m[i], m[i + 1], h(i, xs));
+    }
+    Ok(())
+}
+
+/// Calculate the coefficients of the spline through xs and ys with corners at
+/// the knots listed in `corners`, e.g. waypoints of a robot path where the
+/// direction changes sharply.
+///
+/// At a corner, only the value is continuous: the knots between two corners
+/// get an independent natural spline each, or a straight line between two
+/// adjacent ones. The result is a single coefficient table on all knots, for
+/// evaluation and plotting like any other spline. Corners may be listed in any
+/// order, and listing the first or last knot has no effect.
+///
+/// ```
+/// use cubic_splinterpol::splinterpol_with_corners;
+///
+/// // A tent: rising, then falling
+/// let xs = [0f32, 1.0, 2.0, 3.0, 4.0];
+/// let ys = [0f32, 1.0, 2.0, 1.0, 0.0];
+/// let mut coefficients = [(0f32, 0f32, 0f32, 0f32); 4];
+/// splinterpol_with_corners(&xs, &ys, &mut coefficients, &[2]).unwrap();
+/// assert_eq!((0.0, 1.0, 0.0, 0.0), coefficients[0]);
+/// assert_eq!((2.0, -1.0, 0.0, 0.0), coefficients[2]);
+/// ```
+pub fn splinterpol_with_corners<const N: usize>(
+    xs: &[f32; N],
+    ys: &[f32; N],
+    coefficients: &mut [(f32, f32, f32, f32)],
+    corners: &[usize],
+) -> Result<(), Error> {
+    if N < 2 || coefficients.len() < N - 1 {
+        return Err(Error::InvalidSliceLength);
+    }
+    if corners.iter().any(|corner| *corner >= N) {
+        return Err(Error::OutOfRange);
+    }
+    check_increasing(xs)?;
+    let mut start = 0;
+    for end in 1..N {
+        if end < N - 1 && !corners.contains(&end) {
+            continue;
+        }
+        if end - start == 1 {
+            let slope = (ys[end] - ys[start]) / h(start, xs);
+            coefficients[start] = (ys[start], slope, 0f32, 0f32);
+        } else {
+            splinterpol_slices::<N>(
+                &xs[start..=end],
+                &ys[start..=end],
+                &mut coefficients[start..end],
+                Boundary::Natural,
+                Solver::Thomas,
+            )?;
+        }
+        start = end;
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{eval_segment, eval_segment_derivative, eval_segment_second_derivative};
+
+    const XS: [f32; 6] = [0f32, 1.0, 2.5, 3.0, 4.0, 5.5];
+    const YS: [f32; 6] = [1f32, 2.0, 0.5, 3.0, 2.0, 2.5];
+
+    #[test]
+    fn without_constraints_is_natural_spline() {
+        let mut natural = [(0f32, 0f32, 0f32, 0f32); 5];
+        crate::splinterpol(&XS, &YS, &mut natural).unwrap();
+        let mut coeffs = [(0f32, 0f32, 0f32, 0f32); 5];
+        splinterpol_with_slopes(&XS, &YS, &mut coeffs, &[]).unwrap();
+        for (lhs, rhs) in natural.iter().zip(&coeffs) {
+            assert!((lhs.0 - rhs.0).abs() < 1e-5);
+            assert!((lhs.1 - rhs.1).abs() < 1e-4);
+            assert!((lhs.2 - rhs.2).abs() < 1e-4);
+            assert!((lhs.3 - rhs.3).abs() < 1e-4);
+        }
+
+        // Clamped ends as with the boundary condition
+        let boundary = crate::Boundary::Clamped {
+            start: 1.0,
+            end: -0.5,
+        };
+        let mut clamped = [(0f32, 0f32, 0f32, 0f32); 5];
+        crate::splinterpol_with_boundary(&XS, &YS, &mut clamped, boundary).unwrap();
+        splinterpol_with_slopes(&XS, &YS, &mut coeffs, &[(5, -0.5), (0, 1.0)]).unwrap();
+        for (lhs, rhs) in clamped.iter().zip(&coeffs) {
+            assert!((lhs.1 - rhs.1).abs() < 1e-4);
+            assert!((lhs.3 - rhs.3).abs() < 1e-4);
+        }
+    }
+
+    #[test]
+    fn interior_constraints() {
+        let mut coeffs = [(0f32, 0f32, 0f32, 0f32); 5];
+        splinterpol_with_slopes(&XS, &YS, &mut coeffs, &[(2, 0.0), (3, 4.0)]).unwrap();
+        assert_eq!(0.0, coeffs[2].1);
+        assert_eq!(4.0, coeffs[3].1);
+        for i in 0..5 {
+            let h = XS[i + 1] - XS[i];
+            assert!((eval_segment(coeffs[i], h) - YS[i + 1]).abs() < 1e-5);
+            if i < 4 {
+                let slope = eval_segment_derivative(coeffs[i], h);
+                assert!((slope - coeffs[i + 1].1).abs() < 1e-4);
+            }
+        }
+        // C2 at the free knots 1 and 4
+        for i in [0, 3] {
+            let h = XS[i + 1] - XS[i];
+            let curvature = eval_segment_second_derivative(coeffs[i], h);
+            let next = eval_segment_second_derivative(coeffs[i + 1], 0f32);
+            assert!((curvature - next).abs() < 1e-3);
+        }
+        assert_eq!(
+            Err(Error::OutOfRange),
+            splinterpol_with_slopes(&XS, &YS, &mut coeffs, &[(6, 0.0)])
+        );
+    }
+
+    #[test]
+    fn corners_split_into_sections() {
+        let mut coeffs = [(0f32, 0f32, 0f32, 0f32); 5];
+        splinterpol_with_corners(&XS, &YS, &mut coeffs, &[3]).unwrap();
+        let mut left = [(0f32, 0f32, 0f32, 0f32); 3];
+        crate::splinterpol(&[0f32, 1.0, 2.5, 3.0], &[1f32, 2.0, 0.5, 3.0], &mut left).unwrap();
+        let mut right = [(0f32, 0f32, 0f32, 0f32); 2];
+        crate::splinterpol(&[3f32, 4.0, 5.5], &[3f32, 2.0, 2.5], &mut right).unwrap();
+        assert_eq!(left, coeffs[..3]);
+        assert_eq!(right, coeffs[3..]);
+
+        // Without corners, the natural spline
+        let mut natural = [(0f32, 0f32, 0f32, 0f32); 5];
+        crate::splinterpol(&XS, &YS, &mut natural).unwrap();
+        splinterpol_with_corners(&XS, &YS, &mut coeffs, &[0, 5]).unwrap();
+        assert_eq!(natural, coeffs);
+
+        // Adjacent corners give a line in between
+        splinterpol_with_corners(&XS, &YS, &mut coeffs, &[2, 1]).unwrap();
+        assert_eq!((2.0, -1.0, 0.0, 0.0), coeffs[1]);
+        assert_eq!(
+            Err(Error::OutOfRange),
+            splinterpol_with_corners(&XS, &YS, &mut coeffs, &[6])
+        );
+    }
+}
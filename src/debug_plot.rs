@@ -0,0 +1,89 @@
+use std::path::Path;
+
+use plotters::prelude::*;
+
+use crate::plot_spline::{sample_uniform, uniform_x};
+use crate::{check_spline, eval_segment, Error};
+
+/// Render the spline to a PNG at `path` for inspection during host-side
+/// development. The curve is sampled at `resolution` points from the first to
+/// the last knot, and the knots are marked with circles. Fails with
+/// [`Error::InvalidLimit`](crate::Error::InvalidLimit) for a `resolution`
+/// below 2, which leaves no curve to scale the plot to.
+pub fn debug_plot<P: AsRef<Path>>(
+    path: P,
+    coefficients: &[(f32, f32, f32, f32)],
+    xs: &[f32],
+    resolution: usize,
+) -> Result<(), Box<dyn std::error::Error>> {
+    check_spline(coefficients, xs)?;
+    if resolution < 2 {
+        return Err(Error::InvalidLimit.into());
+    }
+
+    let first = xs[0];
+    let last = xs[xs.len() - 1];
+    let mut samples = Vec::with_capacity(resolution);
+    sample_uniform(coefficients, xs, resolution, |index, y| {
+        samples.push((uniform_x(xs, resolution, index), y))
+    });
+
+    let (y_min, y_max) = samples
+        .iter()
+        .fold((f32::INFINITY, f32::NEG_INFINITY), |(min, max), (_, y)| {
+            (min.min(*y), max.max(*y))
+        });
+    let margin = ((y_max - y_min) * 0.05).max(f32::EPSILON);
+
+    let root = BitMapBackend::new(path.as_ref(), (640, 480)).into_drawing_area();
+    root.fill(&WHITE)?;
+    let mut chart = ChartBuilder::on(&root)
+        .caption("spline", ("sans-serif", 50).into_font())
+        .margin(5)
+        .x_label_area_size(30)
+        .y_label_area_size(30)
+        .build_cartesian_2d(first..last, (y_min - margin)..(y_max + margin))?;
+
+    chart.configure_mesh().draw()?;
+
+    chart.draw_series(LineSeries::new(samples, RED))?;
+    let last_segment = coefficients[coefficients.len() - 1];
+    let last_y = eval_segment(last_segment, last - xs[xs.len() - 2]);
+    chart.draw_series(
+        xs.iter()
+            .zip(coefficients.iter().map(|c| c.0).chain(Some(last_y)))
+            .map(|(x, y)| Circle::new((*x, y), 3, BLUE.filled())),
+    )?;
+
+    root.present()?;
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn debug_plot_writes_png() {
+        let xs = [0.1, 0.8, 1.6, 3.1, 4.2, 4.6, 5.5, 7.0];
+        let ys = [0.4, 1.2, 6.0, 6.0, 5.0, 2.4, 0.5, 0.4];
+        let mut coeffs = [(0f32, 0f32, 0f32, 0f32); 7];
+        crate::splinterpol::<8>(&xs, &ys, &mut coeffs).unwrap();
+
+        let path = std::env::temp_dir().join("cubic-splinterpol-debug-plot.png");
+        debug_plot(&path, &coeffs, &xs, 500).unwrap();
+        assert!(std::fs::metadata(&path).unwrap().len() > 0);
+    }
+
+    #[test]
+    fn debug_plot_rejects_degenerate_resolutions() {
+        let xs = [0f32, 1.0, 2.0];
+        let coeffs = [(0f32, 1f32, 0f32, 0f32), (1.0, 1.0, 0.0, 0.0)];
+        let path = std::env::temp_dir().join("cubic-splinterpol-debug-plot-degenerate.png");
+        for resolution in [0, 1] {
+            let error = debug_plot(&path, &coeffs, &xs, resolution).unwrap_err();
+            assert_eq!(Some(&Error::InvalidLimit), error.downcast_ref::<Error>());
+        }
+        debug_plot(&path, &coeffs, &xs, 2).unwrap();
+    }
+}
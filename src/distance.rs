@@ -0,0 +1,118 @@
+use crate::extrema::critical_points;
+use crate::{check_spline, eval_segment, for_each_difference, Error};
+
+/// How far apart the spline given by `first_coefficients` and `first_xs` and
+/// the one given by `second_coefficients` and `second_xs` are over the
+/// overlap of both ranges of knots, e.g. to detect the drift of a fresh
+/// calibration from the stored one. Returns the L2 norm of the difference
+/// and its largest magnitude.
+///
+/// Between every two consecutive knots of either spline, the difference is a
+/// single cubic, whose square is integrated exactly and whose extrema are
+/// found from the roots of its derivative, so both are exact up to rounding.
+/// Divide the square of the L2 norm by the width of the overlap for the mean
+/// squared difference. Fails with [`Error::InvalidRange`] if the ranges do
+/// not overlap.
+///
+/// ```
+/// use cubic_splinterpol::{distance, splinterpol};
+///
+/// let xs = [0f32, 1.0, 2.0, 3.0, 4.0];
+/// let mut stored = [(0f32, 0f32, 0f32, 0f32); 4];
+/// splinterpol(&xs, &[0.0, 1.0, 4.0, 9.0, 16.0], &mut stored).unwrap();
+/// let mut fresh = [(0f32, 0f32, 0f32, 0f32); 4];
+/// splinterpol(&xs, &[0.0, 1.0, 4.1, 9.0, 16.0], &mut fresh).unwrap();
+///
+/// let (l2, max) = distance(&stored, &xs, &fresh, &xs).unwrap();
+/// assert!((max - 0.1).abs() < 1e-5);
+/// assert!(l2 > 0.0 && l2 < 0.1);
+/// ```
+pub fn distance(
+    first_coefficients: &[(f32, f32, f32, f32)],
+    first_xs: &[f32],
+    second_coefficients: &[(f32, f32, f32, f32)],
+    second_xs: &[f32],
+) -> Result<(f32, f32), Error> {
+    check_spline(first_coefficients, first_xs)?;
+    check_spline(second_coefficients, second_xs)?;
+    let mut squares = 0f32;
+    let mut max = 0f32;
+    let overlap = for_each_difference(
+        first_coefficients,
+        first_xs,
+        second_coefficients,
+        second_xs,
+        |difference, start, end, _| {
+            let h = end - start;
+            squares += integral_of_square(difference, h);
+            for t in critical_points(difference, h) {
+                max = max.max(libm::fabsf(eval_segment(difference, t)));
+            }
+            Ok(())
+        },
+    )?;
+    if !overlap {
+        return Err(Error::InvalidRange);
+    }
+    Ok((libm::sqrtf(squares), max))
+}
+
+/// Integral of the square of the cubic `polynomial` over `[0, h]`.
+fn integral_of_square(polynomial: (f32, f32, f32, f32), h: f32) -> f32 {
+    let (a, b, c, d) = polynomial;
+    // Coefficients of the square by power, divided by the power plus one
+    let powers = [
+        a * a,
+        a * b,
+        (b * b + 2f32 * a * c) / 3f32,
+        (a * d + b * c) / 2f32,
+        (c * c + 2f32 * b * d) / 5f32,
+        c * d / 3f32,
+        d * d / 7f32,
+    ];
+    h * powers.iter().rev().fold(0f32, |sum, power| sum * h + power)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn offset_and_identical_splines() {
+        let xs = [0f32, 1.0, 2.5, 4.0];
+        let mut coeffs = [(0f32, 0f32, 0f32, 0f32); 3];
+        crate::splinterpol(&xs, &[1.0, 3.0, 2.0, 0.5], &mut coeffs).unwrap();
+        assert_eq!(Ok((0.0, 0.0)), distance(&coeffs, &xs, &coeffs, &xs));
+
+        let mut offset = coeffs;
+        crate::shift_y(&mut offset, 0.5).unwrap();
+        let (l2, max) = distance(&coeffs, &xs, &offset, &xs).unwrap();
+        assert!((max - 0.5).abs() < 1e-5);
+        // 0.5² over a width of 4
+        assert!((l2 - 1.0).abs() < 1e-5);
+    }
+
+    #[test]
+    fn different_knots_over_overlap() {
+        // x² against x on [1, 3], the overlap
+        let parabola = [(0f32, 0f32, 1f32, 0f32), (4.0, 4.0, 1.0, 0.0)];
+        let line = [(1f32, 1f32, 0f32, 0f32); 1];
+        let (l2, max) = distance(&parabola, &[0.0, 2.0, 4.0], &line, &[1.0, 3.0]).unwrap();
+        assert!((max - 6.0).abs() < 1e-5);
+        // Integral of (x² - x)² from 1 to 3
+        let antiderivative = |x: f32| x.powi(5) / 5.0 - x.powi(4) / 2.0 + x.powi(3) / 3.0;
+        let expected = libm::sqrtf(antiderivative(3.0) - antiderivative(1.0));
+        assert!((l2 - expected).abs() < 1e-4, "{} vs {}", l2, expected);
+        assert_eq!(
+            Err(Error::InvalidRange),
+            distance(&parabola, &[0.0, 2.0, 4.0], &line, &[5.0, 6.0])
+        );
+    }
+
+    #[test]
+    fn integrates_squares_exactly() {
+        // (1 + t³)² = 1 + 2t³ + t⁶ over [0, 2]
+        let integral = integral_of_square((1.0, 0.0, 0.0, 1.0), 2.0);
+        assert!((integral - (2.0 + 8.0 + 128.0 / 7.0)).abs() < 1e-4);
+    }
+}
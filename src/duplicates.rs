@@ -0,0 +1,159 @@
+use crate::Error;
+
+/// Handling of points whose x is within an epsilon of the point before, which
+/// the spline cannot pass through as separate knots.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum Duplicates {
+    /// Fail with [`Error::NonIncreasingKnots`]
+    #[default]
+    Error,
+    /// Merge into one point at the mean x and the mean y
+    Average,
+    /// Keep only the last point
+    KeepLast,
+}
+
+impl Duplicates {
+    /// The point replacing `count` merged points `merged` when `point` joins
+    /// them, or `None` if duplicates are an error.
+    pub(crate) fn merge(
+        self,
+        merged: (f32, f32),
+        count: usize,
+        point: (f32, f32),
+    ) -> Option<(f32, f32)> {
+        match self {
+            Duplicates::Error => None,
+            Duplicates::Average => {
+                let weight = 1f32 / (count + 1) as f32;
+                Some((
+                    merged.0 + (point.0 - merged.0) * weight,
+                    merged.1 + (point.1 - merged.1) * weight,
+                ))
+            }
+            Duplicates::KeepLast => Some(point),
+        }
+    }
+}
+
+/// Check that `epsilon` is a valid distance below which two knots count as
+/// duplicates.
+pub(crate) fn check_epsilon(epsilon: f32) -> Result<(), Error> {
+    if epsilon >= 0f32 {
+        Ok(())
+    } else {
+        Err(Error::InvalidLimit)
+    }
+}
+
+/// Merge the points with knots `xs` and values `ys` whose x is within
+/// `epsilon` of the first x of a run, according to `duplicates`, and move the
+/// remaining points to the front of both slices. Returns their number.
+///
+/// `xs` must be sorted, but may repeat values. Fails with
+/// [`Error::NonIncreasingKnots`] at the index of the first duplicate or
+/// decreasing knot, with [`Error::InvalidLimit`] for a negative or NaN
+/// epsilon, and with [`Error::InvalidSliceLength`] for slices of different
+/// lengths.
+///
+/// ```
+/// use cubic_splinterpol::{merge_duplicates, Duplicates};
+///
+/// let mut xs = [0f32, 1.0, 1.0, 2.0];
+/// let mut ys = [0f32, 1.0, 3.0, 0.0];
+/// let len = merge_duplicates(&mut xs, &mut ys, Duplicates::Average, 0.0).unwrap();
+/// assert_eq!([0.0, 1.0, 2.0], xs[..len]);
+/// assert_eq!([0.0, 2.0, 0.0], ys[..len]);
+/// ```
+pub fn merge_duplicates(
+    xs: &mut [f32],
+    ys: &mut [f32],
+    duplicates: Duplicates,
+    epsilon: f32,
+) -> Result<usize, Error> {
+    if xs.len() != ys.len() {
+        return Err(Error::InvalidSliceLength);
+    }
+    check_epsilon(epsilon)?;
+    if xs.is_empty() {
+        return Ok(0);
+    }
+    let mut len = 1;
+    let mut run_start = xs[0];
+    let mut run_len = 1;
+    for i in 1..xs.len() {
+        let point = (xs[i], ys[i]);
+        if !matches!(
+            point.0.partial_cmp(&run_start),
+            Some(core::cmp::Ordering::Greater | core::cmp::Ordering::Equal)
+        ) {
+            return Err(Error::NonIncreasingKnots { index: i });
+        }
+        if point.0 - run_start <= epsilon {
+            let merged = (xs[len - 1], ys[len - 1]);
+            let (x, y) = duplicates
+                .merge(merged, run_len, point)
+                .ok_or(Error::NonIncreasingKnots { index: i })?;
+            xs[len - 1] = x;
+            ys[len - 1] = y;
+            run_len += 1;
+        } else {
+            xs[len] = point.0;
+            ys[len] = point.1;
+            len += 1;
+            run_start = point.0;
+            run_len = 1;
+        }
+    }
+    Ok(len)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn merges_runs() {
+        let xs = [0f32, 1.0, 1.01, 1.02, 2.0, 3.0, 3.0];
+        let ys = [0f32, 1.0, 2.0, 6.0, 0.0, 1.0, 2.0];
+
+        let (mut x, mut y) = (xs, ys);
+        let len = merge_duplicates(&mut x, &mut y, Duplicates::KeepLast, 0.05).unwrap();
+        assert_eq!([0.0, 1.02, 2.0, 3.0], x[..len]);
+        assert_eq!([0.0, 6.0, 0.0, 2.0], y[..len]);
+
+        let (mut x, mut y) = (xs, ys);
+        let len = merge_duplicates(&mut x, &mut y, Duplicates::Average, 0.05).unwrap();
+        assert_eq!(4, len);
+        assert!((x[1] - 1.01).abs() < 1e-6);
+        assert!((y[1] - 3.0).abs() < 1e-6);
+        assert_eq!((3.0, 1.5), (x[3], y[3]));
+
+        // Without epsilon, only equal knots are duplicates
+        let (mut x, mut y) = (xs, ys);
+        let len = merge_duplicates(&mut x, &mut y, Duplicates::KeepLast, 0.0).unwrap();
+        assert_eq!([0.0, 1.0, 1.01, 1.02, 2.0, 3.0], x[..len]);
+    }
+
+    #[test]
+    fn rejects_duplicates_and_invalid_input() {
+        let (mut x, mut y) = ([0f32, 1.0, 1.0], [0f32; 3]);
+        assert_eq!(
+            Err(Error::NonIncreasingKnots { index: 2 }),
+            merge_duplicates(&mut x, &mut y, Duplicates::Error, 0.0)
+        );
+        let (mut x, mut y) = ([0f32, 1.0, 0.5], [0f32; 3]);
+        assert_eq!(
+            Err(Error::NonIncreasingKnots { index: 2 }),
+            merge_duplicates(&mut x, &mut y, Duplicates::Average, 0.0)
+        );
+        assert_eq!(
+            Err(Error::InvalidLimit),
+            merge_duplicates(&mut x, &mut y, Duplicates::Average, -1.0)
+        );
+        assert_eq!(
+            Err(Error::InvalidSliceLength),
+            merge_duplicates(&mut x, &mut y[..2], Duplicates::Average, 0.0)
+        );
+    }
+}
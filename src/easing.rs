@@ -0,0 +1,140 @@
+//! Custom easing curves for animations, e.g. menu transitions or LED fades,
+//! defined by a handful of keyframes.
+
+use crate::{
+    check_increasing, eval_segment, find_segment, splinterpol_slices, Boundary, Error, Solver,
+};
+
+/// An easing curve through `N` keyframes, mapping animation progress in
+/// `[0, 1]` to eased progress.
+///
+/// The keyframes are normalized so that the first one maps to `(0, 0)` and
+/// the last one to `(1, 1)`. [`ease`](Easing::ease) clamps its argument to
+/// `[0, 1]` and returns exactly 0 and 1 at the ends, so an animation always
+/// starts and stops precisely at its start and target values. In between, the
+/// curve may overshoot, e.g. for a "back" or "elastic" style easing.
+///
+/// ```
+/// use cubic_splinterpol::easing::Easing;
+///
+/// // Slow start, then fast, with a slight overshoot before settling
+/// let easing = Easing::new(&[(0f32, 0f32), (40.0, 10.0), (80.0, 105.0), (100.0, 100.0)]).unwrap();
+/// assert_eq!(0.0, easing.ease(-0.5));
+/// assert_eq!(1.0, easing.ease(1.0));
+/// assert!(easing.ease(0.8) > 1.0);
+/// ```
+#[derive(Debug, Clone)]
+pub struct Easing<const N: usize> {
+    xs: [f32; N],
+    /// N-1 of N used
+    coefficients: [(f32, f32, f32, f32); N],
+}
+
+impl<const N: usize> Easing<N> {
+    /// A natural spline easing through the keyframes `(t, value)`, with
+    /// strictly increasing t. At least 3 keyframes are needed, and the first
+    /// and last value must differ.
+    pub fn new(keyframes: &[(f32, f32); N]) -> Result<Self, Error> {
+        Self::with_boundary(keyframes, Boundary::Natural)
+    }
+
+    /// Like [`new`](Easing::new), with the given boundary condition. The
+    /// derivatives of [`Boundary::Clamped`] and
+    /// [`Boundary::SecondDerivative`] refer to the normalized curve, e.g.
+    /// `Clamped { start: 0.0, end: 0.0 }` gives an ease-in-out.
+    pub fn with_boundary(keyframes: &[(f32, f32); N], boundary: Boundary) -> Result<Self, Error> {
+        if N < 3 {
+            return Err(Error::InvalidSliceLength);
+        }
+        let (t_first, value_first) = keyframes[0];
+        let (t_last, value_last) = keyframes[N - 1];
+        let span = value_last - value_first;
+        if span == 0f32 || span.is_nan() {
+            return Err(Error::InvalidRange);
+        }
+        let mut xs = [0f32; N];
+        let mut ys = [0f32; N];
+        for (i, (t, value)) in keyframes.iter().enumerate() {
+            xs[i] = (t - t_first) / (t_last - t_first);
+            ys[i] = (value - value_first) / span;
+        }
+        check_increasing(&xs)?;
+
+        let mut coefficients = [(0f32, 0f32, 0f32, 0f32); N];
+        splinterpol_slices::<N>(&xs, &ys, &mut coefficients, boundary, Solver::Thomas)?;
+        Ok(Self { xs, coefficients })
+    }
+
+    /// Eased progress at animation progress `t01`, which is clamped to
+    /// `[0, 1]`.
+    pub fn ease(&self, t01: f32) -> f32 {
+        if t01.is_nan() || t01 <= 0f32 {
+            return 0f32;
+        }
+        if t01 >= 1f32 {
+            return 1f32;
+        }
+        let segment = find_segment(&self.xs, t01);
+        eval_segment(self.coefficients[segment], t01 - self.xs[segment])
+    }
+
+    /// Interpolate from `from` to `to` at animation progress `t01`.
+    pub fn interpolate(&self, from: f32, to: f32, t01: f32) -> f32 {
+        from + (to - from) * self.ease(t01)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn endpoints_and_clamping() {
+        let easing = Easing::new(&[(2f32, 5f32), (3.0, 6.0), (4.0, 9.0), (6.0, 7.0)]).unwrap();
+        assert_eq!(0.0, easing.ease(0.0));
+        assert_eq!(0.0, easing.ease(-3.0));
+        assert_eq!(0.0, easing.ease(f32::NAN));
+        assert_eq!(1.0, easing.ease(1.0));
+        assert_eq!(1.0, easing.ease(7.0));
+        // Keyframe (3, 6) normalized
+        assert!((easing.ease(0.25) - 0.5).abs() < 1e-6);
+        assert_eq!(5.0, easing.interpolate(5.0, 9.0, 0.0));
+        assert_eq!(9.0, easing.interpolate(5.0, 9.0, 1.0));
+    }
+
+    #[test]
+    fn ease_in_out() {
+        let keyframes = [(0f32, 0f32), (0.5, 0.5), (1.0, 1.0)];
+        let easing = Easing::with_boundary(
+            &keyframes,
+            Boundary::Clamped {
+                start: 0.0,
+                end: 0.0,
+            },
+        )
+        .unwrap();
+        let slope = |t: f32| (easing.ease(t + 0.001) - easing.ease(t)) / 0.001;
+        assert!(slope(0.0) < 0.01);
+        assert!(slope(0.999) < 0.01);
+        assert!((easing.ease(0.5) - 0.5).abs() < 1e-6);
+        // Linear keyframes stay linear with the natural boundary
+        let linear = Easing::new(&keyframes).unwrap();
+        assert!((linear.ease(0.3) - 0.3).abs() < 1e-6);
+    }
+
+    #[test]
+    fn rejects_invalid_keyframes() {
+        assert_eq!(
+            Err(Error::InvalidRange),
+            Easing::new(&[(0f32, 1f32), (0.5, 2.0), (1.0, 1.0)]).map(|_| ())
+        );
+        assert_eq!(
+            Err(Error::NonIncreasingKnots { index: 2 }),
+            Easing::new(&[(0f32, 0f32), (0.5, 2.0), (0.5, 1.0)]).map(|_| ())
+        );
+        assert_eq!(
+            Err(Error::InvalidSliceLength),
+            Easing::new(&[(0f32, 0f32), (1.0, 1.0)]).map(|_| ())
+        );
+    }
+}
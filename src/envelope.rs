@@ -0,0 +1,231 @@
+//! ADSR envelopes for synthesizers and sound effects, with cubic attack,
+//! decay and release shapes, evaluated sample by sample.
+
+use crate::{eval_segment, hermite, Error};
+
+/// Shape and length of one stage of an [`Envelope`], a cubic from the start
+/// to the end level of the stage.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Stage {
+    samples: usize,
+    /// Cubic from 0 to 1 on the normalized progress `[0, 1]`
+    shape: (f32, f32, f32, f32),
+}
+
+impl Stage {
+    /// A stage taking `samples` samples, with the slopes of the normalized
+    /// shape at its start and end, relative to a straight line. Slopes from
+    /// 0 to 3 keep the shape monotone, e.g. `(1, 1)` is linear and `(3, 0)`
+    /// rises fast, then settles.
+    ///
+    /// Zero samples skip the stage. Fails with [`Error::InvalidLimit`] for a
+    /// slope which is not finite.
+    pub fn new(samples: usize, start_slope: f32, end_slope: f32) -> Result<Self, Error> {
+        if !start_slope.is_finite() || !end_slope.is_finite() {
+            return Err(Error::InvalidLimit);
+        }
+        Ok(Self {
+            samples,
+            shape: hermite(0f32, 1f32, start_slope, end_slope, 1f32),
+        })
+    }
+
+    /// A linear stage taking `samples` samples.
+    pub fn linear(samples: usize) -> Self {
+        Self {
+            samples,
+            shape: (0f32, 1f32, 0f32, 0f32),
+        }
+    }
+
+    /// Number of samples the stage takes.
+    pub fn samples(&self) -> usize {
+        self.samples
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Phase {
+    Idle,
+    Attack,
+    Decay,
+    Sustain,
+    Release,
+}
+
+/// An attack, decay, sustain, release envelope with levels from 0 to 1.
+///
+/// [`trigger`](Envelope::trigger) starts the attack to 1, followed by the
+/// decay to the sustain level, which holds until
+/// [`release`](Envelope::release) starts the release to 0. Both start from
+/// the current level, so retriggering or releasing early does not click.
+///
+/// ```
+/// use cubic_splinterpol::envelope::{Envelope, Stage};
+///
+/// let attack = Stage::new(4, 3.0, 0.0).unwrap();
+/// let mut envelope = Envelope::new(attack, Stage::linear(2), 0.5, Stage::linear(4)).unwrap();
+/// envelope.trigger();
+/// let mut buffer = [0f32; 8];
+/// envelope.fill(&mut buffer);
+/// assert_eq!([0.0, 1.0, 0.75, 0.5, 0.5], [buffer[0], buffer[4], buffer[5], buffer[6], buffer[7]]);
+///
+/// envelope.release();
+/// envelope.fill(&mut buffer);
+/// assert_eq!([0.5, 0.375, 0.0], [buffer[0], buffer[1], buffer[4]]);
+/// assert!(!envelope.is_active());
+/// ```
+#[derive(Debug, Clone)]
+pub struct Envelope {
+    attack: Stage,
+    decay: Stage,
+    sustain: f32,
+    release: Stage,
+    phase: Phase,
+    /// Sample within the current stage
+    index: usize,
+    /// Level at the start of the current stage
+    from: f32,
+    level: f32,
+}
+
+impl Envelope {
+    /// An idle envelope with the given stages and sustain level.
+    ///
+    /// Fails with [`Error::InvalidRange`] for a sustain level outside of
+    /// `[0, 1]`.
+    pub fn new(attack: Stage, decay: Stage, sustain: f32, release: Stage) -> Result<Self, Error> {
+        if !(0f32..=1f32).contains(&sustain) {
+            return Err(Error::InvalidRange);
+        }
+        Ok(Self {
+            attack,
+            decay,
+            sustain,
+            release,
+            phase: Phase::Idle,
+            index: 0,
+            from: 0f32,
+            level: 0f32,
+        })
+    }
+
+    /// Start the attack, from the current level.
+    pub fn trigger(&mut self) {
+        self.enter(Phase::Attack);
+    }
+
+    /// Start the release, from the current level, unless idle.
+    pub fn release(&mut self) {
+        if self.phase != Phase::Idle {
+            self.enter(Phase::Release);
+        }
+    }
+
+    /// Whether the envelope is anywhere but idle after its release.
+    pub fn is_active(&self) -> bool {
+        self.phase != Phase::Idle
+    }
+
+    /// The current level, that of the last sample.
+    pub fn level(&self) -> f32 {
+        self.level
+    }
+
+    /// The level of the next sample.
+    pub fn next_sample(&mut self) -> f32 {
+        loop {
+            let (stage, to, next) = match self.phase {
+                Phase::Idle => return 0f32,
+                Phase::Sustain => return self.sustain,
+                Phase::Attack => (self.attack, 1f32, Phase::Decay),
+                Phase::Decay => (self.decay, self.sustain, Phase::Sustain),
+                Phase::Release => (self.release, 0f32, Phase::Idle),
+            };
+            if self.index < stage.samples {
+                let progress = self.index as f32 / stage.samples as f32;
+                self.level = self.from + (to - self.from) * eval_segment(stage.shape, progress);
+                self.index += 1;
+                return self.level;
+            }
+            self.level = to;
+            self.enter(next);
+        }
+    }
+
+    /// Fill `chunk` with the next samples, e.g. one audio block.
+    pub fn fill(&mut self, chunk: &mut [f32]) {
+        for elem in chunk.iter_mut() {
+            *elem = self.next_sample();
+        }
+    }
+
+    fn enter(&mut self, phase: Phase) {
+        self.phase = phase;
+        self.index = 0;
+        self.from = self.level;
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn stages_in_order() {
+        let attack = Stage::new(10, 3.0, 0.0).unwrap();
+        let decay = Stage::new(5, 0.0, 0.0).unwrap();
+        let mut envelope = Envelope::new(attack, decay, 0.25, Stage::linear(8)).unwrap();
+        assert_eq!(0.0, envelope.next_sample());
+        envelope.trigger();
+        let mut samples = [0f32; 20];
+        envelope.fill(&mut samples);
+        // Rising to 1, then falling to the sustain level
+        for pair in samples[..10].windows(2) {
+            assert!(pair[1] > pair[0]);
+        }
+        assert_eq!(1.0, samples[10]);
+        for pair in samples[10..15].windows(2) {
+            assert!(pair[1] < pair[0]);
+        }
+        assert_eq!([0.25; 5], samples[15..]);
+        assert!(envelope.is_active());
+
+        envelope.release();
+        envelope.fill(&mut samples);
+        assert_eq!(0.25, samples[0]);
+        assert_eq!(0.125, samples[4]);
+        assert_eq!([0.0; 12], samples[8..]);
+        assert!(!envelope.is_active());
+    }
+
+    #[test]
+    fn retrigger_and_early_release_continue() {
+        let mut envelope =
+            Envelope::new(Stage::linear(4), Stage::linear(0), 1.0, Stage::linear(4)).unwrap();
+        envelope.trigger();
+        let mut samples = [0f32; 3];
+        envelope.fill(&mut samples);
+        assert_eq!([0.0, 0.25, 0.5], samples);
+        // Released during the attack, from 0.5
+        envelope.release();
+        assert_eq!(0.5, envelope.next_sample());
+        assert_eq!(0.375, envelope.next_sample());
+        envelope.trigger();
+        assert_eq!(0.375, envelope.next_sample());
+        assert_eq!(0.375, envelope.level());
+        // Skipped decay, straight to sustain
+        envelope.fill(&mut [0f32; 3]);
+        assert_eq!(1.0, envelope.next_sample());
+        assert_eq!(1.0, envelope.next_sample());
+
+        assert_eq!(
+            Err(Error::InvalidRange),
+            Envelope::new(Stage::linear(1), Stage::linear(1), 1.5, Stage::linear(1)).map(|_| ())
+        );
+        assert_eq!(
+            Err(Error::InvalidLimit),
+            Stage::new(1, f32::NAN, 0.0).map(|_| ())
+        );
+    }
+}
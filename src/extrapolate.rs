@@ -0,0 +1,237 @@
+use crate::{
+    check_range, check_spline, eval_segment, eval_segment_derivative, find_segment_by, Error,
+};
+
+/// Behavior of evaluation at positions outside of the first and last knot.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum Extrapolate {
+    /// Fail with [`Error::OutOfRange`], also for a NaN position
+    Error,
+    /// Hold the value at the nearest end knot
+    ClampToEndValue,
+    /// Continue along the tangent at the nearest end knot
+    LinearFromEndSlope,
+    /// Evaluate the first or last polynomial beyond its segment
+    #[default]
+    ExtendCubic,
+}
+
+/// Evaluate the spline given by `coefficients` and the knots `xs` at global
+/// position x, extrapolating outside of the knots according to `extrapolate`.
+/// A NaN x evaluates to NaN, except with [`Extrapolate::Error`], which
+/// rejects it as out of range.
+///
+/// ```
+/// use cubic_splinterpol::{eval, splinterpol, Error, Extrapolate};
+///
+/// let xs = [0f32, 1.0, 2.0];
+/// let ys = [0f32, 1.0, 0.0];
+/// let mut coefficients = [(0f32, 0f32, 0f32, 0f32); 2];
+/// splinterpol(&xs, &ys, &mut coefficients).unwrap();
+///
+/// assert_eq!(Ok(0.0), eval(&coefficients, &xs, 3.0, Extrapolate::ClampToEndValue));
+/// assert_eq!(Err(Error::OutOfRange), eval(&coefficients, &xs, 3.0, Extrapolate::Error));
+/// ```
+pub fn eval(
+    coefficients: &[(f32, f32, f32, f32)],
+    xs: &[f32],
+    x: f32,
+    extrapolate: Extrapolate,
+) -> Result<f32, Error> {
+    check_spline(coefficients, xs)?;
+    let (polynomial, t) = locate(|segment| coefficients[segment], xs, x, extrapolate)?;
+    Ok(eval_segment(polynomial, t))
+}
+
+/// Like [`eval`], but saturating the value to `[y_min, y_max]`, e.g. the safe
+/// range of a DAC voltage or a servo angle, which overshoot between the knots
+/// must never exceed. NaN saturates to `y_min`, so the result is always
+/// within the range, unless a NaN x is rejected by [`Extrapolate::Error`] as
+/// for [`eval`]. Fails with [`Error::InvalidRange`] unless `y_min` is
+/// below `y_max`.
+///
+/// ```
+/// use cubic_splinterpol::{eval_clamped, splinterpol, Extrapolate};
+///
+/// // The natural spline rings above 1 after the step
+/// let xs = [0f32, 1.0, 2.0, 3.0];
+/// let mut coefficients = [(0f32, 0f32, 0f32, 0f32); 3];
+/// splinterpol(&xs, &[0.0, 0.0, 1.0, 1.0], &mut coefficients).unwrap();
+/// let y = eval_clamped(&coefficients, &xs, 2.5, Extrapolate::Error, 0.0, 1.0);
+/// assert_eq!(Ok(1.0), y);
+/// ```
+pub fn eval_clamped(
+    coefficients: &[(f32, f32, f32, f32)],
+    xs: &[f32],
+    x: f32,
+    extrapolate: Extrapolate,
+    y_min: f32,
+    y_max: f32,
+) -> Result<f32, Error> {
+    check_range(y_min, y_max)?;
+    Ok(saturate(
+        eval(coefficients, xs, x, extrapolate)?,
+        y_min,
+        y_max,
+    ))
+}
+
+/// Saturate y to `[y_min, y_max]`, NaN to `y_min`.
+pub(crate) fn saturate(y: f32, y_min: f32, y_max: f32) -> f32 {
+    // max picks the number over NaN
+    y.max(y_min).min(y_max)
+}
+
+/// Coefficients of a polynomial, and the position to evaluate it at
+pub(crate) type Located = ((f32, f32, f32, f32), f32);
+
+/// The polynomial to evaluate for global position x and the position within
+/// it. Inside the knots, or with [`Extrapolate::ExtendCubic`], this is the
+/// segment containing x. Otherwise, the constant or linear extrapolation is
+/// expressed as a polynomial starting at the end knot. The knots must already
+/// have been checked against the coefficient storage.
+pub(crate) fn locate(
+    coefficients: impl Fn(usize) -> (f32, f32, f32, f32),
+    xs: &[f32],
+    x: f32,
+    extrapolate: Extrapolate,
+) -> Result<Located, Error> {
+    locate_by(coefficients, xs.len(), |i| xs[i], x, extrapolate)
+}
+
+/// Like [`locate`], for `len` knots read through `knot`.
+pub(crate) fn locate_by(
+    coefficients: impl Fn(usize) -> (f32, f32, f32, f32),
+    len: usize,
+    knot: impl Fn(usize) -> f32,
+    x: f32,
+    extrapolate: Extrapolate,
+) -> Result<Located, Error> {
+    let first = knot(0);
+    let last = knot(len - 1);
+    if x.is_nan() && extrapolate == Extrapolate::Error {
+        return Err(Error::OutOfRange);
+    }
+    if extrapolate == Extrapolate::ExtendCubic || (x >= first && x <= last) || x.is_nan() {
+        let segment = find_segment_by(len, &knot, x);
+        return Ok((coefficients(segment), x - knot(segment)));
+    }
+    if extrapolate == Extrapolate::Error {
+        return Err(Error::OutOfRange);
+    }
+    let (value, slope, t) = if x < first {
+        let (a, b, _, _) = coefficients(0);
+        (a, b, x - first)
+    } else {
+        let segment = len - 2;
+        let polynomial = coefficients(segment);
+        let h = last - knot(segment);
+        let value = eval_segment(polynomial, h);
+        let slope = eval_segment_derivative(polynomial, h);
+        (value, slope, x - last)
+    };
+    match extrapolate {
+        Extrapolate::ClampToEndValue => Ok(((value, 0f32, 0f32, 0f32), t)),
+        _ => Ok(((value, slope, 0f32, 0f32), t)),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    use crate::fixture::natural;
+
+    // A last segment of width 0.5, which the expected end values build on
+    const XS: [f32; 4] = [0f32, 1.0, 2.5, 3.0];
+
+    fn spline() -> [(f32, f32, f32, f32); 3] {
+        natural(&XS, &[1f32, 2.0, 0.0, 0.5])
+    }
+
+    #[test]
+    fn extrapolation_policies() {
+        let (xs, coeffs) = (XS, spline());
+        let end = eval_segment(coeffs[2], 0.5);
+        let (_, b, c, d) = coeffs[2];
+        let end_slope = b + 2f32 * c * 0.5 + 3f32 * d * 0.25;
+
+        assert_eq!(
+            Err(Error::OutOfRange),
+            eval(&coeffs, &xs, -1.0, Extrapolate::Error)
+        );
+        assert_eq!(
+            Err(Error::OutOfRange),
+            eval(&coeffs, &xs, 3.5, Extrapolate::Error)
+        );
+        assert_eq!(Ok(end), eval(&coeffs, &xs, 3.0, Extrapolate::Error));
+        assert_eq!(
+            Err(Error::OutOfRange),
+            eval(&coeffs, &xs, f32::NAN, Extrapolate::Error)
+        );
+        assert!(eval(&coeffs, &xs, f32::NAN, Extrapolate::ClampToEndValue)
+            .unwrap()
+            .is_nan());
+
+        let clamp = Extrapolate::ClampToEndValue;
+        assert_eq!(Ok(1.0), eval(&coeffs, &xs, -1.0, clamp));
+        assert_eq!(Ok(end), eval(&coeffs, &xs, 5.0, clamp));
+
+        let linear = Extrapolate::LinearFromEndSlope;
+        assert_eq!(Ok(1.0 - coeffs[0].1), eval(&coeffs, &xs, -1.0, linear));
+        let y = eval(&coeffs, &xs, 5.0, linear).unwrap();
+        assert!((y - (end + 2f32 * end_slope)).abs() < 0.0001);
+
+        let cubic = Extrapolate::ExtendCubic;
+        assert_eq!(
+            Ok(eval_segment(coeffs[2], 2.5)),
+            eval(&coeffs, &xs, 5.0, cubic)
+        );
+        assert_eq!(
+            Ok(eval_segment(coeffs[0], -1.0)),
+            eval(&coeffs, &xs, -1.0, cubic)
+        );
+    }
+
+    #[test]
+    fn inside_knots_unaffected() {
+        let (xs, coeffs) = (XS, spline());
+        for i in 0..=30 {
+            let x = i as f32 / 10f32;
+            let expected = eval(&coeffs, &xs, x, Extrapolate::ExtendCubic);
+            for extrapolate in [
+                Extrapolate::Error,
+                Extrapolate::ClampToEndValue,
+                Extrapolate::LinearFromEndSlope,
+            ] {
+                assert_eq!(expected, eval(&coeffs, &xs, x, extrapolate));
+            }
+        }
+    }
+
+    #[test]
+    fn eval_clamped_saturates() {
+        let coeffs = [(0f32, 4f32, -4f32, 0f32)];
+        let xs = [0f32, 1.0];
+        assert_eq!(
+            Ok(0.75),
+            eval_clamped(&coeffs, &xs, 0.25, Extrapolate::Error, 0.0, 0.8)
+        );
+        assert_eq!(
+            Ok(0.8),
+            eval_clamped(&coeffs, &xs, 0.5, Extrapolate::Error, 0.0, 0.8)
+        );
+        assert_eq!(
+            Ok(-1.0),
+            eval_clamped(&coeffs, &xs, 2.0, Extrapolate::ExtendCubic, -1.0, 0.8)
+        );
+        assert_eq!(
+            Ok(-1.0),
+            eval_clamped(&coeffs, &xs, f32::NAN, Extrapolate::ExtendCubic, -1.0, 0.8)
+        );
+        assert_eq!(
+            Err(Error::InvalidRange),
+            eval_clamped(&coeffs, &xs, 0.5, Extrapolate::Error, 1.0, 1.0)
+        );
+    }
+}
@@ -0,0 +1,281 @@
+use crate::{check_increasing, check_spline, eval_segment, find_segment, Error, Segment};
+
+/// Positions relative to the start of a segment of width h at which the cubic
+/// `polynomial` can take its extreme values: both ends and the roots of the
+/// derivative in between. Unused entries repeat the end, so every entry can be
+/// evaluated.
+pub(crate) fn critical_points(polynomial: (f32, f32, f32, f32), h: f32) -> [f32; 4] {
+    let (_, b, c, d) = polynomial;
+    let mut points = [0f32, h, h, h];
+    let mut len = 2;
+    let mut push = |t: f32| {
+        if t > 0f32 && t < h {
+            points[len] = t;
+            len += 1;
+        }
+    };
+    // Roots of 3d t² + 2c t + b
+    let (qa, qb, qc) = (3f32 * d, 2f32 * c, b);
+    if qa == 0f32 {
+        if qb != 0f32 {
+            push(-qc / qb);
+        }
+    } else {
+        let discriminant = qb * qb - 4f32 * qa * qc;
+        if discriminant >= 0f32 {
+            // Numerically stable form, without cancellation in either root
+            let q = -0.5f32 * (qb + libm::copysignf(libm::sqrtf(discriminant), qb));
+            push(q / qa);
+            if q != 0f32 {
+                push(qc / q);
+            }
+        }
+    }
+    points
+}
+
+/// How far the spline given by `coefficients` and the knots `xs` leaves the
+/// range of the data `ys` at the knots, e.g. to assert that a DAC output
+/// stays within safe bounds.
+///
+/// Within every segment, the spline is compared to the smaller and larger of
+/// the two values at its ends, at the extrema of the cubic found from the
+/// roots of its derivative. Returns the largest undershoot below the smaller
+/// and the largest overshoot above the larger value, both zero or positive.
+/// The overshoot beyond the global range of `ys` is at most these.
+///
+/// ```
+/// use cubic_splinterpol::{overshoot, splinterpol};
+///
+/// // A step, which the natural spline rings on
+/// let xs = [0f32, 1.0, 2.0, 3.0];
+/// let ys = [0f32, 0.0, 1.0, 1.0];
+/// let mut coefficients = [(0f32, 0f32, 0f32, 0f32); 3];
+/// splinterpol(&xs, &ys, &mut coefficients).unwrap();
+/// let (below, above) = overshoot(&coefficients, &xs, &ys).unwrap();
+/// assert!(below > 0.05 && above > 0.05);
+/// ```
+pub fn overshoot(
+    coefficients: &[(f32, f32, f32, f32)],
+    xs: &[f32],
+    ys: &[f32],
+) -> Result<(f32, f32), Error> {
+    check_spline(coefficients, xs)?;
+    if ys.len() != xs.len() {
+        return Err(Error::InvalidSliceLength);
+    }
+    let mut below = 0f32;
+    let mut above = 0f32;
+    for (i, polynomial) in coefficients.iter().enumerate() {
+        let low = ys[i].min(ys[i + 1]);
+        let high = ys[i].max(ys[i + 1]);
+        for t in critical_points(*polynomial, xs[i + 1] - xs[i]) {
+            let y = eval_segment(*polynomial, t);
+            below = below.max(low - y);
+            above = above.max(y - high);
+        }
+    }
+    Ok((below, above))
+}
+
+/// The smallest and largest value of the spline given by `coefficients` and
+/// the knots `xs` on `[from, to]`, e.g. guaranteed bounds of an interpolated
+/// temperature profile, which no amount of sampling gives.
+///
+/// Within every segment, the spline is evaluated at the ends of the interval
+/// in it and at the roots of its derivative in between, which is exact up to
+/// rounding. `from` may equal `to`. Fails with [`Error::InvalidRange`] if
+/// `from` is above `to` or either is NaN, and [`Error::OutOfRange`] if the
+/// interval leaves the knots.
+///
+/// ```
+/// use cubic_splinterpol::{min_max, splinterpol};
+///
+/// let xs = [0f32, 1.0, 2.0, 3.0];
+/// let mut coefficients = [(0f32, 0f32, 0f32, 0f32); 3];
+/// splinterpol(&xs, &[20.0, 80.0, 85.0, 40.0], &mut coefficients).unwrap();
+/// let (min, max) = min_max(&coefficients, &xs, 0.5, 3.0).unwrap();
+/// assert_eq!(40.0, min);
+/// // Above the largest value at a knot
+/// assert!((90.0..91.0).contains(&max));
+/// ```
+pub fn min_max(
+    coefficients: &[(f32, f32, f32, f32)],
+    xs: &[f32],
+    from: f32,
+    to: f32,
+) -> Result<(f32, f32), Error> {
+    check_spline(coefficients, xs)?;
+    if from.is_nan() || to.is_nan() || from > to {
+        return Err(Error::InvalidRange);
+    }
+    if from < xs[0] || to > xs[xs.len() - 1] {
+        return Err(Error::OutOfRange);
+    }
+    let mut bounds = (f32::INFINITY, f32::NEG_INFINITY);
+    for segment in find_segment(xs, from)..=find_segment(xs, to) {
+        let start = from.max(xs[segment]) - xs[segment];
+        let end = to.min(xs[segment + 1]) - xs[segment];
+        let h = xs[segment + 1] - xs[segment];
+        extend_bounds(&mut bounds, coefficients[segment], h, start, end);
+    }
+    Ok(bounds)
+}
+
+/// Axis-aligned bounding box of the parametric curve given by `coefficients`
+/// of all `D` components, as computed by
+/// [`splinterpol_multi`](crate::splinterpol_multi), and the parameter knots
+/// `ts`, e.g. for collision pre-checks or clipping to a display area. Returns
+/// the smallest and the largest value of every component, exact up to
+/// rounding as with [`min_max`]. Fails with [`Error::NonIncreasingKnots`]
+/// unless `ts` is strictly increasing.
+///
+/// ```
+/// use cubic_splinterpol::{bounds, splinterpol_multi, Segment};
+///
+/// // Around a square, through its corners
+/// let ts = [0f32, 1.0, 2.0, 3.0, 4.0];
+/// let points = [[1f32, 0.0], [0.0, 1.0], [-1.0, 0.0], [0.0, -1.0], [1.0, 0.0]];
+/// let mut coefficients = [[Segment::default(); 2]; 4];
+/// splinterpol_multi(&ts, &points, &mut coefficients).unwrap();
+/// let (min, max) = bounds(&coefficients, &ts).unwrap();
+/// assert_eq!(1.0, max[0]);
+/// assert!(min[1] <= -1.0 && max[1] >= 1.0);
+/// ```
+pub fn bounds<const D: usize>(
+    coefficients: &[[Segment; D]],
+    ts: &[f32],
+) -> Result<([f32; D], [f32; D]), Error> {
+    if coefficients.is_empty() {
+        return Err(Error::EmptyInput);
+    }
+    if ts.len() != coefficients.len() + 1 {
+        return Err(Error::InvalidSliceLength);
+    }
+    check_increasing(ts)?;
+    let mut min = [f32::INFINITY; D];
+    let mut max = [f32::NEG_INFINITY; D];
+    for (i, segment) in coefficients.iter().enumerate() {
+        let h = ts[i + 1] - ts[i];
+        for (component, polynomial) in segment.iter().enumerate() {
+            let mut bounds = (min[component], max[component]);
+            extend_bounds(&mut bounds, polynomial.to_tuple(), h, 0f32, h);
+            (min[component], max[component]) = bounds;
+        }
+    }
+    Ok((min, max))
+}
+
+/// Extend `(min, max)` by the values of the cubic `polynomial` of a segment
+/// of width h on `[start, end]` within it.
+fn extend_bounds(
+    bounds: &mut (f32, f32),
+    polynomial: (f32, f32, f32, f32),
+    h: f32,
+    start: f32,
+    end: f32,
+) {
+    for t in critical_points(polynomial, h).iter().chain(&[start, end]) {
+        if (start..=end).contains(t) {
+            let y = eval_segment(polynomial, *t);
+            bounds.0 = bounds.0.min(y);
+            bounds.1 = bounds.1.max(y);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn critical_points_of_cubics() {
+        // (t - 1)(t - 3) derivative of t³/3 - 2t² + 3t
+        let polynomial = (0f32, 3f32, -2f32, 1f32 / 3f32);
+        let mut points = critical_points(polynomial, 4.0);
+        points.sort_by(|a, b| a.total_cmp(b));
+        assert_eq!([0.0, 1.0, 3.0, 4.0], points);
+        assert_eq!([0.0, 2.0, 1.0, 2.0], critical_points(polynomial, 2.0));
+        // Quadratic and linear
+        assert_eq!(
+            [0.0, 2.0, 0.5, 2.0],
+            critical_points((0.0, -1.0, 1.0, 0.0), 2.0)
+        );
+        assert_eq!(
+            [0.0, 2.0, 2.0, 2.0],
+            critical_points((0.0, -1.0, 0.0, 0.0), 2.0)
+        );
+    }
+
+    #[test]
+    fn overshoot_per_segment() {
+        // Peaks above 1 inside [0, 1], between the values 0 and 1
+        let coefficients = [(0f32, 3f32, -1f32, -1f32)];
+        let xs = [0f32, 1.0];
+        let (below, above) = overshoot(&coefficients, &xs, &[0.0, 1.0]).unwrap();
+        assert_eq!(0.0, below);
+        let t = (-1f32 + 10f32.sqrt()) / 3f32;
+        let peak = 3f32 * t - t * t - t * t * t;
+        assert!((above - (peak - 1f32)).abs() < 1e-6);
+
+        // Linear interpolation never overshoots
+        let linear = [(0f32, 1f32, 0f32, 0f32), (1.0, -2.0, 0.0, 0.0)];
+        assert_eq!(
+            Ok((0.0, 0.0)),
+            overshoot(&linear, &[0.0, 1.0, 2.0], &[0.0, 1.0, -1.0])
+        );
+        assert_eq!(
+            Err(Error::InvalidSliceLength),
+            overshoot(&linear, &[0.0, 1.0, 2.0], &[0.0, 1.0])
+        );
+    }
+
+    #[test]
+    fn min_max_on_intervals() {
+        // Peaks at t = 0.5 with value 1 inside [0, 1]
+        let coefficients = [(0f32, 4f32, -4f32, 0f32), (0.0, -1.0, 0.0, 0.0)];
+        let xs = [0f32, 1.0, 2.0];
+        assert_eq!(Ok((-1.0, 1.0)), min_max(&coefficients, &xs, 0.0, 2.0));
+        assert_eq!(Ok((0.0, 1.0)), min_max(&coefficients, &xs, 0.25, 1.0));
+        assert_eq!(Ok((0.75, 0.75)), min_max(&coefficients, &xs, 0.25, 0.25));
+        assert_eq!(Ok((0.0, 0.75)), min_max(&coefficients, &xs, 0.0, 0.25));
+        assert_eq!(Ok((-0.5, 0.0)), min_max(&coefficients, &xs, 1.0, 1.5));
+        assert_eq!(
+            Err(Error::InvalidRange),
+            min_max(&coefficients, &xs, 1.0, 0.5)
+        );
+        assert_eq!(
+            Err(Error::OutOfRange),
+            min_max(&coefficients, &xs, 1.0, 2.5)
+        );
+    }
+
+    #[test]
+    fn bounds_of_components() {
+        let coefficients = [
+            [
+                Segment::new(0.0, 4.0, -4.0, 0.0),
+                Segment::new(2.0, 0.0, 0.0, 0.0),
+            ],
+            [
+                Segment::new(0.0, -1.0, 0.0, 0.0),
+                Segment::new(-1.0, 0.0, 1.0, 0.0),
+            ],
+        ];
+        let ts = [0f32, 1.0, 2.0];
+        assert_eq!(Ok(([-1.0, -1.0], [1.0, 2.0])), bounds(&coefficients, &ts));
+        assert_eq!(
+            Err(Error::InvalidSliceLength),
+            bounds(&coefficients, &ts[..2])
+        );
+        assert_eq!(Err(Error::EmptyInput), bounds::<2>(&[], &ts[..1]));
+        assert_eq!(
+            Err(Error::NonIncreasingKnots { index: 2 }),
+            bounds(&coefficients, &[0.0, 1.0, 0.5])
+        );
+        assert_eq!(
+            Err(Error::NonIncreasingKnots { index: 1 }),
+            bounds(&coefficients, &[f32::NAN, 1.0, 2.0])
+        );
+    }
+}
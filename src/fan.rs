@@ -0,0 +1,190 @@
+//! Fan curves mapping a temperature to a PWM duty cycle, e.g. for the cooling
+//! fan of a power supply or a PC case.
+//!
+//! The curve is a monotone cubic through the `(temperature, duty)` points of a
+//! table, so the fan speeds up smoothly and never runs faster than the next
+//! point demands. Below the first point and above the last, the duty holds
+//! flat, and the curve runs into both plateaus with zero slope, without a kink
+//! that makes the fan audibly hunt around a threshold. Repeating a duty gives
+//! a flat stretch in between as well.
+
+use crate::monotone::monotone_slices;
+use crate::quantize::quantize;
+use crate::{eval_segment, find_segment, hermite, Error, Quantize};
+
+/// A fan curve through `N` points.
+///
+/// ```
+/// use cubic_splinterpol::fan::FanCurve;
+///
+/// // Quiet at 20 % up to 35 °C, flat out from 75 °C
+/// let curve = FanCurve::new(&[(35.0, 0.2), (50.0, 0.3), (65.0, 0.6), (75.0, 1.0)]).unwrap();
+/// assert_eq!(0.2, curve.duty(20.0));
+/// assert_eq!(0.3, curve.duty(50.0));
+/// assert_eq!(1.0, curve.duty(90.0));
+/// // Straight to the compare register of a timer counting to 999
+/// assert_eq!(300, curve.compare_value(50.0, 999));
+/// assert_eq!(77u8, curve.duty_code::<u8>(50.0));
+/// ```
+#[derive(Debug, Clone)]
+pub struct FanCurve<const N: usize> {
+    temperatures: [f32; N],
+    duties: [f32; N],
+    /// N-1 of N used
+    coefficients: [(f32, f32, f32, f32); N],
+}
+
+impl<const N: usize> FanCurve<N> {
+    /// A curve through the table `(temperature, duty)`, with strictly
+    /// increasing temperatures and duties from 0 to 1 which never decrease.
+    /// At least 2 points are needed.
+    ///
+    /// Fails with [`Error::InvalidRange`] if a duty is outside of `[0, 1]` or
+    /// below the one before.
+    pub fn new(table: &[(f32, f32); N]) -> Result<Self, Error> {
+        if N < 2 {
+            return Err(Error::InvalidSliceLength);
+        }
+        let mut temperatures = [0f32; N];
+        let mut duties = [0f32; N];
+        for (i, (temperature, duty)) in table.iter().enumerate() {
+            temperatures[i] = *temperature;
+            duties[i] = *duty;
+        }
+        let valid = duties.iter().all(|duty| (0f32..=1f32).contains(duty));
+        if !valid || duties.windows(2).any(|pair| pair[1] < pair[0]) {
+            return Err(Error::InvalidRange);
+        }
+        let mut coefficients = [(0f32, 0f32, 0f32, 0f32); N];
+        monotone_slices(&temperatures, &duties, &mut coefficients[..N - 1])?;
+        // Enter the plateaus at both ends with zero slope; lowering the
+        // slope at the end of a monotone segment keeps it monotone
+        let h = |i: usize| temperatures[i + 1] - temperatures[i];
+        let end_slope = |i: usize| {
+            if i + 1 < N - 1 {
+                coefficients[i + 1].1
+            } else {
+                0f32
+            }
+        };
+        let first = hermite(duties[0], duties[1], 0f32, end_slope(0), h(0));
+        coefficients[0] = first;
+        if N > 2 {
+            let last = N - 2;
+            let start_slope = coefficients[last].1;
+            coefficients[last] = hermite(duties[last], duties[N - 1], start_slope, 0f32, h(last));
+        }
+        Ok(Self {
+            temperatures,
+            duties,
+            coefficients,
+        })
+    }
+
+    /// The coefficients of the N-1 segments between the points.
+    pub fn coefficients(&self) -> &[(f32, f32, f32, f32)] {
+        &self.coefficients[..N - 1]
+    }
+
+    /// Duty cycle from 0 to 1 at `temperature`. A NaN temperature, e.g. from
+    /// a failed sensor, gives full duty to be safe.
+    pub fn duty(&self, temperature: f32) -> f32 {
+        if temperature.is_nan() || temperature >= self.temperatures[N - 1] {
+            return self.duties[N - 1];
+        }
+        if temperature <= self.temperatures[0] {
+            return self.duties[0];
+        }
+        let segment = find_segment(&self.temperatures, temperature);
+        let t = temperature - self.temperatures[segment];
+        // Rounding must not leave the range of the table
+        eval_segment(self.coefficients[segment], t).clamp(self.duties[0], self.duties[N - 1])
+    }
+
+    /// Duty cycle at `temperature` as the nearest code of `T`, with full duty
+    /// at [`Quantize::MAX_CODE`].
+    pub fn duty_code<T: Quantize>(&self, temperature: f32) -> T {
+        quantize(self.duty(temperature), 0f32, 1f32)
+    }
+
+    /// Duty cycle at `temperature` as the nearest compare value of a PWM
+    /// timer counting from 0 to `top`, with `top` for full duty.
+    pub fn compare_value(&self, temperature: f32, top: u32) -> u32 {
+        let value = libm::roundf(self.duty(temperature) * top as f32);
+        // Within [0, top] for a duty within [0, 1]
+        (value as u32).min(top)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::eval_segment_derivative;
+
+    const TABLE: [(f32, f32); 5] = [
+        (30.0, 0.25),
+        (45.0, 0.4),
+        (55.0, 0.4),
+        (65.0, 0.7),
+        (80.0, 1.0),
+    ];
+
+    #[test]
+    fn monotone_with_flat_plateaus() {
+        let curve = FanCurve::new(&TABLE).unwrap();
+        for (temperature, duty) in &TABLE {
+            assert!((curve.duty(*temperature) - duty).abs() < 1e-6);
+        }
+        let mut previous = 0f32;
+        for i in 0..=1000 {
+            let duty = curve.duty(20.0 + i as f32 * 0.07);
+            assert!(duty >= previous);
+            previous = duty;
+        }
+        // Flat between the equal duties
+        for i in 0..=10 {
+            assert_eq!(0.4, curve.duty(45.0 + i as f32));
+        }
+        // Zero slope into the plateaus at both ends
+        let coefficients = curve.coefficients();
+        assert_eq!(0.0, coefficients[0].1);
+        assert!(eval_segment_derivative(coefficients[3], 15.0).abs() < 1e-6);
+        assert_eq!(0.25, curve.duty(-10.0));
+        assert_eq!(1.0, curve.duty(f32::NAN));
+    }
+
+    #[test]
+    fn quantized_duty() {
+        let curve = FanCurve::new(&TABLE).unwrap();
+        assert_eq!(
+            0,
+            FanCurve::new(&[(0.0, 0.0), (1.0, 1.0)])
+                .unwrap()
+                .compare_value(0.0, 1000)
+        );
+        assert_eq!(1000, curve.compare_value(100.0, 1000));
+        assert_eq!(400, curve.compare_value(50.0, 1000));
+        assert_eq!(255u8, curve.duty_code::<u8>(85.0));
+        assert_eq!(crate::U12(1638), curve.duty_code::<crate::U12>(50.0));
+    }
+
+    #[test]
+    fn rejects_invalid_tables() {
+        assert_eq!(
+            Err(Error::InvalidRange),
+            FanCurve::new(&[(0.0, 0.5), (1.0, 0.4)]).map(|_| ())
+        );
+        assert_eq!(
+            Err(Error::InvalidRange),
+            FanCurve::new(&[(0.0, 0.5), (1.0, 1.5)]).map(|_| ())
+        );
+        assert_eq!(
+            Err(Error::NonIncreasingKnots { index: 1 }),
+            FanCurve::new(&[(1.0, 0.5), (1.0, 0.6)]).map(|_| ())
+        );
+        assert_eq!(
+            Err(Error::InvalidSliceLength),
+            FanCurve::new(&[(1.0, 0.5)]).map(|_| ())
+        );
+    }
+}
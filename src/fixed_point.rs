@@ -0,0 +1,292 @@
+//! Cubic spline interpolation in Q16.16 fixed-point arithmetic, for cores
+//! without a floating point unit.
+//!
+//! This mirrors the `f32` pipeline: the tridiagonal system is solved with the
+//! Thomas algorithm, coefficients are stored per segment, and evaluation uses
+//! Horner's scheme. All arithmetic is checked; results which do not fit into
+//! Q16.16 are reported as [`Error::Overflow`] instead of silently wrapping.
+//!
+//! Every step from the coefficients through evaluation to
+//! [`sample_uniform_fixed`] is integer arithmetic, so no float emulation is
+//! linked in, e.g. on Cortex-M0 and M0+ parts. Inputs can be converted from
+//! integers, such as ADC codes, with `I16F16::from_num` or `from_bits`.
+//!
+//! # Scaling
+//!
+//! Q16.16 holds values of magnitude below 32768 to a resolution of 2^-16,
+//! which bounds the usable knot spacings h:
+//!
+//! - The secants `Δy / h` and three times their differences must stay below
+//!   32768, and the cubic coefficients grow like `Δy / h^3`, so small
+//!   spacings with large steps in y overflow.
+//! - Every coefficient is rounded to 2^-16, and the rounding of the cubic
+//!   coefficient is multiplied by up to `h^3` on evaluation, e.g. about 0.06
+//!   at the end of a segment of width 16, so large spacings lose precision.
+//!
+//! Spacings between about 1/8 and 8 work well for values up to a few
+//! thousand. Rescale x to get there, e.g. seconds instead of milliseconds, or
+//! the index of the knot for uniform knots, and y for example to volts
+//! instead of millivolts.
+
+use fixed::types::I16F16;
+
+use crate::{find_segment, Error};
+
+/// Coefficients `(a, b, c, d)` of one fixed-point cubic segment
+/// `a + b*t + c*t^2 + d*t^3`.
+pub type FixedCoefficients = (I16F16, I16F16, I16F16, I16F16);
+
+fn add(a: I16F16, b: I16F16) -> Result<I16F16, Error> {
+    a.checked_add(b).ok_or(Error::Overflow)
+}
+
+fn sub(a: I16F16, b: I16F16) -> Result<I16F16, Error> {
+    a.checked_sub(b).ok_or(Error::Overflow)
+}
+
+fn mul(a: I16F16, b: I16F16) -> Result<I16F16, Error> {
+    a.checked_mul(b).ok_or(Error::Overflow)
+}
+
+fn div(a: I16F16, b: I16F16) -> Result<I16F16, Error> {
+    a.checked_div(b).ok_or(Error::Overflow)
+}
+
+fn h(i: usize, vals: &[I16F16]) -> Result<I16F16, Error> {
+    sub(vals[i + 1], vals[i])
+}
+
+/// Check that the knots are strictly increasing, as the float path does.
+fn check_increasing(xs: &[I16F16]) -> Result<(), Error> {
+    match xs.windows(2).position(|pair| pair[0] >= pair[1]) {
+        Some(i) => Err(Error::NonIncreasingKnots { index: i + 1 }),
+        None => Ok(()),
+    }
+}
+
+/// Solve Ax = r for A being tridiagonal and symmetric in fixed-point.
+///
+/// `main` and `r` are used as scratch space and are overwritten. A zero pivot
+/// is reported as [`Error::SingularSystem`].
+pub fn thomas_algorithm_symmetric_fixed(
+    sub_diagonal: &[I16F16],
+    main: &mut [I16F16],
+    r: &mut [I16F16],
+    x: &mut [I16F16],
+) -> Result<(), Error> {
+    let n = main.len();
+    if n == 0 {
+        return Err(Error::InvalidSliceLength);
+    }
+    if sub_diagonal.len() != n - 1 {
+        return Err(Error::InvalidSliceLength);
+    }
+    if r.len() != n {
+        return Err(Error::InvalidSliceLength);
+    }
+    if x.len() != n {
+        return Err(Error::InvalidSliceLength);
+    }
+    for i in 1..n {
+        if main[i - 1] == 0 {
+            return Err(Error::SingularSystem { row: i - 1 });
+        }
+        let mc = div(sub_diagonal[i - 1], main[i - 1])?;
+        main[i] = sub(main[i], mul(mc, sub_diagonal[i - 1])?)?;
+        r[i] = sub(r[i], mul(mc, r[i - 1])?)?;
+    }
+    if main[n - 1] == 0 {
+        return Err(Error::SingularSystem { row: n - 1 });
+    }
+    x[n - 1] = div(r[n - 1], main[n - 1])?;
+
+    for i in (0..n - 1).rev() {
+        x[i] = div(sub(r[i], mul(sub_diagonal[i], x[i + 1])?)?, main[i])?;
+    }
+    Ok(())
+}
+
+/// Given xs and ys of same length n, calculate the fixed-point coefficients
+/// of the n-1 cubic polynomials of the natural spline through the points.
+///
+/// Fails with [`Error::NonIncreasingKnots`] unless xs is strictly increasing.
+pub fn splinterpol_fixed<const N: usize>(
+    xs: &[I16F16; N],
+    ys: &[I16F16; N],
+    coefficients: &mut [FixedCoefficients],
+) -> Result<(), Error> {
+    if N < 3 || coefficients.len() != N - 1 {
+        return Err(Error::InvalidSliceLength);
+    }
+    check_increasing(xs)?;
+    let two = I16F16::from_num(2);
+    let three = I16F16::from_num(3);
+
+    // Array size const expression workaround
+    let mut diagonal = [I16F16::ZERO; N];
+    let diagonal = &mut diagonal[0..N - 2];
+    let mut r = [I16F16::ZERO; N];
+    let r = &mut r[0..N - 2];
+    let mut sub_diagonal = [I16F16::ZERO; N];
+    let sub_diagonal = &mut sub_diagonal[0..N - 3];
+    for i in 0..N - 2 {
+        diagonal[i] = mul(two, add(h(i, xs)?, h(i + 1, xs)?)?)?;
+        let div1 = div(sub(ys[i + 2], ys[i + 1])?, h(i + 1, xs)?)?;
+        let div2 = div(sub(ys[i + 1], ys[i])?, h(i, xs)?)?;
+        r[i] = mul(three, sub(div1, div2)?)?;
+    }
+    for (i, elem) in sub_diagonal.iter_mut().enumerate() {
+        *elem = h(i + 1, xs)?;
+    }
+
+    let mut c = [I16F16::ZERO; N];
+    thomas_algorithm_symmetric_fixed(sub_diagonal, diagonal, r, &mut c[1..N - 1])?;
+
+    for i in 0..N - 1 {
+        let h_i = h(i, xs)?;
+        let slope = div(sub(ys[i + 1], ys[i])?, h_i)?;
+        let b = sub(
+            slope,
+            mul(div(add(mul(two, c[i])?, c[i + 1])?, three)?, h_i)?,
+        )?;
+        let d = div(sub(c[i + 1], c[i])?, mul(three, h_i)?)?;
+        coefficients[i] = (ys[i], b, c[i], d);
+    }
+    Ok(())
+}
+
+/// Evaluate a fixed-point segment at offset `t` from its start with Horner's
+/// scheme. Intermediate results saturate instead of wrapping.
+pub fn eval_segment_fixed(coefficients: FixedCoefficients, t: I16F16) -> I16F16 {
+    let (a, b, c, d) = coefficients;
+    let acc = d.saturating_mul(t).saturating_add(c);
+    let acc = acc.saturating_mul(t).saturating_add(b);
+    acc.saturating_mul(t).saturating_add(a)
+}
+
+/// Evaluate the fixed-point spline at global position x. Positions outside of
+/// the knots are evaluated on the first or last polynomial.
+///
+/// As with the `f32` pipeline, the knots must be strictly increasing, as
+/// [`splinterpol_fixed`] checks, and are not checked again here.
+pub fn eval_fixed(
+    coefficients: &[FixedCoefficients],
+    xs: &[I16F16],
+    x: I16F16,
+) -> Result<I16F16, Error> {
+    if coefficients.is_empty() {
+        return Err(Error::EmptyInput);
+    }
+    if xs.len() != coefficients.len() + 1 {
+        return Err(Error::InvalidSliceLength);
+    }
+    let segment = find_segment(xs, x);
+    Ok(eval_segment_fixed(
+        coefficients[segment],
+        x.saturating_sub(xs[segment]),
+    ))
+}
+
+/// Sample the fixed-point spline uniformly into the buffer, from the first to
+/// the last knot inclusive, like
+/// [`sample_uniform_into`](crate::sample_uniform_into). The positions are
+/// computed exactly from the sample indices in integer arithmetic. The knots
+/// must be strictly increasing, as for [`eval_fixed`].
+pub fn sample_uniform_fixed(
+    buffer: &mut [I16F16],
+    coefficients: &[FixedCoefficients],
+    xs: &[I16F16],
+) -> Result<(), Error> {
+    if coefficients.is_empty() {
+        return Err(Error::EmptyInput);
+    }
+    if xs.len() != coefficients.len() + 1 {
+        return Err(Error::InvalidSliceLength);
+    }
+    let first = xs[0].to_bits() as i64;
+    let range = xs[xs.len() - 1].to_bits() as i64 - first;
+    let intervals = buffer.len().saturating_sub(1).max(1) as i64;
+    for (index, elem) in buffer.iter_mut().enumerate() {
+        // Between the first and last knot, so it fits
+        let x = I16F16::from_bits((first + range * index as i64 / intervals) as i32);
+        let segment = find_segment(xs, x);
+        *elem = eval_segment_fixed(coefficients[segment], x.saturating_sub(xs[segment]));
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn fixed_matches_float() {
+        let xs = [0.5f32, 1f32, 2f32, 3f32, 4.5f32, 5f32, 6f32, 7f32];
+        let ys = [0f32, 0f32, 1f32, 2f32, 4f32, 7f32, 9f32, 10f32];
+        let mut expected = [(0f32, 0f32, 0f32, 0f32); 7];
+        crate::splinterpol::<8>(&xs, &ys, &mut expected).unwrap();
+
+        let fxs = xs.map(I16F16::from_num);
+        let fys = ys.map(I16F16::from_num);
+        let mut coeffs = [(I16F16::ZERO, I16F16::ZERO, I16F16::ZERO, I16F16::ZERO); 7];
+        splinterpol_fixed::<8>(&fxs, &fys, &mut coeffs).unwrap();
+
+        for (f, e) in coeffs.iter().zip(&expected) {
+            assert!((f.0.to_num::<f32>() - e.0).abs() < 0.001);
+            assert!((f.1.to_num::<f32>() - e.1).abs() < 0.001);
+            assert!((f.2.to_num::<f32>() - e.2).abs() < 0.001);
+            assert!((f.3.to_num::<f32>() - e.3).abs() < 0.001);
+        }
+
+        for (x, y) in fxs.iter().zip(&ys) {
+            let value = eval_fixed(&coeffs, &fxs, *x).unwrap();
+            assert!((value.to_num::<f32>() - y).abs() < 0.001);
+        }
+    }
+
+    #[test]
+    fn fixed_rejects_non_increasing_knots() {
+        let xs = [0, 1, 1, 2].map(I16F16::from_num);
+        let ys = [0, 1, 2, 3].map(I16F16::from_num);
+        let mut coeffs = [(I16F16::ZERO, I16F16::ZERO, I16F16::ZERO, I16F16::ZERO); 3];
+        assert_eq!(
+            Err(Error::NonIncreasingKnots { index: 2 }),
+            splinterpol_fixed::<4>(&xs, &ys, &mut coeffs)
+        );
+        // Same index as the float path
+        let decreasing = [0f32, 2.0, 1.0, 3.0];
+        let mut float_coeffs = [(0f32, 0f32, 0f32, 0f32); 3];
+        assert_eq!(
+            Err(Error::NonIncreasingKnots { index: 2 }),
+            crate::splinterpol(&decreasing, &[0f32, 1.0, 2.0, 3.0], &mut float_coeffs)
+        );
+        assert_eq!(
+            Err(Error::NonIncreasingKnots { index: 2 }),
+            splinterpol_fixed::<4>(&decreasing.map(I16F16::from_num), &ys, &mut coeffs)
+        );
+    }
+
+    #[test]
+    fn sample_uniform_fixed_matches_float() {
+        let xs = [0f32, 1.5, 2f32, 4f32];
+        let ys = [1f32, 3f32, 2f32, -1f32];
+        let mut expected_coeffs = [(0f32, 0f32, 0f32, 0f32); 3];
+        crate::splinterpol::<4>(&xs, &ys, &mut expected_coeffs).unwrap();
+        let mut expected = [0f32; 33];
+        crate::sample_uniform_into(&mut expected, &expected_coeffs, &xs).unwrap();
+
+        let fxs = xs.map(I16F16::from_num);
+        let mut coeffs = [(I16F16::ZERO, I16F16::ZERO, I16F16::ZERO, I16F16::ZERO); 3];
+        splinterpol_fixed::<4>(&fxs, &ys.map(I16F16::from_num), &mut coeffs).unwrap();
+        let mut samples = [I16F16::ZERO; 33];
+        sample_uniform_fixed(&mut samples, &coeffs, &fxs).unwrap();
+        for (sample, expected) in samples.iter().zip(&expected) {
+            assert!((sample.to_num::<f32>() - expected).abs() < 0.001);
+        }
+        assert_eq!(I16F16::from_num(1), samples[0]);
+        assert_eq!(
+            Err(Error::InvalidSliceLength),
+            sample_uniform_fixed(&mut samples, &coeffs, &fxs[..3])
+        );
+    }
+}
@@ -0,0 +1,164 @@
+//! Gain scheduling of PID controllers, e.g. by airspeed for a flight
+//! controller or by RPM for an engine.
+//!
+//! Every gain is interpolated through its values at the scheduling points of
+//! a table, so between the points the gains change smoothly instead of the
+//! kinks of a linear lookup, whose jumping derivative upsets the loop. The
+//! interpolant of every gain is a monotone cubic: it never overshoots the
+//! table between two points, so a gain tuned at zero never turns negative.
+
+use crate::monotone::monotone_slices;
+use crate::{eval_segment, find_segment, Error};
+
+/// A set of PID gains.
+#[derive(Debug, Clone, Copy, PartialEq, Default)]
+pub struct Gains {
+    /// Proportional gain
+    pub kp: f32,
+    /// Integral gain
+    pub ki: f32,
+    /// Derivative gain
+    pub kd: f32,
+}
+
+impl Gains {
+    /// Gains from the proportional, integral and derivative gain.
+    pub const fn new(kp: f32, ki: f32, kd: f32) -> Self {
+        Self { kp, ki, kd }
+    }
+}
+
+/// A gain schedule through `N` table entries.
+///
+/// ```
+/// use cubic_splinterpol::gain_schedule::{GainSchedule, Gains};
+///
+/// // Pitch gains by airspeed in m/s
+/// let schedule = GainSchedule::new(&[
+///     (10.0, Gains::new(0.8, 0.20, 0.05)),
+///     (20.0, Gains::new(0.5, 0.10, 0.03)),
+///     (35.0, Gains::new(0.3, 0.05, 0.02)),
+/// ])
+/// .unwrap();
+/// assert_eq!(Gains::new(0.5, 0.10, 0.03), schedule.gains(20.0));
+/// let gains = schedule.gains(15.0);
+/// assert!(gains.kp < 0.8 && gains.kp > 0.5);
+/// // Held beyond the table
+/// assert_eq!(Gains::new(0.3, 0.05, 0.02), schedule.gains(50.0));
+/// ```
+#[derive(Debug, Clone)]
+pub struct GainSchedule<const N: usize> {
+    points: [f32; N],
+    /// N-1 of N used, kp, ki and kd per segment
+    coefficients: [[(f32, f32, f32, f32); 3]; N],
+    /// Gains of the last entry, exact unlike the end of the last segment
+    last: Gains,
+}
+
+impl<const N: usize> GainSchedule<N> {
+    /// A schedule through the table `(operating point, gains)`, with strictly
+    /// increasing operating points. At least 2 entries are needed.
+    pub fn new(table: &[(f32, Gains); N]) -> Result<Self, Error> {
+        if N < 2 {
+            return Err(Error::InvalidSliceLength);
+        }
+        let mut points = [0f32; N];
+        for (point, (entry, _)) in points.iter_mut().zip(table) {
+            *point = *entry;
+        }
+        let mut coefficients = [[(0f32, 0f32, 0f32, 0f32); 3]; N];
+        let mut values = [0f32; N];
+        let mut gain_coefficients = [(0f32, 0f32, 0f32, 0f32); N];
+        for gain in 0..3 {
+            for (value, (_, gains)) in values.iter_mut().zip(table) {
+                *value = [gains.kp, gains.ki, gains.kd][gain];
+            }
+            monotone_slices(&points, &values, &mut gain_coefficients[..N - 1])?;
+            for (segment, polynomial) in coefficients.iter_mut().zip(&gain_coefficients) {
+                segment[gain] = *polynomial;
+            }
+        }
+        Ok(Self {
+            points,
+            coefficients,
+            last: table[N - 1].1,
+        })
+    }
+
+    /// The operating points of the table.
+    pub fn points(&self) -> &[f32; N] {
+        &self.points
+    }
+
+    /// The gains at `operating_point`, which is clamped to the table, so the
+    /// gains of the first or last entry hold beyond it. A NaN operating point
+    /// gives the gains of the first entry.
+    pub fn gains(&self, operating_point: f32) -> Gains {
+        if operating_point >= self.points[N - 1] {
+            return self.last;
+        }
+        let x = operating_point.max(self.points[0]);
+        let segment = find_segment(&self.points, x);
+        let [kp, ki, kd] = self.coefficients[segment];
+        let t = x - self.points[segment];
+        Gains {
+            kp: eval_segment(kp, t),
+            ki: eval_segment(ki, t),
+            kd: eval_segment(kd, t),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const TABLE: [(f32, Gains); 4] = [
+        (1000.0, Gains::new(2.0, 0.5, 0.0)),
+        (2000.0, Gains::new(1.5, 0.5, 0.0)),
+        (3000.0, Gains::new(1.0, 0.3, 0.1)),
+        (5000.0, Gains::new(0.8, 0.1, 0.1)),
+    ];
+
+    #[test]
+    fn smooth_and_within_table() {
+        let schedule = GainSchedule::new(&TABLE).unwrap();
+        for (point, gains) in &TABLE {
+            let at = schedule.gains(*point);
+            assert!((at.kp - gains.kp).abs() < 1e-6);
+            assert!((at.ki - gains.ki).abs() < 1e-6);
+            assert!((at.kd - gains.kd).abs() < 1e-6);
+        }
+        let mut previous = schedule.gains(1000.0);
+        for rpm in (1010..=5000).step_by(10) {
+            let gains = schedule.gains(rpm as f32);
+            // Monotone where the table is, no overshoot on flat stretches
+            assert!(gains.kp <= previous.kp + 1e-6);
+            assert!(gains.ki <= previous.ki + 1e-6);
+            assert!((0f32..=0.1 + 1e-6).contains(&gains.kd));
+            if rpm <= 2000 {
+                assert_eq!(0.5, gains.ki);
+            }
+            // No jumps
+            assert!((gains.kp - previous.kp).abs() < 0.01);
+            previous = gains;
+        }
+    }
+
+    #[test]
+    fn clamps_operating_point() {
+        let schedule = GainSchedule::new(&TABLE).unwrap();
+        assert_eq!(TABLE[0].1, schedule.gains(0.0));
+        assert_eq!(TABLE[0].1, schedule.gains(f32::NAN));
+        assert_eq!(TABLE[3].1, schedule.gains(9000.0));
+        assert_eq!(1000.0, schedule.points()[0]);
+        assert_eq!(
+            Err(Error::NonIncreasingKnots { index: 1 }),
+            GainSchedule::new(&[(1.0, Gains::default()), (1.0, Gains::default())]).map(|_| ())
+        );
+        assert_eq!(
+            Err(Error::InvalidSliceLength),
+            GainSchedule::new(&[(1.0, Gains::default())]).map(|_| ())
+        );
+    }
+}
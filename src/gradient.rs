@@ -0,0 +1,184 @@
+//! Color gradients through color stops, rendered straight into the byte
+//! buffers of LED strips such as WS2812.
+
+use crate::angles::unwrap;
+use crate::plot_spline::uniform_x;
+use crate::quantize::quantize;
+use crate::{check_increasing, eval_segment, find_segment, splinterpol_multi, Error, Segment};
+
+/// Color space in which a [`Gradient`] interpolates its stops.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum ColorSpace {
+    /// Red, green and blue, each from 0 to 1
+    #[default]
+    Rgb,
+    /// Hue, saturation and value, each from 0 to 1, with the hue wrapping
+    /// around so that it takes the shorter way between stops
+    Hsv,
+}
+
+/// A smooth gradient through `S` color stops, a spline per channel on the
+/// positions of the stops.
+///
+/// ```
+/// use cubic_splinterpol::gradient::{ColorSpace, Gradient};
+///
+/// let sunset = Gradient::new(
+///     &[(0f32, [1.0, 0.8, 0.2]), (0.5, [1.0, 0.3, 0.0]), (1.0, [0.3, 0.0, 0.4])],
+///     ColorSpace::Rgb,
+/// )
+/// .unwrap();
+/// // 300 LEDs with 3 bytes each
+/// let mut strip = [0u8; 900];
+/// sunset.write_rgb(&mut strip, 2.2).unwrap();
+/// assert_eq!([255, 156, 7], strip[..3]);
+/// ```
+#[derive(Debug, Clone)]
+pub struct Gradient<const S: usize> {
+    positions: [f32; S],
+    /// S-1 of S used
+    coefficients: [[Segment; 3]; S],
+    space: ColorSpace,
+}
+
+impl<const S: usize> Gradient<S> {
+    /// A gradient through the stops `(position, color)`, with strictly
+    /// increasing positions and colors in `space`. Two stops give a straight
+    /// line per channel.
+    pub fn new(stops: &[(f32, [f32; 3]); S], space: ColorSpace) -> Result<Self, Error> {
+        if S < 2 {
+            return Err(Error::InvalidSliceLength);
+        }
+        let mut positions = [0f32; S];
+        let mut colors = [[0f32; 3]; S];
+        for (i, (position, color)) in stops.iter().enumerate() {
+            positions[i] = *position;
+            colors[i] = *color;
+        }
+        check_increasing(&positions)?;
+        if space == ColorSpace::Hsv {
+            let mut hues = colors.map(|color| color[0]);
+            unwrap(&mut hues, 1f32)?;
+            for (color, hue) in colors.iter_mut().zip(hues) {
+                color[0] = hue;
+            }
+        }
+        let mut coefficients = [[Segment::default(); 3]; S];
+        if S == 2 {
+            let width = positions[1] - positions[0];
+            for (channel, segment) in coefficients[0].iter_mut().enumerate() {
+                let slope = (colors[1][channel] - colors[0][channel]) / width;
+                *segment = Segment::new(colors[0][channel], slope, 0f32, 0f32);
+            }
+        } else {
+            splinterpol_multi(&positions, &colors, &mut coefficients)?;
+        }
+        Ok(Self {
+            positions,
+            coefficients,
+            space,
+        })
+    }
+
+    /// The RGB color at `position`, clamped to the first and last stop. The
+    /// channels may slightly overshoot `[0, 1]` between stops.
+    pub fn color(&self, position: f32) -> [f32; 3] {
+        let clamped = position.clamp(self.positions[0], self.positions[S - 1]);
+        let segment = find_segment(&self.positions, clamped);
+        let t = clamped - self.positions[segment];
+        let color = self.coefficients[segment].map(|channel| eval_segment(channel.to_tuple(), t));
+        match self.space {
+            ColorSpace::Rgb => color,
+            ColorSpace::Hsv => hsv_to_rgb(color),
+        }
+    }
+
+    /// Render the gradient from the first to the last stop to `buffer`, 3
+    /// bytes of red, green and blue per LED, corrected by `gamma`, e.g. 2.2
+    /// for the nonlinear brightness of LEDs, or 1 for none. Strips expecting
+    /// another byte order, such as GRB, need the channel stops swapped.
+    ///
+    /// Fails with [`Error::InvalidSliceLength`] unless the buffer holds a
+    /// multiple of 3 bytes, and with [`Error::InvalidLimit`] for a gamma
+    /// which is not strictly positive.
+    pub fn write_rgb(&self, buffer: &mut [u8], gamma: f32) -> Result<(), Error> {
+        if !buffer.len().is_multiple_of(3) {
+            return Err(Error::InvalidSliceLength);
+        }
+        if gamma.is_nan() || gamma <= 0f32 {
+            return Err(Error::InvalidLimit);
+        }
+        let count = buffer.len() / 3;
+        for (index, led) in buffer.chunks_exact_mut(3).enumerate() {
+            let color = self.color(uniform_x(&self.positions, count, index));
+            for (byte, channel) in led.iter_mut().zip(color) {
+                let corrected = libm::powf(channel.clamp(0f32, 1f32), gamma);
+                *byte = quantize(corrected, 0f32, 1f32);
+            }
+        }
+        Ok(())
+    }
+}
+
+/// RGB of the HSV color, with the hue wrapped into `[0, 1)` and saturation
+/// and value clamped to `[0, 1]`.
+fn hsv_to_rgb(hsv: [f32; 3]) -> [f32; 3] {
+    let hue = hsv[0] - libm::floorf(hsv[0]);
+    let saturation = hsv[1].clamp(0f32, 1f32);
+    let value = hsv[2].clamp(0f32, 1f32);
+    let sector = hue * 6f32;
+    let channel = |offset: f32| {
+        // Distance from the sector where the channel peaks
+        let k = (offset + sector) % 6f32;
+        let ramp = k.min(4f32 - k).clamp(0f32, 1f32);
+        value - value * saturation * ramp
+    };
+    [channel(5f32), channel(3f32), channel(1f32)]
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn rgb_stops_and_rendering() {
+        let stops = [(0f32, [0.0, 0.0, 1.0]), (2.0, [1.0, 0.0, 0.0])];
+        let gradient = Gradient::new(&stops, ColorSpace::Rgb).unwrap();
+        assert_eq!([0.5, 0.0, 0.5], gradient.color(1.0));
+        assert_eq!([1.0, 0.0, 0.0], gradient.color(5.0));
+        let mut strip = [0u8; 9];
+        gradient.write_rgb(&mut strip, 1.0).unwrap();
+        assert_eq!([0, 0, 255, 128, 0, 128, 255, 0, 0], strip);
+        gradient.write_rgb(&mut strip, 2.0).unwrap();
+        assert_eq!([64, 0, 64], strip[3..6]);
+
+        let mut odd = [0u8; 4];
+        assert_eq!(
+            Err(Error::InvalidSliceLength),
+            gradient.write_rgb(&mut odd, 1.0)
+        );
+        assert_eq!(
+            Err(Error::InvalidLimit),
+            gradient.write_rgb(&mut strip, 0.0)
+        );
+    }
+
+    #[test]
+    fn hsv_takes_short_way_round() {
+        assert_eq!([1.0, 0.0, 0.0], hsv_to_rgb([0.0, 1.0, 1.0]));
+        assert_eq!([0.0, 1.0, 0.0], hsv_to_rgb([1.0 / 3.0, 1.0, 1.0]));
+        assert_eq!([0.0, 0.0, 1.0], hsv_to_rgb([2.0 / 3.0, 1.0, 1.0]));
+        assert_eq!([0.5, 0.5, 0.5], hsv_to_rgb([0.3, 0.0, 0.5]));
+        // Magenta to orange through red, not through green
+        let stops = [
+            (0f32, [0.9, 1.0, 1.0]),
+            (1.0, [0.0, 1.0, 1.0]),
+            (2.0, [0.1, 1.0, 1.0]),
+        ];
+        let gradient = Gradient::new(&stops, ColorSpace::Hsv).unwrap();
+        for i in 0..=20 {
+            let [red, green, blue] = gradient.color(i as f32 / 10f32);
+            assert!(red > 0.99 && green < 0.7 && blue < 0.7, "at {}", i);
+        }
+    }
+}
@@ -0,0 +1,135 @@
+use embedded_graphics::prelude::*;
+use embedded_graphics::primitives::{Line, PrimitiveStyle, Rectangle};
+
+use crate::{check_range, check_spline, eval_segment, find_segment, Error};
+
+/// A spline drawn as a polyline onto an `embedded-graphics` [`DrawTarget`].
+///
+/// The curve is sampled once per pixel column of `area`, from the first to the
+/// last knot. The value range `[y_min, y_max]` is mapped onto the rows of
+/// `area`, with `y_max` on top; values outside of that range are clamped to
+/// the top or bottom row.
+#[derive(Debug, Clone, Copy)]
+pub struct SplineCurve<'a, C: PixelColor> {
+    coefficients: &'a [(f32, f32, f32, f32)],
+    xs: &'a [f32],
+    area: Rectangle,
+    y_min: f32,
+    y_max: f32,
+    color: C,
+    stroke_width: u32,
+}
+
+impl<'a, C: PixelColor> SplineCurve<'a, C> {
+    /// Construct a curve for the spline given by `coefficients` and the knots
+    /// `xs`, drawn with a line of the given color and width.
+    pub fn new(
+        coefficients: &'a [(f32, f32, f32, f32)],
+        xs: &'a [f32],
+        area: Rectangle,
+        y_min: f32,
+        y_max: f32,
+        color: C,
+        stroke_width: u32,
+    ) -> Result<Self, Error> {
+        check_spline(coefficients, xs)?;
+        check_range(y_min, y_max)?;
+        Ok(Self {
+            coefficients,
+            xs,
+            area,
+            y_min,
+            y_max,
+            color,
+            stroke_width,
+        })
+    }
+
+    /// The pixel at which the curve crosses the given column of the area.
+    fn point(&self, column: u32) -> Point {
+        let first = self.xs[0];
+        let x_range = self.xs[self.xs.len() - 1] - first;
+        let columns = self.area.size.width.saturating_sub(1).max(1) as f32;
+        let x = first + x_range * (column as f32 / columns);
+        let segment = find_segment(self.xs, x);
+        let y = eval_segment(self.coefficients[segment], x - self.xs[segment]);
+
+        let rows = self.area.size.height.saturating_sub(1) as f32;
+        let fraction = ((self.y_max - y) / (self.y_max - self.y_min)).clamp(0f32, 1f32);
+        // f32::round not available in no_std, but the row is never negative
+        let row = (fraction * rows + 0.5) as i32;
+        self.area.top_left + Point::new(column as i32, row)
+    }
+}
+
+impl<'a, C: PixelColor> Drawable for SplineCurve<'a, C> {
+    type Color = C;
+    type Output = ();
+
+    fn draw<D>(&self, target: &mut D) -> Result<Self::Output, D::Error>
+    where
+        D: DrawTarget<Color = Self::Color>,
+    {
+        let width = self.area.size.width;
+        if width == 0 || self.area.size.height == 0 {
+            return Ok(());
+        }
+        let mut previous = self.point(0);
+        if width == 1 {
+            return Pixel(previous, self.color).draw(target);
+        }
+        let style = PrimitiveStyle::with_stroke(self.color, self.stroke_width);
+        for column in 1..width {
+            let current = self.point(column);
+            Line::new(previous, current)
+                .into_styled(style)
+                .draw(target)?;
+            previous = current;
+        }
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use embedded_graphics::mock_display::MockDisplay;
+    use embedded_graphics::pixelcolor::BinaryColor;
+
+    #[test]
+    fn draw_straight_line() {
+        let xs = [0f32, 1f32];
+        let coeffs = [(0f32, 1f32, 0f32, 0f32)];
+        let area = Rectangle::new(Point::zero(), Size::new(5, 5));
+        let curve = SplineCurve::new(&coeffs, &xs, area, 0.0, 1.0, BinaryColor::On, 1).unwrap();
+
+        let mut display = MockDisplay::new();
+        display.set_allow_overdraw(true);
+        curve.draw(&mut display).unwrap();
+        display.assert_pattern(&[
+            "    #", //
+            "   # ", //
+            "  #  ", //
+            " #   ", //
+            "#    ", //
+        ]);
+    }
+
+    #[test]
+    fn clamp_to_area() {
+        let xs = [0f32, 1f32, 2f32];
+        let coeffs = [(5f32, 0f32, 0f32, 0f32), (-5f32, 0f32, 0f32, 0f32)];
+        let area = Rectangle::new(Point::new(1, 1), Size::new(3, 3));
+        let curve = SplineCurve::new(&coeffs, &xs, area, 0.0, 1.0, BinaryColor::On, 1).unwrap();
+
+        let mut display = MockDisplay::new();
+        display.set_allow_overdraw(true);
+        curve.draw(&mut display).unwrap();
+        display.assert_pattern(&[
+            "    ", //
+            " #  ", //
+            " #  ", //
+            "  ##", //
+        ]);
+    }
+}
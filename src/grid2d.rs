@@ -0,0 +1,259 @@
+//! Bicubic spline interpolation of values on a rectangular grid, e.g. a sensor
+//! compensation map over temperature and pressure.
+//!
+//! The interpolant is the tensor product of natural cubic splines: a spline
+//! along y through every row of the grid, and a spline along x through the
+//! values of the rows at the requested y. It passes through every grid value
+//! and has continuous second derivatives in both directions.
+
+use crate::{
+    check_increasing, eval_segment, eval_segment_derivative, find_segment, splinterpol_slices,
+    Boundary, Error, Solver,
+};
+
+/// Bicubic interpolation of the values `z[i][j]` at `(xs[i], ys[j])` on a grid
+/// of `M` by `N` knots. Both dimensions need at least 3 knots.
+///
+/// The splines along y are precomputed on construction. Evaluation solves the
+/// spline along x through `M` values, so it takes time and stack linear in `M`:
+/// put the dimension with fewer knots first.
+///
+/// ```
+/// use cubic_splinterpol::grid2d::Grid2d;
+///
+/// let temperatures = [-20f32, 0.0, 25.0, 60.0];
+/// let pressures = [0.5f32, 1.0, 1.5];
+/// let offsets = [
+///     [0.3f32, 0.2, 0.1],
+///     [0.1, 0.0, -0.1],
+///     [0.0, 0.0, 0.0],
+///     [-0.2, -0.4, -0.5],
+/// ];
+/// let map = Grid2d::new(&temperatures, &pressures, &offsets).unwrap();
+/// assert_eq!(Ok(-0.1), map.eval(0.0, 1.5));
+/// ```
+#[derive(Debug, Clone)]
+pub struct Grid2d<const M: usize, const N: usize> {
+    xs: [f32; M],
+    ys: [f32; N],
+    /// Coefficients of the spline along y through each row, N-1 of N used
+    rows: [[(f32, f32, f32, f32); N]; M],
+}
+
+impl<const M: usize, const N: usize> Grid2d<M, N> {
+    /// Precompute the interpolation of the values `z` over the strictly
+    /// increasing knots `xs` and `ys`.
+    pub fn new(xs: &[f32; M], ys: &[f32; N], z: &[[f32; N]; M]) -> Result<Self, Error> {
+        if M < 3 || N < 3 {
+            return Err(Error::InvalidSliceLength);
+        }
+        check_increasing(xs)?;
+        check_increasing(ys)?;
+        let mut rows = [[(0f32, 0f32, 0f32, 0f32); N]; M];
+        for (row, values) in rows.iter_mut().zip(z) {
+            splinterpol_slices::<N>(ys, values, row, Boundary::Natural, Solver::Thomas)?;
+        }
+        Ok(Self {
+            xs: *xs,
+            ys: *ys,
+            rows,
+        })
+    }
+
+    /// The knots along x.
+    pub fn xs(&self) -> &[f32; M] {
+        &self.xs
+    }
+
+    /// The knots along y.
+    pub fn ys(&self) -> &[f32; N] {
+        &self.ys
+    }
+
+    /// Interpolated value at `(x, y)`. Positions outside of the grid are
+    /// evaluated on the outermost polynomials.
+    pub fn eval(&self, x: f32, y: f32) -> Result<f32, Error> {
+        self.interpolate(x, y, false, false)
+    }
+
+    /// Partial derivative of the interpolant with respect to x at `(x, y)`.
+    pub fn partial_x(&self, x: f32, y: f32) -> Result<f32, Error> {
+        self.interpolate(x, y, true, false)
+    }
+
+    /// Partial derivative of the interpolant with respect to y at `(x, y)`.
+    pub fn partial_y(&self, x: f32, y: f32) -> Result<f32, Error> {
+        self.interpolate(x, y, false, true)
+    }
+
+    /// Value and gradient `(z, ∂z/∂x, ∂z/∂y)` of the interpolant at `(x, y)`,
+    /// e.g. for the Jacobian of a measurement model in a Kalman filter. This
+    /// takes two splines along x instead of the three of separate calls.
+    ///
+    /// ```
+    /// use cubic_splinterpol::grid2d::Grid2d;
+    ///
+    /// let xs = [0f32, 1.0, 2.0];
+    /// let ys = [0f32, 1.0, 2.0];
+    /// // z = 2x + 3y
+    /// let z = [[0f32, 3.0, 6.0], [2.0, 5.0, 8.0], [4.0, 7.0, 10.0]];
+    /// let map = Grid2d::new(&xs, &ys, &z).unwrap();
+    /// let (value, dz_dx, dz_dy) = map.eval_with_gradient(0.5, 1.5).unwrap();
+    /// assert!((value - 5.5).abs() < 1e-5);
+    /// assert!((dz_dx - 2.0).abs() < 1e-5 && (dz_dy - 3.0).abs() < 1e-5);
+    /// ```
+    pub fn eval_with_gradient(&self, x: f32, y: f32) -> Result<(f32, f32, f32), Error> {
+        let along_x = self.spline_x(&self.rows_at(y, false))?;
+        let partial_y = self.spline_x(&self.rows_at(y, true))?;
+        let segment = find_segment(&self.xs, x);
+        let t = x - self.xs[segment];
+        Ok((
+            eval_segment(along_x[segment], t),
+            eval_segment_derivative(along_x[segment], t),
+            eval_segment(partial_y[segment], t),
+        ))
+    }
+
+    /// Value or derivative of the rows at y, splined along x. Spline
+    /// interpolation is linear in the values, so splining the row derivatives
+    /// gives the derivative of the interpolant with respect to y.
+    fn interpolate(&self, x: f32, y: f32, derive_x: bool, derive_y: bool) -> Result<f32, Error> {
+        let coefficients = self.spline_x(&self.rows_at(y, derive_y))?;
+        let segment = find_segment(&self.xs, x);
+        let t = x - self.xs[segment];
+        if derive_x {
+            Ok(eval_segment_derivative(coefficients[segment], t))
+        } else {
+            Ok(eval_segment(coefficients[segment], t))
+        }
+    }
+
+    /// Value or derivative with respect to y of every row at y.
+    fn rows_at(&self, y: f32, derive_y: bool) -> [f32; M] {
+        let column = find_segment(&self.ys, y);
+        let t = y - self.ys[column];
+        let mut values = [0f32; M];
+        for (value, row) in values.iter_mut().zip(&self.rows) {
+            *value = if derive_y {
+                eval_segment_derivative(row[column], t)
+            } else {
+                eval_segment(row[column], t)
+            };
+        }
+        values
+    }
+
+    /// Coefficients of the spline along x through `values`, M-1 of M used.
+    fn spline_x(&self, values: &[f32; M]) -> Result<[(f32, f32, f32, f32); M], Error> {
+        let mut coefficients = [(0f32, 0f32, 0f32, 0f32); M];
+        splinterpol_slices::<M>(
+            &self.xs,
+            values,
+            &mut coefficients,
+            Boundary::Natural,
+            Solver::Thomas,
+        )?;
+        Ok(coefficients)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const XS: [f32; 4] = [0f32, 1.0, 2.5, 3.0];
+    const YS: [f32; 5] = [-1f32, 0.0, 0.5, 2.0, 4.0];
+
+    #[test]
+    fn reproduces_grid_values() {
+        let mut z = [[0f32; 5]; 4];
+        for (i, row) in z.iter_mut().enumerate() {
+            for (j, value) in row.iter_mut().enumerate() {
+                *value = ((i * 7 + j * 3) % 5) as f32 - 2f32;
+            }
+        }
+        let grid = Grid2d::new(&XS, &YS, &z).unwrap();
+        for (i, x) in XS.iter().enumerate() {
+            for (j, y) in YS.iter().enumerate() {
+                assert!((grid.eval(*x, *y).unwrap() - z[i][j]).abs() < 0.0001);
+            }
+        }
+    }
+
+    #[test]
+    fn bilinear_surface_and_partials() {
+        let f = |x: f32, y: f32| x * y + x - 2f32 * y;
+        let mut z = [[0f32; 5]; 4];
+        for (i, row) in z.iter_mut().enumerate() {
+            for (j, value) in row.iter_mut().enumerate() {
+                *value = f(XS[i], YS[j]);
+            }
+        }
+        let grid = Grid2d::new(&XS, &YS, &z).unwrap();
+        for step in 0..=12 {
+            let x = step as f32 / 4f32;
+            let y = -1f32 + step as f32 * 0.4;
+            assert!((grid.eval(x, y).unwrap() - f(x, y)).abs() < 0.0001);
+            assert!((grid.partial_x(x, y).unwrap() - (y + 1f32)).abs() < 0.0001);
+            assert!((grid.partial_y(x, y).unwrap() - (x - 2f32)).abs() < 0.0001);
+        }
+    }
+
+    #[test]
+    fn gradient_matches_partials() {
+        let mut z = [[0f32; 5]; 4];
+        for (i, row) in z.iter_mut().enumerate() {
+            for (j, value) in row.iter_mut().enumerate() {
+                *value = ((i * 5 + j * 2) % 3) as f32 + XS[i] * YS[j];
+            }
+        }
+        let grid = Grid2d::new(&XS, &YS, &z).unwrap();
+        for (x, y) in [(0.3f32, 1.7f32), (2.7, -0.5), (1.0, 3.9), (3.5, 5.0)] {
+            let (value, dz_dx, dz_dy) = grid.eval_with_gradient(x, y).unwrap();
+            assert_eq!(Ok(value), grid.eval(x, y));
+            assert_eq!(Ok(dz_dx), grid.partial_x(x, y));
+            assert_eq!(Ok(dz_dy), grid.partial_y(x, y));
+            // Against central differences
+            let e = 1e-2;
+            let dx = (grid.eval(x + e, y).unwrap() - grid.eval(x - e, y).unwrap()) / (2f32 * e);
+            let dy = (grid.eval(x, y + e).unwrap() - grid.eval(x, y - e).unwrap()) / (2f32 * e);
+            assert!((dz_dx - dx).abs() < 1e-2, "{} vs {}", dz_dx, dx);
+            assert!((dz_dy - dy).abs() < 1e-2, "{} vs {}", dz_dy, dy);
+        }
+    }
+
+    #[test]
+    fn symmetric_in_the_dimensions() {
+        let mut z = [[0f32; 5]; 4];
+        let mut transposed = [[0f32; 4]; 5];
+        for i in 0..4 {
+            for j in 0..5 {
+                z[i][j] = ((i * 3 + j * j) % 4) as f32;
+                transposed[j][i] = z[i][j];
+            }
+        }
+        let grid = Grid2d::new(&XS, &YS, &z).unwrap();
+        let transposed = Grid2d::new(&YS, &XS, &transposed).unwrap();
+        for (x, y) in [(0.3f32, 1.7f32), (2.7, -0.5), (1.0, 3.9)] {
+            let value = grid.eval(x, y).unwrap();
+            assert!((value - transposed.eval(y, x).unwrap()).abs() < 0.0001);
+            let partial = grid.partial_x(x, y).unwrap();
+            assert!((partial - transposed.partial_y(y, x).unwrap()).abs() < 0.0001);
+        }
+    }
+
+    #[test]
+    fn rejects_invalid_knots() {
+        let z = [[0f32; 5]; 4];
+        let xs = [0f32, 1.0, 1.0, 3.0];
+        assert_eq!(
+            Err(Error::NonIncreasingKnots { index: 2 }),
+            Grid2d::new(&xs, &YS, &z).map(|_| ())
+        );
+        let z = [[0f32; 2]; 4];
+        assert_eq!(
+            Err(Error::InvalidSliceLength),
+            Grid2d::new(&XS, &[0f32, 1.0], &z).map(|_| ())
+        );
+    }
+}
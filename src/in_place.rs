@@ -0,0 +1,134 @@
+use crate::thomas_algorithm::{check_pivot, DEFAULT_PIVOT_EPSILON};
+use crate::{check_increasing, h, Error};
+
+/// Like [`splinterpol`](crate::splinterpol), but without any scratch arrays:
+/// the values `ys` are overwritten, for callers which only keep the spline.
+///
+/// The values move to the constant coefficients first, `ys` then takes the
+/// right hand side of the system and its solution, and the eliminated main
+/// diagonal waits in the cubic coefficients until they are computed last.
+/// The result is exactly that of [`splinterpol`](crate::splinterpol), and
+/// `ys` is left holding the quadratic coefficients c at all knots, zero at
+/// both ends.
+///
+/// ```
+/// use cubic_splinterpol::{splinterpol, splinterpol_in_place};
+///
+/// let xs = [0f32, 1.0, 2.5, 3.0];
+/// let mut ys = [1f32, 2.0, 0.5, 3.0];
+/// let mut expected = [(0f32, 0f32, 0f32, 0f32); 3];
+/// splinterpol(&xs, &ys, &mut expected).unwrap();
+///
+/// let mut coefficients = [(0f32, 0f32, 0f32, 0f32); 3];
+/// splinterpol_in_place(&xs, &mut ys, &mut coefficients).unwrap();
+/// assert_eq!(expected, coefficients);
+/// assert_eq!(expected[1].2, ys[1]);
+/// ```
+pub fn splinterpol_in_place<const N: usize>(
+    xs: &[f32; N],
+    ys: &mut [f32; N],
+    coefficients: &mut [(f32, f32, f32, f32)],
+) -> Result<(), Error> {
+    splinterpol_in_place_slices(xs, ys, coefficients)
+}
+
+/// [`splinterpol_in_place`] for xs and ys of same runtime length.
+pub(crate) fn splinterpol_in_place_slices(
+    xs: &[f32],
+    ys: &mut [f32],
+    coefficients: &mut [(f32, f32, f32, f32)],
+) -> Result<(), Error> {
+    let n = xs.len();
+    if n < 3 || ys.len() != n || coefficients.len() < n - 1 {
+        return Err(Error::InvalidSliceLength);
+    }
+    check_increasing(xs)?;
+    let coefficients = &mut coefficients[..n - 1];
+    let last_y = ys[n - 1];
+    for (elem, y) in coefficients.iter_mut().zip(ys.iter()) {
+        *elem = (*y, 0f32, 0f32, 0f32);
+    }
+    let y = |coefficients: &[(f32, f32, f32, f32)], i: usize| {
+        if i < n - 1 {
+            coefficients[i].0
+        } else {
+            last_y
+        }
+    };
+
+    // Rows of the interior c[1..n-1], with the right hand side in ys and the
+    // main diagonal in the cubic coefficients, eliminated as they are written
+    for i in 0..n - 2 {
+        let div1 = (y(coefficients, i + 2) - y(coefficients, i + 1)) / h(i + 1, xs);
+        let div2 = (y(coefficients, i + 1) - y(coefficients, i)) / h(i, xs);
+        ys[i + 1] = 3f32 * (div1 - div2);
+        coefficients[i].3 = 2f32 * (h(i, xs) + h(i + 1, xs));
+        if i > 0 {
+            let previous = coefficients[i - 1].3;
+            check_pivot(previous, i - 1, DEFAULT_PIVOT_EPSILON)?;
+            let mc = h(i, xs) / previous;
+            coefficients[i].3 -= mc * h(i, xs);
+            ys[i + 1] -= mc * ys[i];
+        }
+    }
+    check_pivot(coefficients[n - 3].3, n - 3, DEFAULT_PIVOT_EPSILON)?;
+    ys[n - 2] /= coefficients[n - 3].3;
+    for i in (0..n - 3).rev() {
+        ys[i + 1] = (ys[i + 1] - h(i + 1, xs) * ys[i + 2]) / coefficients[i].3;
+    }
+    ys[0] = 0f32;
+    ys[n - 1] = 0f32;
+
+    for i in 0..n - 1 {
+        let div_1 = (y(coefficients, i + 1) - y(coefficients, i)) / (h(i, xs));
+        let div_2 = (2f32 * ys[i] + ys[i + 1]) / 3f32;
+        coefficients[i].1 = div_1 - div_2 * h(i, xs);
+        coefficients[i].2 = ys[i];
+        coefficients[i].3 = (ys[i + 1] - ys[i]) / (3f32 * h(i, xs));
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn matches_splinterpol() {
+        let xs = [
+            0.5f32, 1.0, 2.0, 3.0, 4.5, 5.0, 6.0, 7.0, 8.0, 9.0, 10.0, 11.5, 12.0, 13.0, 14.0, 15.0,
+        ];
+        let ys = [
+            0f32, 1.0, -2.0, 4.0, 1.0, -1.0, 0.0, 0.0, 1.0, 2.0, 4.0, 5.0, 4.0, 3.0, 2.0, 0.0,
+        ];
+        let mut expected = [(0f32, 0f32, 0f32, 0f32); 15];
+        crate::splinterpol(&xs, &ys, &mut expected).unwrap();
+        let mut values = ys;
+        let mut coeffs = [(0f32, 0f32, 0f32, 0f32); 15];
+        splinterpol_in_place(&xs, &mut values, &mut coeffs).unwrap();
+        assert_eq!(expected, coeffs);
+        for i in 0..15 {
+            assert_eq!(expected[i].2, values[i]);
+        }
+        assert_eq!(0.0, values[15]);
+
+        let mut three = [1f32, 3.0, 2.0];
+        let mut expected = [(0f32, 0f32, 0f32, 0f32); 2];
+        crate::splinterpol(&[0.0, 1.0, 3.0], &three, &mut expected).unwrap();
+        splinterpol_in_place(&[0.0, 1.0, 3.0], &mut three, &mut coeffs).unwrap();
+        assert_eq!(expected, coeffs[..2]);
+    }
+
+    #[test]
+    fn rejects_invalid_input() {
+        let mut coeffs = [(0f32, 0f32, 0f32, 0f32); 2];
+        assert_eq!(
+            Err(Error::InvalidSliceLength),
+            splinterpol_in_place(&[0.0, 1.0, 2.0], &mut [0.0; 3], &mut coeffs[..1])
+        );
+        assert_eq!(
+            Err(Error::NonIncreasingKnots { index: 2 }),
+            splinterpol_in_place(&[0.0, 1.0, 1.0], &mut [0.0; 3], &mut coeffs)
+        );
+    }
+}
@@ -0,0 +1,462 @@
+use crate::extrema::critical_points;
+use crate::{check_spline, eval_segment, eval_segment_derivative, for_each_difference, Error};
+
+/// Maximum number of iterations of the root search within a segment. Every
+/// iteration at least halves the bracket, so this reaches f32 resolution.
+const MAX_ITERATIONS: usize = 64;
+
+/// Find the position x at which the spline given by `coefficients` and the
+/// knots `xs` takes the value y, e.g. to get from a calibrated physical
+/// quantity back to the raw reading.
+///
+/// The spline must be monotone, increasing or decreasing, between its knots,
+/// so that there is exactly one such position. The segment containing y is
+/// found by binary search over the values at the knots, then the cubic is
+/// solved for y by Newton's method, falling back to bisection whenever a step
+/// would leave the bracket of the root.
+///
+/// Returns `None` if y is outside of the values at the first and last knot,
+/// or if the coefficients and knots do not describe a spline.
+///
+/// ```
+/// use cubic_splinterpol::{eval_batch, inverse_eval, splinterpol};
+///
+/// let xs = [0f32, 1.0, 2.0, 3.0];
+/// let ys = [0f32, 1.0, 4.0, 9.0];
+/// let mut coefficients = [(0f32, 0f32, 0f32, 0f32); 3];
+/// splinterpol(&xs, &ys, &mut coefficients).unwrap();
+///
+/// let x = inverse_eval(&coefficients, &xs, 2.5).unwrap();
+/// let mut y = [0f32];
+/// eval_batch(&coefficients, &xs, &[x], &mut y).unwrap();
+/// assert!((y[0] - 2.5).abs() < 1e-5);
+/// assert_eq!(None, inverse_eval(&coefficients, &xs, 10.0));
+/// ```
+pub fn inverse_eval(coefficients: &[(f32, f32, f32, f32)], xs: &[f32], y: f32) -> Option<f32> {
+    check_spline(coefficients, xs).ok()?;
+    let segments = coefficients.len();
+    let value = |knot: usize| {
+        if knot < segments {
+            coefficients[knot].0
+        } else {
+            eval_segment(coefficients[segments - 1], xs[segments] - xs[segments - 1])
+        }
+    };
+    let first = value(0);
+    let last = value(segments);
+    // Compare in the direction the spline runs
+    let sign = if last < first { -1f32 } else { 1f32 };
+    let y_rel = sign * y;
+    if y.is_nan() || y_rel < sign * first || y_rel > sign * last {
+        return None;
+    }
+
+    // Last segment starting at or below y
+    let mut low = 0;
+    let mut high = segments - 1;
+    while low < high {
+        let mid = low + (high - low).div_ceil(2);
+        if sign * value(mid) <= y_rel {
+            low = mid;
+        } else {
+            high = mid - 1;
+        }
+    }
+    let segment = low;
+    let t = bracketed_root(
+        coefficients[segment],
+        y,
+        sign,
+        0f32,
+        xs[segment + 1] - xs[segment],
+    );
+    Some(xs[segment] + t)
+}
+
+/// Draw from the distribution whose cumulative distribution function is the
+/// spline given by `coefficients` and the knots `xs`, by inverse transform
+/// sampling: returns the position at which the spline reaches the fraction
+/// `u` of the way from its value at the first knot to that at the last.
+///
+/// For `u` uniformly distributed in `[0, 1]`, e.g. from a PRNG, the result is
+/// distributed accordingly; the spline need not reach exactly 0 and 1, so
+/// cumulative counts do as well. It must not decrease, e.g. the spline of
+/// cumulative data passed through [`hyman_filter`](crate::hyman_filter). `u`
+/// is clamped to `[0, 1]`; NaN is returned for a NaN `u` or if the coefficients
+/// and knots do not describe a spline.
+///
+/// ```
+/// use cubic_splinterpol::{invert_cdf, splinterpol};
+///
+/// // Uniform on [2, 6]
+/// let xs = [2f32, 4.0, 6.0];
+/// let mut coefficients = [(0f32, 0f32, 0f32, 0f32); 2];
+/// splinterpol(&xs, &[0.0, 0.5, 1.0], &mut coefficients).unwrap();
+/// assert_eq!(2.0, invert_cdf(&coefficients, &xs, 0.0));
+/// assert!((invert_cdf(&coefficients, &xs, 0.25) - 3.0).abs() < 1e-5);
+/// assert_eq!(6.0, invert_cdf(&coefficients, &xs, 1.5));
+/// ```
+pub fn invert_cdf(coefficients: &[(f32, f32, f32, f32)], xs: &[f32], u: f32) -> f32 {
+    if check_spline(coefficients, xs).is_err() || u.is_nan() {
+        return f32::NAN;
+    }
+    let segments = coefficients.len();
+    let first = coefficients[0].0;
+    let last = eval_segment(coefficients[segments - 1], xs[segments] - xs[segments - 1]);
+    let u = u.clamp(0f32, 1f32);
+    if u == 0f32 {
+        return xs[0];
+    }
+    if u == 1f32 {
+        return xs[segments];
+    }
+    // Rounding must not take the target beyond the ends
+    let y = (first + u * (last - first)).max(first).min(last);
+    inverse_eval(coefficients, xs, y).unwrap_or(f32::NAN)
+}
+
+/// Find all positions x at which the spline given by `coefficients` and the
+/// knots `xs` takes the value `y0`, e.g. when an interpolated temperature
+/// crosses a limit, and write them to the front of `out` in increasing
+/// order. Returns their number.
+///
+/// Every segment is split into monotone pieces at the roots of its
+/// derivative, and each piece is searched for `y0` as in [`inverse_eval`].
+/// A root at a knot counts once. Where the spline is constant at `y0`, only
+/// the start of that stretch within each segment is reported. Fails with
+/// [`Error::CapacityExceeded`] if `out` is too short for all roots.
+///
+/// ```
+/// use cubic_splinterpol::{solve_for, splinterpol};
+///
+/// let xs = [0f32, 10.0, 20.0, 30.0];
+/// let temperatures = [60f32, 90.0, 95.0, 70.0];
+/// let mut coefficients = [(0f32, 0f32, 0f32, 0f32); 3];
+/// splinterpol(&xs, &temperatures, &mut coefficients).unwrap();
+///
+/// let mut crossings = [0f32; 4];
+/// let len = solve_for(&coefficients, &xs, 85.0, &mut crossings).unwrap();
+/// assert_eq!(2, len);
+/// assert!(crossings[0] > 0.0 && crossings[0] < 10.0);
+/// assert!(crossings[1] > 20.0 && crossings[1] < 30.0);
+/// ```
+pub fn solve_for(
+    coefficients: &[(f32, f32, f32, f32)],
+    xs: &[f32],
+    y0: f32,
+    out: &mut [f32],
+) -> Result<usize, Error> {
+    check_spline(coefficients, xs)?;
+    let mut len = 0;
+    for (segment, polynomial) in coefficients.iter().enumerate() {
+        let h = xs[segment + 1] - xs[segment];
+        let last_segment = segment + 1 == coefficients.len();
+        len = roots_in_segment(*polynomial, h, y0, last_segment, out, len, xs[segment])?;
+    }
+    Ok(len)
+}
+
+/// Find all positions x at which the spline given by `first_coefficients`
+/// and `first_xs` and the one given by `second_coefficients` and
+/// `second_xs` take the same value, e.g. where a demand curve crosses a
+/// capacity curve, and write them to the front of `out` in increasing order.
+/// Returns their number.
+///
+/// Only the overlap of both ranges of knots is searched, zero crossings if
+/// they do not overlap. Between every two consecutive knots of either spline,
+/// the difference of both is a single cubic, whose roots are found as by
+/// [`solve_for`], with a bounded number of iterations each. Fails with
+/// [`Error::CapacityExceeded`] if `out` is too short for all crossings.
+///
+/// ```
+/// use cubic_splinterpol::{intersect, splinterpol};
+///
+/// let demand_xs = [0f32, 6.0, 12.0, 18.0, 24.0];
+/// let mut demand = [(0f32, 0f32, 0f32, 0f32); 4];
+/// splinterpol(&demand_xs, &[2.0, 5.0, 9.0, 6.0, 2.0], &mut demand).unwrap();
+/// let capacity_xs = [0f32, 24.0];
+/// let capacity = [(7f32, 0f32, 0f32, 0f32)];
+///
+/// let mut crossings = [0f32; 4];
+/// let len = intersect(&demand, &demand_xs, &capacity, &capacity_xs, &mut crossings).unwrap();
+/// assert_eq!(2, len);
+/// assert!(crossings[0] > 6.0 && crossings[1] < 18.0);
+/// ```
+pub fn intersect(
+    first_coefficients: &[(f32, f32, f32, f32)],
+    first_xs: &[f32],
+    second_coefficients: &[(f32, f32, f32, f32)],
+    second_xs: &[f32],
+    out: &mut [f32],
+) -> Result<usize, Error> {
+    check_spline(first_coefficients, first_xs)?;
+    check_spline(second_coefficients, second_xs)?;
+    let mut len = 0;
+    for_each_difference(
+        first_coefficients,
+        first_xs,
+        second_coefficients,
+        second_xs,
+        |difference, start, end, last| {
+            len = roots_in_segment(difference, end - start, 0f32, last, out, len, start)?;
+            Ok(())
+        },
+    )?;
+    Ok(len)
+}
+
+/// Write the global positions of the roots of `polynomial` - y0 within a
+/// segment of width h starting at `offset` to `out`, in increasing order,
+/// after the `len` roots already there. Returns the new number of roots.
+///
+/// The segment is split into monotone pieces at the roots of the derivative.
+/// A root at the start of a piece counts for that piece, one at the end of
+/// the segment only with `include_end`.
+#[allow(clippy::too_many_arguments)]
+fn roots_in_segment(
+    polynomial: (f32, f32, f32, f32),
+    h: f32,
+    y0: f32,
+    include_end: bool,
+    out: &mut [f32],
+    mut len: usize,
+    offset: f32,
+) -> Result<usize, Error> {
+    let mut points = critical_points(polynomial, h);
+    points.sort_unstable_by(|a, b| a.total_cmp(b));
+    let residual = |t: f32| eval_segment(polynomial, t) - y0;
+    for bounds in points.windows(2) {
+        let (lower, upper) = (bounds[0], bounds[1]);
+        if lower == upper {
+            continue;
+        }
+        let (r_lower, r_upper) = (residual(lower), residual(upper));
+        let t = if r_lower == 0f32 {
+            lower
+        } else if r_lower * r_upper < 0f32 {
+            let sign = if r_lower < 0f32 { 1f32 } else { -1f32 };
+            bracketed_root(polynomial, y0, sign, lower, upper)
+        } else if r_upper == 0f32 && include_end && upper == h {
+            upper
+        } else {
+            continue;
+        };
+        *out.get_mut(len).ok_or(Error::CapacityExceeded)? = offset + t;
+        len += 1;
+    }
+    Ok(len)
+}
+
+/// Position within `[lower, upper]` at which `polynomial` takes the value y,
+/// for a polynomial monotone there, increasing for a sign of 1 and
+/// decreasing for -1. Newton's method, falling back to bisection whenever a
+/// step would leave the bracket of the root.
+fn bracketed_root(
+    polynomial: (f32, f32, f32, f32),
+    y: f32,
+    sign: f32,
+    lower: f32,
+    upper: f32,
+) -> f32 {
+    let residual = |t: f32| sign * (eval_segment(polynomial, t) - y);
+
+    // Bracket with residual(lower) <= 0 <= residual(upper)
+    let (mut lower, mut upper) = (lower, upper);
+    let mut t = lower + (upper - lower) / 2f32;
+    for _ in 0..MAX_ITERATIONS {
+        let r = residual(t);
+        if r == 0f32 {
+            break;
+        }
+        if r < 0f32 {
+            lower = t;
+        } else {
+            upper = t;
+        }
+        let newton = t - r / (sign * eval_segment_derivative(polynomial, t));
+        let next = if newton > lower && newton < upper {
+            newton
+        } else {
+            lower + (upper - lower) / 2f32
+        };
+        if next == t {
+            break;
+        }
+        t = next;
+    }
+    t
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::eval;
+    use crate::Extrapolate;
+
+    #[test]
+    fn inverse_roundtrip_increasing() {
+        let xs = [0f32, 1.0, 2.0, 3.5, 5.0, 8.0];
+        let ys = [0f32, 0.5, 2.0, 2.5, 4.0, 10.0];
+        let mut coeffs = [(0f32, 0f32, 0f32, 0f32); 5];
+        crate::splinterpol(&xs, &ys, &mut coeffs).unwrap();
+        for (x, y) in xs.iter().zip(&ys) {
+            assert!((inverse_eval(&coeffs, &xs, *y).unwrap() - x).abs() < 1e-4);
+        }
+        for i in 0..=100 {
+            let y = i as f32 / 10f32;
+            let x = inverse_eval(&coeffs, &xs, y).unwrap();
+            let value = eval(&coeffs, &xs, x, Extrapolate::Error).unwrap();
+            assert!((value - y).abs() < 1e-4, "{} vs {}", value, y);
+        }
+    }
+
+    #[test]
+    fn invert_cdf_samples_distribution() {
+        // Cumulative counts of a histogram, rising faster in the middle
+        let xs = [0f32, 1.0, 2.0, 3.0, 4.0];
+        let counts = [0f32, 10.0, 50.0, 90.0, 100.0];
+        let mut coeffs = [(0f32, 0f32, 0f32, 0f32); 4];
+        crate::monotone::monotone_slices(&xs, &counts, &mut coeffs).unwrap();
+        let mut previous = 0f32;
+        for i in 0..=100 {
+            let u = i as f32 / 100f32;
+            let x = invert_cdf(&coeffs, &xs, u);
+            assert!(x >= previous);
+            let value = eval(&coeffs, &xs, x, Extrapolate::Error).unwrap();
+            assert!((value - 100f32 * u).abs() < 1e-3, "{} vs {}", value, u);
+            previous = x;
+        }
+        assert_eq!(4.0, previous);
+        // Half of the draws fall between the quartiles 1.5 and 2.5
+        let lower = invert_cdf(&coeffs, &xs, 0.25);
+        let upper = invert_cdf(&coeffs, &xs, 0.75);
+        assert!(lower > 1.0 && upper < 3.0 && upper - lower < 1.5);
+        assert_eq!(0.0, invert_cdf(&coeffs, &xs, -0.5));
+        assert!(invert_cdf(&coeffs, &xs, f32::NAN).is_nan());
+        assert!(invert_cdf(&coeffs, &xs[..4], 0.5).is_nan());
+    }
+
+    #[test]
+    fn inverse_roundtrip_decreasing() {
+        // Thermistor-like: resistance falls with temperature
+        let xs = [-40f32, -20.0, 0.0, 25.0, 50.0, 85.0];
+        let ys = [336.0f32, 97.1, 32.6, 10.0, 3.6, 1.1];
+        let mut coeffs = [(0f32, 0f32, 0f32, 0f32); 5];
+        let mut monotone = [(0f32, 0f32, 0f32, 0f32); 5];
+        crate::splinterpol(&xs, &ys, &mut coeffs).unwrap();
+        // Replace with the linear interpolant, which is monotone for sure
+        for i in 0..5 {
+            monotone[i] = (ys[i], (ys[i + 1] - ys[i]) / (xs[i + 1] - xs[i]), 0.0, 0.0);
+        }
+        let x = inverse_eval(&monotone, &xs, 50.0).unwrap();
+        let expected = -20.0 + 20.0 * (97.1 - 50.0) / (97.1 - 32.6);
+        assert!((x - expected).abs() < 1e-3);
+        assert!((inverse_eval(&coeffs, &xs, 10.0).unwrap() - 25.0).abs() < 1e-3);
+        assert_eq!(None, inverse_eval(&coeffs, &xs, 400.0));
+        assert_eq!(None, inverse_eval(&coeffs, &xs, 1.0));
+    }
+
+    #[test]
+    fn inverse_rejects_invalid_input() {
+        let coeffs = [(0f32, 1f32, 0f32, 0f32)];
+        assert_eq!(None, inverse_eval(&coeffs, &[0f32], 0.5));
+        assert_eq!(None, inverse_eval(&[], &[], 0.5));
+        assert_eq!(None, inverse_eval(&coeffs, &[0f32, 1.0], f32::NAN));
+    }
+
+    #[test]
+    fn solve_for_finds_all_crossings() {
+        // (x - 1)(x - 2)(x - 3) = x³ - 6x² + 11x - 6 on two segments
+        let coeffs = [(-6f32, 11f32, -6f32, 1f32), (0.0, 2.0, -3.0, 1.0)];
+        let xs = [0f32, 1.0, 4.0];
+        let mut out = [0f32; 4];
+        assert_eq!(Ok(3), solve_for(&coeffs, &xs, 0.0, &mut out));
+        for (root, expected) in out.iter().zip(&[1f32, 2.0, 3.0]) {
+            assert!((root - expected).abs() < 1e-5, "{} vs {}", root, expected);
+        }
+        // At the last knot
+        let len = solve_for(&coeffs, &xs, 6.0, &mut out).unwrap();
+        assert_eq!((1, 4.0), (len, out[0]));
+        // Just above the local maximum at 2 - 1/sqrt(3), beyond it only
+        let peak = 2f32 / (3f32 * libm::sqrtf(3f32));
+        assert_eq!(Ok(1), solve_for(&coeffs, &xs, peak + 0.01, &mut out));
+        assert!(out[0] > 3.0);
+        assert_eq!(Ok(3), solve_for(&coeffs, &xs, peak - 0.01, &mut out));
+        assert_eq!(
+            Err(Error::CapacityExceeded),
+            solve_for(&coeffs, &xs, 0.0, &mut out[..2])
+        );
+        assert_eq!(Ok(0), solve_for(&coeffs, &xs, f32::NAN, &mut out));
+    }
+
+    #[test]
+    fn solve_for_level_at_last_knot() {
+        let mut out = [0f32; 4];
+        // Without critical points inside
+        let line = [(0f32, 1f32, 0f32, 0f32)];
+        assert_eq!(Ok(1), solve_for(&line, &[0.0, 1.0], 1.0, &mut out));
+        assert_eq!(1.0, out[0]);
+        let ramp = [(0f32, 1f32, 0f32, 0f32), (1.0, 1.0, 0.0, 0.0)];
+        assert_eq!(Ok(1), solve_for(&ramp, &[0.0, 1.0, 2.0], 2.0, &mut out));
+        assert_eq!(2.0, out[0]);
+        // With one: x² - 2x + 1 has its minimum at 1 inside [0, 2]
+        let parabola = [(1f32, -2f32, 1f32, 0f32)];
+        assert_eq!(Ok(2), solve_for(&parabola, &[0.0, 2.0], 1.0, &mut out));
+        assert_eq!([0.0, 2.0], out[..2]);
+        // With two: x³ - 3x² + 2x has its extrema inside [0, 3]
+        let cubic = [(0f32, 2f32, -3f32, 1f32)];
+        assert_eq!(Ok(1), solve_for(&cubic, &[0.0, 3.0], 6.0, &mut out));
+        assert_eq!(3.0, out[0]);
+        // Not at the last knot of an inner segment twice
+        assert_eq!(Ok(1), solve_for(&ramp, &[0.0, 1.0, 2.0], 1.0, &mut out));
+    }
+
+    #[test]
+    fn intersect_over_overlap() {
+        // x² on [0, 3] and x + 1 on [1, 4], crossing at the golden ratio
+        let parabola = [(0f32, 0f32, 1f32, 0f32), (1.0, 2.0, 1.0, 0.0)];
+        let parabola_xs = [0f32, 1.0, 3.0];
+        let line = [(2f32, 1f32, 0f32, 0f32); 3];
+        let line_xs = [1f32, 2.0, 2.5, 4.0];
+        let mut out = [0f32; 2];
+        assert_eq!(
+            Ok(1),
+            intersect(&parabola, &parabola_xs, &line, &line_xs, &mut out)
+        );
+        assert!((out[0] - (1f32 + libm::sqrtf(5f32)) / 2f32).abs() < 1e-5);
+        // Symmetric
+        let mut swapped = [0f32; 2];
+        intersect(&line, &line_xs, &parabola, &parabola_xs, &mut swapped).unwrap();
+        assert_eq!(out[0], swapped[0]);
+
+        // Without overlap, or too little room
+        let far = [5f32, 6.0];
+        assert_eq!(
+            Ok(0),
+            intersect(&parabola, &parabola_xs, &line[..1], &far, &mut out)
+        );
+        assert_eq!(
+            Err(Error::CapacityExceeded),
+            intersect(&parabola, &parabola_xs, &line, &line_xs, &mut out[..0])
+        );
+    }
+
+    #[test]
+    fn intersect_at_last_knot() {
+        let ramp = [(0f32, 1f32, 0f32, 0f32), (1.0, 1.0, 0.0, 0.0)];
+        let ramp_xs = [0f32, 1.0, 2.0];
+        let constant = [(2f32, 0f32, 0f32, 0f32)];
+        let mut out = [0f32; 2];
+        assert_eq!(
+            Ok(1),
+            intersect(&ramp, &ramp_xs, &constant, &[0.0, 2.0], &mut out)
+        );
+        assert_eq!(2.0, out[0]);
+        // At the end of the overlap, before the end of the constant
+        assert_eq!(
+            Ok(1),
+            intersect(&ramp, &ramp_xs, &constant, &[1.0, 3.0], &mut out)
+        );
+        assert_eq!(2.0, out[0]);
+    }
+}
@@ -0,0 +1,510 @@
+use crate::plot_spline::sample_uniform;
+use crate::{
+    check_increasing, check_spline, eval_segment, find_segment, rebase,
+    splinterpol_array_unchecked, splinterpol_slices, ArrayLengths, Boundary, Error, Solver,
+};
+
+/// Number of positions within each segment of the original spline at which
+/// [`simplify`] compares the simplified spline to it, besides the knots.
+const SIMPLIFY_SAMPLES: usize = 7;
+
+/// Split the segment containing `new_x` into two at `new_x`, without changing
+/// the curve, writing the knots and coefficients of the refined spline to
+/// `out_xs` and `out_coefficients`. Those must hold one more element than `xs`
+/// and `coefficients`, respectively. Returns the index of the new knot.
+///
+/// The second half is the polynomial of the original segment expanded around
+/// `new_x`. No system is solved, so this is exact up to rounding, and the
+/// splines of different knots can be refined onto a common set of knots before
+/// [combining](crate::arithmetic) them.
+///
+/// ```
+/// use cubic_splinterpol::insert_knot;
+///
+/// let xs = [0f32, 2.0];
+/// let coefficients = [(0f32, 0f32, 1f32, 0f32)];
+/// let mut out_xs = [0f32; 3];
+/// let mut out_coefficients = [(0f32, 0f32, 0f32, 0f32); 2];
+/// let index = insert_knot(&coefficients, &xs, 1.0, &mut out_coefficients, &mut out_xs).unwrap();
+/// assert_eq!(1, index);
+/// assert_eq!([0.0, 1.0, 2.0], out_xs);
+/// assert_eq!((1.0, 2.0, 1.0, 0.0), out_coefficients[1]);
+/// ```
+pub fn insert_knot(
+    coefficients: &[(f32, f32, f32, f32)],
+    xs: &[f32],
+    new_x: f32,
+    out_coefficients: &mut [(f32, f32, f32, f32)],
+    out_xs: &mut [f32],
+) -> Result<usize, Error> {
+    check_spline(coefficients, xs)?;
+    if out_coefficients.len() != coefficients.len() + 1 || out_xs.len() != xs.len() + 1 {
+        return Err(Error::InvalidSliceLength);
+    }
+    if new_x.is_nan() || new_x < xs[0] || new_x > xs[xs.len() - 1] {
+        return Err(Error::OutOfRange);
+    }
+    let segment = find_segment(xs, new_x);
+    if new_x == xs[segment] || new_x == xs[segment + 1] {
+        let index = if new_x == xs[segment] {
+            segment
+        } else {
+            segment + 1
+        };
+        return Err(Error::NonIncreasingKnots { index: index + 1 });
+    }
+
+    let index = segment + 1;
+    out_xs[..index].copy_from_slice(&xs[..index]);
+    out_xs[index] = new_x;
+    out_xs[index + 1..].copy_from_slice(&xs[index..]);
+
+    out_coefficients[..index].copy_from_slice(&coefficients[..index]);
+    out_coefficients[index] = rebase(coefficients[segment], new_x - xs[segment]);
+    out_coefficients[index + 1..].copy_from_slice(&coefficients[index..]);
+    Ok(index)
+}
+
+/// Remove knots from the natural spline given by `coefficients` and the `N`
+/// knots `xs` as long as the natural spline through the remaining knots stays
+/// within `epsilon` of the original curve, e.g. to compress a long logged
+/// dataset for storage in flash.
+///
+/// The knots and coefficients of the simplified spline are written to the
+/// front of `out_xs` and `out_coefficients`, which must hold at least `N` and
+/// `N - 1` elements. Returns the number of knots kept; the first and last knot
+/// are always kept.
+///
+/// Interior knots are tried once each, from first to last, and dropped if the
+/// spline without them deviates by at most `epsilon` from the original at the
+/// original knots and at a few positions within every original segment. Every
+/// attempt solves the spline again, so this takes time quadratic in `N`.
+pub fn simplify<const N: usize>(
+    coefficients: &[(f32, f32, f32, f32)],
+    xs: &[f32; N],
+    epsilon: f32,
+    out_coefficients: &mut [(f32, f32, f32, f32)],
+    out_xs: &mut [f32],
+) -> Result<usize, Error> {
+    check_spline(coefficients, xs)?;
+    if N < 3 || out_xs.len() < N || out_coefficients.len() < N - 1 {
+        return Err(Error::InvalidSliceLength);
+    }
+    if epsilon.is_nan() || epsilon < 0f32 {
+        return Err(Error::InvalidLimit);
+    }
+    let mut ys = [0f32; N];
+    for (y, segment) in ys.iter_mut().zip(coefficients) {
+        *y = segment.0;
+    }
+    ys[N - 1] = eval_segment(coefficients[N - 2], xs[N - 1] - xs[N - 2]);
+
+    let mut kept = [true; N];
+    let mut trial_xs = [0f32; N];
+    let mut trial_ys = [0f32; N];
+    let mut trial = [(0f32, 0f32, 0f32, 0f32); N];
+    let mut len = N;
+    for candidate in 1..N - 1 {
+        if len <= 3 {
+            break;
+        }
+        kept[candidate] = false;
+        let mut m = 0;
+        for i in (0..N).filter(|i| kept[*i]) {
+            trial_xs[m] = xs[i];
+            trial_ys[m] = ys[i];
+            m += 1;
+        }
+        let (trial_xs, trial_ys) = (&trial_xs[..m], &trial_ys[..m]);
+        splinterpol_slices::<N>(
+            trial_xs,
+            trial_ys,
+            &mut trial,
+            Boundary::Natural,
+            Solver::Thomas,
+        )?;
+        if max_deviation(coefficients, xs, &trial[..m - 1], trial_xs) <= epsilon {
+            len -= 1;
+        } else {
+            kept[candidate] = true;
+        }
+    }
+
+    let mut m = 0;
+    for i in (0..N).filter(|i| kept[*i]) {
+        out_xs[m] = xs[i];
+        trial_ys[m] = ys[i];
+        m += 1;
+    }
+    splinterpol_slices::<N>(
+        &out_xs[..m],
+        &trial_ys[..m],
+        out_coefficients,
+        Boundary::Natural,
+        Solver::Thomas,
+    )?;
+    Ok(m)
+}
+
+/// Pick knots among the dense samples `xs` and `ys` such that the natural
+/// spline through the picked samples stays within `tolerance` of every sample,
+/// e.g. to compress a log into a spline in one call.
+///
+/// Starts with the first, middle and last sample and greedily adds the sample
+/// the current spline misses by the most, until no sample is missed by more
+/// than `tolerance`. At most `CAP` knots are picked. The knots and
+/// coefficients are written to the front of `out_xs` and `out_coefficients`,
+/// which must hold at least `CAP` and `CAP - 1` elements, and the number of
+/// knots is returned. If `CAP` knots do not suffice, fails with
+/// [`Error::CapacityExceeded`], leaving the spline through those in the
+/// output.
+///
+/// ```
+/// use cubic_splinterpol::select_knots;
+///
+/// let mut xs = [0f32; 200];
+/// let mut ys = [0f32; 200];
+/// for i in 0..200 {
+///     xs[i] = i as f32 * 0.05;
+///     ys[i] = if xs[i] < 5.0 { xs[i] } else { 10.0 - xs[i] };
+/// }
+/// let mut knots = [0f32; 16];
+/// let mut coefficients = [(0f32, 0f32, 0f32, 0f32); 15];
+/// let len = select_knots::<16>(&xs, &ys, 0.1, &mut knots, &mut coefficients).unwrap();
+/// assert!(len < 16);
+/// ```
+pub fn select_knots<const CAP: usize>(
+    xs: &[f32],
+    ys: &[f32],
+    tolerance: f32,
+    out_xs: &mut [f32],
+    out_coefficients: &mut [(f32, f32, f32, f32)],
+) -> Result<usize, Error> {
+    let n = xs.len();
+    if n < 3 || ys.len() != n || CAP < 3 || out_xs.len() < CAP || out_coefficients.len() < CAP - 1 {
+        return Err(Error::InvalidSliceLength);
+    }
+    check_increasing(xs)?;
+    if tolerance.is_nan() || tolerance < 0f32 {
+        return Err(Error::InvalidLimit);
+    }
+
+    // Indices of the picked samples, in increasing order
+    let mut picked = [0usize; CAP];
+    picked[1] = n / 2;
+    picked[2] = n - 1;
+    let mut len = 3;
+    let mut knot_ys = [0f32; CAP];
+    loop {
+        for (i, index) in picked[..len].iter().enumerate() {
+            out_xs[i] = xs[*index];
+            knot_ys[i] = ys[*index];
+        }
+        splinterpol_slices::<CAP>(
+            &out_xs[..len],
+            &knot_ys[..len],
+            out_coefficients,
+            Boundary::Natural,
+            Solver::Thomas,
+        )?;
+
+        let mut worst = (0usize, 0f32);
+        let mut segment = 0;
+        for (index, (x, y)) in xs.iter().zip(ys).enumerate() {
+            while segment + 2 < len && *x >= out_xs[segment + 1] {
+                segment += 1;
+            }
+            // Picked samples are only missed by rounding
+            if *x == out_xs[segment] || *x == out_xs[segment + 1] {
+                continue;
+            }
+            let error = (eval_segment(out_coefficients[segment], x - out_xs[segment]) - y).abs();
+            if error > worst.1 {
+                worst = (index, error);
+            }
+        }
+        if worst.1 <= tolerance {
+            return Ok(len);
+        }
+        if len == CAP {
+            return Err(Error::CapacityExceeded);
+        }
+        let position = picked[..len].partition_point(|index| *index < worst.0);
+        picked.copy_within(position..len, position + 1);
+        picked[position] = worst.0;
+        len += 1;
+    }
+}
+
+/// Largest deviation of the spline `other` from the spline `coefficients`,
+/// at the knots `xs` and at `SIMPLIFY_SAMPLES` positions within each segment.
+fn max_deviation(
+    coefficients: &[(f32, f32, f32, f32)],
+    xs: &[f32],
+    other: &[(f32, f32, f32, f32)],
+    other_xs: &[f32],
+) -> f32 {
+    let mut deviation = 0f32;
+    for (segment, polynomial) in coefficients.iter().enumerate() {
+        let h = xs[segment + 1] - xs[segment];
+        for step in 0..=SIMPLIFY_SAMPLES + 1 {
+            let t = h * (step as f32 / (SIMPLIFY_SAMPLES + 1) as f32);
+            let x = xs[segment] + t;
+            let other_segment = find_segment(other_xs, x);
+            let y = eval_segment(other[other_segment], x - other_xs[other_segment]);
+            deviation = deviation.max((eval_segment(*polynomial, t) - y).abs());
+        }
+    }
+    deviation
+}
+
+/// At least three strictly increasing, finite knots, validated once by
+/// [`Knots::new`], so that interpolating, evaluating and plotting over them
+/// skip all checks, e.g. to evaluate a million times after one validation.
+///
+/// The coefficient tables taken by the methods are arrays of `M = N - 1`
+/// elements, which is checked at compile time as in [`splinterpol_array`].
+///
+/// ```
+/// use cubic_splinterpol::Knots;
+///
+/// let knots = Knots::new([0f32, 1.0, 2.5, 3.0]).unwrap();
+/// let mut coefficients = [(0f32, 0f32, 0f32, 0f32); 3];
+/// knots.interpolate(&[1.0, 2.0, 0.5, 3.0], &mut coefficients).unwrap();
+/// assert_eq!(2.0, knots.eval(&coefficients, 1.0));
+///
+/// let mut buffer = [0f32; 7];
+/// knots.plot_into(&coefficients, &mut buffer);
+/// assert_eq!(3.0, buffer[6]);
+/// ```
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Knots<const N: usize> {
+    xs: [f32; N],
+}
+
+impl<const N: usize> Knots<N> {
+    /// Validate the knots `xs`. Fails with [`Error::InvalidSliceLength`] for
+    /// fewer than three, [`Error::NonIncreasingKnots`] if they are not
+    /// strictly increasing or NaN, and [`Error::InvalidRange`] if one is
+    /// infinite.
+    pub fn new(xs: [f32; N]) -> Result<Self, Error> {
+        if N < 3 {
+            return Err(Error::InvalidSliceLength);
+        }
+        check_increasing(&xs)?;
+        if !xs[0].is_finite() || !xs[N - 1].is_finite() {
+            return Err(Error::InvalidRange);
+        }
+        Ok(Self { xs })
+    }
+
+    /// The knots.
+    pub fn as_array(&self) -> &[f32; N] {
+        &self.xs
+    }
+
+    /// Calculate the coefficients of the natural spline through the values
+    /// `ys` at the knots, as [`splinterpol`](crate::splinterpol) does.
+    pub fn interpolate<const M: usize>(
+        &self,
+        ys: &[f32; N],
+        coefficients: &mut [(f32, f32, f32, f32); M],
+    ) -> Result<(), Error> {
+        splinterpol_array_unchecked(&self.xs, ys, coefficients)
+    }
+
+    /// Evaluate the spline given by `coefficients` at global position x,
+    /// continuing the first and last polynomial outside of the knots.
+    pub fn eval<const M: usize>(&self, coefficients: &[(f32, f32, f32, f32); M], x: f32) -> f32 {
+        let () = ArrayLengths::<N, M>::VALID;
+        let segment = find_segment(&self.xs, x);
+        eval_segment(coefficients[segment], x - self.xs[segment])
+    }
+
+    /// Sample the spline given by `coefficients` uniformly into the buffer,
+    /// at the same positions as [`sample_uniform_into`](crate::sample_uniform_into).
+    pub fn plot_into<const M: usize>(
+        &self,
+        coefficients: &[(f32, f32, f32, f32); M],
+        buffer: &mut [f32],
+    ) {
+        let () = ArrayLengths::<N, M>::VALID;
+        sample_uniform(coefficients, &self.xs, buffer.len(), |index, y| {
+            buffer[index] = y
+        });
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{eval, Extrapolate};
+
+    #[test]
+    fn insert_knot_keeps_curve() {
+        let xs = [0f32, 1.0, 2.5, 3.0, 4.0];
+        let ys = [1f32, 2.0, 0.5, 3.0, 2.0];
+        let mut coeffs = [(0f32, 0f32, 0f32, 0f32); 4];
+        crate::splinterpol(&xs, &ys, &mut coeffs).unwrap();
+
+        let mut out_xs = [0f32; 6];
+        let mut out_coeffs = [(0f32, 0f32, 0f32, 0f32); 5];
+        assert_eq!(
+            Ok(2),
+            insert_knot(&coeffs, &xs, 1.7, &mut out_coeffs, &mut out_xs)
+        );
+        assert_eq!([0.0, 1.0, 1.7, 2.5, 3.0, 4.0], out_xs);
+        for i in 0..=40 {
+            let x = i as f32 / 10f32;
+            let before = eval(&coeffs, &xs, x, Extrapolate::Error).unwrap();
+            let after = eval(&out_coeffs, &out_xs, x, Extrapolate::Error).unwrap();
+            assert!((before - after).abs() < 1e-5, "{} vs {}", before, after);
+        }
+    }
+
+    #[test]
+    fn insert_knot_rejects_invalid_positions() {
+        let xs = [0f32, 1.0, 2.0];
+        let coeffs = [(0f32, 1f32, 0f32, 0f32); 2];
+        let mut out_xs = [0f32; 4];
+        let mut out_coeffs = [(0f32, 0f32, 0f32, 0f32); 3];
+        assert_eq!(
+            Err(Error::OutOfRange),
+            insert_knot(&coeffs, &xs, 2.5, &mut out_coeffs, &mut out_xs)
+        );
+        assert_eq!(
+            Err(Error::NonIncreasingKnots { index: 2 }),
+            insert_knot(&coeffs, &xs, 1.0, &mut out_coeffs, &mut out_xs)
+        );
+        assert_eq!(
+            Err(Error::NonIncreasingKnots { index: 3 }),
+            insert_knot(&coeffs, &xs, 2.0, &mut out_coeffs, &mut out_xs)
+        );
+        assert_eq!(
+            Err(Error::InvalidSliceLength),
+            insert_knot(&coeffs, &xs, 0.5, &mut out_coeffs[..2], &mut out_xs)
+        );
+    }
+
+    #[test]
+    fn simplify_drops_redundant_knots() {
+        // Samples of a single cubic: the knots in between carry no information
+        let xs = [0f32, 0.5, 1.0, 1.5, 2.0, 2.5, 3.0, 3.5, 4.0];
+        let mut ys = [0f32; 9];
+        for (y, x) in ys.iter_mut().zip(&xs) {
+            *y = x * x - 2f32 * x;
+        }
+        let mut coeffs = [(0f32, 0f32, 0f32, 0f32); 8];
+        crate::splinterpol(&xs, &ys, &mut coeffs).unwrap();
+
+        let mut out_xs = [0f32; 9];
+        let mut out_coeffs = [(0f32, 0f32, 0f32, 0f32); 8];
+        let kept = simplify(&coeffs, &xs, 0.05, &mut out_coeffs, &mut out_xs).unwrap();
+        assert!(kept < 9);
+        assert_eq!(0.0, out_xs[0]);
+        assert_eq!(4.0, out_xs[kept - 1]);
+        for i in 0..=40 {
+            let x = i as f32 / 10f32;
+            let original = eval(&coeffs, &xs, x, Extrapolate::Error).unwrap();
+            let simplified = eval(
+                &out_coeffs[..kept - 1],
+                &out_xs[..kept],
+                x,
+                Extrapolate::Error,
+            )
+            .unwrap();
+            assert!((original - simplified).abs() < 0.06);
+        }
+
+        // Nothing can be dropped without tolerance
+        let ys = [0f32, 1.0, -1.0, 2.0, 3.0, 1.0, -2.0, 0.5, 0.0];
+        crate::splinterpol(&xs, &ys, &mut coeffs).unwrap();
+        assert_eq!(
+            Ok(9),
+            simplify(&coeffs, &xs, 0.0, &mut out_coeffs, &mut out_xs)
+        );
+    }
+
+    #[test]
+    fn simplify_rejects_invalid_input() {
+        let xs = [0f32, 1.0, 2.0];
+        let coeffs = [(0f32, 1f32, 0f32, 0f32); 2];
+        let mut out_xs = [0f32; 3];
+        let mut out_coeffs = [(0f32, 0f32, 0f32, 0f32); 2];
+        assert_eq!(
+            Err(Error::InvalidLimit),
+            simplify(&coeffs, &xs, -1.0, &mut out_coeffs, &mut out_xs)
+        );
+        assert_eq!(
+            Err(Error::InvalidSliceLength),
+            simplify(&coeffs, &xs, 1.0, &mut out_coeffs[..1], &mut out_xs)
+        );
+    }
+
+    #[test]
+    fn select_knots_within_tolerance() {
+        let mut xs = [0f32; 101];
+        let mut ys = [0f32; 101];
+        for i in 0..101 {
+            xs[i] = i as f32 / 10f32;
+            ys[i] = xs[i] * xs[i] * 0.1 - xs[i];
+        }
+        ys[60] += 2.0;
+        let mut out_xs = [0f32; 32];
+        let mut out_coeffs = [(0f32, 0f32, 0f32, 0f32); 31];
+        let len = select_knots::<32>(&xs, &ys, 0.05, &mut out_xs, &mut out_coeffs).unwrap();
+        assert!(len < 32);
+        assert_eq!(0.0, out_xs[0]);
+        assert_eq!(10.0, out_xs[len - 1]);
+        assert!(out_xs[..len].contains(&6.0));
+        for (x, y) in xs.iter().zip(&ys) {
+            let value = eval(
+                &out_coeffs[..len - 1],
+                &out_xs[..len],
+                *x,
+                Extrapolate::Error,
+            );
+            assert!((value.unwrap() - y).abs() <= 0.05);
+        }
+
+        let mut out_xs = [0f32; 4];
+        let mut out_coeffs = [(0f32, 0f32, 0f32, 0f32); 3];
+        assert_eq!(
+            Err(Error::CapacityExceeded),
+            select_knots::<4>(&xs, &ys, 0.05, &mut out_xs, &mut out_coeffs)
+        );
+    }
+
+    #[test]
+    fn validated_knots() {
+        let xs = [0f32, 1.0, 2.5, 3.0, 4.0];
+        let ys = [1f32, -1.0, 0.5, 2.0, 0.0];
+        let knots = Knots::new(xs).unwrap();
+        let mut expected = [(0f32, 0f32, 0f32, 0f32); 4];
+        crate::splinterpol(&xs, &ys, &mut expected).unwrap();
+        let mut coeffs = [(0f32, 0f32, 0f32, 0f32); 4];
+        knots.interpolate(&ys, &mut coeffs).unwrap();
+        assert_eq!(expected, coeffs);
+        for x in [-1f32, 0.0, 1.7, 4.0, 5.0] {
+            assert_eq!(
+                eval(&coeffs, &xs, x, Extrapolate::ExtendCubic),
+                Ok(knots.eval(&coeffs, x))
+            );
+        }
+        let mut buffer = [0f32; 13];
+        knots.plot_into(&coeffs, &mut buffer);
+        let mut samples = [0f32; 13];
+        crate::sample_uniform_into(&mut samples, &coeffs, &xs).unwrap();
+        assert_eq!(samples, buffer);
+
+        assert_eq!(Err(Error::InvalidSliceLength), Knots::new([0f32, 1.0]));
+        assert_eq!(
+            Err(Error::NonIncreasingKnots { index: 2 }),
+            Knots::new([0f32, 1.0, f32::NAN])
+        );
+        assert_eq!(
+            Err(Error::InvalidRange),
+            Knots::new([0f32, 1.0, f32::INFINITY])
+        );
+    }
+}
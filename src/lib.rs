@@ -2,203 +2,780 @@
 
 #![deny(unsafe_code)]
 #![deny(missing_docs)]
-#![cfg_attr(not(test), no_std)]
-
-mod plot_spline;
-mod thomas_algorithm;
+#![cfg_attr(not(any(test, feature = "std")), no_std)]
+
+pub mod angles;
+pub mod anim;
+mod approximate;
+pub mod arithmetic;
+mod batch;
+mod blend;
+pub mod bspline;
+mod builder;
+mod cache;
+mod calculus;
+pub mod calibration;
+mod const_spline;
+mod constrained;
+#[cfg(feature = "std")]
+mod debug_plot;
+mod distance;
+mod duplicates;
+pub mod easing;
+pub mod envelope;
+mod extrapolate;
+mod extrema;
+pub mod fan;
+#[cfg(feature = "fixed")]
+pub mod fixed_point;
+pub mod gain_schedule;
+pub mod gradient;
+#[cfg(feature = "embedded-graphics")]
+mod graphics;
+pub mod grid2d;
+mod in_place;
+mod inverse;
+mod knots;
+mod monotone;
+pub mod motion;
+mod multi;
+pub mod orientation;
+pub mod path;
+pub mod plot_spline;
+pub mod ppoly;
+mod prefilter;
+mod quantize;
+pub mod rational;
+pub mod resample;
+mod sampler;
+pub mod scattered;
+mod segment;
+mod setpoint;
+mod sliding;
+mod smoothing;
+mod soa;
+pub mod soc;
+mod splice;
+#[cfg(feature = "std")]
+mod spline;
+pub mod storage;
+pub mod thermal;
+pub mod thomas_algorithm;
+mod transform;
+mod verify;
+
+pub use approximate::{approximate, max_error, Nodes};
+pub use batch::{eval_batch, eval_batch_extrapolated, eval_sorted, LANES};
+pub use blend::{Blend, BlendWeight};
+pub use builder::SplineBuilder;
+pub use cache::SplineCache;
+pub use calculus::{differentiate, splinterpol_from_derivative};
+pub use const_spline::splinterpol_const;
+pub use constrained::{splinterpol_with_corners, splinterpol_with_slopes};
+#[cfg(feature = "std")]
+pub use debug_plot::debug_plot;
+pub use distance::distance;
+pub use duplicates::{merge_duplicates, Duplicates};
+pub use extrapolate::{eval, eval_clamped, Extrapolate};
+pub use extrema::{bounds, min_max, overshoot};
+#[cfg(feature = "embedded-graphics")]
+pub use graphics::SplineCurve;
+pub use in_place::splinterpol_in_place;
+pub use inverse::{intersect, inverse_eval, invert_cdf, solve_for};
+pub use knots::{insert_knot, select_knots, simplify, Knots};
+pub use monotone::hyman_filter;
+pub use multi::splinterpol_multi;
+pub use plot_spline::{
+    flatten, for_each_sample, plot_coeffs_into, sample_adaptive_into, sample_clamped_into,
+    sample_interleaved_into, sample_segments_into, sample_uniform_into, segment_sample_count,
+    wavetable_into, ChunkedSampler, SplineSamples,
+};
+pub use prefilter::{prefilter_into, splinterpol_prefiltered, Prefilter};
+pub use quantize::{
+    bake_lut, plot_dithered_into, plot_quantized_interleaved_into, plot_quantized_into, Dither,
+    Quantize, U12,
+};
+pub use sampler::Sampler;
+pub use segment::Segment;
+pub use setpoint::SetpointGenerator;
+pub use sliding::SlidingSpline;
+pub use smoothing::{robust_smoothing_spline, smoothing_spline, RobustLoss};
+pub use soa::CoefficientsSoA;
+pub use splice::{concat, split_at, trim, Continuity};
+#[cfg(feature = "std")]
+pub use spline::Spline;
+pub use thomas_algorithm::Solver;
+pub use transform::{reverse, scale_x, scale_y, shift_x, shift_y};
+pub use verify::{verify, VerifyError};
 
 /// The possible errors of this crate
 #[derive(Debug, Clone, Copy, PartialEq, Eq)]
 pub enum Error {
     /// Slice of invalid length passed
     InvalidSliceLength,
+    /// The linear system has a (near-)zero pivot in the given row and cannot
+    /// be solved
+    SingularSystem {
+        /// Row of the offending pivot
+        row: usize,
+    },
+    /// Step size is not strictly positive
+    InvalidStep,
+    /// No coefficients or knots were passed
+    EmptyInput,
+    /// Lower bound of a range is not below its upper bound
+    InvalidRange,
+    /// An intermediate result does not fit into the number format
+    Overflow,
+    /// The knot at the given index is not strictly greater than its
+    /// predecessor, or not a number
+    NonIncreasingKnots {
+        /// Index of the offending knot
+        index: usize,
+    },
+    /// No more elements fit into a fixed capacity buffer
+    CapacityExceeded,
+    /// Periodic boundary conditions need equal first and last values
+    NotPeriodic,
+    /// Position outside of the first and last knot
+    OutOfRange,
+    /// A limit or tolerance is negative, or zero where it must be strictly
+    /// positive
+    InvalidLimit,
+    /// Serialized data is truncated, malformed or of an unsupported version
+    InvalidFormat,
+    /// Serialized data does not match its checksum
+    ChecksumMismatch,
+}
+
+impl core::fmt::Display for Error {
+    fn fmt(&self, f: &mut core::fmt::Formatter<'_>) -> core::fmt::Result {
+        match self {
+            Error::InvalidSliceLength => write!(f, "slice of invalid length"),
+            Error::SingularSystem { row } => {
+                write!(f, "singular system, zero pivot in row {}", row)
+            }
+            Error::InvalidStep => write!(f, "step size is not strictly positive"),
+            Error::EmptyInput => write!(f, "empty input"),
+            Error::InvalidRange => write!(f, "lower bound is not below upper bound"),
+            Error::Overflow => write!(f, "arithmetic overflow"),
+            Error::NonIncreasingKnots { index } => {
+                write!(f, "knot {} is not greater than its predecessor", index)
+            }
+            Error::CapacityExceeded => write!(f, "capacity exceeded"),
+            Error::NotPeriodic => write!(f, "first and last value differ"),
+            Error::OutOfRange => write!(f, "position outside of the knots"),
+            Error::InvalidLimit => write!(f, "invalid limit or tolerance"),
+            Error::InvalidFormat => write!(f, "invalid serialized data"),
+            Error::ChecksumMismatch => write!(f, "checksum mismatch"),
+        }
+    }
+}
+
+#[cfg(feature = "std")]
+impl std::error::Error for Error {}
+
+/// Conditions which determine the spline at its first and last knot.
+#[derive(Debug, Clone, Copy, PartialEq, Default)]
+pub enum Boundary {
+    /// Zero second derivative at both ends
+    #[default]
+    Natural,
+    /// Prescribed first derivative at both ends
+    Clamped {
+        /// First derivative at the first knot
+        start: f32,
+        /// First derivative at the last knot
+        end: f32,
+    },
+    /// The third derivative is continuous at the second and the second to last
+    /// knot, so the first two and the last two segments are the same cubic.
+    /// Needs at least four points.
+    NotAKnot,
+    /// Value, first and second derivative at the last knot match the first
+    /// knot, for closed curves and periodic signals. The first and last y must
+    /// be equal. Needs at least four points. Always solved with the cyclic
+    /// Thomas algorithm, whatever [`Solver`] is selected.
+    Periodic,
+    /// Prescribed second derivative at both ends
+    SecondDerivative {
+        /// Second derivative at the first knot
+        start: f32,
+        /// Second derivative at the last knot
+        end: f32,
+    },
 }
 
 /// Given xs and ys of same length n, calculate the coefficients of n-1 cubic
 /// polynomials.
+///
+/// # Stack usage
+///
+/// Besides the inputs and `coefficients`, the computation takes two scratch
+/// arrays of `N` f32 on the stack, `8 * N` bytes, for this and every other
+/// boundary condition solved with [`Solver::Thomas`]. [`Solver::PartialPivoting`]
+/// takes three more, `20 * N` bytes, and [`Boundary::Periodic`] five more,
+/// `28 * N` bytes, in frames of their own.
 pub fn splinterpol<const N: usize>(
     xs: &[f32; N],
     ys: &[f32; N],
     coefficients: &mut [(f32, f32, f32, f32)],
 ) -> Result<(), Error> {
-    // Array size const expression workaround
-    let mut diagonal = [0f32; N];
-    let mut diagonal = &mut diagonal[0..N - 2];
+    splinterpol_with_solver::<N>(xs, ys, coefficients, Solver::Thomas)
+}
 
-    calc_diagonal::<N>(xs, &mut diagonal).unwrap();
+/// Like [`splinterpol`], but with a choice of the algorithm used to solve the
+/// tridiagonal system. [`Solver::PartialPivoting`] trades speed for accuracy
+/// when knot spacings vary by several orders of magnitude.
+pub fn splinterpol_with_solver<const N: usize>(
+    xs: &[f32; N],
+    ys: &[f32; N],
+    coefficients: &mut [(f32, f32, f32, f32)],
+    solver: Solver,
+) -> Result<(), Error> {
+    splinterpol_slices::<N>(xs, ys, coefficients, Boundary::Natural, solver)
+}
 
-    let mut r = [0f32; N];
-    let mut r = &mut r[0..N - 2];
+/// Like [`splinterpol`], but with the given conditions at the first and last
+/// knot instead of the natural zero curvature.
+pub fn splinterpol_with_boundary<const N: usize>(
+    xs: &[f32; N],
+    ys: &[f32; N],
+    coefficients: &mut [(f32, f32, f32, f32)],
+    boundary: Boundary,
+) -> Result<(), Error> {
+    splinterpol_slices::<N>(xs, ys, coefficients, boundary, Solver::Thomas)
+}
 
-    if let Err(e) = calc_r::<N>(xs, ys, &mut r) {
-        return Err(e);
-    }
+/// Calculate the coefficients of the spline with second derivative `start` at
+/// the first and `end` at the last knot, e.g. for a beam or rail profile with
+/// prescribed end curvature. Zero at both ends gives the natural spline.
+pub fn splinterpol_with_curvature<const N: usize>(
+    xs: &[f32; N],
+    ys: &[f32; N],
+    coefficients: &mut [(f32, f32, f32, f32)],
+    start: f32,
+    end: f32,
+) -> Result<(), Error> {
+    splinterpol_with_boundary::<N>(
+        xs,
+        ys,
+        coefficients,
+        Boundary::SecondDerivative { start, end },
+    )
+}
 
-    let mut sub_diagonal = [0f32; N];
-    let mut sub_diagonal = &mut sub_diagonal[0..N - 3];
+/// Checks the lengths of [`splinterpol_array`] when it is instantiated, so
+/// that wrong ones fail to compile.
+struct ArrayLengths<const N: usize, const M: usize>;
 
-    if let Err(e) = calc_subdiagonal(xs, &mut sub_diagonal) {
-        return Err(e);
-    }
+impl<const N: usize, const M: usize> ArrayLengths<N, M> {
+    const VALID: () = assert!(N >= 3 && M + 1 == N, "M must be N - 1, N at least 3");
+}
 
-    let c = {
-        let mut c = [0f32; N];
-        let mut c_body = &mut c[1..N - 1];
-        if let Err(e) = thomas_algorithm::thomas_algorithm_symmetric(
-            &sub_diagonal,
-            &mut diagonal,
-            &mut r,
-            &mut c_body,
-        ) {
-            return Err(e);
-        }
-        c
-    };
+/// Like [`splinterpol`], but with the coefficients as an array of `M = N - 1`
+/// elements. Lengths which do not match are a compile error instead of
+/// [`Error::InvalidSliceLength`], and are not checked at runtime.
+///
+/// ```
+/// use cubic_splinterpol::{splinterpol, splinterpol_array};
+///
+/// let xs = [0f32, 1.0, 2.5, 3.0];
+/// let ys = [1f32, 2.0, 0.5, 3.0];
+/// let mut coefficients = [(0f32, 0f32, 0f32, 0f32); 3];
+/// splinterpol_array(&xs, &ys, &mut coefficients).unwrap();
+///
+/// let mut expected = [(0f32, 0f32, 0f32, 0f32); 3];
+/// splinterpol(&xs, &ys, &mut expected).unwrap();
+/// assert_eq!(expected, coefficients);
+/// ```
+///
+/// ```compile_fail
+/// use cubic_splinterpol::splinterpol_array;
+///
+/// let mut coefficients = [(0f32, 0f32, 0f32, 0f32); 4];
+/// splinterpol_array(&[0f32, 1.0, 2.0, 3.0], &[0f32; 4], &mut coefficients).unwrap();
+/// ```
+pub fn splinterpol_array<const N: usize, const M: usize>(
+    xs: &[f32; N],
+    ys: &[f32; N],
+    coefficients: &mut [(f32, f32, f32, f32); M],
+) -> Result<(), Error> {
+    check_increasing(xs)?;
+    splinterpol_array_unchecked(xs, ys, coefficients)
+}
 
-    let mut b = [0f32; N];
-    let mut b = &mut b[0..N - 1];
+/// [`splinterpol_array`] for knots which are known to be strictly
+/// increasing.
+fn splinterpol_array_unchecked<const N: usize, const M: usize>(
+    xs: &[f32; N],
+    ys: &[f32; N],
+    coefficients: &mut [(f32, f32, f32, f32); M],
+) -> Result<(), Error> {
+    let () = ArrayLengths::<N, M>::VALID;
+    let mut c = [0f32; N];
+    calc_c::<N>(xs, ys, &mut c, Boundary::Natural, Solver::Thomas)?;
+    calc_coefficients(xs, ys, &c, coefficients)
+}
 
-    if let Err(e) = calc_b::<N>(xs, ys, &c, &mut b) {
-        return Err(e);
+/// Calculate the coefficients of the spline through xs and ys of same runtime
+/// length n, using scratch buffers of const size `CAP >= n`. Writes the first
+/// n-1 elements of coefficients.
+///
+/// This is the single place where the linear system for the quadratic
+/// coefficients is assembled and solved for every [`Boundary`] condition.
+///
+/// Only two scratch arrays are live with the Thomas solver: c, which first
+/// holds the right hand side of the system and then its solution, and the
+/// main diagonal. The sub-diagonals are computed from the knots whenever the
+/// solver needs them, and b and d go straight to `coefficients`.
+fn splinterpol_slices<const CAP: usize>(
+    xs: &[f32],
+    ys: &[f32],
+    coefficients: &mut [(f32, f32, f32, f32)],
+    boundary: Boundary,
+    solver: Solver,
+) -> Result<(), Error> {
+    let n = xs.len();
+    if n < 3 || n > CAP || ys.len() != n || coefficients.len() < n - 1 {
+        return Err(Error::InvalidSliceLength);
+    }
+    // Equal knots would divide by zero in the system
+    check_increasing(xs)?;
+
+    let mut c = [0f32; CAP];
+    let c = &mut c[0..n];
+    match boundary {
+        Boundary::Periodic => calc_c_periodic::<CAP>(xs, ys, c)?,
+        _ => calc_c::<CAP>(xs, ys, c, boundary, solver)?,
     }
 
-    let mut d = [0f32; N];
-    let mut d = &mut d[0..N - 1];
+    calc_coefficients(xs, ys, c, &mut coefficients[..n - 1])
+}
 
-    if let Err(e) = calc_d::<N>(xs, &c, &mut d) {
-        return Err(e);
+/// Solve for the quadratic coefficients c of all knots. The unknowns are the
+/// interior c[1..n-1]; the boundary condition determines how c[0] and c[n-1]
+/// are eliminated from the first and last rows of the system.
+fn calc_c<const CAP: usize>(
+    xs: &[f32],
+    ys: &[f32],
+    c: &mut [f32],
+    boundary: Boundary,
+    solver: Solver,
+) -> Result<(), Error> {
+    let n = xs.len();
+
+    // Array size const expression workaround
+    let mut diagonal = [0f32; CAP];
+    let diagonal = &mut diagonal[0..n - 2];
+
+    calc_diagonal(xs, diagonal)?;
+
+    // The right hand side is solved in place, so it starts out where the
+    // solution ends up
+    calc_r(xs, ys, &mut c[1..n - 1])?;
+
+    let slope = |i: usize| (ys[i + 1] - ys[i]) / h(i, xs);
+    let last = n - 3;
+    // Replacements for the first upper and the last lower sub-diagonal entry,
+    // which differ from the knot spacing for the not-a-knot condition
+    let mut first_upper = None;
+    let mut last_lower = None;
+    match boundary {
+        Boundary::Natural | Boundary::Periodic => {}
+        Boundary::SecondDerivative { start, end } => {
+            c[0] = start / 2f32;
+            c[n - 1] = end / 2f32;
+            c[1] -= h(0, xs) * c[0];
+            c[n - 2] -= h(n - 2, xs) * c[n - 1];
+        }
+        Boundary::Clamped { start, end } => {
+            // 2*h0*c0 + h0*c1 = 3*(slope0 - start), eliminated into row 0
+            diagonal[0] -= h(0, xs) / 2f32;
+            c[1] -= 1.5f32 * (slope(0) - start);
+            diagonal[last] -= h(n - 2, xs) / 2f32;
+            c[n - 2] -= 1.5f32 * (end - slope(n - 2));
+        }
+        Boundary::NotAKnot => {
+            if n < 4 {
+                return Err(Error::InvalidSliceLength);
+            }
+            // d0 = d1, i.e. c0 = ((h0 + h1)*c1 - h0*c2) / h1, eliminated into
+            // row 0, and likewise at the end
+            let (h0, h1) = (h(0, xs), h(1, xs));
+            diagonal[0] = (h0 + h1) * (h0 + 2f32 * h1) / h1;
+            first_upper = Some((h1 - h0) * (h1 + h0) / h1);
+            let (a, b) = (h(n - 3, xs), h(n - 2, xs));
+            diagonal[last] = (a + b) * (2f32 * a + b) / a;
+            last_lower = Some((a - b) * (a + b) / a);
+        }
+    }
+
+    let lower = |i: usize| match last_lower {
+        Some(value) if i + 1 == last => value,
+        _ => h(i + 1, xs),
+    };
+    let upper = |i: usize| match first_upper {
+        Some(value) if i == 0 => value,
+        _ => h(i + 1, xs),
+    };
+    let c_body = &mut c[1..n - 1];
+    match solver {
+        Solver::Thomas => {
+            thomas_algorithm::thomas_algorithm_in_place(lower, diagonal, upper, c_body)?
+        }
+        Solver::PartialPivoting => calc_c_pivoting::<CAP>(lower, diagonal, upper, c_body)?,
     }
 
-    for i in 0..N - 1 {
-        coefficients[i].0 = ys[i];
-        coefficients[i].1 = b[i];
-        coefficients[i].2 = c[i];
-        coefficients[i].3 = d[i];
+    match boundary {
+        Boundary::Clamped { start, end } => {
+            c[0] = 1.5f32 * (slope(0) - start) / h(0, xs) - c[1] / 2f32;
+            c[n - 1] = 1.5f32 * (end - slope(n - 2)) / h(n - 2, xs) - c[n - 2] / 2f32;
+        }
+        Boundary::NotAKnot => {
+            let (h0, h1) = (h(0, xs), h(1, xs));
+            c[0] = ((h0 + h1) * c[1] - h0 * c[2]) / h1;
+            let (a, b) = (h(n - 3, xs), h(n - 2, xs));
+            c[n - 1] = ((a + b) * c[n - 2] - b * c[n - 3]) / a;
+        }
+        _ => {}
     }
     Ok(())
 }
 
-fn calc_subdiagonal(vals: &[f32], sub: &mut [f32]) -> Result<(), Error> {
-    if vals.len() != sub.len() + 3 {
+/// Solve the system of [`calc_c`] with partial pivoting, which needs the
+/// sub-diagonals and the right hand side as arrays of their own. Kept out of
+/// line so that these do not add to the stack frame of the Thomas path.
+#[inline(never)]
+fn calc_c_pivoting<const CAP: usize>(
+    lower: impl Fn(usize) -> f32,
+    diagonal: &mut [f32],
+    upper: impl Fn(usize) -> f32,
+    rx: &mut [f32],
+) -> Result<(), Error> {
+    let m = diagonal.len();
+    let mut lower_diagonal = [0f32; CAP];
+    let lower_diagonal = &mut lower_diagonal[0..m - 1];
+    let mut upper_diagonal = [0f32; CAP];
+    let upper_diagonal = &mut upper_diagonal[0..m - 1];
+    for i in 0..m - 1 {
+        lower_diagonal[i] = lower(i);
+        upper_diagonal[i] = upper(i);
+    }
+    let mut r = [0f32; CAP];
+    let r = &mut r[0..m];
+    r.copy_from_slice(rx);
+    thomas_algorithm::tridiagonal_partial_pivoting(lower_diagonal, diagonal, upper_diagonal, r, rx)
+}
+
+/// Solve for the quadratic coefficients c of a periodic spline, where
+/// c[n-1] = c[0] and the system wraps around from the last to the first knot.
+/// Kept out of line like [`calc_c_pivoting`] for the sake of its scratch
+/// arrays.
+#[inline(never)]
+fn calc_c_periodic<const CAP: usize>(xs: &[f32], ys: &[f32], c: &mut [f32]) -> Result<(), Error> {
+    let n = xs.len();
+    if n < 4 {
         return Err(Error::InvalidSliceLength);
     }
-    let n = vals.len();
-    for i in 0..(n - 3) {
-        sub[i] = vals[i + 2] - vals[i + 1];
+    if ys[0] != ys[n - 1] {
+        return Err(Error::NotPeriodic);
+    }
+    let m = n - 1;
+    let slope = |i: usize| (ys[i + 1] - ys[i]) / h(i, xs);
+
+    let mut main = [0f32; CAP];
+    let main = &mut main[0..m];
+    let mut r = [0f32; CAP];
+    let r = &mut r[0..m];
+    let mut off_diagonal = [0f32; CAP];
+    let off_diagonal = &mut off_diagonal[0..m - 1];
+    for i in 0..m {
+        let previous = if i == 0 { m - 1 } else { i - 1 };
+        main[i] = 2f32 * (h(previous, xs) + h(i, xs));
+        r[i] = 3f32 * (slope(i) - slope(previous));
     }
+    for (i, elem) in off_diagonal.iter_mut().enumerate() {
+        *elem = h(i, xs);
+    }
+    let corner = h(m - 1, xs);
+
+    let mut scratch = [[0f32; 3]; CAP];
+    let scratch = scratch.as_flattened_mut();
+    thomas_algorithm::thomas_algorithm_cyclic(
+        off_diagonal,
+        main,
+        off_diagonal,
+        (corner, corner),
+        r,
+        &mut c[0..m],
+        &mut scratch[0..3 * m],
+    )?;
+    c[m] = c[0];
     Ok(())
 }
 
-fn cubic_spline(a: f32, b: f32, c: f32, d: f32, vec: &mut [f32], step_size: f32) {
-    for (index, elem) in vec.iter_mut().enumerate() {
-        let base = index as f32 * step_size;
-        let value = a + b * base + c * (base * base) + d * (base * base * base);
-        *elem = value;
-    }
+/// Evaluate the polynomial `a + b*t + c*t^2 + d*t^3` with the given
+/// coefficients at offset `t` from the start of its segment.
+///
+/// Uses Horner's scheme `((d*t + c)*t + b)*t + a`, which takes three
+/// multiplications instead of six.
+pub const fn eval_segment(coefficients: (f32, f32, f32, f32), t: f32) -> f32 {
+    let (a, b, c, d) = coefficients;
+    ((d * t + c) * t + b) * t + a
 }
 
-fn h(i: usize, vals: &[f32]) -> f32 {
-    vals[i + 1] - vals[i]
+/// Evaluate the derivative `b + 2*c*t + 3*d*t^2` of the polynomial with the
+/// given coefficients at offset `t` from the start of its segment.
+pub(crate) const fn eval_segment_derivative(coefficients: (f32, f32, f32, f32), t: f32) -> f32 {
+    let (_, b, c, d) = coefficients;
+    (3f32 * d * t + 2f32 * c) * t + b
 }
 
-fn calc_diagonal<const N: usize>(xs: &[f32], result: &mut [f32]) -> Result<(), Error> {
-    if xs.len() != N {
-        return Err(Error::InvalidSliceLength);
+/// Evaluate the second derivative `2*c + 6*d*t` of the polynomial with the
+/// given coefficients at offset `t` from the start of its segment.
+pub(crate) const fn eval_segment_second_derivative(
+    coefficients: (f32, f32, f32, f32),
+    t: f32,
+) -> f32 {
+    let (_, _, c, d) = coefficients;
+    6f32 * d * t + 2f32 * c
+}
+
+/// Check that coefficients and knots describe a spline: at least one segment,
+/// and one more knot than segments.
+fn check_spline(coefficients: &[(f32, f32, f32, f32)], xs: &[f32]) -> Result<(), Error> {
+    if coefficients.is_empty() {
+        return Err(Error::EmptyInput);
     }
-    for (i, elem) in result.iter_mut().enumerate().take(N - 2) {
-        *elem = 2f32 * (h(i, &xs) + h(i + 1, &xs));
+    if xs.len() != coefficients.len() + 1 {
+        return Err(Error::InvalidSliceLength);
     }
     Ok(())
 }
 
-fn calc_r<const N: usize>(xs: &[f32], ys: &[f32], r: &mut [f32]) -> Result<(), Error> {
-    if r.len() != N - 2 {
-        return Err(Error::InvalidSliceLength);
+/// Check that the knots are strictly increasing, rejecting NaN knots as well.
+fn check_increasing(xs: &[f32]) -> Result<(), Error> {
+    for (i, pair) in xs.windows(2).enumerate() {
+        if !matches!(
+            pair[0].partial_cmp(&pair[1]),
+            Some(core::cmp::Ordering::Less)
+        ) {
+            return Err(Error::NonIncreasingKnots { index: i + 1 });
+        }
     }
-    if xs.len() != N {
-        return Err(Error::InvalidSliceLength);
+    Ok(())
+}
+
+/// Check that min is strictly below max, rejecting NaN bounds as well.
+fn check_range(min: f32, max: f32) -> Result<(), Error> {
+    if min < max {
+        Ok(())
+    } else {
+        Err(Error::InvalidRange)
     }
-    if ys.len() != N {
-        return Err(Error::InvalidSliceLength);
+}
+
+/// The index of the segment of the spline with knots `xs` containing global
+/// position x, by binary search, or `None` outside of the knots, for NaN and
+/// for fewer than two knots.
+///
+/// These are the conventions of evaluation and plotting: every knot belongs to
+/// the segment it starts, and the last knot to the last segment, which ends
+/// there. A segment index i evaluates at `x - xs[i]`.
+///
+/// ```
+/// use cubic_splinterpol::segment_index;
+///
+/// let xs = [0f32, 1.0, 2.0, 4.0];
+/// assert_eq!(Some(0), segment_index(&xs, 0.0));
+/// assert_eq!(Some(1), segment_index(&xs, 1.0));
+/// assert_eq!(Some(2), segment_index(&xs, 4.0));
+/// assert_eq!(None, segment_index(&xs, 4.5));
+/// ```
+pub fn segment_index(xs: &[f32], x: f32) -> Option<usize> {
+    if xs.len() < 2 || !(xs[0]..=xs[xs.len() - 1]).contains(&x) {
+        return None;
     }
-    for i in 0..N - 2 {
-        let div1 = (ys[i + 2] - ys[i + 1]) / (h(i + 1, &xs));
-        let div2 = (ys[i + 1] - ys[i]) / (h(i, &xs));
-        r[i] = 3f32 * (div1 - div2);
+    Some(find_segment(xs, x))
+}
+
+/// Find the index of the segment containing x by binary search. Knots belong
+/// to the segment they start, positions outside of the knots belong to the
+/// first or last segment. xs must have at least two elements.
+fn find_segment<T: PartialOrd + Copy>(xs: &[T], x: T) -> usize {
+    find_segment_by(xs.len(), |i| xs[i], x)
+}
+
+/// Like [`find_segment`], for `len` knots read through `knot`, e.g. straight
+/// from serialized bytes.
+fn find_segment_by<T: PartialOrd>(len: usize, knot: impl Fn(usize) -> T, x: T) -> usize {
+    let last = len - 2;
+    // Invariant: the segment is within low..=high
+    let mut low = 0;
+    let mut high = last;
+    while low < high {
+        let mid = low + (high - low).div_ceil(2);
+        if knot(mid) <= x {
+            low = mid;
+        } else {
+            high = mid - 1;
+        }
     }
-    Ok(())
+    low
 }
 
-fn calc_b<const N: usize>(xs: &[f32], ys: &[f32], cs: &[f32], b: &mut [f32]) -> Result<(), Error> {
-    if cs.len() != N {
-        return Err(Error::InvalidSliceLength);
+/// Coefficients of the same cubic expanded around offset s from the start of
+/// its segment instead, i.e. as polynomial in t - s.
+fn rebase(polynomial: (f32, f32, f32, f32), s: f32) -> (f32, f32, f32, f32) {
+    let (_, _, c, d) = polynomial;
+    (
+        eval_segment(polynomial, s),
+        eval_segment_derivative(polynomial, s),
+        c + 3f32 * d * s,
+        d,
+    )
+}
+
+/// Call `f` for every piece of the overlap of the ranges of knots of two
+/// splines between two consecutive knots of either, with the difference of
+/// the first and the second spline on it as cubic relative to the start of
+/// the piece, the global start and end of the piece, and whether it is the
+/// last. Returns whether the ranges overlap at all.
+fn for_each_difference(
+    first_coefficients: &[(f32, f32, f32, f32)],
+    first_xs: &[f32],
+    second_coefficients: &[(f32, f32, f32, f32)],
+    second_xs: &[f32],
+    mut f: impl FnMut((f32, f32, f32, f32), f32, f32, bool) -> Result<(), Error>,
+) -> Result<bool, Error> {
+    let from = first_xs[0].max(second_xs[0]);
+    let to = first_xs[first_xs.len() - 1].min(second_xs[second_xs.len() - 1]);
+    if check_range(from, to).is_err() {
+        return Ok(false);
     }
-    if b.len() != N - 1 {
+    let mut i = find_segment(first_xs, from);
+    let mut j = find_segment(second_xs, from);
+    let mut start = from;
+    loop {
+        let end = first_xs[i + 1].min(second_xs[j + 1]).min(to);
+        let first = rebase(first_coefficients[i], start - first_xs[i]);
+        let second = rebase(second_coefficients[j], start - second_xs[j]);
+        let difference = (
+            first.0 - second.0,
+            first.1 - second.1,
+            first.2 - second.2,
+            first.3 - second.3,
+        );
+        f(difference, start, end, end == to)?;
+        if end == to {
+            return Ok(true);
+        }
+        if first_xs[i + 1] == end {
+            i += 1;
+        }
+        if second_xs[j + 1] == end {
+            j += 1;
+        }
+        start = end;
+    }
+}
+
+/// Coefficients of the cubic with values y0, y1 and derivatives m0, m1 at the
+/// ends of an interval of width h.
+fn hermite(y0: f32, y1: f32, m0: f32, m1: f32, h: f32) -> (f32, f32, f32, f32) {
+    let delta = (y1 - y0) / h;
+    (
+        y0,
+        m0,
+        (3f32 * delta - 2f32 * m0 - m1) / h,
+        (m0 + m1 - 2f32 * delta) / (h * h),
+    )
+}
+
+fn h(i: usize, vals: &[f32]) -> f32 {
+    vals[i + 1] - vals[i]
+}
+
+fn calc_diagonal(xs: &[f32], result: &mut [f32]) -> Result<(), Error> {
+    let n = xs.len();
+    if result.len() != n - 2 {
         return Err(Error::InvalidSliceLength);
     }
-    for i in 0..N - 1 {
-        let div_1 = (ys[i + 1] - ys[i]) / (h(i, &xs));
-        let div_2 = (2f32 * cs[i] + cs[i + 1]) / 3f32;
-        b[i] = div_1 - div_2 * h(i, &xs);
+    for (i, elem) in result.iter_mut().enumerate() {
+        *elem = 2f32 * (h(i, xs) + h(i + 1, xs));
     }
     Ok(())
 }
 
-fn calc_d<const N: usize>(xs: &[f32], cs: &[f32], d: &mut [f32]) -> Result<(), Error> {
-    if xs.len() != N {
-        return Err(Error::InvalidSliceLength);
-    }
-    if cs.len() != N {
+fn calc_r(xs: &[f32], ys: &[f32], r: &mut [f32]) -> Result<(), Error> {
+    let n = xs.len();
+    if r.len() != n - 2 {
         return Err(Error::InvalidSliceLength);
     }
-    if d.len() != N - 1 {
+    if ys.len() != n {
         return Err(Error::InvalidSliceLength);
     }
-    for i in 0..N - 1 {
-        d[i] = (cs[i + 1] - cs[i]) / (3f32 * h(i, &xs));
+    for i in 0..n - 2 {
+        let div1 = (ys[i + 2] - ys[i + 1]) / (h(i + 1, xs));
+        let div2 = (ys[i + 1] - ys[i]) / (h(i, xs));
+        r[i] = 3f32 * (div1 - div2);
     }
     Ok(())
 }
 
-/// Plot given coefficients into the buffer according to the intervals given in xs
-pub fn plot_coeffs_into(
-    buffer: &mut [f32],
-    coefficients: &[(f32, f32, f32, f32)],
+/// Write the polynomials of all segments from the quadratic coefficients c of
+/// all knots.
+fn calc_coefficients(
     xs: &[f32],
+    ys: &[f32],
+    cs: &[f32],
+    coefficients: &mut [(f32, f32, f32, f32)],
 ) -> Result<(), Error> {
-    let x_range = xs.last().unwrap() - xs.first().unwrap();
-    let step_size = x_range as f64 / buffer.len() as f64;
-    let mut current_index = 0;
-    for i in 0..coefficients.len() {
-        let range = xs[i + 1] - xs[i];
-        let ratio = range / x_range;
-        // f32::round not available in no_std
-        let buffer_ratio = {
-            let r = buffer.len() as f32 * ratio;
-            if r - ((r as u32) as f32) < 0.5 {
-                r as u32
-            } else {
-                r as u32 + 1
-            }
-        };
-        let mut upper = current_index + buffer_ratio as usize;
-        if upper >= buffer.len() {
-            upper = buffer.len()
-        };
-        let mut current_slice = &mut buffer[current_index..upper];
-        cubic_spline(
-            coefficients[i].0,
-            coefficients[i].1,
-            coefficients[i].2,
-            coefficients[i].3,
-            &mut current_slice,
-            step_size as f32,
-        );
-        current_index += buffer_ratio as usize;
+    let n = xs.len();
+    if ys.len() != n || cs.len() != n || coefficients.len() != n - 1 {
+        return Err(Error::InvalidSliceLength);
+    }
+    for (i, elem) in coefficients.iter_mut().enumerate() {
+        let div_1 = (ys[i + 1] - ys[i]) / (h(i, xs));
+        let div_2 = (2f32 * cs[i] + cs[i + 1]) / 3f32;
+        let b = div_1 - div_2 * h(i, xs);
+        let d = (cs[i + 1] - cs[i]) / (3f32 * h(i, xs));
+        *elem = (ys[i], b, cs[i], d);
     }
     Ok(())
 }
 
+/// Splines shared by the unit tests of the modules.
+#[cfg(test)]
+mod fixture {
+    /// Unevenly spaced knots, with a short segment between a local minimum
+    /// and maximum of [`YS`].
+    pub(crate) const XS: [f32; 5] = [0f32, 1.0, 2.5, 3.0, 4.0];
+    pub(crate) const YS: [f32; 5] = [1f32, 2.0, 0.5, 3.0, 2.0];
+
+    /// The natural spline through [`XS`] and [`YS`].
+    pub(crate) fn spline() -> [(f32, f32, f32, f32); 4] {
+        spline_through(&YS)
+    }
+
+    /// The natural spline through other values at [`XS`].
+    pub(crate) fn spline_through(ys: &[f32; 5]) -> [(f32, f32, f32, f32); 4] {
+        natural(&XS, ys)
+    }
+
+    /// The natural spline through xs and ys, with `M = N - 1` segments.
+    pub(crate) fn natural<const N: usize, const M: usize>(
+        xs: &[f32; N],
+        ys: &[f32; N],
+    ) -> [(f32, f32, f32, f32); M] {
+        assert_eq!(N, M + 1);
+        let mut coefficients = [(0f32, 0f32, 0f32, 0f32); M];
+        crate::splinterpol(xs, ys, &mut coefficients).unwrap();
+        coefficients
+    }
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -231,6 +808,48 @@ mod tests {
         dbg!(buffer);
     }
 
+    #[test]
+    fn plot_coeffs_into_rejects_invalid_input() {
+        let mut buffer = [0f32; 10];
+        assert_eq!(
+            Err(Error::EmptyInput),
+            plot_coeffs_into(&mut buffer, &[], &[])
+        );
+        let coeffs = [(0f32, 1f32, 0f32, 0f32); 2];
+        assert_eq!(
+            Err(Error::InvalidSliceLength),
+            plot_coeffs_into(&mut buffer, &coeffs, &[0f32, 1f32])
+        );
+        assert_eq!(
+            Ok(()),
+            plot_coeffs_into(&mut [], &coeffs, &[0f32, 1f32, 2f32])
+        );
+    }
+
+    #[test]
+    fn find_segment_edges() {
+        let xs = [0f32, 1f32, 2f32, 4f32];
+        assert_eq!(0, find_segment(&xs, -1.0));
+        assert_eq!(0, find_segment(&xs, 0.0));
+        assert_eq!(0, find_segment(&xs, 0.5));
+        assert_eq!(1, find_segment(&xs, 1.0));
+        assert_eq!(2, find_segment(&xs, 3.0));
+        assert_eq!(2, find_segment(&xs, 4.0));
+        assert_eq!(2, find_segment(&xs, 5.0));
+        assert_eq!(0, find_segment(&xs[..2], 5.0));
+    }
+
+    #[test]
+    fn segment_index_edges() {
+        let xs = [0f32, 1f32, 2f32, 4f32];
+        assert_eq!(None, segment_index(&xs, -0.1));
+        assert_eq!(Some(0), segment_index(&xs, 0.5));
+        assert_eq!(Some(2), segment_index(&xs, 2.0));
+        assert_eq!(Some(2), segment_index(&xs, 3.9));
+        assert_eq!(None, segment_index(&xs, f32::NAN));
+        assert_eq!(None, segment_index(&xs[..1], 0.0));
+    }
+
     #[test]
     fn test_splinterpol() {
         let xs = [
@@ -285,6 +904,22 @@ mod tests {
         assert_eq!(expected, coeffs);
     }
 
+    #[test]
+    fn test_splinterpol_partial_pivoting() {
+        let xs = [
+            0.001f32, 0.002f32, 0.5f32, 1f32, 100f32, 100.01f32, 200f32, 1000f32,
+        ];
+        let ys = [0f32, 1f32, 2f32, 1f32, 4f32, 7f32, 9f32, 10f32];
+        let mut thomas = [(0f32, 0f32, 0f32, 0f32); 7];
+        splinterpol::<8>(&xs, &ys, &mut thomas).unwrap();
+        let mut pivoting = [(0f32, 0f32, 0f32, 0f32); 7];
+        splinterpol_with_solver::<8>(&xs, &ys, &mut pivoting, Solver::PartialPivoting).unwrap();
+        for (t, p) in thomas.iter().zip(&pivoting) {
+            assert_eq!(t.0, p.0);
+            assert!((t.1 - p.1).abs() <= 0.001 * t.1.abs().max(1.0));
+        }
+    }
+
     #[test]
     fn plot_splinterpol() {
         use plotters::prelude::*;
@@ -330,14 +965,14 @@ mod tests {
         chart
             .draw_series(LineSeries::new(
                 buffer.iter().enumerate().map(|(i, v)| (i as f32, *v)),
-                &RED,
+                RED,
             ))
             .unwrap();
 
         chart
             .configure_series_labels()
-            .background_style(&WHITE.mix(0.0))
-            .border_style(&WHITE)
+            .background_style(WHITE.mix(0.0))
+            .border_style(WHITE)
             .draw()
             .unwrap()
     }
@@ -369,14 +1004,14 @@ mod tests {
         chart
             .draw_series(LineSeries::new(
                 buffer.iter().enumerate().map(|(i, v)| (i as f32, *v)),
-                &RED,
+                RED,
             ))
             .unwrap();
 
         chart
             .configure_series_labels()
-            .background_style(&WHITE.mix(0.0))
-            .border_style(&WHITE)
+            .background_style(WHITE.mix(0.0))
+            .border_style(WHITE)
             .draw()
             .unwrap()
     }
@@ -408,49 +1043,46 @@ mod tests {
         chart
             .draw_series(LineSeries::new(
                 buffer.iter().enumerate().map(|(i, v)| (i as f32, *v)),
-                &BLACK,
+                BLACK,
             ))
             .unwrap();
 
         chart
             .configure_series_labels()
-            .background_style(&WHITE.mix(0.0))
-            .border_style(&WHITE)
+            .background_style(WHITE.mix(0.0))
+            .border_style(WHITE)
             .draw()
             .unwrap()
     }
 
-    #[test]
-    fn test_calc_subdiagonal() {
-        let xs = [
-            0.5f32, 1f32, 2f32, 3f32, 4.5f32, 5f32, 6f32, 7f32, 8f32, 9f32, 10f32, 11.5f32, 12f32,
-            13f32, 14f32, 15f32,
-        ];
-        let mut sub = [0f32; 13];
-        calc_subdiagonal(&xs, &mut sub).unwrap();
-        let expected = [
-            1.0, 1.0, 1.5, 0.5, 1.0, 1.0, 1.0, 1.0, 1.0, 1.5, 0.5, 1.0, 1.0,
-        ];
-        assert_eq!(expected, sub);
-    }
-
     #[test]
     fn do_cubic_spline() {
         let mut xs = [0f32; 64];
-        cubic_spline(4.0, 2.0, 2.0, 1.5, &mut xs, 0.05);
+        for (index, elem) in xs.iter_mut().enumerate() {
+            *elem = eval_segment((4.0, 2.0, 2.0, 1.5), index as f32 * 0.05);
+        }
         let expected = [
-            4.0, 4.1051874, 4.2215, 4.350063, 4.492, 4.6484375, 4.8205, 5.009312, 5.2160006,
-            5.4416876, 5.6875, 5.9545627, 6.244, 6.556938, 6.8945003, 7.2578125, 7.6480002,
-            8.066188, 8.5135, 8.991062, 9.5, 10.041438, 10.6165, 11.226313, 11.872001, 12.5546875,
-            13.275501, 14.035563, 14.836, 15.677938, 16.5625, 17.490814, 18.464, 19.483187,
-            20.5495, 21.664063, 22.828003, 24.042439, 25.3085, 26.627316, 28.0, 29.427689,
-            30.911507, 32.452568, 34.052002, 35.710938, 37.4305, 39.21182, 41.056, 42.964188,
-            44.9375, 46.97706, 49.084003, 51.25944, 53.5045, 55.820313, 58.208, 60.668694,
-            63.203506, 65.81357, 68.5, 71.26394, 74.10651, 77.028824,
+            4.0, 4.1051874, 4.2215, 4.3500624, 4.492, 4.6484375, 4.8205, 5.0093126, 5.216,
+            5.4416876, 5.6875, 5.9545627, 6.2440004, 6.5569377, 6.8945, 7.2578125, 7.6480007,
+            8.066188, 8.5135, 8.991062, 9.5, 10.041438, 10.616501, 11.226312, 11.872001,
+            12.5546875, 13.275501, 14.0355625, 14.8359995, 15.677939, 16.5625, 17.490814, 18.464,
+            19.483189, 20.549501, 21.664063, 22.828001, 24.042439, 25.3085, 26.627314, 28.0,
+            29.427687, 30.911503, 32.452568, 34.052002, 35.710938, 37.430496, 39.21182, 41.056004,
+            42.964188, 44.9375, 46.97706, 49.084003, 51.259445, 53.504505, 55.820313, 58.207996,
+            60.668694, 63.203506, 65.81357, 68.5, 71.26393, 74.10651, 77.028824,
         ];
         assert_eq!(expected, xs);
     }
 
+    #[test]
+    fn eval_segment_horner() {
+        let coeffs = (4.0, 2.0, 2.0, 1.5);
+        assert_eq!(4.0, eval_segment(coeffs, 0.0));
+        assert_eq!(9.5, eval_segment(coeffs, 1.0));
+        assert_eq!(28.0, eval_segment(coeffs, 2.0));
+        assert_eq!(2.5, eval_segment(coeffs, -1.0));
+    }
+
     #[test]
     fn diagonal_test() {
         const N: usize = 16;
@@ -460,7 +1092,7 @@ mod tests {
         });
         xs[4] = 4.5f32;
         let mut diagonal = [0f32; N - 2];
-        calc_diagonal::<N>(&xs, &mut diagonal).unwrap();
+        calc_diagonal(&xs, &mut diagonal).unwrap();
         let expected = [
             4f32, 4f32, 5f32, 4f32, 3f32, 4f32, 4f32, 4f32, 4f32, 4f32, 4f32, 4f32, 4f32, 4f32,
         ];
@@ -480,7 +1112,7 @@ mod tests {
         xs[11] = 11.5f32;
 
         let mut diagonal = [0f32; N - 2];
-        calc_diagonal::<N>(&xs, &mut diagonal).unwrap();
+        calc_diagonal(&xs, &mut diagonal).unwrap();
         let expected = [
             3f32, 4f32, 5f32, 4f32, 3f32, 4f32, 4f32, 4f32, 4f32, 5f32, 4f32, 3f32, 4f32, 4f32,
         ];
@@ -505,12 +1137,12 @@ mod tests {
         ];
 
         let mut r = [0f32; N - 2];
-        calc_r::<N>(&xs, &ys, &mut r).unwrap();
+        calc_r(&xs, &ys, &mut r).unwrap();
         let expected = [
             3f32, 0f32, 1f32, 14f32, -12f32, -3f32, -9f32, 0f32, -3f32, 7f32, 2f32, -3f32, 3f32,
             -3f32,
         ];
-        for (r, expected) in (&r).iter().zip(&expected) {
+        for (r, expected) in r.iter().zip(&expected) {
             assert!(r - expected < 0.0001);
         }
     }
@@ -530,8 +1162,9 @@ mod tests {
             0.0, -1.8847, 1.9041, -1.5906, -0.15336, 2.6013, -1.2517, 0.95437, -0.22289, -0.062811,
             0.29988, -1.6737, 0.39473, 0.094739, -0.77368, 0.0,
         ];
-        let mut b = [0f32; N - 1];
-        calc_b::<N>(&xs, &ys, &cs, &mut b).unwrap();
+        let mut coeffs = [(0f32, 0f32, 0f32, 0f32); N - 1];
+        calc_coefficients(&xs, &ys, &cs, &mut coeffs).unwrap();
+        let b = coeffs.map(|(_, b, _, _)| b);
         let expected: [f32; N - 1] = [
             1.6282333,
             -0.25646675,
@@ -564,8 +1197,9 @@ mod tests {
             0f32, -1.8847, 1.9041, -1.5906, -0.15336, 2.6013, -1.2517, 0.95437, -0.22289,
             -0.062811, 0.29988, -1.6737, 0.39473, 0.094739, -0.77368, 0f32,
         ];
-        let mut d = [0f32; N - 1];
-        calc_d::<N>(&xs, &cs, &mut d).unwrap();
+        let mut coeffs = [(0f32, 0f32, 0f32, 0f32); N - 1];
+        calc_coefficients(&xs, &[0f32; N], &cs, &mut coeffs).unwrap();
+        let d = coeffs.map(|(_, _, _, d)| d);
         let expected: [f32; N - 1] = [
             -0.6282333,
             0.6314666,
@@ -598,8 +1232,9 @@ mod tests {
             1.6693, 0.058282, -1.4741, 1.3931, -1.0983, 0.0,
         ];
 
-        let mut d = [0f32; N - 1];
-        calc_d::<N>(&xs, &cs, &mut d).unwrap();
+        let mut coeffs = [(0f32, 0f32, 0f32, 0f32); N - 1];
+        calc_coefficients(&xs, &[0f32; N], &cs, &mut coeffs).unwrap();
+        let d = coeffs.map(|(_, _, _, d)| d);
         let expected: [f32; N - 1] = [
             0.65525335,
             -0.310505,
@@ -619,4 +1254,46 @@ mod tests {
         ];
         assert_eq!(expected, d);
     }
+
+    #[test]
+    fn splinterpol_with_curvature_ends() {
+        let xs = [0f32, 1.0, 2.5, 3.0, 4.0];
+        let ys = [1f32, -1.0, 0.5, 2.0, 0.0];
+
+        let mut natural = [(0f32, 0f32, 0f32, 0f32); 4];
+        splinterpol(&xs, &ys, &mut natural).unwrap();
+        let mut coeffs = [(0f32, 0f32, 0f32, 0f32); 4];
+        splinterpol_with_curvature(&xs, &ys, &mut coeffs, 0.0, 0.0).unwrap();
+        assert_eq!(natural, coeffs);
+
+        splinterpol_with_curvature(&xs, &ys, &mut coeffs, 3.0, -1.5).unwrap();
+        assert_eq!(3.0, 2f32 * coeffs[0].2);
+        let (_, _, c, d) = coeffs[3];
+        assert!((2f32 * c + 6f32 * d * (xs[4] - xs[3]) + 1.5).abs() < 0.0001);
+        for i in 0..3 {
+            let (a, b, c, d) = coeffs[i];
+            let h = xs[i + 1] - xs[i];
+            assert!((a + b * h + c * h * h + d * h * h * h - ys[i + 1]).abs() < 0.0001);
+            assert!((b + 2f32 * c * h + 3f32 * d * h * h - coeffs[i + 1].1).abs() < 0.0001);
+        }
+    }
+
+    #[test]
+    fn splinterpol_array_matches_slices() {
+        let xs = [0f32, 1.0, 2.5, 3.0, 4.0];
+        let ys = [1f32, -1.0, 0.5, 2.0, 0.0];
+        let mut expected = [(0f32, 0f32, 0f32, 0f32); 4];
+        splinterpol(&xs, &ys, &mut expected).unwrap();
+        let mut coeffs = [(0f32, 0f32, 0f32, 0f32); 4];
+        splinterpol_array(&xs, &ys, &mut coeffs).unwrap();
+        assert_eq!(expected, coeffs);
+        assert_eq!(
+            Err(Error::NonIncreasingKnots { index: 1 }),
+            splinterpol_array(
+                &[0f32, 0.0, 0.0],
+                &[1.0, 1.0, 1.0],
+                &mut [(0f32, 0f32, 0f32, 0f32); 2]
+            )
+        );
+    }
 }
@@ -0,0 +1,211 @@
+use crate::{
+    check_increasing, check_spline, eval_segment, eval_segment_derivative, h, hermite, Error,
+};
+
+/// Write the coefficients of the monotone piecewise cubic Hermite interpolant
+/// (Fritsch–Carlson, as PCHIP) through `xs` and `ys` to `coefficients`, which
+/// must hold `xs.len() - 1` elements.
+///
+/// Unlike the C2 spline, the interpolant is only C1, but it is monotone
+/// wherever the data is and never overshoots the data between two knots.
+pub(crate) fn monotone_slices(
+    xs: &[f32],
+    ys: &[f32],
+    coefficients: &mut [(f32, f32, f32, f32)],
+) -> Result<(), Error> {
+    if xs.len() < 2 || ys.len() != xs.len() || coefficients.len() != xs.len() - 1 {
+        return Err(Error::InvalidSliceLength);
+    }
+    check_increasing(xs)?;
+    for (i, elem) in coefficients.iter_mut().enumerate() {
+        let m0 = slope(xs, ys, i);
+        let m1 = slope(xs, ys, i + 1);
+        *elem = hermite(ys[i], ys[i + 1], m0, m1, h(i, xs));
+    }
+    Ok(())
+}
+
+/// Adjust the derivatives at the knots of the spline given by `coefficients`
+/// and the knots `xs` in place, so that it is monotone on every segment where
+/// the values at the knots are, and so never leaves the range of the values at
+/// its knots. Returns the number of knots whose derivative was changed.
+///
+/// This is the monotonicity filter of Hyman: where the data is monotone on
+/// both sides of a knot, the derivative is clipped to the same sign and to at
+/// most three times the smaller adjacent secant, at local extrema of the data
+/// it is set to zero. Values at the knots and all derivatives which pass stay
+/// as they are, so the result is the C2 spline wherever that does not
+/// overshoot, and C1 elsewhere.
+///
+/// ```
+/// use cubic_splinterpol::{hyman_filter, splinterpol};
+///
+/// // A step, which the natural spline overshoots
+/// let xs = [0f32, 1.0, 2.0, 3.0, 4.0];
+/// let ys = [0f32, 0.0, 1.0, 1.0, 1.0];
+/// let mut coefficients = [(0f32, 0f32, 0f32, 0f32); 4];
+/// splinterpol(&xs, &ys, &mut coefficients).unwrap();
+/// assert!(coefficients[0].1 < 0.0);
+///
+/// assert_eq!(Ok(5), hyman_filter(&mut coefficients, &xs));
+/// assert_eq!((0.0, 0.0, 0.0, 0.0), coefficients[0]);
+/// ```
+pub fn hyman_filter(coefficients: &mut [(f32, f32, f32, f32)], xs: &[f32]) -> Result<usize, Error> {
+    check_spline(coefficients, xs)?;
+    let segments = coefficients.len();
+    let last_h = h(segments - 1, xs);
+    let end_value = eval_segment(coefficients[segments - 1], last_h);
+    let end_slope = eval_segment_derivative(coefficients[segments - 1], last_h);
+    let value = |coefficients: &[(f32, f32, f32, f32)], knot: usize| {
+        if knot < segments {
+            coefficients[knot].0
+        } else {
+            end_value
+        }
+    };
+    let secant = |coefficients: &[(f32, f32, f32, f32)], segment: usize| {
+        (value(coefficients, segment + 1) - value(coefficients, segment)) / h(segment, xs)
+    };
+
+    let filter = |slope: f32, before: Option<f32>, after: Option<f32>| {
+        let filtered = match (before, after) {
+            (Some(before), Some(after)) if before * after > 0f32 => {
+                let limit = 3f32 * before.abs().min(after.abs());
+                before.signum() * (before.signum() * slope).clamp(0f32, limit)
+            }
+            (Some(_), Some(_)) => 0f32,
+            (Some(secant), None) | (None, Some(secant)) => {
+                if slope * secant <= 0f32 {
+                    0f32
+                } else if slope.abs() > 3f32 * secant.abs() {
+                    3f32 * secant
+                } else {
+                    slope
+                }
+            }
+            (None, None) => slope,
+        };
+        (filtered, filtered != slope)
+    };
+
+    let (mut m0, mut m0_changed) = filter(coefficients[0].1, None, Some(secant(coefficients, 0)));
+    let mut changed = usize::from(m0_changed);
+    for segment in 0..segments {
+        let before = secant(coefficients, segment);
+        let (slope, after) = if segment + 1 < segments {
+            (
+                coefficients[segment + 1].1,
+                Some(secant(coefficients, segment + 1)),
+            )
+        } else {
+            (end_slope, None)
+        };
+        let (m1, m1_changed) = filter(slope, Some(before), after);
+        // Leave segments with both derivatives intact bit for bit
+        if m0_changed || m1_changed {
+            let y1 = value(coefficients, segment + 1);
+            coefficients[segment] = hermite(coefficients[segment].0, y1, m0, m1, h(segment, xs));
+        }
+        changed += usize::from(m1_changed);
+        m0 = m1;
+        m0_changed = m1_changed;
+    }
+    Ok(changed)
+}
+
+/// Derivative at knot i: the weighted harmonic mean of the neighbouring
+/// secants if they have the same sign, else zero, and a shape-preserving
+/// three-point estimate at the ends.
+fn slope(xs: &[f32], ys: &[f32], i: usize) -> f32 {
+    let n = xs.len();
+    let delta = |k: usize| (ys[k + 1] - ys[k]) / h(k, xs);
+    if n == 2 {
+        return delta(0);
+    }
+    if i == 0 || i == n - 1 {
+        // Secant and width at the end and next to it
+        let (k0, k1) = if i == 0 { (0, 1) } else { (n - 2, n - 3) };
+        let (h0, h1) = (h(k0, xs), h(k1, xs));
+        let (d0, d1) = (delta(k0), delta(k1));
+        let m = ((2f32 * h0 + h1) * d0 - h0 * d1) / (h0 + h1);
+        return if m * d0 <= 0f32 {
+            0f32
+        } else if d0 * d1 < 0f32 && m.abs() > 3f32 * d0.abs() {
+            3f32 * d0
+        } else {
+            m
+        };
+    }
+    let (d0, d1) = (delta(i - 1), delta(i));
+    if d0 * d1 <= 0f32 {
+        return 0f32;
+    }
+    let (h0, h1) = (h(i - 1, xs), h(i, xs));
+    let w0 = 2f32 * h1 + h0;
+    let w1 = h1 + 2f32 * h0;
+    (w0 + w1) / (w0 / d0 + w1 / d1)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::eval_segment;
+
+    #[test]
+    fn preserves_monotonicity_and_data() {
+        // A step that the C2 spline overshoots
+        let xs = [0f32, 1.0, 2.0, 3.0, 4.0, 5.0];
+        let ys = [0f32, 0.0, 0.1, 1.0, 1.0, 1.0];
+        let mut coeffs = [(0f32, 0f32, 0f32, 0f32); 5];
+        monotone_slices(&xs, &ys, &mut coeffs).unwrap();
+        let mut previous = 0f32;
+        for i in 0..=500 {
+            let x = i as f32 / 100f32;
+            let segment = crate::find_segment(&xs, x);
+            let y = eval_segment(coeffs[segment], x - xs[segment]);
+            assert!(y >= previous - 1e-6 && y <= 1f32 + 1e-6, "{} at {}", y, x);
+            previous = y;
+        }
+        for i in 0..5 {
+            assert_eq!(ys[i], coeffs[i].0);
+            assert!((eval_segment(coeffs[i], 1f32) - ys[i + 1]).abs() < 1e-6);
+        }
+    }
+
+    #[test]
+    fn linear_data_stays_linear() {
+        let xs = [0f32, 1.0, 3.0];
+        let ys = [1f32, 3.0, 7.0];
+        let mut coeffs = [(0f32, 0f32, 0f32, 0f32); 2];
+        monotone_slices(&xs, &ys, &mut coeffs).unwrap();
+        assert_eq!([(1.0, 2.0, 0.0, 0.0), (3.0, 2.0, 0.0, 0.0)], coeffs);
+        assert_eq!(
+            Err(Error::NonIncreasingKnots { index: 2 }),
+            monotone_slices(&[0f32, 1.0, 1.0], &ys, &mut coeffs)
+        );
+    }
+
+    #[test]
+    fn hyman_filter_keeps_good_derivatives() {
+        let xs = [0f32, 1.0, 2.0, 3.0, 4.0, 5.0];
+        let ys = [0f32, 1.0, 2.0, 3.0, 2.0, 0.0];
+        let mut coeffs = [(0f32, 0f32, 0f32, 0f32); 5];
+        crate::splinterpol(&xs, &ys, &mut coeffs).unwrap();
+        let original = coeffs;
+        // Only the derivative at the maximum of the data at knot 3 changes
+        assert_eq!(Ok(1), hyman_filter(&mut coeffs, &xs));
+        assert_eq!(original[..2], coeffs[..2]);
+        assert_eq!(original[4].1, coeffs[4].1);
+        assert_eq!(0.0, coeffs[3].1);
+        for i in 0..5 {
+            assert_eq!(ys[i], coeffs[i].0);
+            assert!((eval_segment(coeffs[i], 1f32) - ys[i + 1]).abs() < 1e-5);
+            for step in 0..=10 {
+                let y = eval_segment(coeffs[i], step as f32 / 10f32);
+                assert!((-1e-6..=3f32 + 1e-6).contains(&y));
+            }
+        }
+        // Idempotent
+        assert_eq!(Ok(0), hyman_filter(&mut coeffs, &xs));
+    }
+}
@@ -0,0 +1,406 @@
+//! Smooth motion profiles for stepper and servo control loops.
+//!
+//! A [`MotionProfile`] passes through position via-points at given times and
+//! starts and ends at rest. Position, velocity and acceleration are
+//! continuous, so it can be fed directly to a position or velocity loop as
+//! setpoint generator.
+
+use crate::{
+    check_increasing, eval_segment, eval_segment_derivative, eval_segment_second_derivative,
+    find_segment, overshoot, splinterpol_slices, Boundary, Error, Solver,
+};
+
+/// Number of rounds [`MotionProfile::with_limits`] stretches segments
+/// individually before falling back to stretching the whole motion.
+const RETIMING_ROUNDS: usize = 8;
+
+/// Stretch factors are increased by this much, so that rounding does not
+/// leave a peak just above its limit.
+const RETIMING_MARGIN: f32 = 1.001;
+
+/// Which limit a [`MotionProfile`] exceeds.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum LimitKind {
+    /// The magnitude of the velocity exceeds its limit
+    Velocity,
+    /// The magnitude of the acceleration exceeds its limit
+    Acceleration,
+}
+
+/// The earliest extremum of a [`MotionProfile`] which exceeds a limit.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct LimitViolation {
+    /// Time of the extremum
+    pub t: f32,
+    /// The limit which is exceeded
+    pub kind: LimitKind,
+}
+
+/// A motion through `N` via-points, with zero velocity at the first and last
+/// one.
+///
+/// Before the first via-point, the profile holds the first position, and after
+/// the last via-point, it holds the last position, both at rest.
+///
+/// ```
+/// use cubic_splinterpol::motion::MotionProfile;
+///
+/// // Move to 10 mm, pass 25 mm at 1.5 s and stop at 40 mm after 3 s
+/// let profile = MotionProfile::new(&[0f32, 1.5, 3.0], &[10f32, 25.0, 40.0]).unwrap();
+/// assert_eq!(10.0, profile.position(-1.0));
+/// assert_eq!(0.0, profile.velocity(0.0));
+/// assert!(profile.velocity(1.5) > 10.0);
+/// assert_eq!(40.0, profile.position(5.0));
+/// ```
+#[derive(Debug, Clone)]
+pub struct MotionProfile<const N: usize> {
+    ts: [f32; N],
+    /// N-1 of N used
+    coefficients: [(f32, f32, f32, f32); N],
+}
+
+impl<const N: usize> MotionProfile<N> {
+    /// A profile through `positions` at the strictly increasing times `ts`.
+    /// At least 3 via-points are needed.
+    pub fn new(ts: &[f32; N], positions: &[f32; N]) -> Result<Self, Error> {
+        if N < 3 {
+            return Err(Error::InvalidSliceLength);
+        }
+        check_increasing(ts)?;
+        let mut coefficients = [(0f32, 0f32, 0f32, 0f32); N];
+        let rest = Boundary::Clamped {
+            start: 0f32,
+            end: 0f32,
+        };
+        splinterpol_slices::<N>(ts, positions, &mut coefficients, rest, Solver::Thomas)?;
+        Ok(Self {
+            ts: *ts,
+            coefficients,
+        })
+    }
+
+    /// A profile through `positions` which, starting from the times `ts`, is
+    /// slowed down until the magnitudes of velocity and acceleration stay
+    /// within `max_vel` and `max_acc`. The via-points are passed in the same
+    /// order, only later.
+    ///
+    /// Every segment which is too fast is stretched by the factor its peaks
+    /// exceed the limits by, since stretching by k divides velocity by k and
+    /// acceleration by k^2. As the spline is solved again for the new times,
+    /// this changes the neighbouring segments a little, so it is repeated for
+    /// a few rounds.
+    ///
+    /// Solving again re-plans the positions between the via-points, so the
+    /// result is kept only if it overshoots the via-points of no segment more
+    /// than the profile at `ts` does. Otherwise, or if the limits are still
+    /// exceeded after these rounds, the profile at `ts` is stretched
+    /// uniformly instead, which keeps its positions between the via-points
+    /// exactly and scales all peaks exactly.
+    pub fn with_limits(
+        ts: &[f32; N],
+        positions: &[f32; N],
+        max_vel: f32,
+        max_acc: f32,
+    ) -> Result<Self, Error> {
+        for limit in [max_vel, max_acc] {
+            if limit.is_nan() || limit <= 0f32 {
+                return Err(Error::InvalidLimit);
+            }
+        }
+        let original = Self::new(ts, positions)?;
+        let stretch = |peaks: (f32, f32)| {
+            let k = (peaks.0 / max_vel).max(libm::sqrtf(peaks.1 / max_acc));
+            if k > 1f32 {
+                k * RETIMING_MARGIN
+            } else {
+                1f32
+            }
+        };
+        let mut profile = original.clone();
+        for _ in 0..RETIMING_ROUNDS {
+            let mut retimed = profile.ts;
+            let mut stretched = false;
+            for segment in 0..N - 1 {
+                let k = stretch(profile.peaks(segment));
+                stretched |= k > 1f32;
+                let h = profile.ts[segment + 1] - profile.ts[segment];
+                retimed[segment + 1] = retimed[segment] + h * k;
+            }
+            if !stretched {
+                if (0..N - 1).all(|segment| {
+                    // Up to rounding, relative to the positions
+                    let scale = positions[segment].abs().max(positions[segment + 1].abs());
+                    let slack = 4f32 * f32::EPSILON * scale;
+                    let (below, above) = profile.overshoot(segment, positions);
+                    let bound = original.overshoot(segment, positions);
+                    below <= bound.0 * RETIMING_MARGIN + slack
+                        && above <= bound.1 * RETIMING_MARGIN + slack
+                }) {
+                    return Ok(profile);
+                }
+                break;
+            }
+            profile = Self::new(&retimed, positions)?;
+        }
+        let mut peaks = (0f32, 0f32);
+        for segment in 0..N - 1 {
+            let (vel, acc) = original.peaks(segment);
+            peaks = (peaks.0.max(vel), peaks.1.max(acc));
+        }
+        let k = stretch(peaks);
+        let mut retimed = *ts;
+        for t in retimed.iter_mut() {
+            *t = ts[0] + (*t - ts[0]) * k;
+        }
+        Self::new(&retimed, positions)
+    }
+
+    /// The times of the via-points.
+    pub fn ts(&self) -> &[f32; N] {
+        &self.ts
+    }
+
+    /// The coefficients of the N-1 segments between the via-points.
+    pub fn coefficients(&self) -> &[(f32, f32, f32, f32)] {
+        &self.coefficients[..N - 1]
+    }
+
+    /// Time from the first to the last via-point.
+    pub fn duration(&self) -> f32 {
+        self.ts[N - 1] - self.ts[0]
+    }
+
+    /// Position setpoint at time t.
+    pub fn position(&self, t: f32) -> f32 {
+        match self.locate(t) {
+            Some((segment, t)) => eval_segment(self.coefficients[segment], t),
+            None if t < self.ts[0] => self.coefficients[0].0,
+            None => eval_segment(self.coefficients[N - 2], self.ts[N - 1] - self.ts[N - 2]),
+        }
+    }
+
+    /// Velocity setpoint at time t.
+    pub fn velocity(&self, t: f32) -> f32 {
+        match self.locate(t) {
+            Some((segment, t)) => eval_segment_derivative(self.coefficients[segment], t),
+            None => 0f32,
+        }
+    }
+
+    /// Acceleration setpoint at time t. Outside of the motion, this is zero,
+    /// so it jumps at the first and last via-point unless the acceleration
+    /// there happens to be zero.
+    pub fn acceleration(&self, t: f32) -> f32 {
+        match self.locate(t) {
+            Some((segment, t)) => eval_segment_second_derivative(self.coefficients[segment], t),
+            None => 0f32,
+        }
+    }
+
+    /// Check that the magnitudes of velocity and acceleration stay within
+    /// `max_vel` and `max_acc` for the whole motion.
+    ///
+    /// Instead of sampling, the extrema are found analytically per segment:
+    /// the acceleration is linear in each segment, so it peaks at one of the
+    /// via-points, and the velocity is quadratic, so it peaks at a via-point
+    /// or where the acceleration crosses zero.
+    pub fn check_limits(&self, max_vel: f32, max_acc: f32) -> Result<(), LimitViolation> {
+        for (segment, coefficients) in self.coefficients().iter().enumerate() {
+            for t in self.extrema(segment) {
+                let violation = |kind| LimitViolation {
+                    t: self.ts[segment] + t,
+                    kind,
+                };
+                if eval_segment_derivative(*coefficients, t).abs() > max_vel {
+                    return Err(violation(LimitKind::Velocity));
+                }
+                if eval_segment_second_derivative(*coefficients, t).abs() > max_acc {
+                    return Err(violation(LimitKind::Acceleration));
+                }
+            }
+        }
+        Ok(())
+    }
+
+    /// Times relative to the start of the segment, in order, at which the
+    /// magnitudes of velocity and acceleration can peak: the start, the end,
+    /// and where the acceleration crosses zero, if that is in between.
+    fn extrema(&self, segment: usize) -> [f32; 3] {
+        let h = self.ts[segment + 1] - self.ts[segment];
+        let (_, _, c, d) = self.coefficients[segment];
+        let turn = -c / (3f32 * d);
+        if turn > 0f32 && turn < h {
+            [0f32, turn, h]
+        } else {
+            [0f32, h, h]
+        }
+    }
+
+    /// Peak magnitudes of velocity and acceleration within the segment.
+    fn peaks(&self, segment: usize) -> (f32, f32) {
+        let coefficients = self.coefficients[segment];
+        let mut peaks = (0f32, 0f32);
+        for t in self.extrema(segment) {
+            let vel = eval_segment_derivative(coefficients, t).abs();
+            let acc = eval_segment_second_derivative(coefficients, t).abs();
+            peaks = (peaks.0.max(vel), peaks.1.max(acc));
+        }
+        peaks
+    }
+
+    /// How far the segment leaves the range of the positions at its ends,
+    /// below and above.
+    fn overshoot(&self, segment: usize, positions: &[f32; N]) -> (f32, f32) {
+        overshoot(
+            &self.coefficients[segment..=segment],
+            &self.ts[segment..=segment + 1],
+            &positions[segment..=segment + 1],
+        )
+        .unwrap_or((f32::INFINITY, f32::INFINITY))
+    }
+
+    /// Segment and time relative to its start, or `None` outside of the
+    /// motion.
+    fn locate(&self, t: f32) -> Option<(usize, f32)> {
+        if t.is_nan() || t < self.ts[0] || t > self.ts[N - 1] {
+            return None;
+        }
+        let segment = find_segment(&self.ts, t);
+        Some((segment, t - self.ts[segment]))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn starts_and_ends_at_rest() {
+        let ts = [0f32, 0.5, 1.2, 2.0, 3.0];
+        let positions = [0f32, 100.0, 150.0, 120.0, 200.0];
+        let profile = MotionProfile::new(&ts, &positions).unwrap();
+        assert_eq!(3.0, profile.duration());
+        for (t, p) in ts.iter().zip(&positions) {
+            assert!((profile.position(*t) - p).abs() < 0.001);
+        }
+        assert!(profile.velocity(0.0).abs() < 0.001);
+        assert!(profile.velocity(3.0).abs() < 0.001);
+        assert_eq!(0.0, profile.position(-1.0));
+        assert!((profile.position(4.0) - 200.0).abs() < 0.001);
+        assert_eq!(0.0, profile.velocity(-1.0));
+        assert_eq!(0.0, profile.acceleration(4.0));
+        assert_eq!(0.0, profile.velocity(f32::NAN));
+    }
+
+    #[test]
+    fn derivatives_are_consistent() {
+        let ts = [0f32, 0.5, 1.2, 2.0, 3.0];
+        let positions = [0f32, 100.0, 150.0, 120.0, 200.0];
+        let profile = MotionProfile::new(&ts, &positions).unwrap();
+        let dt = 1e-3;
+        for i in 1..30 {
+            let t = i as f32 * 0.1;
+            let velocity = (profile.position(t + dt) - profile.position(t - dt)) / (2f32 * dt);
+            assert!((velocity - profile.velocity(t)).abs() < 0.5);
+            let acceleration = (profile.velocity(t + dt) - profile.velocity(t - dt)) / (2f32 * dt);
+            assert!((acceleration - profile.acceleration(t)).abs() < 5.0);
+        }
+    }
+
+    #[test]
+    fn rejects_invalid_via_points() {
+        assert_eq!(
+            Err(Error::NonIncreasingKnots { index: 1 }),
+            MotionProfile::new(&[0f32, 0.0, 1.0], &[0f32, 1.0, 2.0]).map(|_| ())
+        );
+        assert_eq!(
+            Err(Error::InvalidSliceLength),
+            MotionProfile::new(&[0f32, 1.0], &[0f32, 1.0]).map(|_| ())
+        );
+    }
+
+    #[test]
+    fn check_limits_finds_extrema() {
+        // Symmetric move, peak velocity 1.5 * 10 / 2 at t = 1
+        let profile = MotionProfile::new(&[0f32, 1.0, 2.0], &[0f32, 5.0, 10.0]).unwrap();
+        assert!((profile.velocity(1.0) - 7.5).abs() < 0.001);
+        assert!((profile.acceleration(0.0) - 15.0).abs() < 0.001);
+        assert_eq!(Ok(()), profile.check_limits(7.6, 15.1));
+        assert_eq!(
+            Err(LimitViolation {
+                t: 0.0,
+                kind: LimitKind::Acceleration
+            }),
+            profile.check_limits(7.6, 14.0)
+        );
+        let violation = profile.check_limits(7.0, 100.0).unwrap_err();
+        assert_eq!(LimitKind::Velocity, violation.kind);
+        assert!((violation.t - 1.0).abs() < 0.001);
+
+        // Peak velocity between via-points
+        let profile = MotionProfile::new(&[0f32, 2.0, 3.0], &[0f32, 10.0, 10.0]).unwrap();
+        let mut peak = 0f32;
+        for i in 0..=3000 {
+            peak = peak.max(profile.velocity(i as f32 / 1000f32).abs());
+        }
+        assert_eq!(Ok(()), profile.check_limits(peak + 0.01, 1000.0));
+        let violation = profile.check_limits(peak - 0.01, 1000.0).unwrap_err();
+        assert_eq!(LimitKind::Velocity, violation.kind);
+        assert!(violation.t > 0.0 && violation.t < 2.0);
+    }
+
+    #[test]
+    fn with_limits_keeps_shape_if_replanning_overshoots() {
+        // Re-planning would overshoot the plateau between knots 1 and 2 more
+        let ts = [0f32, 0.5, 1.0, 1.2, 2.0];
+        let positions = [0f32, 100.0, 100.0, 180.0, 200.0];
+        let fast = MotionProfile::new(&ts, &positions).unwrap();
+        let profile = MotionProfile::with_limits(&ts, &positions, 150.0, 800.0).unwrap();
+        assert_eq!(Ok(()), profile.check_limits(150.0, 800.0));
+        // Stretched uniformly, so the positions between via-points are kept
+        let k = profile.duration() / fast.duration();
+        for (t, stretched) in ts.iter().zip(profile.ts()) {
+            assert!((t * k - stretched).abs() < 1e-5);
+        }
+        for i in 0..=200 {
+            let t = i as f32 / 100f32;
+            assert!((fast.position(t) - profile.position(t * k)).abs() < 0.01);
+        }
+    }
+
+    #[test]
+    fn with_limits_respects_limits() {
+        let ts = [0f32, 0.5, 1.0, 1.2, 2.0];
+        let positions = [0f32, 100.0, 120.0, 180.0, 200.0];
+        let fast = MotionProfile::new(&ts, &positions).unwrap();
+        assert!(fast.check_limits(150.0, 800.0).is_err());
+
+        let profile = MotionProfile::with_limits(&ts, &positions, 150.0, 800.0).unwrap();
+        assert_eq!(Ok(()), profile.check_limits(150.0, 800.0));
+        assert_eq!(0.0, profile.ts()[0]);
+        assert!(profile.duration() > fast.duration());
+        for (t, p) in profile.ts().iter().zip(&positions) {
+            assert!((profile.position(*t) - p).abs() < 0.001);
+        }
+
+        // Re-planned, but without overshooting more than the fast profile
+        assert_ne!(
+            profile.ts()[1] / profile.duration(),
+            ts[1] / fast.duration()
+        );
+        for segment in 0..4 {
+            let (below, above) = profile.overshoot(segment, &positions);
+            let bound = fast.overshoot(segment, &positions);
+            assert!(below <= bound.0 && above <= bound.1);
+        }
+
+        // Already slow enough profiles are left alone
+        let same = MotionProfile::with_limits(&ts, &positions, 1e6, 1e6).unwrap();
+        assert_eq!(&ts, same.ts());
+
+        assert_eq!(
+            Err(Error::InvalidLimit),
+            MotionProfile::with_limits(&ts, &positions, 0.0, 1.0).map(|_| ())
+        );
+    }
+}
@@ -0,0 +1,113 @@
+use crate::thomas_algorithm::{factorize, solve};
+use crate::{check_increasing, h, Error, Segment};
+
+/// Like [`splinterpol`](crate::splinterpol), but for `D` channels sharing the
+/// knots `xs`, e.g. the three axes of an accelerometer. `ys` holds the values
+/// of all channels at every knot.
+///
+/// The tridiagonal matrix only depends on the knots, so it is eliminated once
+/// and only the right hand sides of the channels are solved separately. The
+/// result of every channel is exactly that of [`splinterpol`](crate::splinterpol)
+/// on its values.
+///
+/// ```
+/// use cubic_splinterpol::{splinterpol_multi, Segment};
+///
+/// let xs = [0f32, 1.0, 2.0, 3.0];
+/// let ys = [[0f32, 1.0, 9.8], [0.5, 0.8, 9.7], [0.7, 0.2, 9.9], [0.4, -0.1, 9.8]];
+/// let mut coefficients = [[Segment::default(); 3]; 3];
+/// splinterpol_multi(&xs, &ys, &mut coefficients).unwrap();
+/// assert_eq!(9.7, coefficients[1][2].a);
+/// assert_eq!(0.2, coefficients[2][1].eval(0.0));
+/// ```
+pub fn splinterpol_multi<const N: usize, const D: usize>(
+    xs: &[f32; N],
+    ys: &[[f32; D]],
+    coefficients: &mut [[Segment; D]],
+) -> Result<(), Error> {
+    if N < 3 || ys.len() != N || coefficients.len() < N - 1 {
+        return Err(Error::InvalidSliceLength);
+    }
+    check_increasing(xs)?;
+    let m = N - 2;
+
+    // Natural spline: symmetric system for the interior c, eliminated once
+    let mut main = [0f32; N];
+    let mut sub_diagonal = [0f32; N];
+    for (i, elem) in main[..m].iter_mut().enumerate() {
+        *elem = 2f32 * (h(i, xs) + h(i + 1, xs));
+    }
+    for (i, elem) in sub_diagonal[..m - 1].iter_mut().enumerate() {
+        *elem = h(i + 1, xs);
+    }
+    let factorization = factorize::<N>(&sub_diagonal[..m - 1], &main[..m])?;
+
+    let mut r = [0f32; N];
+    let mut c = [0f32; N];
+    for channel in 0..D {
+        let y = |i: usize| ys[i][channel];
+        let slope = |i: usize| (y(i + 1) - y(i)) / h(i, xs);
+        for (i, elem) in r[..m].iter_mut().enumerate() {
+            *elem = 3f32 * (slope(i + 1) - slope(i));
+        }
+        solve(&factorization, &r[..m], &mut c[1..N - 1])?;
+
+        for (i, segments) in coefficients[..N - 1].iter_mut().enumerate() {
+            let b = slope(i) - (2f32 * c[i] + c[i + 1]) / 3f32 * h(i, xs);
+            let d = (c[i + 1] - c[i]) / (3f32 * h(i, xs));
+            segments[channel] = Segment::new(y(i), b, c[i], d);
+        }
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn channels_match_scalar_splines() {
+        let xs = [0f32, 0.5, 2.0, 2.5, 4.0, 6.0];
+        let ys = [
+            [1f32, -2.0],
+            [3.0, 0.5],
+            [2.0, 0.5],
+            [-1.0, 4.0],
+            [0.5, 3.0],
+            [0.0, -1.0],
+        ];
+        let mut coeffs = [[Segment::default(); 2]; 5];
+        splinterpol_multi(&xs, &ys, &mut coeffs).unwrap();
+        for channel in 0..2 {
+            let mut channel_ys = [0f32; 6];
+            for (y, values) in channel_ys.iter_mut().zip(&ys) {
+                *y = values[channel];
+            }
+            let mut scalar = [(0f32, 0f32, 0f32, 0f32); 5];
+            crate::splinterpol(&xs, &channel_ys, &mut scalar).unwrap();
+            for (segments, expected) in coeffs.iter().zip(&scalar) {
+                assert_eq!(Segment::from(*expected), segments[channel]);
+            }
+        }
+    }
+
+    #[test]
+    fn rejects_invalid_input() {
+        let mut coeffs = [[Segment::default(); 1]; 2];
+        let ys = [[0f32], [1.0], [0.0]];
+        assert_eq!(
+            Err(Error::InvalidSliceLength),
+            splinterpol_multi(&[0f32, 1.0, 2.0], &ys[..2], &mut coeffs)
+        );
+        assert_eq!(
+            Err(Error::NonIncreasingKnots { index: 2 }),
+            splinterpol_multi(&[0f32, 1.0, 1.0], &ys, &mut coeffs)
+        );
+        assert_eq!(
+            Ok(()),
+            splinterpol_multi(&[0f32, 1.0, 2.0], &ys, &mut coeffs)
+        );
+        // Symmetric about the peak
+        assert_eq!(0.0, coeffs[1][0].b);
+    }
+}
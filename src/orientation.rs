@@ -0,0 +1,249 @@
+//! Smooth rotations through keyframe orientations, e.g. for camera gimbals or
+//! IMU-driven animation.
+//!
+//! The rotations from the first keyframe to all others are taken to the log
+//! map, as rotation vectors, whose three components are interpolated by
+//! natural splines on the shared knots and mapped back. The result passes
+//! through every keyframe and turns with continuous angular velocity and
+//! acceleration, but all keyframes must be less than half a turn away from
+//! the first one, where the log map is unique.
+
+use crate::extrapolate::locate;
+use crate::{eval_segment, splinterpol_multi, Error, Extrapolate, Segment};
+
+/// A rotation as a unit quaternion `w + x i + y j + z k`.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Quaternion {
+    /// Real part, the cosine of half the rotation angle
+    pub w: f32,
+    /// First imaginary part
+    pub x: f32,
+    /// Second imaginary part
+    pub y: f32,
+    /// Third imaginary part
+    pub z: f32,
+}
+
+impl Quaternion {
+    /// No rotation
+    pub const IDENTITY: Quaternion = Quaternion::new(1f32, 0f32, 0f32, 0f32);
+
+    /// A quaternion with the given parts.
+    pub const fn new(w: f32, x: f32, y: f32, z: f32) -> Self {
+        Self { w, x, y, z }
+    }
+
+    /// The rotation by `angle` in radians about `axis`, which need not be
+    /// normalized. A zero axis gives no rotation.
+    pub fn from_axis_angle(axis: [f32; 3], angle: f32) -> Self {
+        let norm = norm3(axis);
+        if norm == 0f32 {
+            return Self::IDENTITY;
+        }
+        Self::exp(axis.map(|component| component / norm * angle))
+    }
+
+    /// The inverse rotation of a unit quaternion.
+    pub fn conjugate(self) -> Self {
+        Self::new(self.w, -self.x, -self.y, -self.z)
+    }
+
+    /// The vector `v` rotated by this unit quaternion.
+    pub fn rotate(self, v: [f32; 3]) -> [f32; 3] {
+        let rotated = self * Quaternion::new(0f32, v[0], v[1], v[2]) * self.conjugate();
+        [rotated.x, rotated.y, rotated.z]
+    }
+
+    /// Angle in radians of the rotation from this one to `other`, between 0
+    /// and π.
+    pub fn angle_to(self, other: Quaternion) -> f32 {
+        let dot = self.w * other.w + self.x * other.x + self.y * other.y + self.z * other.z;
+        2f32 * libm::acosf(dot.abs().min(1f32))
+    }
+
+    /// The rotation vector, axis times angle, of a unit quaternion with a non
+    /// negative real part.
+    fn log(self) -> [f32; 3] {
+        let v = [self.x, self.y, self.z];
+        let sine = norm3(v);
+        if sine < 1e-6 {
+            // Small angles, where the axis is ill-defined
+            return v.map(|component| 2f32 * component);
+        }
+        let angle = 2f32 * libm::atan2f(sine, self.w);
+        v.map(|component| component / sine * angle)
+    }
+
+    /// The unit quaternion of the rotation vector `r`.
+    fn exp(r: [f32; 3]) -> Self {
+        let angle = norm3(r);
+        if angle < 1e-6 {
+            let q = Quaternion::new(1f32, r[0] / 2f32, r[1] / 2f32, r[2] / 2f32);
+            let norm = libm::sqrtf(q.w * q.w + q.x * q.x + q.y * q.y + q.z * q.z);
+            return Quaternion::new(q.w / norm, q.x / norm, q.y / norm, q.z / norm);
+        }
+        let (sine, cosine) = (libm::sinf(angle / 2f32), libm::cosf(angle / 2f32));
+        let [x, y, z] = r.map(|component| component / angle * sine);
+        Quaternion::new(cosine, x, y, z)
+    }
+}
+
+impl core::ops::Mul for Quaternion {
+    type Output = Quaternion;
+
+    /// The Hamilton product, the rotation `rhs` followed by `self`.
+    fn mul(self, rhs: Quaternion) -> Quaternion {
+        Quaternion::new(
+            self.w * rhs.w - self.x * rhs.x - self.y * rhs.y - self.z * rhs.z,
+            self.w * rhs.x + self.x * rhs.w + self.y * rhs.z - self.z * rhs.y,
+            self.w * rhs.y - self.x * rhs.z + self.y * rhs.w + self.z * rhs.x,
+            self.w * rhs.z + self.x * rhs.y - self.y * rhs.x + self.z * rhs.w,
+        )
+    }
+}
+
+fn norm3(v: [f32; 3]) -> f32 {
+    libm::sqrtf(v[0] * v[0] + v[1] * v[1] + v[2] * v[2])
+}
+
+/// Smooth orientations through `N` keyframes.
+///
+/// ```
+/// use cubic_splinterpol::orientation::{OrientationSpline, Quaternion};
+/// use cubic_splinterpol::Extrapolate;
+///
+/// let xs = [0f32, 1.0, 2.0];
+/// let keyframes = [
+///     Quaternion::IDENTITY,
+///     Quaternion::from_axis_angle([0.0, 0.0, 1.0], 0.5),
+///     Quaternion::from_axis_angle([0.0, 1.0, 1.0], 1.0),
+/// ];
+/// let spline = OrientationSpline::new(&xs, &keyframes).unwrap();
+/// let q = spline.eval(1.0, Extrapolate::Error).unwrap();
+/// assert!(q.angle_to(keyframes[1]) < 1e-3);
+/// let camera = spline.eval(1.5, Extrapolate::Error).unwrap().rotate([1.0, 0.0, 0.0]);
+/// assert!(camera[1] > 0.0);
+/// ```
+#[derive(Debug, Clone)]
+pub struct OrientationSpline<const N: usize> {
+    xs: [f32; N],
+    base: Quaternion,
+    /// N-1 of N used, per component of the rotation vector from `base`
+    coefficients: [[Segment; 3]; N],
+}
+
+impl<const N: usize> OrientationSpline<N> {
+    /// A spline through the `keyframes` at the strictly increasing `xs`. At
+    /// least 3 keyframes are needed, which are normalized, so need not be
+    /// unit quaternions, but not zero either.
+    ///
+    /// Fails with [`Error::InvalidRange`] for a zero keyframe or one half a
+    /// turn or more away from the first.
+    pub fn new(xs: &[f32; N], keyframes: &[Quaternion; N]) -> Result<Self, Error> {
+        if N < 3 {
+            return Err(Error::InvalidSliceLength);
+        }
+        let mut unit = [Quaternion::IDENTITY; N];
+        for (unit, q) in unit.iter_mut().zip(keyframes) {
+            let norm = libm::sqrtf(q.w * q.w + q.x * q.x + q.y * q.y + q.z * q.z);
+            if norm.is_nan() || norm == 0f32 {
+                return Err(Error::InvalidRange);
+            }
+            *unit = Quaternion::new(q.w / norm, q.x / norm, q.y / norm, q.z / norm);
+        }
+        let base = unit[0];
+        let mut rotations = [[0f32; 3]; N];
+        for (rotation, q) in rotations.iter_mut().zip(&unit) {
+            let mut relative = base.conjugate() * *q;
+            // q and -q are the same rotation, take the shorter way
+            if relative.w < 0f32 {
+                relative = Quaternion::new(-relative.w, -relative.x, -relative.y, -relative.z);
+            }
+            if relative.w <= 0f32 {
+                return Err(Error::InvalidRange);
+            }
+            *rotation = relative.log();
+        }
+        let mut coefficients = [[Segment::default(); 3]; N];
+        splinterpol_multi(xs, &rotations, &mut coefficients)?;
+        Ok(Self {
+            xs: *xs,
+            base,
+            coefficients,
+        })
+    }
+
+    /// The orientation at `x`, with `extrapolate` deciding about positions
+    /// outside of the knots.
+    pub fn eval(&self, x: f32, extrapolate: Extrapolate) -> Result<Quaternion, Error> {
+        let mut rotation = [0f32; 3];
+        for (component, value) in rotation.iter_mut().enumerate() {
+            let coefficients = |segment: usize| self.coefficients[segment][component].to_tuple();
+            let (polynomial, t) = locate(coefficients, &self.xs, x, extrapolate)?;
+            *value = eval_segment(polynomial, t);
+        }
+        Ok(self.base * Quaternion::exp(rotation))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn quaternion_basics() {
+        let quarter = Quaternion::from_axis_angle([0.0, 0.0, 2.0], core::f32::consts::FRAC_PI_2);
+        let rotated = quarter.rotate([1.0, 0.0, 0.0]);
+        assert!(rotated[0].abs() < 1e-6 && (rotated[1] - 1f32).abs() < 1e-6);
+        let half = quarter * quarter;
+        assert!((Quaternion::IDENTITY.angle_to(half) - core::f32::consts::PI).abs() < 1e-3);
+        assert!(quarter.angle_to(quarter.conjugate().conjugate()) < 1e-3);
+        let r = [0.1f32, -0.4, 0.3];
+        let back = Quaternion::exp(r).log();
+        for (lhs, rhs) in r.iter().zip(&back) {
+            assert!((lhs - rhs).abs() < 1e-6);
+        }
+    }
+
+    #[test]
+    fn single_axis_follows_scalar_spline() {
+        let xs = [0f32, 1.0, 2.0, 3.0];
+        let angles = [0.2f32, 0.8, 0.5, 1.5];
+        let axis = [0f32, 1.0, 0.0];
+        let keyframes = angles.map(|angle| Quaternion::from_axis_angle(axis, angle));
+        let spline = OrientationSpline::new(&xs, &keyframes).unwrap();
+        // Relative to the first keyframe, the angle is a natural spline
+        let mut coeffs = [(0f32, 0f32, 0f32, 0f32); 3];
+        crate::splinterpol(&xs, &angles.map(|angle| angle - 0.2), &mut coeffs).unwrap();
+        for i in 0..=30 {
+            let x = i as f32 / 10f32;
+            let q = spline.eval(x, Extrapolate::Error).unwrap();
+            let angle = crate::eval(&coeffs, &xs, x, Extrapolate::Error).unwrap() + 0.2;
+            let expected = Quaternion::from_axis_angle(axis, angle);
+            assert!(q.angle_to(expected) < 1e-3, "at {}", x);
+        }
+        assert_eq!(Err(Error::OutOfRange), spline.eval(3.5, Extrapolate::Error));
+    }
+
+    #[test]
+    fn rejects_invalid_keyframes() {
+        let xs = [0f32, 1.0, 2.0];
+        let half = Quaternion::new(0f32, 1f32, 0f32, 0f32);
+        let zero = Quaternion::new(0f32, 0f32, 0f32, 0f32);
+        for keyframe in [half, zero] {
+            assert_eq!(
+                Err(Error::InvalidRange),
+                OrientationSpline::new(&xs, &[Quaternion::IDENTITY, keyframe, keyframe])
+                    .map(|_| ())
+            );
+        }
+        // Sign of the keyframe does not matter
+        let q = Quaternion::from_axis_angle([1.0, 0.0, 0.0], 0.3);
+        let flipped = Quaternion::new(-q.w, -q.x, -q.y, -q.z);
+        let spline =
+            OrientationSpline::new(&xs, &[Quaternion::IDENTITY, flipped, Quaternion::IDENTITY])
+                .unwrap();
+        let at = spline.eval(1.0, Extrapolate::Error).unwrap();
+        assert!(at.angle_to(q) < 1e-3);
+    }
+}
@@ -0,0 +1,327 @@
+//! Planar paths through waypoints, e.g. for path-following robots, AGVs or
+//! pen plotters.
+//!
+//! Both coordinates are interpolated by natural splines on the same knots,
+//! the cumulative straight-line distances between the waypoints. This chord
+//! length parameter approximates the distance along the path, so that the
+//! path does not speed up or loop where waypoints are far apart. The true
+//! length along the path up to every waypoint is kept in a table, for stepping
+//! along the path in equal distances.
+
+use crate::{
+    eval_segment, eval_segment_derivative, eval_segment_second_derivative, find_segment,
+    splinterpol_multi, Error, Segment,
+};
+
+/// Number of equally spaced positions within every segment at which
+/// [`Path::closest_point`] starts its search, besides the start.
+const CLOSEST_SAMPLES: usize = 4;
+
+/// Number of Newton steps of [`Path::closest_point`] and
+/// [`Path::t_at_arc_length`] within a segment.
+const NEWTON_STEPS: usize = 4;
+
+/// Nodes and weights of the 5-point Gauss-Legendre rule on `[-1, 1]`, exact
+/// for polynomials up to degree 9.
+const GAUSS_LEGENDRE: [(f32, f32); 5] = [
+    (-0.906_179_85, 0.236_926_88),
+    (-0.538_469_3, 0.478_628_67),
+    (0.0, 0.568_888_9),
+    (0.538_469_3, 0.478_628_67),
+    (0.906_179_85, 0.236_926_88),
+];
+
+/// A smooth path through `N` waypoints in the plane.
+///
+/// ```
+/// use cubic_splinterpol::path::Path;
+///
+/// let path = Path::new(&[[0.0, 0.0], [3.0, 4.0], [6.0, 0.0]]).unwrap();
+/// assert_eq!(&[0.0, 5.0, 10.0], path.ts());
+/// assert_eq!([3.0, 4.0], path.point(5.0));
+/// ```
+#[derive(Debug, Clone, PartialEq)]
+pub struct Path<const N: usize> {
+    ts: [f32; N],
+    /// N-1 of N used, x and y per segment
+    coefficients: [[Segment; 2]; N],
+    /// Arc length from the start to every waypoint
+    lengths: [f32; N],
+}
+
+impl<const N: usize> Path<N> {
+    /// A path through the `waypoints`, of which at least 3 are needed.
+    /// Fails with [`Error::NonIncreasingKnots`] where two consecutive ones
+    /// are equal.
+    pub fn new(waypoints: &[[f32; 2]; N]) -> Result<Self, Error> {
+        if N < 3 {
+            return Err(Error::InvalidSliceLength);
+        }
+        let mut ts = [0f32; N];
+        for i in 1..N {
+            let dx = waypoints[i][0] - waypoints[i - 1][0];
+            let dy = waypoints[i][1] - waypoints[i - 1][1];
+            ts[i] = ts[i - 1] + libm::sqrtf(dx * dx + dy * dy);
+        }
+        let mut coefficients = [[Segment::default(); 2]; N];
+        splinterpol_multi(&ts, waypoints, &mut coefficients)?;
+        let mut lengths = [0f32; N];
+        for i in 1..N {
+            lengths[i] = lengths[i - 1] + arc_length(coefficients[i - 1], ts[i] - ts[i - 1]);
+        }
+        Ok(Self {
+            ts,
+            coefficients,
+            lengths,
+        })
+    }
+
+    /// The parameter at every waypoint, from 0 at the first.
+    pub fn ts(&self) -> &[f32; N] {
+        &self.ts
+    }
+
+    /// The arc length from the start to every waypoint.
+    pub fn lengths(&self) -> &[f32; N] {
+        &self.lengths
+    }
+
+    /// The arc length of the whole path.
+    pub fn length(&self) -> f32 {
+        self.lengths[N - 1]
+    }
+
+    /// The coefficients of x and y of all segments.
+    pub fn coefficients(&self) -> &[[Segment; 2]] {
+        &self.coefficients[..N - 1]
+    }
+
+    /// The point at parameter t, continuing the first and last segment
+    /// beyond the ends.
+    pub fn point(&self, t: f32) -> [f32; 2] {
+        let segment = find_segment(&self.ts, t);
+        let [x, y] = self.coefficients[segment];
+        let t = t - self.ts[segment];
+        [x.eval(t), y.eval(t)]
+    }
+
+    /// The derivative of the point by the parameter at t, the direction of
+    /// travel.
+    pub fn tangent(&self, t: f32) -> [f32; 2] {
+        let segment = find_segment(&self.ts, t);
+        let [x, y] = self.coefficients[segment];
+        let t = t - self.ts[segment];
+        [
+            eval_segment_derivative(x.to_tuple(), t),
+            eval_segment_derivative(y.to_tuple(), t),
+        ]
+    }
+
+    /// The parameter of the point at arc length `s` from the start, clamped to
+    /// the path.
+    ///
+    /// The segment is looked up in the table of lengths, and the length within
+    /// it inverted by a few Newton steps from the proportional estimate.
+    pub fn t_at_arc_length(&self, s: f32) -> f32 {
+        let s = s.max(0f32).min(self.length());
+        let segment = find_segment(&self.lengths, s);
+        let polynomials = self.coefficients[segment];
+        let h = self.ts[segment + 1] - self.ts[segment];
+        let target = s - self.lengths[segment];
+        let total = self.lengths[segment + 1] - self.lengths[segment];
+        let mut t = h * target / total;
+        for _ in 0..NEWTON_STEPS {
+            let speed = speed(polynomials, t);
+            if speed.is_nan() || speed <= 0f32 {
+                break;
+            }
+            t = (t - (arc_length(polynomials, t) - target) / speed).clamp(0f32, h);
+        }
+        self.ts[segment] + t
+    }
+
+    /// The point at arc length `s` from the start, clamped to the path, e.g.
+    /// to step a plotter or AGV along the path in equal distances rather than
+    /// equal parameter increments.
+    ///
+    /// ```
+    /// use cubic_splinterpol::path::Path;
+    ///
+    /// let path = Path::new(&[[0.0, 0.0], [1.0, 1.0], [2.0, 0.0]]).unwrap();
+    /// // Longer than the chords
+    /// assert!(path.length() > 2.0 * core::f32::consts::SQRT_2);
+    /// let [x, _] = path.point_at_arc_length(0.5 * path.length());
+    /// assert!((x - 1.0).abs() < 1e-5);
+    /// ```
+    pub fn point_at_arc_length(&self, s: f32) -> [f32; 2] {
+        self.point(self.t_at_arc_length(s))
+    }
+
+    /// The parameter of the point of the path closest to `p` and its
+    /// distance from `p`, e.g. the cross-track error of a robot following the
+    /// path.
+    ///
+    /// Within every segment, the search starts at the closest of a few
+    /// equally spaced positions and takes a few Newton steps on the squared
+    /// distance, so it finds the closest point unless a segment comes close
+    /// to `p` twice within a fraction of its length.
+    ///
+    /// ```
+    /// use cubic_splinterpol::path::Path;
+    ///
+    /// let path = Path::new(&[[0.0, 0.0], [1.0, 0.0], [2.0, 0.0], [3.0, 0.0]]).unwrap();
+    /// let (t, distance) = path.closest_point([1.5, 2.0]);
+    /// assert!((t - 1.5).abs() < 1e-4);
+    /// assert!((distance - 2.0).abs() < 1e-4);
+    /// ```
+    pub fn closest_point(&self, p: [f32; 2]) -> (f32, f32) {
+        let mut best = (0f32, f32::INFINITY);
+        for (segment, [x, y]) in self.coefficients[..N - 1].iter().enumerate() {
+            let (x, y) = (x.to_tuple(), y.to_tuple());
+            let squared = |t: f32| {
+                let dx = eval_segment(x, t) - p[0];
+                let dy = eval_segment(y, t) - p[1];
+                dx * dx + dy * dy
+            };
+            let h = self.ts[segment + 1] - self.ts[segment];
+            let mut closest = (0f32, squared(0f32));
+            for sample in 1..=CLOSEST_SAMPLES {
+                let t = h * sample as f32 / CLOSEST_SAMPLES as f32;
+                let distance = squared(t);
+                if distance < closest.1 {
+                    closest = (t, distance);
+                }
+            }
+            // Newton's method on the derivative of the squared distance
+            let mut t = closest.0;
+            for _ in 0..NEWTON_STEPS {
+                let (dx, dy) = (eval_segment(x, t) - p[0], eval_segment(y, t) - p[1]);
+                let (vx, vy) = (eval_segment_derivative(x, t), eval_segment_derivative(y, t));
+                let (ax, ay) = (
+                    eval_segment_second_derivative(x, t),
+                    eval_segment_second_derivative(y, t),
+                );
+                let slope = dx * vx + dy * vy;
+                let curvature = vx * vx + vy * vy + dx * ax + dy * ay;
+                if curvature.is_nan() || curvature <= 0f32 {
+                    break;
+                }
+                t = (t - slope / curvature).clamp(0f32, h);
+            }
+            let distance = squared(t);
+            if distance < closest.1 {
+                closest = (t, distance);
+            }
+            if closest.1 < best.1 {
+                best = (self.ts[segment] + closest.0, closest.1);
+            }
+        }
+        (best.0, libm::sqrtf(best.1))
+    }
+}
+
+/// Length of the curve given by the `polynomials` of x and y from the start
+/// of their segment to t within it.
+fn arc_length(polynomials: [Segment; 2], t: f32) -> f32 {
+    let half = 0.5f32 * t;
+    GAUSS_LEGENDRE
+        .iter()
+        .map(|(node, weight)| weight * speed(polynomials, half * (1f32 + node)))
+        .sum::<f32>()
+        * half
+}
+
+/// Length of the derivative of the curve given by the `polynomials` of x and
+/// y at t.
+fn speed(polynomials: [Segment; 2], t: f32) -> f32 {
+    let [x, y] = polynomials;
+    let vx = eval_segment_derivative(x.to_tuple(), t);
+    let vy = eval_segment_derivative(y.to_tuple(), t);
+    libm::sqrtf(vx * vx + vy * vy)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn passes_through_waypoints() {
+        let waypoints = [[0f32, 0.0], [1.0, 1.0], [2.0, 0.0], [3.0, 1.0], [3.0, 3.0]];
+        let path = Path::new(&waypoints).unwrap();
+        for (t, waypoint) in path.ts().iter().zip(&waypoints) {
+            let point = path.point(*t);
+            assert!((point[0] - waypoint[0]).abs() < 1e-5);
+            assert!((point[1] - waypoint[1]).abs() < 1e-5);
+        }
+        assert_eq!(2f32 * core::f32::consts::SQRT_2, path.ts()[2]);
+        assert_eq!(
+            Err(Error::NonIncreasingKnots { index: 2 }),
+            Path::new(&[[0f32, 0.0], [1.0, 1.0], [1.0, 1.0]])
+        );
+    }
+
+    #[test]
+    fn closest_point_on_curve() {
+        let waypoints = [[0f32, 0.0], [1.0, 1.0], [2.0, 0.0], [3.0, 1.0], [3.0, 3.0]];
+        let path = Path::new(&waypoints).unwrap();
+        // Points on the path
+        for t in [0f32, 0.7, 2.0, 3.3, 5.0] {
+            let (closest, distance) = path.closest_point(path.point(t));
+            assert!((closest - t).abs() < 1e-3, "{} vs {}", closest, t);
+            assert!(distance < 1e-4);
+        }
+        // Off the path, the offset is along the normal
+        let t = 3.3f32;
+        let [vx, vy] = path.tangent(t);
+        let norm = libm::sqrtf(vx * vx + vy * vy);
+        let [x, y] = path.point(t);
+        let offset = [x - 0.1 * vy / norm, y + 0.1 * vx / norm];
+        let (closest, distance) = path.closest_point(offset);
+        assert!((closest - t).abs() < 1e-3, "{} vs {}", closest, t);
+        assert!((distance - 0.1).abs() < 1e-4);
+        // Beyond the end, the end is closest
+        let end = path.ts()[4];
+        assert_eq!((end, 1.0), path.closest_point([3.0, 4.0]));
+    }
+
+    #[test]
+    fn arc_length_table() {
+        // Straight, the arc lengths are the chord lengths
+        let line = Path::new(&[[0f32, 0.0], [3.0, 4.0], [6.0, 8.0]]).unwrap();
+        assert_eq!(&[0.0, 5.0, 10.0], line.lengths());
+        let [x, y] = line.point_at_arc_length(2.5);
+        assert!((x - 1.5).abs() < 1e-5 && (y - 2.0).abs() < 1e-5);
+        assert_eq!([0.0, 0.0], line.point_at_arc_length(-1.0));
+        assert_eq!([6.0, 8.0], line.point_at_arc_length(11.0));
+
+        // Against a fine polyline
+        let waypoints = [[0f32, 0.0], [1.0, 1.0], [2.0, 0.0], [3.0, 1.0], [3.0, 3.0]];
+        let path = Path::new(&waypoints).unwrap();
+        let steps = 4000;
+        let end = path.ts()[4];
+        let mut polyline = 0f32;
+        let mut previous = path.point(0.0);
+        for step in 1..=steps {
+            let point = path.point(end * step as f32 / steps as f32);
+            let (dx, dy) = (point[0] - previous[0], point[1] - previous[1]);
+            polyline += libm::sqrtf(dx * dx + dy * dy);
+            previous = point;
+        }
+        assert!((path.length() - polyline).abs() < 1e-3);
+        for (length, t) in path.lengths().iter().zip(path.ts()) {
+            assert!(length >= t);
+            assert!((path.t_at_arc_length(*length) - t).abs() < 1e-4);
+        }
+
+        // Equal steps in distance are equally far apart
+        let step = path.length() / 50f32;
+        let mut previous = path.point_at_arc_length(0.0);
+        for i in 1..=50 {
+            let point = path.point_at_arc_length(step * i as f32);
+            let (dx, dy) = (point[0] - previous[0], point[1] - previous[1]);
+            let chord = libm::sqrtf(dx * dx + dy * dy);
+            assert!(chord <= step + 1e-4 && chord > 0.98 * step, "{}", chord);
+            previous = point;
+        }
+    }
+}
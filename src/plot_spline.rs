@@ -1,3 +1,661 @@
+//! Sampling splines for plotting, DACs and displays.
+//!
+//! All functions take a spline as its coefficient table and knots, as written
+//! by [`splinterpol`](crate::splinterpol) and its variants, and are also
+//! exported from the crate root.
+//!
+//! - Uniform sampling from the first to the last knot inclusive: into a
+//!   buffer with [`sample_uniform_into`] and [`sample_clamped_into`], one
+//!   buffer of several channels with [`sample_interleaved_into`], streamed
+//!   with [`for_each_sample`], or chunk by chunk with [`ChunkedSampler`].
+//! - Per-segment sampling with the same number of samples in every segment,
+//!   starting at its knot, with [`sample_segments_into`], for which
+//!   [`segment_sample_count`] tells the buffer length.
+//! - Sampling by curvature with [`sample_adaptive_into`] and [`flatten`], and
+//!   at a fixed step over any range with [`SplineSamples`].
+//! - [`plot_coeffs_into`], which splits the buffer between the segments by
+//!   their width, and [`wavetable_into`] for periodic splines.
+
+use crate::extrapolate::{locate, saturate};
+use crate::{
+    check_range, check_spline, eval_segment, eval_segment_second_derivative, find_segment, Error,
+    Extrapolate,
+};
+
+/// Upper limit on the number of lines [`flatten`] emits per segment, which
+/// caps the work for tolerances near the rounding error.
+const MAX_LINES_PER_SEGMENT: usize = 1 << 16;
+
+/// Number of pieces per segment over which [`sample_adaptive_into`] takes the
+/// bending as constant.
+const ADAPTIVE_PIECES: usize = 16;
+
+/// Share of the samples of [`sample_adaptive_into`] spread uniformly, so that
+/// straight parts keep some.
+const UNIFORM_SHARE: f32 = 0.1;
+
+/// Iterator over `(x, y)` samples of a spline, evaluated lazily one at a
+/// time. Useful where there is no RAM for a buffer of samples, e.g. when
+/// streaming samples to a DAC.
+///
+/// Samples are taken at `from`, `from + step`, `from + 2 * step`, ... up to and
+/// including `to`. Positions outside of the knots are evaluated on the first
+/// or last polynomial, unless another policy is selected with
+/// [`extrapolate`](SplineSamples::extrapolate).
+#[derive(Debug, Clone)]
+pub struct SplineSamples<'a> {
+    coefficients: &'a [(f32, f32, f32, f32)],
+    xs: &'a [f32],
+    from: f32,
+    to: f32,
+    step: f32,
+    index: usize,
+    segment: usize,
+    extrapolate: Extrapolate,
+}
+
+impl<'a> SplineSamples<'a> {
+    /// Construct a sample iterator over the spline given by `coefficients` and
+    /// the knots `xs`, which must be one longer than `coefficients`.
+    ///
+    /// Fails with [`Error::InvalidStep`] unless `step` is strictly positive,
+    /// and with [`Error::InvalidRange`] unless `from` and `to` are finite and
+    /// `from` is at most `to`, so that the iterator always ends.
+    pub fn new(
+        coefficients: &'a [(f32, f32, f32, f32)],
+        xs: &'a [f32],
+        from: f32,
+        to: f32,
+        step: f32,
+    ) -> Result<Self, Error> {
+        check_spline(coefficients, xs)?;
+        if step.is_nan() || step <= 0f32 {
+            return Err(Error::InvalidStep);
+        }
+        if !from.is_finite() || !to.is_finite() || from > to {
+            return Err(Error::InvalidRange);
+        }
+        Ok(Self {
+            coefficients,
+            xs,
+            from,
+            to,
+            step,
+            index: 0,
+            segment: 0,
+            extrapolate: Extrapolate::ExtendCubic,
+        })
+    }
+
+    /// Extrapolate samples outside of the knots according to `extrapolate`.
+    /// With [`Extrapolate::Error`], fails if `from` or `to` is outside of the
+    /// knots.
+    pub fn extrapolate(mut self, extrapolate: Extrapolate) -> Result<Self, Error> {
+        let first = self.xs[0];
+        let last = self.xs[self.xs.len() - 1];
+        if extrapolate == Extrapolate::Error && (self.from < first || self.to > last) {
+            return Err(Error::OutOfRange);
+        }
+        self.extrapolate = extrapolate;
+        Ok(self)
+    }
+}
+
+impl<'a> Iterator for SplineSamples<'a> {
+    type Item = (f32, f32);
+
+    fn next(&mut self) -> Option<Self::Item> {
+        // Multiply instead of accumulating to avoid drift over long ranges
+        let x = self.from + self.index as f32 * self.step;
+        if x > self.to {
+            return None;
+        }
+        self.index += 1;
+        // Sample positions only ever increase, so only walk forward
+        while self.segment + 1 < self.coefficients.len() && x >= self.xs[self.segment + 1] {
+            self.segment += 1;
+        }
+        let segment = self.segment;
+        let coefficients = self.coefficients;
+        let mut polynomial = (coefficients[segment], x - self.xs[segment]);
+        if x < self.xs[0] || x > self.xs[self.xs.len() - 1] {
+            // Error was ruled out for the whole sample range up front
+            polynomial = locate(
+                |segment| coefficients[segment],
+                self.xs,
+                x,
+                self.extrapolate,
+            )
+            .unwrap_or(polynomial);
+        }
+        Some((x, eval_segment(polynomial.0, polynomial.1)))
+    }
+}
+
+/// Plot given coefficients into the buffer according to the intervals given in xs.
+///
+/// xs must hold exactly one more element than coefficients.
+pub fn plot_coeffs_into(
+    buffer: &mut [f32],
+    coefficients: &[(f32, f32, f32, f32)],
+    xs: &[f32],
+) -> Result<(), Error> {
+    check_spline(coefficients, xs)?;
+    let x_range = xs[xs.len() - 1] - xs[0];
+    let step_size = x_range as f64 / buffer.len() as f64;
+    let mut current_index = 0;
+    for i in 0..coefficients.len() {
+        let range = xs[i + 1] - xs[i];
+        let ratio = range / x_range;
+        // f32::round not available in no_std
+        let buffer_ratio = {
+            let r = buffer.len() as f32 * ratio;
+            if r - ((r as u32) as f32) < 0.5 {
+                r as u32
+            } else {
+                r as u32 + 1
+            }
+        };
+        let lower = current_index.min(buffer.len());
+        let mut upper = current_index + buffer_ratio as usize;
+        if upper >= buffer.len() {
+            upper = buffer.len()
+        };
+        let current_slice = &mut buffer[lower..upper];
+        cubic_spline(
+            coefficients[i].0,
+            coefficients[i].1,
+            coefficients[i].2,
+            coefficients[i].3,
+            current_slice,
+            step_size as f32,
+        );
+        current_index += buffer_ratio as usize;
+    }
+    Ok(())
+}
+
+/// Sample the spline uniformly into the buffer, from the first to the last
+/// knot inclusive.
+///
+/// Unlike [`plot_coeffs_into`], the position of
+/// every sample is computed exactly from its index, and its segment is found
+/// by binary search. Samples therefore never drift relative to the knots,
+/// whatever the buffer length.
+pub fn sample_uniform_into(
+    buffer: &mut [f32],
+    coefficients: &[(f32, f32, f32, f32)],
+    xs: &[f32],
+) -> Result<(), Error> {
+    check_spline(coefficients, xs)?;
+    sample_uniform(coefficients, xs, buffer.len(), |index, y| buffer[index] = y);
+    Ok(())
+}
+
+/// Like [`sample_uniform_into`], but saturating every sample to
+/// `[y_min, y_max]` as [`eval_clamped`](crate::eval_clamped) does.
+pub fn sample_clamped_into(
+    buffer: &mut [f32],
+    coefficients: &[(f32, f32, f32, f32)],
+    xs: &[f32],
+    y_min: f32,
+    y_max: f32,
+) -> Result<(), Error> {
+    check_spline(coefficients, xs)?;
+    check_range(y_min, y_max)?;
+    sample_uniform(coefficients, xs, buffer.len(), |index, y| {
+        buffer[index] = saturate(y, y_min, y_max)
+    });
+    Ok(())
+}
+
+/// Stream `count` uniform samples of the spline, from the first to the last
+/// knot inclusive, to `f` as `(x, y)` pairs, e.g. straight to a UART, a DAC
+/// register or a display without a buffer in between. The positions are
+/// those of [`sample_uniform_into`].
+///
+/// ```
+/// use cubic_splinterpol::{for_each_sample, splinterpol};
+///
+/// let xs = [0f32, 1.0, 2.0];
+/// let mut coefficients = [(0f32, 0f32, 0f32, 0f32); 2];
+/// splinterpol(&xs, &[0.0, 1.0, 0.0], &mut coefficients).unwrap();
+/// let mut peak = (0f32, 0f32);
+/// for_each_sample(&coefficients, &xs, 5, |x, y| {
+///     if y > peak.1 {
+///         peak = (x, y);
+///     }
+/// })
+/// .unwrap();
+/// assert_eq!((1.0, 1.0), peak);
+/// ```
+pub fn for_each_sample(
+    coefficients: &[(f32, f32, f32, f32)],
+    xs: &[f32],
+    count: usize,
+    mut f: impl FnMut(f32, f32),
+) -> Result<(), Error> {
+    check_spline(coefficients, xs)?;
+    sample_uniform(coefficients, xs, count, |index, y| {
+        f(uniform_x(xs, count, index), y)
+    });
+    Ok(())
+}
+
+/// Number of samples [`sample_segments_into`] writes for `segments` segments
+/// of `per_segment` samples each, including the one at the last knot.
+pub const fn segment_sample_count(segments: usize, per_segment: usize) -> usize {
+    segments.saturating_mul(per_segment).saturating_add(1)
+}
+
+/// Sample every segment of the spline at `per_segment` equally spaced
+/// positions, the first at its starting knot, and the last knot, into the
+/// buffer as `(x, y)` pairs, e.g. for a plot with knots at fixed sample
+/// positions whatever their spacing.
+///
+/// Returns the number of samples written, which is
+/// [`segment_sample_count`]; the rest of the buffer is left as it is. Fails
+/// with [`Error::InvalidLimit`] if `per_segment` is zero, and with
+/// [`Error::InvalidSliceLength`] if the buffer is shorter.
+///
+/// ```
+/// use cubic_splinterpol::{sample_segments_into, segment_sample_count, splinterpol};
+///
+/// let xs = [0f32, 1.0, 4.0];
+/// let mut coefficients = [(0f32, 0f32, 0f32, 0f32); 2];
+/// splinterpol(&xs, &[0.0, 1.0, 0.0], &mut coefficients).unwrap();
+/// let mut buffer = [(0f32, 0f32); 8];
+/// assert_eq!(7, segment_sample_count(2, 3));
+/// assert_eq!(Ok(7), sample_segments_into(&mut buffer, &coefficients, &xs, 3));
+/// assert_eq!((1.0, 1.0), buffer[3]);
+/// assert_eq!((2.0, buffer[4].1), buffer[4]);
+/// ```
+pub fn sample_segments_into(
+    buffer: &mut [(f32, f32)],
+    coefficients: &[(f32, f32, f32, f32)],
+    xs: &[f32],
+    per_segment: usize,
+) -> Result<usize, Error> {
+    check_spline(coefficients, xs)?;
+    if per_segment == 0 {
+        return Err(Error::InvalidLimit);
+    }
+    let count = segment_sample_count(coefficients.len(), per_segment);
+    if buffer.len() < count {
+        return Err(Error::InvalidSliceLength);
+    }
+    let mut samples = buffer.iter_mut();
+    for (segment, polynomial) in coefficients.iter().enumerate() {
+        let h = xs[segment + 1] - xs[segment];
+        for (index, sample) in samples.by_ref().take(per_segment).enumerate() {
+            let t = h * (index as f32 / per_segment as f32);
+            *sample = (xs[segment] + t, eval_segment(*polynomial, t));
+        }
+    }
+    let last = coefficients.len() - 1;
+    let h = xs[last + 1] - xs[last];
+    buffer[count - 1] = (xs[last + 1], eval_segment(coefficients[last], h));
+    Ok(count)
+}
+
+/// Approximate the spline by a polyline within `tolerance` and stream its
+/// vertices to `f` as `(x, y)` pairs, e.g. for stroke rendering or as line
+/// moves of a G-code style motion controller.
+///
+/// The first vertex is at the first knot, every further one ends a line from
+/// the previous one, and every knot is a vertex. Each line is as long as the
+/// curvature of the spline below it allows: a line of width Δ deviates from a
+/// cubic by at most Δ² / 8 times the largest second derivative over it, which
+/// is at one of its ends. Straight parts thus take a single line, and the
+/// vertical deviation, which bounds the distance to the curve, never exceeds
+/// `tolerance`. Fails with [`Error::InvalidLimit`] unless `tolerance` is
+/// strictly positive.
+///
+/// ```
+/// use cubic_splinterpol::{flatten, splinterpol};
+///
+/// let xs = [0f32, 1.0, 2.0, 3.0];
+/// let mut coefficients = [(0f32, 0f32, 0f32, 0f32); 3];
+/// splinterpol(&xs, &[0.0, 1.0, 1.0, 2.0], &mut coefficients).unwrap();
+/// let mut vertices = 0;
+/// flatten(&coefficients, &xs, 0.01, |_, _| vertices += 1).unwrap();
+/// assert!(vertices > 4 && vertices < 20);
+/// ```
+pub fn flatten(
+    coefficients: &[(f32, f32, f32, f32)],
+    xs: &[f32],
+    tolerance: f32,
+    mut f: impl FnMut(f32, f32),
+) -> Result<(), Error> {
+    check_spline(coefficients, xs)?;
+    if tolerance.is_nan() || tolerance <= 0f32 {
+        return Err(Error::InvalidLimit);
+    }
+    f(xs[0], coefficients[0].0);
+    for (segment, polynomial) in coefficients.iter().enumerate() {
+        let h = xs[segment + 1] - xs[segment];
+        let min_width = h / MAX_LINES_PER_SEGMENT as f32;
+        let curvature = |t: f32| libm::fabsf(eval_segment_second_derivative(*polynomial, t));
+        let width = |curvature: f32| libm::sqrtf(8f32 * tolerance / curvature).max(min_width);
+        let mut t = 0f32;
+        while t < h {
+            let start = curvature(t);
+            let mut end = (t + width(start)).min(h);
+            // The second derivative is linear, so its largest magnitude over
+            // the line is at one end; shortening the line never raises it
+            let largest = start.max(curvature(end));
+            if largest > start {
+                end = (t + width(largest)).min(h);
+            }
+            t = end;
+            if t < h {
+                f(xs[segment] + t, eval_segment(*polynomial, t));
+            }
+        }
+        f(xs[segment + 1], eval_segment(*polynomial, h));
+    }
+    Ok(())
+}
+
+/// Sample the spline into the buffer of `(x, y)` pairs from the first to the
+/// last knot inclusive, placing more samples where it bends sharply and fewer
+/// on straight parts, e.g. to make the most of a small waveform buffer.
+///
+/// The density of samples follows the square root of the magnitude of the
+/// second derivative, which evens out the error of linear interpolation
+/// between them, with a tenth of the samples spread uniformly. Without any
+/// bending, the samples are uniform. The buffer must hold at least 2 samples.
+///
+/// ```
+/// use cubic_splinterpol::{sample_adaptive_into, splinterpol};
+///
+/// // Flat, then a sharp peak at 9
+/// let xs = [0f32, 8.0, 9.0, 10.0];
+/// let mut coefficients = [(0f32, 0f32, 0f32, 0f32); 3];
+/// splinterpol(&xs, &[0.0, 0.0, 1.0, 0.0], &mut coefficients).unwrap();
+/// let mut buffer = [(0f32, 0f32); 32];
+/// sample_adaptive_into(&mut buffer, &coefficients, &xs).unwrap();
+/// assert_eq!((0.0, 0.0), buffer[0]);
+/// // More than the uniform 3 of 32 samples near the peak
+/// assert!(buffer.iter().filter(|(x, _)| *x >= 8.0).count() > 8);
+/// ```
+pub fn sample_adaptive_into(
+    buffer: &mut [(f32, f32)],
+    coefficients: &[(f32, f32, f32, f32)],
+    xs: &[f32],
+) -> Result<(), Error> {
+    check_spline(coefficients, xs)?;
+    let count = buffer.len();
+    if count < 2 {
+        return Err(Error::InvalidSliceLength);
+    }
+    // Every piece as its segment, its start and end in it, and its bending
+    let pieces = || {
+        coefficients
+            .iter()
+            .enumerate()
+            .flat_map(move |(segment, polynomial)| {
+                let h = xs[segment + 1] - xs[segment];
+                (0..ADAPTIVE_PIECES).map(move |piece| {
+                    let start = h * piece as f32 / ADAPTIVE_PIECES as f32;
+                    let end = h * (piece + 1) as f32 / ADAPTIVE_PIECES as f32;
+                    let middle = 0.5f32 * (start + end);
+                    let second = eval_segment_second_derivative(*polynomial, middle);
+                    (
+                        segment,
+                        start,
+                        end,
+                        libm::sqrtf(libm::fabsf(second)) * (end - start),
+                    )
+                })
+            })
+    };
+    let bending: f32 = pieces().map(|piece| piece.3).sum();
+    let last = coefficients.len() - 1;
+    let width = xs[last + 1] - xs[0];
+    let uniform = if bending > 0f32 {
+        UNIFORM_SHARE / (1f32 - UNIFORM_SHARE) * bending / width
+    } else {
+        1f32
+    };
+    let total = bending + uniform * width;
+
+    buffer[0] = (xs[0], coefficients[0].0);
+    let mut index = 1;
+    let mut cumulative = 0f32;
+    for (segment, start, end, bending) in pieces() {
+        let weight = bending + uniform * (end - start);
+        while index < count - 1 {
+            let target = total * index as f32 / (count - 1) as f32;
+            if target > cumulative + weight {
+                break;
+            }
+            let t = start + (end - start) * (target - cumulative) / weight;
+            buffer[index] = (xs[segment] + t, eval_segment(coefficients[segment], t));
+            index += 1;
+        }
+        cumulative += weight;
+    }
+    let end = (
+        xs[last + 1],
+        eval_segment(coefficients[last], xs[last + 1] - xs[last]),
+    );
+    // Rounding of the sum may leave the last targets just beyond the end
+    for sample in &mut buffer[index..] {
+        *sample = end;
+    }
+    Ok(())
+}
+
+/// Sample `coefficients.len()` splines uniformly into the buffer, interleaved
+/// frame by frame, e.g. left and right for a stereo or N channels for a TDM
+/// DMA buffer, without a separate copy pass.
+///
+/// Channel k is the spline given by `coefficients[k]` and the knots `xs[k]`,
+/// sampled from its first to its last knot inclusive like
+/// [`sample_uniform_into`]. The buffer must hold a whole number of frames of
+/// one sample per channel.
+///
+/// ```
+/// use cubic_splinterpol::sample_interleaved_into;
+///
+/// let left = [(0f32, 1f32, 0f32, 0f32)];
+/// let right = [(1f32, -1f32, 0f32, 0f32)];
+/// let xs = [0f32, 1.0];
+/// let mut buffer = [0f32; 6];
+/// sample_interleaved_into(&mut buffer, &[&left, &right], &[&xs, &xs]).unwrap();
+/// assert_eq!([0.0, 1.0, 0.5, 0.5, 1.0, 0.0], buffer);
+/// ```
+pub fn sample_interleaved_into(
+    buffer: &mut [f32],
+    coefficients: &[&[(f32, f32, f32, f32)]],
+    xs: &[&[f32]],
+) -> Result<(), Error> {
+    let frames = check_channels(buffer.len(), coefficients, xs)?;
+    let channels = coefficients.len();
+    for (channel, (coefficients, xs)) in coefficients.iter().zip(xs).enumerate() {
+        sample_uniform(coefficients, xs, frames, |index, y| {
+            buffer[index * channels + channel] = y
+        });
+    }
+    Ok(())
+}
+
+/// Check the splines of an interleaved buffer of `len` samples, returning the
+/// number of frames.
+pub(crate) fn check_channels(
+    len: usize,
+    coefficients: &[&[(f32, f32, f32, f32)]],
+    xs: &[&[f32]],
+) -> Result<usize, Error> {
+    if coefficients.is_empty() {
+        return Err(Error::EmptyInput);
+    }
+    if xs.len() != coefficients.len() || !len.is_multiple_of(coefficients.len()) {
+        return Err(Error::InvalidSliceLength);
+    }
+    for (coefficients, xs) in coefficients.iter().zip(xs) {
+        check_spline(coefficients, xs)?;
+    }
+    Ok(len / coefficients.len())
+}
+
+/// Sample one period of a periodic spline into a wavetable of `L` samples,
+/// where `L` must be a power of two.
+///
+/// The period runs from the first to the last knot. Sample k is taken at
+/// `xs[0] + period * k / L`, so the last sample is one step before the end of
+/// the period and the table loops seamlessly back to its first sample. For the
+/// loop to be free of clicks, the spline must have been computed with
+/// [`Boundary::Periodic`](crate::Boundary::Periodic).
+///
+/// ```
+/// use cubic_splinterpol::{splinterpol_with_boundary, wavetable_into, Boundary};
+///
+/// let xs = [0f32, 1.0, 2.0, 3.0, 4.0];
+/// let ys = [0f32, 1.0, 0.0, -1.0, 0.0];
+/// let mut coefficients = [(0f32, 0f32, 0f32, 0f32); 4];
+/// splinterpol_with_boundary(&xs, &ys, &mut coefficients, Boundary::Periodic).unwrap();
+///
+/// let mut table = [0f32; 256];
+/// wavetable_into(&mut table, &coefficients, &xs).unwrap();
+/// assert_eq!(1.0, table[64]);
+/// ```
+pub fn wavetable_into<const L: usize>(
+    buffer: &mut [f32; L],
+    coefficients: &[(f32, f32, f32, f32)],
+    xs: &[f32],
+) -> Result<(), Error> {
+    check_spline(coefficients, xs)?;
+    if !L.is_power_of_two() {
+        return Err(Error::InvalidSliceLength);
+    }
+    let first = xs[0];
+    let period = xs[xs.len() - 1] - first;
+    let mut segment = 0;
+    for (index, elem) in buffer.iter_mut().enumerate() {
+        let x = first + period * (index as f32 / L as f32);
+        while segment + 1 < coefficients.len() && x >= xs[segment + 1] {
+            segment += 1;
+        }
+        *elem = eval_segment(coefficients[segment], x - xs[segment]);
+    }
+    Ok(())
+}
+
+fn cubic_spline(a: f32, b: f32, c: f32, d: f32, vec: &mut [f32], step_size: f32) {
+    for (index, elem) in vec.iter_mut().enumerate() {
+        let base = index as f32 * step_size;
+        *elem = eval_segment((a, b, c, d), base);
+    }
+}
+
+/// Evaluate `count` uniformly spaced samples from the first to the last knot
+/// inclusive, passing each sample's index and value to `f`. Coefficients and
+/// knots must already have been checked.
+pub(crate) fn sample_uniform(
+    coefficients: &[(f32, f32, f32, f32)],
+    xs: &[f32],
+    count: usize,
+    mut f: impl FnMut(usize, f32),
+) {
+    for index in 0..count {
+        let x = uniform_x(xs, count, index);
+        let segment = find_segment(xs, x);
+        f(index, eval_segment(coefficients[segment], x - xs[segment]));
+    }
+}
+
+/// Position of sample `index` out of `count` uniformly spaced samples from the
+/// first to the last knot inclusive.
+pub(crate) fn uniform_x(xs: &[f32], count: usize, index: usize) -> f32 {
+    let first = xs[0];
+    let x_range = xs[xs.len() - 1] - first;
+    let intervals = count.saturating_sub(1).max(1) as f32;
+    first + x_range * (index as f32 / intervals)
+}
+
+/// Stateful sampler which fills chunks of a uniformly sampled spline on
+/// demand, e.g. to refill DMA half-buffers from an interrupt.
+///
+/// The sampler produces `count` samples from the first to the last knot
+/// inclusive, at the same positions as [`sample_uniform_into`], and remembers
+/// both the next sample index and the current segment between calls.
+#[derive(Debug, Clone)]
+pub struct ChunkedSampler<'a> {
+    coefficients: &'a [(f32, f32, f32, f32)],
+    xs: &'a [f32],
+    count: usize,
+    index: usize,
+    segment: usize,
+}
+
+impl<'a> ChunkedSampler<'a> {
+    /// Construct a sampler producing `count` samples of the spline given by
+    /// `coefficients` and the knots `xs`.
+    pub fn new(
+        coefficients: &'a [(f32, f32, f32, f32)],
+        xs: &'a [f32],
+        count: usize,
+    ) -> Result<Self, Error> {
+        check_spline(coefficients, xs)?;
+        Ok(Self {
+            coefficients,
+            xs,
+            count,
+            index: 0,
+            segment: 0,
+        })
+    }
+
+    /// Fill the chunk with the next samples and return how many were written.
+    /// This is less than the chunk length only once the end of the spline is
+    /// reached; the rest of the chunk is left untouched.
+    pub fn fill(&mut self, chunk: &mut [f32]) -> usize {
+        let mut written = 0;
+        for elem in chunk.iter_mut() {
+            match self.next_sample() {
+                Some(y) => *elem = y,
+                None => break,
+            }
+            written += 1;
+        }
+        written
+    }
+
+    /// Number of samples not yet produced.
+    pub fn remaining(&self) -> usize {
+        self.count - self.index
+    }
+
+    /// Whether all samples have been produced.
+    pub fn is_finished(&self) -> bool {
+        self.index == self.count
+    }
+
+    /// Start over from the first sample, e.g. for looping playback.
+    pub fn reset(&mut self) {
+        self.index = 0;
+        self.segment = 0;
+    }
+
+    fn next_sample(&mut self) -> Option<f32> {
+        if self.index == self.count {
+            return None;
+        }
+        let x = uniform_x(self.xs, self.count, self.index);
+        self.index += 1;
+        // Sample positions only ever increase, so only walk forward
+        while self.segment + 1 < self.coefficients.len() && x >= self.xs[self.segment + 1] {
+            self.segment += 1;
+        }
+        Some(eval_segment(
+            self.coefficients[self.segment],
+            x - self.xs[self.segment],
+        ))
+    }
+}
+
 #[cfg(test)]
 mod tests {
     use plotters::prelude::*;
@@ -19,17 +677,302 @@ mod tests {
         chart
             .draw_series(LineSeries::new(
                 (-50..=50).map(|x| x as f32 / 50.0).map(|x| (x, x * x)),
-                &RED,
+                RED,
             ))
             .unwrap()
             .label("y = x^2")
-            .legend(|(x, y)| PathElement::new(vec![(x, y), (x + 20, y)], &RED));
+            .legend(|(x, y)| PathElement::new(vec![(x, y), (x + 20, y)], RED));
 
         chart
             .configure_series_labels()
-            .background_style(&WHITE.mix(0.8))
-            .border_style(&BLACK)
+            .background_style(WHITE.mix(0.8))
+            .border_style(BLACK)
             .draw()
             .unwrap()
     }
+
+    #[test]
+    fn sample_segments_hits_knots() {
+        let xs = [0f32, 0.5, 2.0, 3.0];
+        let ys = [1f32, -1.0, 2.0, 0.0];
+        let mut coeffs = [(0f32, 0f32, 0f32, 0f32); 3];
+        crate::splinterpol::<4>(&xs, &ys, &mut coeffs).unwrap();
+        let mut buffer = [(f32::NAN, f32::NAN); 14];
+        assert_eq!(
+            Ok(13),
+            super::sample_segments_into(&mut buffer, &coeffs, &xs, 4)
+        );
+        for (i, (x, y)) in xs.iter().zip(&ys).enumerate() {
+            assert_eq!(*x, buffer[4 * i].0);
+            assert!((buffer[4 * i].1 - y).abs() < 1e-5);
+        }
+        assert_eq!(0.5 + 1.5 * 0.75, buffer[7].0);
+        assert!(buffer[13].0.is_nan());
+        assert_eq!(
+            Ok(13),
+            super::sample_segments_into(&mut buffer[..13], &coeffs, &xs, 4)
+        );
+        assert_eq!(
+            Err(crate::Error::InvalidSliceLength),
+            super::sample_segments_into(&mut buffer[..12], &coeffs, &xs, 4)
+        );
+        assert_eq!(
+            Err(crate::Error::InvalidLimit),
+            super::sample_segments_into(&mut buffer, &coeffs, &xs, 0)
+        );
+        assert_eq!(usize::MAX, super::segment_sample_count(usize::MAX, 2));
+    }
+
+    #[test]
+    fn spline_samples_reproduce_knots() {
+        let xs = [0.5f32, 1f32, 2f32, 3f32, 4.5f32, 5f32, 6f32, 7f32];
+        let ys = [0f32, 0f32, 1f32, 2f32, 4f32, 7f32, 9f32, 10f32];
+        let mut coeffs = [(0f32, 0f32, 0f32, 0f32); 7];
+        crate::splinterpol::<8>(&xs, &ys, &mut coeffs).unwrap();
+
+        let samples = super::SplineSamples::new(&coeffs, &xs, 0.5, 7.0, 0.5).unwrap();
+        assert_eq!(14, samples.clone().count());
+        for (x, y) in samples {
+            if let Some(i) = xs.iter().position(|k| *k == x) {
+                assert!((ys[i] - y).abs() < 0.0001);
+            }
+        }
+    }
+
+    #[test]
+    fn spline_samples_reject_invalid_input() {
+        let xs = [0f32, 1f32, 2f32];
+        let coeffs = [(0f32, 1f32, 0f32, 0f32); 2];
+        assert_eq!(
+            crate::Error::InvalidStep,
+            super::SplineSamples::new(&coeffs, &xs, 0.0, 2.0, 0.0).unwrap_err()
+        );
+        assert_eq!(
+            crate::Error::InvalidSliceLength,
+            super::SplineSamples::new(&coeffs, &xs[..2], 0.0, 2.0, 0.1).unwrap_err()
+        );
+        // Ranges the iterator would never reach the end of
+        for (from, to) in [
+            (f32::NAN, 2.0),
+            (0.0, f32::NAN),
+            (0.0, f32::INFINITY),
+            (f32::NEG_INFINITY, 2.0),
+            (2.0, 1.0),
+        ] {
+            assert_eq!(
+                crate::Error::InvalidRange,
+                super::SplineSamples::new(&coeffs, &xs, from, to, 0.1).unwrap_err()
+            );
+        }
+        let single = super::SplineSamples::new(&coeffs, &xs, 1.0, 1.0, 0.1).unwrap();
+        assert_eq!(1, single.count());
+    }
+
+    #[test]
+    fn sample_uniform_hits_knots() {
+        let xs = [0f32, 1f32, 3f32, 4f32, 6f32, 7f32];
+        let ys = [0f32, 2f32, 1f32, 3f32, -1f32, 0f32];
+        let mut coeffs = [(0f32, 0f32, 0f32, 0f32); 5];
+        crate::splinterpol::<6>(&xs, &ys, &mut coeffs).unwrap();
+
+        // 7 samples over a range of 7 puts every sample on an integer x
+        let mut buffer = [0f32; 8];
+        super::sample_uniform_into(&mut buffer, &coeffs, &xs).unwrap();
+        for (x, y) in xs.iter().zip(&ys) {
+            assert!((buffer[*x as usize] - y).abs() < 0.0001);
+        }
+    }
+
+    #[test]
+    fn sample_clamped_saturates() {
+        let xs = [0f32, 1f32, 2f32, 3f32];
+        let ys = [0f32, 0f32, 1f32, 1f32];
+        let mut coeffs = [(0f32, 0f32, 0f32, 0f32); 3];
+        crate::splinterpol::<4>(&xs, &ys, &mut coeffs).unwrap();
+        let mut unclamped = [0f32; 31];
+        super::sample_uniform_into(&mut unclamped, &coeffs, &xs).unwrap();
+        let mut buffer = [0f32; 31];
+        super::sample_clamped_into(&mut buffer, &coeffs, &xs, 0.0, 1.0).unwrap();
+        for (y, unclamped) in buffer.iter().zip(&unclamped) {
+            assert_eq!(unclamped.clamp(0.0, 1.0), *y);
+        }
+        assert!(unclamped.iter().any(|y| *y > 1.0));
+        assert_eq!(
+            Err(crate::Error::InvalidRange),
+            super::sample_clamped_into(&mut buffer, &coeffs, &xs, 1.0, 0.0)
+        );
+    }
+
+    #[test]
+    fn for_each_sample_matches_buffer() {
+        let xs = [0f32, 1f32, 3f32, 4f32];
+        let ys = [0f32, 2f32, 1f32, 3f32];
+        let mut coeffs = [(0f32, 0f32, 0f32, 0f32); 3];
+        crate::splinterpol::<4>(&xs, &ys, &mut coeffs).unwrap();
+        let mut buffer = [0f32; 9];
+        super::sample_uniform_into(&mut buffer, &coeffs, &xs).unwrap();
+        let mut index = 0;
+        super::for_each_sample(&coeffs, &xs, 9, |x, y| {
+            assert_eq!(index as f32 * 0.5, x);
+            assert_eq!(buffer[index], y);
+            index += 1;
+        })
+        .unwrap();
+        assert_eq!(9, index);
+        assert_eq!(
+            Err(crate::Error::InvalidSliceLength),
+            super::for_each_sample(&coeffs, &xs[..3], 9, |_, _| {})
+        );
+    }
+
+    #[test]
+    fn chunked_sampler_matches_uniform_sampling() {
+        let xs = [0.5f32, 1f32, 2f32, 3f32, 4.5f32, 5f32, 6f32, 7f32];
+        let ys = [0f32, 0f32, 1f32, 2f32, 4f32, 7f32, 9f32, 10f32];
+        let mut coeffs = [(0f32, 0f32, 0f32, 0f32); 7];
+        crate::splinterpol::<8>(&xs, &ys, &mut coeffs).unwrap();
+
+        let mut expected = [0f32; 150];
+        super::sample_uniform_into(&mut expected, &coeffs, &xs).unwrap();
+
+        let mut sampler = super::ChunkedSampler::new(&coeffs, &xs, 150).unwrap();
+        let mut output = [0f32; 150];
+        let mut chunk = [0f32; 64];
+        let mut offset = 0;
+        while !sampler.is_finished() {
+            let written = sampler.fill(&mut chunk);
+            output[offset..offset + written].copy_from_slice(&chunk[..written]);
+            offset += written;
+        }
+        assert_eq!(150, offset);
+        assert_eq!(expected, output);
+        assert_eq!(0, sampler.fill(&mut chunk));
+
+        sampler.reset();
+        assert_eq!(150, sampler.remaining());
+        assert_eq!(64, sampler.fill(&mut chunk));
+        assert_eq!(expected[..64], chunk);
+    }
+
+    #[test]
+    fn spline_samples_extrapolate() {
+        let xs = [0f32, 1f32, 2f32];
+        let coeffs = [(0f32, 1f32, 0f32, 0f32), (1f32, 0f32, 1f32, 0f32)];
+        let samples = super::SplineSamples::new(&coeffs, &xs, -1.0, 3.0, 1.0)
+            .unwrap()
+            .extrapolate(crate::Extrapolate::LinearFromEndSlope)
+            .unwrap();
+        let ys: Vec<f32> = samples.map(|(_, y)| y).collect();
+        assert_eq!(vec![-1.0, 0.0, 1.0, 2.0, 4.0], ys);
+
+        let samples = super::SplineSamples::new(&coeffs, &xs, 0.0, 3.0, 1.0).unwrap();
+        assert!(matches!(
+            samples.extrapolate(crate::Extrapolate::Error),
+            Err(crate::Error::OutOfRange)
+        ));
+    }
+
+    #[test]
+    fn wavetable_loops_seamlessly() {
+        let xs = [0f32, 0.7, 1.5, 2.0, 3.1, 4.0];
+        let ys = [0.5f32, 1.0, -0.2, -1.0, 0.1, 0.5];
+        let mut coeffs = [(0f32, 0f32, 0f32, 0f32); 5];
+        crate::splinterpol_with_boundary(&xs, &ys, &mut coeffs, crate::Boundary::Periodic).unwrap();
+        let mut table = [0f32; 512];
+        super::wavetable_into(&mut table, &coeffs, &xs).unwrap();
+        assert_eq!(0.5, table[0]);
+        // The step across the wrap continues the trend of the steps before it
+        let wrap = table[0] - table[511];
+        let before = table[511] - table[510];
+        let after = table[1] - table[0];
+        assert!((wrap - before).abs() < 1e-3);
+        assert!((wrap - after).abs() < 1e-3);
+
+        let mut table = [0f32; 100];
+        assert_eq!(
+            Err(crate::Error::InvalidSliceLength),
+            super::wavetable_into(&mut table, &coeffs, &xs)
+        );
+    }
+
+    #[test]
+    fn flatten_within_tolerance() {
+        let xs = [0f32, 1.0, 2.5, 3.0, 5.0];
+        let mut coeffs = [(0f32, 0f32, 0f32, 0f32); 4];
+        crate::splinterpol(&xs, &[0.0, 2.0, -1.0, 0.5, 0.0], &mut coeffs).unwrap();
+        for tolerance in [0.1f32, 0.01, 0.001] {
+            let mut previous: Option<(f32, f32)> = None;
+            let mut knots = 0;
+            let mut vertices = 0;
+            super::flatten(&coeffs, &xs, tolerance, |x, y| {
+                vertices += 1;
+                if xs.contains(&x) {
+                    knots += 1;
+                }
+                if let Some((x0, y0)) = previous {
+                    assert!(x > x0);
+                    // Deviation of the line from the curve along it
+                    for step in 1..16 {
+                        let fraction = step as f32 / 16f32;
+                        let position = x0 + (x - x0) * fraction;
+                        let line = y0 + (y - y0) * fraction;
+                        let segment = crate::find_segment(&xs, position);
+                        let curve = crate::eval_segment(coeffs[segment], position - xs[segment]);
+                        assert!((line - curve).abs() <= tolerance * 1.01 + 1e-6);
+                    }
+                }
+                previous = Some((x, y));
+            })
+            .unwrap();
+            assert_eq!(5, knots);
+            assert!(vertices < 20 + (4f32 / libm::sqrtf(tolerance)) as usize * 4);
+        }
+
+        // A line takes one line per segment
+        let line = [(0f32, 1f32, 0f32, 0f32), (1.0, 1.0, 0.0, 0.0)];
+        let mut vertices = [(0f32, 0f32); 3];
+        let mut count = 0;
+        super::flatten(&line, &[0.0, 1.0, 2.0], 1e-6, |x, y| {
+            vertices[count] = (x, y);
+            count += 1;
+        })
+        .unwrap();
+        assert_eq!([(0.0, 0.0), (1.0, 1.0), (2.0, 2.0)], vertices);
+        assert_eq!(
+            Err(crate::Error::InvalidLimit),
+            super::flatten(&line, &[0.0, 1.0, 2.0], 0.0, |_, _| ())
+        );
+    }
+
+    #[test]
+    fn sample_adaptive_follows_bending() {
+        let xs = [0f32, 8.0, 9.0, 10.0];
+        let mut coeffs = [(0f32, 0f32, 0f32, 0f32); 3];
+        crate::splinterpol(&xs, &[0.0, 0.0, 1.0, 0.0], &mut coeffs).unwrap();
+        let mut buffer = [(0f32, 0f32); 128];
+        super::sample_adaptive_into(&mut buffer, &coeffs, &xs).unwrap();
+        assert_eq!((10.0, 0.0), buffer[127]);
+        for pair in buffer.windows(2) {
+            assert!(pair[1].0 >= pair[0].0);
+        }
+        for (x, y) in buffer {
+            let segment = crate::find_segment(&xs, x);
+            assert!((crate::eval_segment(coeffs[segment], x - xs[segment]) - y).abs() < 1e-5);
+        }
+        let peak = buffer.iter().filter(|(x, _)| *x >= 8.0).count();
+        assert!(peak > 40, "{}", peak);
+
+        // A line is sampled uniformly
+        let line = [(0f32, 1f32, 0f32, 0f32), (1.0, 1.0, 0.0, 0.0)];
+        let mut buffer = [(0f32, 0f32); 5];
+        super::sample_adaptive_into(&mut buffer, &line, &[0.0, 1.0, 2.0]).unwrap();
+        for (i, (x, y)) in buffer.iter().enumerate() {
+            assert!((x - 0.5 * i as f32).abs() < 1e-6);
+            assert_eq!(x, y);
+        }
+        assert_eq!(
+            Err(crate::Error::InvalidSliceLength),
+            super::sample_adaptive_into(&mut buffer[..1], &line, &[0.0, 1.0, 2.0])
+        );
+    }
 }
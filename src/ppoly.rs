@@ -0,0 +1,168 @@
+//! Conversion of coefficient tables to and from the layout of SciPy's
+//! `PPoly`, e.g. to fit a spline in host tooling and flash it to the device,
+//! or to inspect the spline of the device in Python.
+//!
+//! A `PPoly` of m segments has the breakpoints `x`, the knots, and the
+//! coefficients `c` of shape `(k, m)`, where `c[j, i]` is the coefficient of
+//! the power `k - 1 - j` of segment i, relative to `x[i]`: the highest power
+//! comes first, and every row holds one power of all segments. Here `c` is
+//! flat in row-major order, as by `c.ravel()` in NumPy:
+//!
+//! ```python
+//! from scipy.interpolate import PPoly, CubicSpline
+//!
+//! spline = CubicSpline(x, y, bc_type="natural")
+//! c, breakpoints = spline.c.ravel(), spline.x  # to the device
+//! ppoly = PPoly(c.reshape(4, -1), breakpoints)  # from the device
+//! ```
+
+use crate::{check_increasing, check_spline, Error};
+
+/// Write the spline given by `coefficients` and the knots `xs` to the front of
+/// `c` and `breakpoints` in the layout of `PPoly`, as cubic with `k = 4`.
+/// Fails with [`Error::CapacityExceeded`] if `c` holds fewer than 4 values
+/// per segment or `breakpoints` fewer than the knots.
+///
+/// ```
+/// use cubic_splinterpol::ppoly::to_ppoly;
+///
+/// let xs = [0f32, 1.0, 2.0];
+/// let coefficients = [(1f32, 2f32, 3f32, 4f32), (5.0, 6.0, 7.0, 8.0)];
+/// let mut c = [0f32; 8];
+/// let mut breakpoints = [0f32; 3];
+/// to_ppoly(&coefficients, &xs, &mut c, &mut breakpoints).unwrap();
+/// assert_eq!([4.0, 8.0, 3.0, 7.0, 2.0, 6.0, 1.0, 5.0], c);
+/// assert_eq!(xs, breakpoints);
+/// ```
+pub fn to_ppoly(
+    coefficients: &[(f32, f32, f32, f32)],
+    xs: &[f32],
+    c: &mut [f32],
+    breakpoints: &mut [f32],
+) -> Result<(), Error> {
+    check_spline(coefficients, xs)?;
+    let segments = coefficients.len();
+    if c.len() < 4 * segments || breakpoints.len() < xs.len() {
+        return Err(Error::CapacityExceeded);
+    }
+    breakpoints[..xs.len()].copy_from_slice(xs);
+    for (i, (a, b, c0, d)) in coefficients.iter().enumerate() {
+        for (power, value) in [*a, *b, *c0, *d].iter().enumerate() {
+            c[(3 - power) * segments + i] = *value;
+        }
+    }
+    Ok(())
+}
+
+/// Read a spline in the layout of `PPoly` from `c` and `breakpoints`,
+/// copying its knots and coefficients to the front of `xs` and
+/// `coefficients`. Returns the number of segments.
+///
+/// The order k is the length of `c` per segment, up to 4, so linear and
+/// quadratic `PPoly` are read as well, with the higher coefficients zero.
+/// Fails with [`Error::InvalidSliceLength`] unless `c` holds 1 to 4 values
+/// per segment, [`Error::NonIncreasingKnots`] for breakpoints which do not
+/// increase, as `PPoly` allows, and [`Error::CapacityExceeded`] if `xs` or
+/// `coefficients` is too short.
+///
+/// ```
+/// use cubic_splinterpol::ppoly::from_ppoly;
+///
+/// // Linear, k = 2: x on [0, 1], then 1 - x on [1, 2]
+/// let c = [1f32, -1.0, 0.0, 1.0];
+/// let mut xs = [0f32; 3];
+/// let mut coefficients = [(0f32, 0f32, 0f32, 0f32); 2];
+/// assert_eq!(Ok(2), from_ppoly(&c, &[0.0, 1.0, 2.0], &mut coefficients, &mut xs));
+/// assert_eq!([(0.0, 1.0, 0.0, 0.0), (1.0, -1.0, 0.0, 0.0)], coefficients);
+/// ```
+pub fn from_ppoly(
+    c: &[f32],
+    breakpoints: &[f32],
+    coefficients: &mut [(f32, f32, f32, f32)],
+    xs: &mut [f32],
+) -> Result<usize, Error> {
+    if breakpoints.len() < 2 {
+        return Err(Error::EmptyInput);
+    }
+    let segments = breakpoints.len() - 1;
+    let order = c.len() / segments;
+    if order == 0 || order > 4 || c.len() != order * segments {
+        return Err(Error::InvalidSliceLength);
+    }
+    check_increasing(breakpoints)?;
+    if coefficients.len() < segments || xs.len() < breakpoints.len() {
+        return Err(Error::CapacityExceeded);
+    }
+    xs[..breakpoints.len()].copy_from_slice(breakpoints);
+    for (i, segment) in coefficients[..segments].iter_mut().enumerate() {
+        let mut powers = [0f32; 4];
+        for (power, value) in powers[..order].iter_mut().enumerate() {
+            *value = c[(order - 1 - power) * segments + i];
+        }
+        *segment = (powers[0], powers[1], powers[2], powers[3]);
+    }
+    Ok(segments)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn round_trip() {
+        let xs = [0f32, 1.0, 2.5, 3.0, 4.0];
+        let mut coeffs = [(0f32, 0f32, 0f32, 0f32); 4];
+        crate::splinterpol(&xs, &[1.0, 2.0, 0.5, 3.0, 2.0], &mut coeffs).unwrap();
+        let mut c = [0f32; 20];
+        let mut breakpoints = [0f32; 6];
+        to_ppoly(&coeffs, &xs, &mut c, &mut breakpoints).unwrap();
+        // Rows by descending power
+        assert_eq!(coeffs[2].3, c[2]);
+        assert_eq!(coeffs[1].0, c[13]);
+        let mut back = [(0f32, 0f32, 0f32, 0f32); 5];
+        let mut back_xs = [0f32; 6];
+        assert_eq!(
+            Ok(4),
+            from_ppoly(&c[..16], &breakpoints[..5], &mut back, &mut back_xs)
+        );
+        assert_eq!(coeffs, back[..4]);
+        assert_eq!(xs, back_xs[..5]);
+        assert_eq!(
+            Err(Error::CapacityExceeded),
+            to_ppoly(&coeffs, &xs, &mut c[..15], &mut breakpoints)
+        );
+    }
+
+    #[test]
+    fn rejects_invalid_layouts() {
+        let mut coeffs = [(0f32, 0f32, 0f32, 0f32); 2];
+        let mut xs = [0f32; 3];
+        let breakpoints = [0f32, 1.0, 2.0];
+        assert_eq!(
+            Err(Error::InvalidSliceLength),
+            from_ppoly(&[0f32; 10], &breakpoints, &mut coeffs, &mut xs)
+        );
+        assert_eq!(
+            Err(Error::InvalidSliceLength),
+            from_ppoly(&[0f32; 5], &breakpoints, &mut coeffs, &mut xs)
+        );
+        assert_eq!(
+            Err(Error::NonIncreasingKnots { index: 1 }),
+            from_ppoly(&[0f32; 8], &[2.0, 1.0, 0.0], &mut coeffs, &mut xs)
+        );
+        assert_eq!(
+            Err(Error::CapacityExceeded),
+            from_ppoly(&[0f32; 8], &breakpoints, &mut coeffs[..1], &mut xs)
+        );
+        assert_eq!(
+            Err(Error::EmptyInput),
+            from_ppoly(&[], &breakpoints[..1], &mut coeffs, &mut xs)
+        );
+        // Constant, k = 1
+        assert_eq!(
+            Ok(2),
+            from_ppoly(&[3.0, 4.0], &breakpoints, &mut coeffs, &mut xs)
+        );
+        assert_eq!([(3.0, 0.0, 0.0, 0.0), (4.0, 0.0, 0.0, 0.0)], coeffs);
+    }
+}
@@ -0,0 +1,188 @@
+use crate::{splinterpol, Error};
+
+/// Smoothing of noisy samples before interpolation, over a window of an odd
+/// number of neighbouring samples. Both assume samples equally spaced, or
+/// nearly so, as from an ADC at a fixed rate.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Prefilter {
+    /// Mean of the window, which shrinks symmetrically towards the ends so
+    /// that the first and last sample stay as they are. Keeps straight lines.
+    MovingAverage {
+        /// Number of samples averaged, odd
+        window: usize,
+    },
+    /// Savitzky–Golay: value of the least squares parabola through the
+    /// window. Keeps parabolas, and so peaks, far better than the moving
+    /// average; near the ends, the parabola through the first or last window
+    /// is taken.
+    SavitzkyGolay {
+        /// Number of samples fitted, odd and at least 3
+        window: usize,
+    },
+}
+
+/// Smooth the samples `ys` by `filter` into `out`, which must be as long.
+/// Fails with [`Error::InvalidLimit`] if the window is even, longer than
+/// `ys`, or below 3 for [`Prefilter::SavitzkyGolay`].
+///
+/// ```
+/// use cubic_splinterpol::{prefilter_into, Prefilter};
+///
+/// let noisy = [1f32, 3.0, 1.0, 3.0, 1.0, 3.0, 1.0];
+/// let mut smooth = [0f32; 7];
+/// prefilter_into(&noisy, Prefilter::MovingAverage { window: 5 }, &mut smooth).unwrap();
+/// assert_eq!([1.0, 1.6666666, 1.8, 2.2, 1.8, 1.6666666, 1.0], smooth);
+/// ```
+pub fn prefilter_into(ys: &[f32], filter: Prefilter, out: &mut [f32]) -> Result<(), Error> {
+    let n = ys.len();
+    if out.len() != n {
+        return Err(Error::InvalidSliceLength);
+    }
+    let (Prefilter::MovingAverage { window } | Prefilter::SavitzkyGolay { window }) = filter;
+    let min_window = match filter {
+        Prefilter::MovingAverage { .. } => 1,
+        Prefilter::SavitzkyGolay { .. } => 3,
+    };
+    if window % 2 == 0 || window < min_window || window > n {
+        return Err(Error::InvalidLimit);
+    }
+    let half = window / 2;
+    for (i, elem) in out.iter_mut().enumerate() {
+        *elem = match filter {
+            Prefilter::MovingAverage { .. } => {
+                let half = half.min(i).min(n - 1 - i);
+                let sum: f32 = ys[i - half..=i + half].iter().sum();
+                sum / (2 * half + 1) as f32
+            }
+            Prefilter::SavitzkyGolay { .. } => {
+                let start = i.saturating_sub(half).min(n - window);
+                parabola(&ys[start..start + window], i - start)
+            }
+        };
+    }
+    Ok(())
+}
+
+/// Calculate the coefficients of the spline through xs and the samples `ys`
+/// smoothed by `filter`, e.g. straight from raw ADC readings, as
+/// [`prefilter_into`] followed by [`splinterpol`].
+///
+/// ```
+/// use cubic_splinterpol::{splinterpol_prefiltered, Prefilter};
+///
+/// let xs = [0f32, 1.0, 2.0, 3.0, 4.0, 5.0];
+/// let ys = [0.1f32, 0.9, 2.1, 2.9, 4.1, 4.9];
+/// let mut coefficients = [(0f32, 0f32, 0f32, 0f32); 5];
+/// let filter = Prefilter::SavitzkyGolay { window: 5 };
+/// splinterpol_prefiltered(&xs, &ys, filter, &mut coefficients).unwrap();
+/// assert!((coefficients[2].0 - 2.0).abs() < 0.1);
+/// ```
+pub fn splinterpol_prefiltered<const N: usize>(
+    xs: &[f32; N],
+    ys: &[f32; N],
+    filter: Prefilter,
+    coefficients: &mut [(f32, f32, f32, f32)],
+) -> Result<(), Error> {
+    let mut smooth = [0f32; N];
+    prefilter_into(ys, filter, &mut smooth)?;
+    splinterpol(xs, &smooth, coefficients)
+}
+
+/// Value at sample `at` of the least squares parabola through the equally
+/// spaced `window`, from its expansion in polynomials orthogonal on the
+/// window.
+fn parabola(window: &[f32], at: usize) -> f32 {
+    let w = window.len() as f32;
+    let center = (w - 1f32) / 2f32;
+    // Mean of u² over the window, so that the quadratic term is orthogonal
+    let mean_square = (w * w - 1f32) / 12f32;
+    let quadratic = |u: f32| u * u - mean_square;
+    let (mut mean, mut slope, mut curvature) = (0f32, 0f32, 0f32);
+    let (mut squares, mut quadratics) = (0f32, 0f32);
+    for (k, y) in window.iter().enumerate() {
+        let u = k as f32 - center;
+        mean += y;
+        slope += y * u;
+        curvature += y * quadratic(u);
+        squares += u * u;
+        quadratics += quadratic(u) * quadratic(u);
+    }
+    let v = at as f32 - center;
+    mean / w + slope / squares * v + curvature / quadratics * quadratic(v)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn savitzky_golay_keeps_parabolas() {
+        let mut ys = [0f32; 9];
+        for (i, y) in ys.iter_mut().enumerate() {
+            let x = i as f32;
+            *y = 1f32 - 2f32 * x + 0.5 * x * x;
+        }
+        let mut out = [0f32; 9];
+        for window in [3, 5, 7, 9] {
+            prefilter_into(&ys, Prefilter::SavitzkyGolay { window }, &mut out).unwrap();
+            for (y, smooth) in ys.iter().zip(&out) {
+                assert!((y - smooth).abs() < 1e-4, "{} vs {}", y, smooth);
+            }
+        }
+        // The classic 5-point weights in the middle
+        let mut impulse = [0f32; 9];
+        impulse[4] = 35.0;
+        prefilter_into(&impulse, Prefilter::SavitzkyGolay { window: 5 }, &mut out).unwrap();
+        for (smooth, expected) in out[2..7].iter().zip(&[-3f32, 12.0, 17.0, 12.0, -3.0]) {
+            assert!((smooth - expected).abs() < 1e-4);
+        }
+    }
+
+    #[test]
+    fn moving_average_reduces_noise() {
+        let noisy = [2f32, 4.0, 2.0, 4.0, 2.0, 4.0, 2.0, 4.0];
+        let mut out = [0f32; 8];
+        prefilter_into(&noisy, Prefilter::MovingAverage { window: 3 }, &mut out).unwrap();
+        assert_eq!(2.0, out[0]);
+        assert_eq!(4.0, out[7]);
+        for smooth in &out[1..7] {
+            assert!((smooth - 3f32).abs() < 0.34);
+        }
+        prefilter_into(&noisy, Prefilter::MovingAverage { window: 1 }, &mut out).unwrap();
+        assert_eq!(noisy, out);
+    }
+
+    #[test]
+    fn rejects_invalid_windows() {
+        let ys = [0f32; 5];
+        let mut out = [0f32; 5];
+        for filter in [
+            Prefilter::MovingAverage { window: 4 },
+            Prefilter::MovingAverage { window: 7 },
+            Prefilter::SavitzkyGolay { window: 1 },
+        ] {
+            assert_eq!(
+                Err(Error::InvalidLimit),
+                prefilter_into(&ys, filter, &mut out)
+            );
+        }
+        assert_eq!(
+            Err(Error::InvalidSliceLength),
+            prefilter_into(&ys, Prefilter::MovingAverage { window: 3 }, &mut out[..4])
+        );
+    }
+
+    #[test]
+    fn prefiltered_spline() {
+        let xs = [0f32, 1.0, 2.0, 3.0, 4.0];
+        let ys = [0f32, 1.0, 0.0, 1.0, 0.0];
+        let filter = Prefilter::MovingAverage { window: 3 };
+        let mut smooth = [0f32; 5];
+        prefilter_into(&ys, filter, &mut smooth).unwrap();
+        let mut expected = [(0f32, 0f32, 0f32, 0f32); 4];
+        splinterpol(&xs, &smooth, &mut expected).unwrap();
+        let mut coeffs = [(0f32, 0f32, 0f32, 0f32); 4];
+        splinterpol_prefiltered(&xs, &ys, filter, &mut coeffs).unwrap();
+        assert_eq!(expected, coeffs);
+    }
+}
@@ -0,0 +1,331 @@
+use crate::plot_spline::{check_channels, sample_uniform};
+use crate::{check_range, check_spline, Error};
+
+/// Integer sample formats that spline values can be quantized into, e.g. DAC
+/// codes.
+pub trait Quantize: Copy {
+    /// The largest code representable, corresponding to the top of the output
+    /// range.
+    const MAX_CODE: u32;
+
+    /// Construct a sample from a code between 0 and [`Self::MAX_CODE`].
+    fn from_code(code: u32) -> Self;
+}
+
+impl Quantize for u8 {
+    const MAX_CODE: u32 = u8::MAX as u32;
+
+    fn from_code(code: u32) -> Self {
+        code as u8
+    }
+}
+
+impl Quantize for u16 {
+    const MAX_CODE: u32 = u16::MAX as u32;
+
+    fn from_code(code: u32) -> Self {
+        code as u16
+    }
+}
+
+/// A 12 bit sample, stored right-aligned in a `u16` as most 12 bit DACs expect.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Default)]
+#[repr(transparent)]
+pub struct U12(pub u16);
+
+impl Quantize for U12 {
+    const MAX_CODE: u32 = 0x0fff;
+
+    fn from_code(code: u32) -> Self {
+        U12(code as u16)
+    }
+}
+
+/// Map y from `[y_min, y_max]` to the nearest code, clamping values outside
+/// of the range.
+pub(crate) fn quantize<T: Quantize>(y: f32, y_min: f32, y_max: f32) -> T {
+    nearest_code(scale::<T>(y, y_min, y_max))
+}
+
+/// Map y from `[y_min, y_max]` to `[0, MAX_CODE]`, before rounding.
+fn scale<T: Quantize>(y: f32, y_min: f32, y_max: f32) -> f32 {
+    (y - y_min) / (y_max - y_min) * T::MAX_CODE as f32
+}
+
+/// The code nearest to `scaled`, clamped to the valid codes, with NaN giving
+/// code 0.
+fn nearest_code<T: Quantize>(scaled: f32) -> T {
+    T::from_code(nearest::<T>(scaled))
+}
+
+/// Like [`nearest_code`], as number.
+fn nearest<T: Quantize>(scaled: f32) -> u32 {
+    let clamped = scaled.clamp(0f32, T::MAX_CODE as f32);
+    // f32::round not available in no_std, but clamped is never negative
+    (clamped + 0.5) as u32
+}
+
+/// Dithering of [`plot_dithered_into`], which trades the stair steps of plain
+/// rounding for noise.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum Dither {
+    /// Plain rounding to the nearest code, like [`plot_quantized_into`]
+    #[default]
+    None,
+    /// Carry the rounding error of every sample over to the next one, so that
+    /// the average of neighbouring codes follows the spline
+    ErrorDiffusion,
+    /// Add triangular noise of up to one code before rounding, which makes the
+    /// error independent of the signal. The seed makes the noise reproducible.
+    Tpdf {
+        /// Seed of the pseudo random noise
+        seed: u32,
+    },
+}
+
+/// Plot the spline into a buffer of integer codes, sampled uniformly from the
+/// first to the last knot inclusive. `y_min` maps to code 0 and `y_max` to
+/// [`Quantize::MAX_CODE`]; values outside of that range are clamped.
+pub fn plot_quantized_into<T: Quantize>(
+    buffer: &mut [T],
+    coefficients: &[(f32, f32, f32, f32)],
+    xs: &[f32],
+    y_min: f32,
+    y_max: f32,
+) -> Result<(), Error> {
+    check_spline(coefficients, xs)?;
+    check_range(y_min, y_max)?;
+    sample_uniform(coefficients, xs, buffer.len(), |index, y| {
+        buffer[index] = quantize(y, y_min, y_max)
+    });
+    Ok(())
+}
+
+/// Like [`sample_interleaved_into`](crate::sample_interleaved_into), with the
+/// samples of all channels quantized like [`plot_quantized_into`], e.g. for
+/// the DMA buffer of a multi-channel DAC.
+pub fn plot_quantized_interleaved_into<T: Quantize>(
+    buffer: &mut [T],
+    coefficients: &[&[(f32, f32, f32, f32)]],
+    xs: &[&[f32]],
+    y_min: f32,
+    y_max: f32,
+) -> Result<(), Error> {
+    let frames = check_channels(buffer.len(), coefficients, xs)?;
+    check_range(y_min, y_max)?;
+    let channels = coefficients.len();
+    for (channel, (coefficients, xs)) in coefficients.iter().zip(xs).enumerate() {
+        sample_uniform(coefficients, xs, frames, |index, y| {
+            buffer[index * channels + channel] = quantize(y, y_min, y_max)
+        });
+    }
+    Ok(())
+}
+
+/// Like [`plot_quantized_into`], with `dither` applied before rounding, e.g.
+/// to avoid audible or visible stair steps on slowly changing DAC output.
+///
+/// ```
+/// use cubic_splinterpol::{plot_dithered_into, Dither};
+///
+/// // A shallow ramp over 2 codes
+/// let xs = [0f32, 1.0];
+/// let coefficients = [(0f32, 2.0, 0.0, 0.0)];
+/// let mut codes = [0u8; 9];
+/// plot_dithered_into(&mut codes, &coefficients, &xs, 0.0, 255.0, Dither::ErrorDiffusion).unwrap();
+/// assert_eq!([0, 0, 1, 1, 1, 1, 1, 2, 2], codes);
+/// ```
+pub fn plot_dithered_into<T: Quantize>(
+    buffer: &mut [T],
+    coefficients: &[(f32, f32, f32, f32)],
+    xs: &[f32],
+    y_min: f32,
+    y_max: f32,
+    dither: Dither,
+) -> Result<(), Error> {
+    check_spline(coefficients, xs)?;
+    check_range(y_min, y_max)?;
+    let mut error = 0f32;
+    // Xorshift, which must not start at zero
+    let mut state = match dither {
+        Dither::Tpdf { seed } if seed != 0 => seed,
+        _ => 0x9e37_79b9,
+    };
+    let mut uniform = || {
+        state ^= state << 13;
+        state ^= state >> 17;
+        state ^= state << 5;
+        // 24 bits, exactly representable
+        (state >> 8) as f32 / (1u32 << 24) as f32
+    };
+    sample_uniform(coefficients, xs, buffer.len(), |index, y| {
+        let scaled = scale::<T>(y, y_min, y_max);
+        buffer[index] = match dither {
+            Dither::None => nearest_code(scaled),
+            Dither::ErrorDiffusion => {
+                let target = scaled + error;
+                let code = nearest::<T>(target);
+                // The error of the clamped value, so it cannot wind up
+                error = target.clamp(0f32, T::MAX_CODE as f32) - code as f32;
+                T::from_code(code)
+            }
+            Dither::Tpdf { .. } => nearest_code(scaled + uniform() + uniform() - 1f32),
+        }
+    });
+    Ok(())
+}
+
+/// Sample the spline at the `L` uniformly spaced positions from the first to
+/// the last knot inclusive and round the values to the nearest code, e.g. to
+/// bake a display gamma table or a sensor linearization.
+///
+/// Unlike [`plot_quantized_into`], the values are codes already, so a table
+/// indexed by an input code is built from a spline over input codes with knots
+/// from 0 to `L - 1`. Values outside of 0 to [`Quantize::MAX_CODE`] are
+/// clamped.
+///
+/// ```
+/// use cubic_splinterpol::{bake_lut, splinterpol};
+///
+/// // Gamma 2 through a few points
+/// let xs = [0f32, 64.0, 128.0, 192.0, 255.0];
+/// let ys = xs.map(|x| x * x / 255.0);
+/// let mut coefficients = [(0f32, 0f32, 0f32, 0f32); 4];
+/// splinterpol(&xs, &ys, &mut coefficients).unwrap();
+/// let mut lut = [0u8; 256];
+/// bake_lut(&coefficients, &xs, &mut lut).unwrap();
+/// assert_eq!([0, 16, 64, 255], [lut[0], lut[64], lut[128], lut[255]]);
+/// ```
+pub fn bake_lut<T: Quantize, const L: usize>(
+    coefficients: &[(f32, f32, f32, f32)],
+    xs: &[f32],
+    out: &mut [T; L],
+) -> Result<(), Error> {
+    check_spline(coefficients, xs)?;
+    sample_uniform(coefficients, xs, L, |index, y| {
+        out[index] = nearest_code(y);
+    });
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn quantize_scales_and_clamps() {
+        assert_eq!(0u8, quantize::<u8>(-1.0, 0.0, 1.0));
+        assert_eq!(0u8, quantize::<u8>(0.0, 0.0, 1.0));
+        assert_eq!(128u8, quantize::<u8>(0.5, 0.0, 1.0));
+        assert_eq!(255u8, quantize::<u8>(1.0, 0.0, 1.0));
+        assert_eq!(255u8, quantize::<u8>(2.0, 0.0, 1.0));
+        assert_eq!(U12(4095), quantize(3.3, 0.0, 3.3));
+        assert_eq!(U12(2048), quantize(0.0, -1.0, 1.0));
+        assert_eq!(65535u16, quantize::<u16>(10.0, 0.0, 5.0));
+    }
+
+    #[test]
+    fn plot_quantized_line() {
+        let xs = [0f32, 1f32, 2f32];
+        let coeffs = [(0f32, 1f32, 0f32, 0f32), (1f32, 1f32, 0f32, 0f32)];
+        let mut buffer = [0u8; 3];
+        plot_quantized_into(&mut buffer, &coeffs, &xs, 0.0, 2.0).unwrap();
+        assert_eq!([0, 128, 255], buffer);
+        assert_eq!(
+            Err(Error::InvalidRange),
+            plot_quantized_into(&mut buffer, &coeffs, &xs, 2.0, 2.0)
+        );
+    }
+
+    #[test]
+    fn bake_lut_rounds_and_clamps() {
+        let xs = [0f32, 2f32, 4f32];
+        // y = 1000 * x - 500
+        let coeffs = [
+            (-500f32, 1000f32, 0f32, 0f32),
+            (1500f32, 1000f32, 0f32, 0f32),
+        ];
+        let mut lut = [0u16; 9];
+        bake_lut(&coeffs, &xs, &mut lut).unwrap();
+        assert_eq!([0, 0, 500, 1000, 1500, 2000, 2500, 3000, 3500], lut);
+        let mut lut = [U12(0); 3];
+        bake_lut(&[(0f32, 3000f32, 0f32, 0f32)], &[0.0, 2.0], &mut lut).unwrap();
+        assert_eq!([U12(0), U12(3000), U12(4095)], lut);
+        let mut lut = [0u8; 3];
+        let half = [(0.4f32, 0.1f32, 0f32, 0f32)];
+        bake_lut(&half, &[0.0, 1.0], &mut lut).unwrap();
+        assert_eq!([0, 0, 1], lut);
+    }
+
+    #[test]
+    fn dithered_averages_follow_spline() {
+        let xs = [0f32, 1f32];
+        // Constant a third of a code above code 100
+        let coeffs = [(100.25f32 / 255f32, 0f32, 0f32, 0f32)];
+        let mut plain = [0u8; 400];
+        plot_dithered_into(&mut plain, &coeffs, &xs, 0.0, 1.0, Dither::None).unwrap();
+        assert!(plain.iter().all(|code| *code == 100));
+        let mut rounded = [0u8; 400];
+        plot_quantized_into(&mut rounded, &coeffs, &xs, 0.0, 1.0).unwrap();
+        assert_eq!(plain, rounded);
+
+        for dither in [Dither::ErrorDiffusion, Dither::Tpdf { seed: 7 }] {
+            let mut codes = [0u8; 400];
+            plot_dithered_into(&mut codes, &coeffs, &xs, 0.0, 1.0, dither).unwrap();
+            assert!(codes.iter().all(|code| (99..=102).contains(code)));
+            let mean = codes.iter().map(|code| *code as f32).sum::<f32>() / 400f32;
+            assert!((mean - 100.25).abs() < 0.05, "{:?}: {}", dither, mean);
+        }
+        // Clamped at the top without winding up
+        let mut codes = [U12(0); 4];
+        let high = [(2f32, 0f32, 0f32, 0f32)];
+        plot_dithered_into(&mut codes, &high, &xs, 0.0, 1.0, Dither::ErrorDiffusion).unwrap();
+        assert_eq!([U12(4095); 4], codes);
+        assert_eq!(
+            Err(Error::InvalidRange),
+            plot_dithered_into(&mut codes, &high, &xs, 1.0, 0.0, Dither::None)
+        );
+    }
+
+    #[test]
+    fn plot_quantized_interleaved() {
+        let up = [(0f32, 1f32, 0f32, 0f32)];
+        let down = [(2f32, -1f32, 0f32, 0f32), (1f32, -1f32, 0f32, 0f32)];
+        let up_xs = [0f32, 2f32];
+        let down_xs = [0f32, 1f32, 2f32];
+        let mut buffer = [0u8; 9];
+        plot_quantized_interleaved_into(
+            &mut buffer,
+            &[&up, &down, &up],
+            &[&up_xs, &down_xs, &up_xs],
+            0.0,
+            2.0,
+        )
+        .unwrap();
+        assert_eq!([0, 255, 0, 128, 128, 128, 255, 0, 255], buffer);
+        assert_eq!(
+            Err(Error::InvalidSliceLength),
+            plot_quantized_interleaved_into(
+                &mut buffer[..7],
+                &[&up, &down],
+                &[&up_xs, &down_xs],
+                0.0,
+                2.0
+            )
+        );
+        assert_eq!(
+            Err(Error::InvalidSliceLength),
+            plot_quantized_interleaved_into(
+                &mut buffer,
+                &[&up, &down],
+                &[&up_xs, &up_xs],
+                0.0,
+                2.0
+            )
+        );
+        assert_eq!(
+            Err(Error::EmptyInput),
+            plot_quantized_interleaved_into(&mut buffer, &[], &[], 0.0, 2.0)
+        );
+    }
+}
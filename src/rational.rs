@@ -0,0 +1,183 @@
+//! Rational cubic interpolation which never leaves the range of the data
+//! between two knots, e.g. for safety-relevant actuator commands, where an
+//! overshoot of the spline is a fault.
+//!
+//! Every segment is the rational cubic of Gregory and Delbourgo, with values
+//! and derivatives given at both knots. Its weight r blends between the cubic
+//! Hermite segment at `r = 3` and a curve ever tighter to the chord as r
+//! grows. In Bernstein form, the curve is a weighted mean of the values at the
+//! knots and of `y0 + h m0 / r` and `y1 - h m1 / r`, so it stays between the
+//! values at the knots once these do.
+//!
+//! The derivatives are those of the natural spline, set to zero at every
+//! local extremum of the data and wherever they point against the data, and
+//! the weight of every segment is the smallest from 3 up that bounds it. So
+//! the result is exactly the natural spline wherever that does not leave the
+//! range of its segment, and C1 everywhere.
+
+use crate::{find_segment, splinterpol, Error};
+
+/// A bounded rational interpolant through `N` knots.
+///
+/// ```
+/// use cubic_splinterpol::rational::RationalSpline;
+///
+/// // A step of the commanded valve opening
+/// let xs = [0f32, 1.0, 2.0, 3.0, 4.0];
+/// let ys = [0f32, 0.0, 1.0, 1.0, 1.0];
+/// let spline = RationalSpline::new(&xs, &ys).unwrap();
+/// for i in 0..=40 {
+///     let y = spline.eval(i as f32 / 10.0);
+///     assert!((0.0..=1.0).contains(&y));
+/// }
+/// assert_eq!(0.5, spline.eval(1.5));
+/// ```
+#[derive(Debug, Clone)]
+pub struct RationalSpline<const N: usize> {
+    xs: [f32; N],
+    ys: [f32; N],
+    slopes: [f32; N],
+    /// N-1 of N used
+    weights: [f32; N],
+}
+
+impl<const N: usize> RationalSpline<N> {
+    /// An interpolant through `ys` at the strictly increasing knots `xs`. At
+    /// least 3 knots are needed.
+    pub fn new(xs: &[f32; N], ys: &[f32; N]) -> Result<Self, Error> {
+        let mut coefficients = [(0f32, 0f32, 0f32, 0f32); N];
+        splinterpol(xs, ys, &mut coefficients)?;
+        let secant = |i: usize| (ys[i + 1] - ys[i]) / (xs[i + 1] - xs[i]);
+        let h = xs[N - 1] - xs[N - 2];
+        let (_, b, c, d) = coefficients[N - 2];
+        let mut slopes = [0f32; N];
+        for (i, slope) in slopes.iter_mut().enumerate() {
+            let spline = if i < N - 1 {
+                coefficients[i].1
+            } else {
+                b + h * (2f32 * c + 3f32 * h * d)
+            };
+            let before = if i > 0 { secant(i - 1) } else { spline };
+            let after = if i < N - 1 { secant(i) } else { spline };
+            // Against either secant, or at a local extremum, only zero bounds
+            let bounded = spline * before > 0f32 && spline * after > 0f32;
+            *slope = if bounded { spline } else { 0f32 };
+        }
+        let mut weights = [3f32; N];
+        for (i, weight) in weights[..N - 1].iter_mut().enumerate() {
+            let delta = secant(i);
+            if delta != 0f32 {
+                // Slopes have the sign of delta, so both ratios are positive
+                *weight = weight.max(slopes[i] / delta).max(slopes[i + 1] / delta);
+            }
+        }
+        Ok(Self {
+            xs: *xs,
+            ys: *ys,
+            slopes,
+            weights,
+        })
+    }
+
+    /// The derivatives at the knots.
+    pub fn slopes(&self) -> &[f32; N] {
+        &self.slopes
+    }
+
+    /// The weights of the N-1 segments, 3 for a cubic segment and above for
+    /// a tightened one.
+    pub fn weights(&self) -> &[f32] {
+        &self.weights[..N - 1]
+    }
+
+    /// Evaluate the interpolant at x. Beyond the knots, it holds the value at
+    /// the nearest end, so the result is never outside of the range of `ys`.
+    /// A NaN x gives NaN.
+    pub fn eval(&self, x: f32) -> f32 {
+        if x <= self.xs[0] {
+            return self.ys[0];
+        }
+        if x >= self.xs[N - 1] {
+            return self.ys[N - 1];
+        }
+        if x.is_nan() {
+            return x;
+        }
+        let i = find_segment(&self.xs, x);
+        let h = self.xs[i + 1] - self.xs[i];
+        let (y0, y1) = (self.ys[i], self.ys[i + 1]);
+        let (m0, m1) = (self.slopes[i], self.slopes[i + 1]);
+        let r = self.weights[i];
+        let theta = (x - self.xs[i]) / h;
+        let rest = 1f32 - theta;
+        let numerator = rest * rest * (rest * y0 + theta * (r * y0 + h * m0))
+            + theta * theta * (rest * (r * y1 - h * m1) + theta * y1);
+        let denominator = 1f32 + (r - 3f32) * theta * rest;
+        // Rounding must not leave the segment's range either
+        (numerator / denominator).clamp(y0.min(y1), y0.max(y1))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{eval, Extrapolate};
+
+    #[test]
+    fn equals_spline_where_bounded() {
+        let xs = [0f32, 1.0, 2.0, 3.0, 4.0];
+        let ys = [0f32, 1.0, 2.5, 3.0, 3.2];
+        let mut coeffs = [(0f32, 0f32, 0f32, 0f32); 4];
+        crate::splinterpol(&xs, &ys, &mut coeffs).unwrap();
+        let spline = RationalSpline::new(&xs, &ys).unwrap();
+        assert_eq!(&[3.0; 4], spline.weights());
+        for i in 0..=40 {
+            let x = i as f32 / 10f32;
+            let expected = eval(&coeffs, &xs, x, Extrapolate::Error).unwrap();
+            assert!((spline.eval(x) - expected).abs() < 1e-5, "at {}", x);
+        }
+    }
+
+    #[test]
+    fn stays_within_segments() {
+        let xs = [0f32, 0.5, 1.0, 3.0, 3.2, 5.0];
+        let ys = [0f32, 2.0, 2.1, -1.0, 4.0, 4.0];
+        let spline = RationalSpline::new(&xs, &ys).unwrap();
+        assert!(spline.weights().iter().any(|weight| *weight > 3.0));
+        for i in 0..=500 {
+            let x = i as f32 / 100f32;
+            let segment = find_segment(&xs, x);
+            let (low, high) = (
+                ys[segment].min(ys[segment + 1]),
+                ys[segment].max(ys[segment + 1]),
+            );
+            let y = spline.eval(x);
+            assert!(y >= low && y <= high, "{} at {}", y, x);
+        }
+        // Continuous with continuous slope at the knots
+        for (x, y) in xs.iter().zip(&ys) {
+            assert!((spline.eval(*x) - y).abs() < 1e-6);
+            let dx = 1e-4;
+            let left = (spline.eval(*x) - spline.eval(x - dx)) / dx;
+            let right = (spline.eval(x + dx) - spline.eval(*x)) / dx;
+            if *x > 0.0 && *x < 5.0 {
+                assert!((left - right).abs() < 0.1, "{} vs {} at {}", left, right, x);
+            }
+        }
+        // Local extrema are flat
+        assert_eq!(0.0, spline.slopes()[3]);
+        assert_eq!(0.0, spline.slopes()[4]);
+    }
+
+    #[test]
+    fn holds_beyond_knots() {
+        let spline = RationalSpline::new(&[0f32, 1.0, 2.0], &[1f32, 3.0, 2.0]).unwrap();
+        assert_eq!(1.0, spline.eval(-1.0));
+        assert_eq!(2.0, spline.eval(5.0));
+        assert!(spline.eval(f32::NAN).is_nan());
+        assert_eq!(
+            Err(Error::NonIncreasingKnots { index: 2 }),
+            RationalSpline::new(&[0f32, 1.0, 1.0], &[1f32, 3.0, 2.0]).map(|_| ())
+        );
+    }
+}
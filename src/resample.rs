@@ -0,0 +1,362 @@
+//! Sample rate conversion of audio or sensor streams.
+//!
+//! The input is interpolated by a natural spline over a rolling window of the
+//! `W` latest samples, evaluated only on the segment in the middle of the
+//! window, which approximates the spline through the whole stream. The
+//! knots are uniform, so the tridiagonal matrix is eliminated once, up front,
+//! and every input sample costs one solve of the window.
+//!
+//! Where latency and RAM matter more than a continuous second derivative,
+//! e.g. for audio or image rows, [`Kernel::Keys`] interpolates by cubic
+//! convolution instead, from the 4 samples around every output, without any
+//! solve.
+
+use crate::thomas_algorithm::{factorize, solve, Factorization};
+use crate::{eval_segment, Error};
+
+/// Interpolation of a [`Resampler`] between the input samples.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum Kernel {
+    /// Natural spline over the whole window, C2
+    #[default]
+    Spline,
+    /// Cubic convolution of Keys with `a = -0.5` over the 4 samples around
+    /// the output, the Catmull-Rom spline: C1, exact for quadratics, and
+    /// with a window of 4 the lowest possible latency
+    Keys,
+}
+
+/// A streaming resampler over a window of `W` input samples, an even number
+/// of at least 4. Larger windows approach the global spline more closely but
+/// delay the output by more samples.
+///
+/// Inputs are fed with [`push`](Resampler::push), outputs taken with
+/// [`pull`](Resampler::pull) until it returns `None`, or both at once with
+/// [`process`](Resampler::process). The first output corresponds to input
+/// sample `W / 2 - 1` and is available once `W` samples have been pushed.
+///
+/// ```
+/// use cubic_splinterpol::resample::Resampler;
+///
+/// // 8 kHz to 12 kHz
+/// let mut resampler = Resampler::<6>::new(8000.0, 12000.0).unwrap();
+/// let input = [0f32, 1.0, 2.0, 3.0, 4.0, 5.0, 6.0, 7.0, 8.0];
+/// let mut output = [0f32; 16];
+/// let (consumed, written) = resampler.process(&input, &mut output).unwrap();
+/// assert_eq!(9, consumed);
+/// // A ramp stays a ramp, starting at input sample 2
+/// assert_eq!(6, written);
+/// for (i, y) in output[..written].iter().enumerate() {
+///     assert!((y - (2.0 + i as f32 * 2.0 / 3.0)).abs() < 1e-5);
+/// }
+/// ```
+#[derive(Debug, Clone)]
+pub struct Resampler<const W: usize> {
+    /// Latest inputs, oldest first, the last `filled` valid
+    window: [f32; W],
+    filled: usize,
+    factorization: Factorization<W>,
+    /// Input samples per output sample
+    step: f32,
+    /// Position of the next output within the middle segment, in input
+    /// samples
+    position: f32,
+    middle: (f32, f32, f32, f32),
+    kernel: Kernel,
+}
+
+impl<const W: usize> Resampler<W> {
+    /// A resampler from `input_rate` to `output_rate`, in any unit as long
+    /// as it is the same for both.
+    ///
+    /// Fails with [`Error::InvalidLimit`] unless both rates are strictly
+    /// positive, and with [`Error::InvalidSliceLength`] for a window which is
+    /// odd or shorter than 4.
+    pub fn new(input_rate: f32, output_rate: f32) -> Result<Self, Error> {
+        if W < 4 || !W.is_multiple_of(2) {
+            return Err(Error::InvalidSliceLength);
+        }
+        let step = input_rate / output_rate;
+        if input_rate.is_nan() || input_rate <= 0f32 || output_rate <= 0f32 || !step.is_finite() {
+            return Err(Error::InvalidLimit);
+        }
+        // Natural spline on unit spaced knots, for the interior c
+        let main = [4f32; W];
+        let sub_diagonal = [1f32; W];
+        let factorization = factorize::<W>(&sub_diagonal[..W - 3], &main[..W - 2])?;
+        Ok(Self {
+            window: [0f32; W],
+            filled: 0,
+            factorization,
+            step,
+            position: 0f32,
+            middle: (0f32, 0f32, 0f32, 0f32),
+            kernel: Kernel::Spline,
+        })
+    }
+
+    /// Interpolate by `kernel` instead of the natural spline.
+    ///
+    /// ```
+    /// use cubic_splinterpol::resample::{Kernel, Resampler};
+    ///
+    /// let mut resampler = Resampler::<4>::new(1.0, 2.0).unwrap().with_kernel(Kernel::Keys);
+    /// let mut output = [0f32; 8];
+    /// let (_, written) = resampler.process(&[0.0, 1.0, 4.0, 9.0, 16.0], &mut output).unwrap();
+    /// // Exact for the parabola, one input sample behind
+    /// assert_eq!([1.0, 2.25, 4.0, 6.25], output[..written]);
+    /// ```
+    pub fn with_kernel(mut self, kernel: Kernel) -> Self {
+        self.kernel = kernel;
+        self
+    }
+
+    /// Feed the next input sample.
+    ///
+    /// Fails with [`Error::CapacityExceeded`] while an output of the current
+    /// segment is still to be pulled, so that none is lost.
+    pub fn push(&mut self, sample: f32) -> Result<(), Error> {
+        if self.pending() {
+            return Err(Error::CapacityExceeded);
+        }
+        self.window.copy_within(1.., 0);
+        self.window[W - 1] = sample;
+        if self.filled < W {
+            self.filled += 1;
+            if self.filled < W {
+                return Ok(());
+            }
+        } else {
+            self.position -= 1f32;
+        }
+        self.middle = self.middle_segment()?;
+        Ok(())
+    }
+
+    /// Take the next output sample, or `None` if more input is needed first.
+    pub fn pull(&mut self) -> Option<f32> {
+        if !self.pending() {
+            return None;
+        }
+        let y = eval_segment(self.middle, self.position);
+        self.position += self.step;
+        Some(y)
+    }
+
+    /// Feed samples from `input` and write outputs to `output` until either
+    /// is exhausted. Returns the number of input samples consumed and of
+    /// output samples written.
+    pub fn process(&mut self, input: &[f32], output: &mut [f32]) -> Result<(usize, usize), Error> {
+        let mut consumed = 0;
+        let mut written = 0;
+        loop {
+            while written < output.len() {
+                match self.pull() {
+                    Some(y) => {
+                        output[written] = y;
+                        written += 1;
+                    }
+                    None => break,
+                }
+            }
+            if written == output.len() && self.pending() || consumed == input.len() {
+                return Ok((consumed, written));
+            }
+            self.push(input[consumed])?;
+            consumed += 1;
+        }
+    }
+
+    /// Whether the next output falls into the current middle segment.
+    fn pending(&self) -> bool {
+        self.filled == W && self.position < 1f32
+    }
+
+    /// Coefficients of the segment in the middle of the window.
+    fn middle_segment(&self) -> Result<(f32, f32, f32, f32), Error> {
+        let ys = &self.window;
+        let m = W / 2 - 1;
+        if self.kernel == Kernel::Keys {
+            return Ok(keys_segment(ys[m - 1], ys[m], ys[m + 1], ys[m + 2]));
+        }
+        let mut r = [0f32; W];
+        for (i, elem) in r[..W - 2].iter_mut().enumerate() {
+            *elem = 3f32 * (ys[i + 2] - 2f32 * ys[i + 1] + ys[i]);
+        }
+        let mut c = [0f32; W];
+        solve(&self.factorization, &r[..W - 2], &mut c[1..W - 1])?;
+        let b = (ys[m + 1] - ys[m]) - (2f32 * c[m] + c[m + 1]) / 3f32;
+        let d = (c[m + 1] - c[m]) / 3f32;
+        Ok((ys[m], b, c[m], d))
+    }
+}
+
+/// Resample the uniformly spaced `input` to the length of `output` by the
+/// cubic convolution of [`Kernel::Keys`], with the first and last samples of
+/// both aligned, e.g. to scale an image row. `input` needs at least 3
+/// samples, and is extended beyond its ends as proposed by Keys, which keeps
+/// quadratics exact up to the ends.
+///
+/// ```
+/// use cubic_splinterpol::resample::resample_keys_into;
+///
+/// let row = [0f32, 10.0, 20.0, 30.0];
+/// let mut scaled = [0f32; 7];
+/// resample_keys_into(&row, &mut scaled).unwrap();
+/// assert_eq!([0.0, 5.0, 10.0, 15.0, 20.0, 25.0, 30.0], scaled);
+/// ```
+pub fn resample_keys_into(input: &[f32], output: &mut [f32]) -> Result<(), Error> {
+    let n = input.len();
+    if n < 3 {
+        return Err(Error::InvalidSliceLength);
+    }
+    let sample = |i: isize| match i {
+        -1 => 3f32 * input[0] - 3f32 * input[1] + input[2],
+        i if i as usize == n => 3f32 * input[n - 1] - 3f32 * input[n - 2] + input[n - 3],
+        i => input[i as usize],
+    };
+    let last = output.len().saturating_sub(1).max(1);
+    for (index, y) in output.iter_mut().enumerate() {
+        // Computed from the index, as by sample_uniform_into
+        let x = (n - 1) as f32 * index as f32 / last as f32;
+        let segment = (x as usize).min(n - 2);
+        let i = segment as isize;
+        let polynomial = keys_segment(sample(i - 1), sample(i), sample(i + 1), sample(i + 2));
+        *y = eval_segment(polynomial, x - segment as f32);
+    }
+    Ok(())
+}
+
+/// Coefficients of the cubic convolution of Keys with `a = -0.5` between `y1`
+/// and `y2`, on unit spaced samples.
+fn keys_segment(y0: f32, y1: f32, y2: f32, y3: f32) -> (f32, f32, f32, f32) {
+    (
+        y1,
+        0.5 * (y2 - y0),
+        y0 - 2.5 * y1 + 2f32 * y2 - 0.5 * y3,
+        0.5 * (y3 - y0) + 1.5 * (y1 - y2),
+    )
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn matches_spline_of_window() {
+        let input = [0.3f32, -1.0, 2.0, 0.5, 1.5, -0.5, 0.0, 1.0];
+        let mut resampler = Resampler::<6>::new(1.0, 4.0).unwrap();
+        let mut output = [0f32; 16];
+        let (consumed, written) = resampler.process(&input, &mut output).unwrap();
+        assert_eq!((8, 12), (consumed, written));
+        // The first window's middle segment, from input 2 to 3
+        let xs = [0f32, 1.0, 2.0, 3.0, 4.0, 5.0];
+        let mut coeffs = [(0f32, 0f32, 0f32, 0f32); 5];
+        let mut window = [0f32; 6];
+        window.copy_from_slice(&input[..6]);
+        crate::splinterpol(&xs, &window, &mut coeffs).unwrap();
+        for (i, y) in output[..4].iter().enumerate() {
+            let expected = eval_segment(coeffs[2], i as f32 / 4f32);
+            assert!((y - expected).abs() < 1e-5);
+        }
+        assert_eq!(2.0, output[0]);
+        assert_eq!(0.5, output[4]);
+    }
+
+    #[test]
+    fn downsamples_and_resumes() {
+        let ramp = |i: usize| i as f32 * 0.5;
+        let mut resampler = Resampler::<4>::new(3.0, 1.0).unwrap();
+        let mut output = [0f32; 2];
+        let mut all = [0f32; 6];
+        let mut count = 0;
+        let mut next = 0;
+        // Small output buffers, as from an audio callback
+        while count < all.len() {
+            let input = [ramp(next), ramp(next + 1)];
+            let (consumed, written) = resampler.process(&input, &mut output).unwrap();
+            next += consumed;
+            all[count..count + written].copy_from_slice(&output[..written]);
+            count += written;
+        }
+        for (i, y) in all.iter().enumerate() {
+            assert!((y - ramp(1 + 3 * i)).abs() < 1e-5);
+        }
+    }
+
+    #[test]
+    fn rejects_invalid_use() {
+        assert_eq!(
+            Err(Error::InvalidSliceLength),
+            Resampler::<5>::new(1.0, 2.0).map(|_| ())
+        );
+        assert_eq!(
+            Err(Error::InvalidLimit),
+            Resampler::<4>::new(1.0, 0.0).map(|_| ())
+        );
+        let mut resampler = Resampler::<4>::new(1.0, 2.0).unwrap();
+        for sample in [1f32, 2.0, 3.0, 4.0] {
+            resampler.push(sample).unwrap();
+        }
+        assert_eq!(Err(Error::CapacityExceeded), resampler.push(5.0));
+        assert_eq!(Some(2.0), resampler.pull());
+        assert_eq!(Some(2.5), resampler.pull());
+        assert_eq!(None, resampler.pull());
+        assert_eq!(Ok(()), resampler.push(5.0));
+    }
+
+    #[test]
+    fn keys_kernel_streams_with_low_latency() {
+        let input = [0.3f32, -1.0, 2.0, 0.5, 1.5, -0.5, 0.0, 1.0];
+        let mut resampler = Resampler::<4>::new(1.0, 4.0)
+            .unwrap()
+            .with_kernel(Kernel::Keys);
+        let mut output = [0f32; 32];
+        let (consumed, written) = resampler.process(&input, &mut output).unwrap();
+        assert_eq!((8, 20), (consumed, written));
+        // Starts at input sample 1 and passes through every sample
+        for (i, y) in output[..written].iter().step_by(4).enumerate() {
+            assert_eq!(input[i + 1], *y);
+        }
+        // The same as resampling the whole stream at once, away from its ends
+        let mut batch = [0f32; 29];
+        resample_keys_into(&input, &mut batch).unwrap();
+        for (streamed, batched) in output[..written].iter().zip(&batch[4..]) {
+            assert!((streamed - batched).abs() < 1e-5);
+        }
+        // A wider window only adds latency
+        let mut wide = Resampler::<6>::new(1.0, 4.0)
+            .unwrap()
+            .with_kernel(Kernel::Keys);
+        let (_, written) = wide.process(&input, &mut output).unwrap();
+        assert_eq!(12, written);
+        assert_eq!(input[2], output[0]);
+    }
+
+    #[test]
+    fn keys_batch_keeps_quadratics() {
+        let mut input = [0f32; 6];
+        for (i, y) in input.iter_mut().enumerate() {
+            let x = i as f32;
+            *y = 2f32 - x + 0.5 * x * x;
+        }
+        let mut output = [0f32; 21];
+        resample_keys_into(&input, &mut output).unwrap();
+        for (i, y) in output.iter().enumerate() {
+            let x = i as f32 / 4f32;
+            assert!(
+                (y - (2f32 - x + 0.5 * x * x)).abs() < 1e-5,
+                "{} at {}",
+                y,
+                x
+            );
+        }
+        let mut single = [0f32; 1];
+        resample_keys_into(&input, &mut single).unwrap();
+        assert_eq!(input[0], single[0]);
+        assert_eq!(
+            Err(Error::InvalidSliceLength),
+            resample_keys_into(&input[..2], &mut output)
+        );
+    }
+}
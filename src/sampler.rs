@@ -0,0 +1,173 @@
+use crate::extrapolate::{locate, saturate};
+use crate::{check_range, check_spline, eval_segment, find_segment, Error, Extrapolate};
+
+/// Stateful evaluator for queries which mostly advance, e.g. the current time
+/// of a real-time control loop.
+///
+/// The sampler remembers the segment of the last query. A query in the same
+/// or the next segment takes constant time, anything else, such as a jump
+/// back in time, falls back to the binary search of [`eval`](crate::eval),
+/// with the same result.
+///
+/// ```
+/// use cubic_splinterpol::{splinterpol, Extrapolate, Sampler};
+///
+/// let xs = [0f32, 1.0, 2.0, 3.0];
+/// let ys = [0f32, 1.0, 0.5, 2.0];
+/// let mut coefficients = [(0f32, 0f32, 0f32, 0f32); 3];
+/// splinterpol(&xs, &ys, &mut coefficients).unwrap();
+///
+/// let mut sampler = Sampler::new(&coefficients, &xs, Extrapolate::ClampToEndValue).unwrap();
+/// for tick in 0..30 {
+///     let y = sampler.sample(tick as f32 * 0.1).unwrap();
+///     assert!((-0.5..2.5).contains(&y));
+/// }
+/// assert_eq!(2, sampler.segment());
+/// assert_eq!(Ok(0.0), sampler.sample(0.0));
+/// ```
+#[derive(Debug, Clone)]
+pub struct Sampler<'a> {
+    coefficients: &'a [(f32, f32, f32, f32)],
+    xs: &'a [f32],
+    extrapolate: Extrapolate,
+    output_range: Option<(f32, f32)>,
+    segment: usize,
+}
+
+impl<'a> Sampler<'a> {
+    /// A sampler of the spline given by `coefficients` and the knots `xs`,
+    /// extrapolating outside of the knots according to `extrapolate`.
+    pub fn new(
+        coefficients: &'a [(f32, f32, f32, f32)],
+        xs: &'a [f32],
+        extrapolate: Extrapolate,
+    ) -> Result<Self, Error> {
+        check_spline(coefficients, xs)?;
+        Ok(Self {
+            coefficients,
+            xs,
+            extrapolate,
+            output_range: None,
+            segment: 0,
+        })
+    }
+
+    /// Saturate every sample to `[y_min, y_max]` as
+    /// [`eval_clamped`](crate::eval_clamped) does. Fails with
+    /// [`Error::InvalidRange`] unless `y_min` is below `y_max`.
+    pub fn with_output_range(mut self, y_min: f32, y_max: f32) -> Result<Self, Error> {
+        check_range(y_min, y_max)?;
+        self.output_range = Some((y_min, y_max));
+        Ok(self)
+    }
+
+    /// Evaluate the spline at global position x.
+    pub fn sample(&mut self, x: f32) -> Result<f32, Error> {
+        let y = self.eval(x)?;
+        Ok(match self.output_range {
+            Some((y_min, y_max)) => saturate(y, y_min, y_max),
+            None => y,
+        })
+    }
+
+    fn eval(&mut self, x: f32) -> Result<f32, Error> {
+        let (first, last) = (self.xs[0], self.xs[self.xs.len() - 1]);
+        let inside = x >= first && x <= last;
+        if !inside && self.extrapolate != Extrapolate::ExtendCubic && !x.is_nan() {
+            // Extrapolation does not search
+            let (polynomial, t) = locate(
+                |segment| self.coefficients[segment],
+                self.xs,
+                x,
+                self.extrapolate,
+            )?;
+            return Ok(eval_segment(polynomial, t));
+        }
+        if !self.contains(self.segment, x) {
+            self.segment = if self.contains(self.segment + 1, x) {
+                self.segment + 1
+            } else {
+                find_segment(self.xs, x)
+            };
+        }
+        let segment = self.segment;
+        Ok(eval_segment(
+            self.coefficients[segment],
+            x - self.xs[segment],
+        ))
+    }
+
+    /// Segment of the last query, where the next search starts.
+    pub fn segment(&self) -> usize {
+        self.segment
+    }
+
+    /// Whether x falls into the segment, as found by [`find_segment`], which
+    /// extends the first and last segment beyond the knots.
+    fn contains(&self, segment: usize, x: f32) -> bool {
+        let last = self.coefficients.len() - 1;
+        segment <= last
+            && (segment == 0 || self.xs[segment] <= x)
+            && (segment == last || x < self.xs[segment + 1])
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::eval;
+    use crate::fixture::{spline, XS};
+
+    #[test]
+    fn matches_eval_in_any_order() {
+        let coeffs = spline();
+        for extrapolate in [
+            Extrapolate::ExtendCubic,
+            Extrapolate::ClampToEndValue,
+            Extrapolate::LinearFromEndSlope,
+        ] {
+            let mut sampler = Sampler::new(&coeffs, &XS, extrapolate).unwrap();
+            let queries = [-1f32, 0.0, 0.5, 1.0, 2.7, 3.0, 4.9, 5.0, 6.0, 2.0, 0.1, 4.0];
+            for x in queries {
+                let expected = eval(&coeffs, &XS, x, extrapolate);
+                assert_eq!(expected, sampler.sample(x), "at {}", x);
+            }
+            assert_eq!(3, sampler.segment());
+        }
+        let mut sampler = Sampler::new(&coeffs, &XS, Extrapolate::ExtendCubic).unwrap();
+        assert!(sampler.sample(f32::NAN).unwrap().is_nan());
+    }
+
+    #[test]
+    fn out_of_range_and_invalid_input() {
+        let coeffs = spline();
+        let mut sampler = Sampler::new(&coeffs, &XS, Extrapolate::Error).unwrap();
+        assert_eq!(Err(Error::OutOfRange), sampler.sample(5.5));
+        assert_eq!(Ok(1.0), sampler.sample(0.0));
+        assert_eq!(
+            Err(Error::InvalidSliceLength),
+            Sampler::new(&coeffs, &XS[..4], Extrapolate::Error).map(|_| ())
+        );
+    }
+
+    #[test]
+    fn output_range_saturates() {
+        let coeffs = spline();
+        let mut sampler = Sampler::new(&coeffs, &XS, Extrapolate::LinearFromEndSlope)
+            .unwrap()
+            .with_output_range(0.5, 1.8)
+            .unwrap();
+        for x in [-1f32, 0.0, 1.0, 2.0, 2.7, 6.0] {
+            let expected = eval(&coeffs, &XS, x, Extrapolate::LinearFromEndSlope).unwrap();
+            assert_eq!(Ok(expected.clamp(0.5, 1.8)), sampler.sample(x));
+        }
+        assert_eq!(Ok(0.5), sampler.sample(f32::NAN));
+        assert_eq!(
+            Err(Error::InvalidRange),
+            Sampler::new(&coeffs, &XS, Extrapolate::Error)
+                .unwrap()
+                .with_output_range(1.0, f32::NAN)
+                .map(|_| ())
+        );
+    }
+}
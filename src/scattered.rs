@@ -0,0 +1,329 @@
+//! Interpolation of values at scattered points in the plane, e.g. calibration
+//! measurements which were not taken on a grid.
+//!
+//! The interpolant is a radial basis function expansion plus a linear
+//! polynomial,
+//!
+//! ```text
+//! s(x, y) = sum_i w_i φ(|(x, y) - p_i|) + c_0 + c_1 x + c_2 y,
+//! ```
+//!
+//! with the weights chosen so that it passes through every point and is
+//! orthogonal to linear polynomials, `sum_i w_i = sum_i w_i x_i = sum_i w_i
+//! y_i = 0`. With the thin plate kernel, this is the surface of least bending
+//! energy through the points, the 2D analog of the natural cubic spline.
+//!
+//! The weights and the polynomial are solved for together, from the saddle
+//! point system `[[A, P], [Pᵀ, 0]]` of the kernel matrix A and the columns
+//! 1, x, y of P, by Gaussian elimination with partial pivoting. The kernel
+//! matrix alone need not be invertible: with the thin plate kernel, it is only
+//! conditionally positive definite, and e.g. zero for points at unit distance.
+//! This takes O(N³) time and N² floats of stack, so it is meant for tens of
+//! points.
+
+use crate::Error;
+
+/// Radial basis kernels φ(r).
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum Kernel {
+    /// φ(r) = r² ln r, the thin plate spline
+    ThinPlate,
+    /// φ(r) = r³, the polyharmonic cubic, which is somewhat stiffer
+    Cubic,
+}
+
+impl Kernel {
+    /// φ as function of the squared distance.
+    fn eval(self, r2: f32) -> f32 {
+        match self {
+            Kernel::ThinPlate => {
+                if r2 > 0f32 {
+                    0.5f32 * r2 * libm::logf(r2)
+                } else {
+                    0f32
+                }
+            }
+            Kernel::Cubic => r2 * libm::sqrtf(r2),
+        }
+    }
+}
+
+/// Interpolation of the values at `N` scattered points.
+///
+/// ```
+/// use cubic_splinterpol::scattered::{Kernel, Scattered};
+///
+/// // Offsets measured at irregular (temperature, humidity) points
+/// let points = [(-10f32, 20f32), (0.0, 80.0), (15.0, 45.0), (30.0, 10.0), (40.0, 70.0)];
+/// let offsets = [0.8f32, 0.3, 0.0, -0.2, -0.6];
+/// let map = Scattered::new(&points, &offsets, Kernel::ThinPlate).unwrap();
+/// assert!((map.eval(15.0, 45.0) - 0.0).abs() < 1e-4);
+/// let between = map.eval(20.0, 40.0);
+/// assert!(between < 0.0 && between > -0.6);
+/// ```
+#[derive(Debug, Clone)]
+pub struct Scattered<const N: usize> {
+    points: [(f32, f32); N],
+    weights: [f32; N],
+    /// Constant, x and y coefficient of the polynomial
+    polynomial: [f32; 3],
+    kernel: Kernel,
+}
+
+impl<const N: usize> Scattered<N> {
+    /// Solve for the interpolant through `values` at `points`. At least 3
+    /// points are needed, and they must not all lie on a line. Coincident
+    /// points fail with [`Error::SingularSystem`].
+    pub fn new(points: &[(f32, f32); N], values: &[f32; N], kernel: Kernel) -> Result<Self, Error> {
+        if N < 3 {
+            return Err(Error::InvalidSliceLength);
+        }
+        let mut system = Saddle {
+            top: [Row::default(); N],
+            bottom: [Row::default(); 3],
+        };
+        for (i, row) in system.top.iter_mut().enumerate() {
+            let (x, y) = points[i];
+            for (entry, (u, v)) in row.kernel.iter_mut().zip(points) {
+                *entry = kernel.eval((x - u) * (x - u) + (y - v) * (y - v));
+            }
+            row.polynomial = [1f32, x, y];
+            row.rhs = values[i];
+        }
+        for (k, row) in system.bottom.iter_mut().enumerate() {
+            for (entry, top) in row.kernel.iter_mut().zip(&system.top) {
+                *entry = top.polynomial[k];
+            }
+        }
+        system.solve()?;
+
+        let mut weights = [0f32; N];
+        for (weight, row) in weights.iter_mut().zip(&system.top) {
+            *weight = row.rhs;
+        }
+        let polynomial = system.bottom.map(|row| row.rhs);
+        Ok(Self {
+            points: *points,
+            weights,
+            polynomial,
+            kernel,
+        })
+    }
+
+    /// Interpolated value at `(x, y)`.
+    pub fn eval(&self, x: f32, y: f32) -> f32 {
+        let [c0, c1, c2] = self.polynomial;
+        let mut sum = c0 + c1 * x + c2 * y;
+        for ((u, v), weight) in self.points.iter().zip(&self.weights) {
+            sum += weight * self.kernel.eval((x - u) * (x - u) + (y - v) * (y - v));
+        }
+        sum
+    }
+}
+
+/// One row of the saddle point system: its entries in the N columns of the
+/// weights and the 3 of the polynomial, its right hand side, and its largest
+/// entry as given.
+#[derive(Debug, Clone, Copy)]
+struct Row<const N: usize> {
+    kernel: [f32; N],
+    polynomial: [f32; 3],
+    rhs: f32,
+    scale: f32,
+}
+
+impl<const N: usize> Default for Row<N> {
+    fn default() -> Self {
+        Self {
+            kernel: [0f32; N],
+            polynomial: [0f32; 3],
+            rhs: 0f32,
+            scale: 0f32,
+        }
+    }
+}
+
+impl<const N: usize> Row<N> {
+    fn entry(&self, column: usize) -> f32 {
+        if column < N {
+            self.kernel[column]
+        } else {
+            self.polynomial[column - N]
+        }
+    }
+
+    fn entry_mut(&mut self, column: usize) -> &mut f32 {
+        if column < N {
+            &mut self.kernel[column]
+        } else {
+            &mut self.polynomial[column - N]
+        }
+    }
+}
+
+/// The saddle point system `[[A, P], [Pᵀ, 0]]`, N + 3 rows as the N of the
+/// points and the 3 of the polynomial.
+struct Saddle<const N: usize> {
+    top: [Row<N>; N],
+    bottom: [Row<N>; 3],
+}
+
+impl<const N: usize> Saddle<N> {
+    fn row(&self, i: usize) -> &Row<N> {
+        if i < N {
+            &self.top[i]
+        } else {
+            &self.bottom[i - N]
+        }
+    }
+
+    fn row_mut(&mut self, i: usize) -> &mut Row<N> {
+        if i < N {
+            &mut self.top[i]
+        } else {
+            &mut self.bottom[i - N]
+        }
+    }
+
+    fn swap(&mut self, i: usize, j: usize) {
+        let (i, j) = (i.min(j), i.max(j));
+        if j < N {
+            self.top.swap(i, j);
+        } else if i >= N {
+            self.bottom.swap(i - N, j - N);
+        } else {
+            core::mem::swap(&mut self.top[i], &mut self.bottom[j - N]);
+        }
+    }
+
+    /// Solve by Gaussian elimination with partial pivoting, overwriting the
+    /// right hand sides with the solution and the entries with scratch.
+    fn solve(&mut self) -> Result<(), Error> {
+        let size = N + 3;
+        // Pivots are compared to their rows as given, since the entries of the
+        // kernel and the polynomial part can differ by orders of magnitude
+        for i in 0..size {
+            let row = self.row_mut(i);
+            row.scale = (0..size).fold(0f32, |max, column| max.max(row.entry(column).abs()));
+        }
+        for column in 0..size {
+            let pivot_row = (column..size)
+                .max_by(|i, j| {
+                    let (a, b) = (self.row(*i).entry(column), self.row(*j).entry(column));
+                    a.abs().total_cmp(&b.abs())
+                })
+                .unwrap_or(column);
+            self.swap(column, pivot_row);
+            let pivot = *self.row(column);
+            let epsilon = pivot.scale * size as f32 * f32::EPSILON;
+            if pivot.entry(column).is_nan() || pivot.entry(column).abs() <= epsilon {
+                return Err(Error::SingularSystem { row: column });
+            }
+            for i in column + 1..size {
+                let row = self.row_mut(i);
+                let factor = row.entry(column) / pivot.entry(column);
+                for k in column..size {
+                    *row.entry_mut(k) -= factor * pivot.entry(k);
+                }
+                row.rhs -= factor * pivot.rhs;
+            }
+        }
+        for column in (0..size).rev() {
+            let mut value = self.row(column).rhs;
+            for k in column + 1..size {
+                value -= self.row(column).entry(k) * self.row(k).rhs;
+            }
+            let row = self.row_mut(column);
+            row.rhs = value / row.entry(column);
+        }
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const POINTS: [(f32, f32); 7] = [
+        (0.0, 0.0),
+        (1.0, 0.2),
+        (0.3, 1.1),
+        (1.4, 1.3),
+        (0.7, 0.6),
+        (2.0, 0.4),
+        (1.8, 2.0),
+    ];
+
+    #[test]
+    fn passes_through_points() {
+        let values = [1f32, -0.5, 2.0, 0.0, 0.7, 1.2, -1.0];
+        for kernel in [Kernel::ThinPlate, Kernel::Cubic] {
+            let map = Scattered::new(&POINTS, &values, kernel).unwrap();
+            for ((x, y), value) in POINTS.iter().zip(values) {
+                assert!((map.eval(*x, *y) - value).abs() < 1e-3, "{:?}", kernel);
+            }
+        }
+    }
+
+    #[test]
+    fn reproduces_linear_functions() {
+        let f = |x: f32, y: f32| 0.5 - 2f32 * x + 3f32 * y;
+        let mut values = [0f32; 7];
+        for (value, (x, y)) in values.iter_mut().zip(POINTS) {
+            *value = f(x, y);
+        }
+        let map = Scattered::new(&POINTS, &values, Kernel::ThinPlate).unwrap();
+        for weight in map.weights {
+            assert!(weight.abs() < 1e-3);
+        }
+        for (x, y) in [(0.5f32, 0.5f32), (-1.0, 3.0), (1.9, 1.0)] {
+            assert!((map.eval(x, y) - f(x, y)).abs() < 1e-3);
+        }
+    }
+
+    #[test]
+    fn singular_kernel_matrix() {
+        // All at distance 1, where the thin plate kernel is zero
+        let points = [(0f32, 0f32), (1.0, 0.0), (0.0, 1.0)];
+        let f = |x: f32, y: f32| 1f32 + x + 2f32 * y;
+        let map = Scattered::new(&points, &[1f32, 2.0, 3.0], Kernel::ThinPlate).unwrap();
+        assert_eq!([1.0, 1.0, 2.0], map.polynomial);
+        assert_eq!([0.0; 3], map.weights);
+        for (x, y) in [(0.5f32, 0.5f32), (-1.0, 3.0), (2.0, -0.5)] {
+            assert_eq!(f(x, y), map.eval(x, y));
+        }
+
+        // A unit grid, whose kernel matrix is singular as well
+        let grid = [(0f32, 0f32), (1.0, 0.0), (0.0, 1.0), (1.0, 1.0)];
+        let values = grid.map(|(x, y)| f(x, y));
+        let map = Scattered::new(&grid, &values, Kernel::ThinPlate).unwrap();
+        for (x, y) in [(0.25f32, 0.75f32), (3.0, -2.0)] {
+            assert!((map.eval(x, y) - f(x, y)).abs() < 1e-5);
+        }
+    }
+
+    #[test]
+    fn rejects_degenerate_points() {
+        let values = [0f32, 1.0, 2.0];
+        assert!(matches!(
+            Scattered::new(
+                &[(0f32, 0f32), (1.0, 1.0), (2.0, 2.0)],
+                &values,
+                Kernel::Cubic
+            ),
+            Err(Error::SingularSystem { .. })
+        ));
+        assert!(matches!(
+            Scattered::new(
+                &[(0f32, 0f32), (1.0, 0.0), (1.0, 0.0)],
+                &values,
+                Kernel::ThinPlate
+            ),
+            Err(Error::SingularSystem { .. })
+        ));
+        assert_eq!(
+            Err(Error::InvalidSliceLength),
+            Scattered::new(&[(0f32, 0f32), (1.0, 0.0)], &[0f32; 2], Kernel::Cubic).map(|_| ())
+        );
+    }
+}
@@ -0,0 +1,120 @@
+use crate::eval_segment;
+
+/// Coefficients of one spline segment, the polynomial
+/// `a + b * t + c * t^2 + d * t^3` in the position t relative to the start of
+/// the segment.
+///
+/// The layout is that of four consecutive `f32` in the order `a, b, c, d`, so
+/// tables can be loaded from flash or shared with C without depending on the
+/// layout of a tuple. Converts to and from the `(a, b, c, d)` tuples used by
+/// the rest of the crate.
+///
+/// With the `bytemuck` or `zerocopy` feature, `Segment` implements the traits
+/// of those crates for plain old data, so a table can be cast from a suitably
+/// aligned byte slice, e.g. a DMA buffer or flash page, without copying and
+/// without unsafe code.
+#[repr(C)]
+#[derive(Debug, Clone, Copy, PartialEq, Default)]
+#[cfg_attr(feature = "bytemuck", derive(bytemuck::Pod, bytemuck::Zeroable))]
+#[cfg_attr(
+    feature = "zerocopy",
+    derive(
+        zerocopy::FromBytes,
+        zerocopy::IntoBytes,
+        zerocopy::Immutable,
+        zerocopy::KnownLayout
+    )
+)]
+pub struct Segment {
+    /// Constant coefficient, the value at the start of the segment
+    pub a: f32,
+    /// Linear coefficient, the slope at the start of the segment
+    pub b: f32,
+    /// Quadratic coefficient, half the curvature at the start of the segment
+    pub c: f32,
+    /// Cubic coefficient
+    pub d: f32,
+}
+
+impl Segment {
+    /// A segment with the given coefficients.
+    pub const fn new(a: f32, b: f32, c: f32, d: f32) -> Self {
+        Self { a, b, c, d }
+    }
+
+    /// The coefficients as an `(a, b, c, d)` tuple.
+    pub const fn to_tuple(self) -> (f32, f32, f32, f32) {
+        (self.a, self.b, self.c, self.d)
+    }
+
+    /// Evaluate the polynomial at position t relative to the start of the
+    /// segment.
+    pub const fn eval(&self, t: f32) -> f32 {
+        eval_segment(self.to_tuple(), t)
+    }
+}
+
+impl From<(f32, f32, f32, f32)> for Segment {
+    fn from((a, b, c, d): (f32, f32, f32, f32)) -> Self {
+        Self { a, b, c, d }
+    }
+}
+
+impl From<Segment> for (f32, f32, f32, f32) {
+    fn from(segment: Segment) -> Self {
+        segment.to_tuple()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn segment_matches_tuple() {
+        let tuple = (1f32, -2f32, 0.5f32, 0.25f32);
+        let segment = Segment::from(tuple);
+        assert_eq!(Segment::new(1.0, -2.0, 0.5, 0.25), segment);
+        assert_eq!(tuple, segment.into());
+        for i in 0..10 {
+            let t = i as f32 * 0.3;
+            assert_eq!(eval_segment(tuple, t), segment.eval(t));
+        }
+        assert_eq!(16, core::mem::size_of::<Segment>());
+    }
+
+    #[cfg(feature = "bytemuck")]
+    #[test]
+    fn cast_with_bytemuck() {
+        let table = [
+            Segment::new(1.0, 2.0, 3.0, 4.0),
+            Segment::new(5.0, 6.0, 7.0, 8.0),
+        ];
+        let bytes: &[u8] = bytemuck::cast_slice(&table);
+        assert_eq!(32, bytes.len());
+        let segments: &[Segment] = bytemuck::cast_slice(bytes);
+        assert_eq!(table, segments);
+        assert_eq!(
+            table[1],
+            bytemuck::cast::<[f32; 4], Segment>([5.0, 6.0, 7.0, 8.0])
+        );
+    }
+
+    #[cfg(feature = "zerocopy")]
+    #[test]
+    fn cast_with_zerocopy() {
+        use zerocopy::{FromBytes, IntoBytes};
+
+        let table = [
+            Segment::new(1.0, 2.0, 3.0, 4.0),
+            Segment::new(5.0, 6.0, 7.0, 8.0),
+        ];
+        let bytes = table.as_bytes();
+        assert_eq!(32, bytes.len());
+        let segments = <[Segment]>::ref_from_bytes(bytes).unwrap();
+        assert_eq!(table, segments);
+        let (segment, rest) = Segment::read_from_prefix(&bytes[16..]).unwrap();
+        assert_eq!(table[1], segment);
+        assert!(rest.is_empty());
+    }
+}
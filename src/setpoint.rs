@@ -0,0 +1,168 @@
+use crate::{Error, Extrapolate, Sampler};
+
+/// Setpoints along a spline over time, e.g. the reference of a motor control
+/// loop, advanced by the elapsed time of every tick.
+///
+/// The clock starts at the first knot. Beyond the last knot, the setpoint
+/// follows `extrapolate`; with [`Extrapolate::Error`], the clock stops at the
+/// last knot and the setpoint holds there. Lookups walk forward from the
+/// segment of the last tick as in [`Sampler`], so a tick takes constant time.
+///
+/// ```
+/// use cubic_splinterpol::{splinterpol, Extrapolate, SetpointGenerator};
+///
+/// let xs = [0f32, 0.5, 1.0];
+/// let mut coefficients = [(0f32, 0f32, 0f32, 0f32); 2];
+/// splinterpol(&xs, &[0.0, 100.0, 50.0], &mut coefficients).unwrap();
+///
+/// let mut setpoints = SetpointGenerator::new(&coefficients, &xs, Extrapolate::Error).unwrap();
+/// // Every 1 ms in the control ISR
+/// for _ in 0..500 {
+///     let rpm = setpoints.next(0.001);
+///     assert!((0.0..=110.0).contains(&rpm));
+/// }
+/// assert!(!setpoints.finished());
+/// for _ in 0..600 {
+///     setpoints.next(0.001);
+/// }
+/// assert!(setpoints.finished());
+/// assert_eq!(50.0, setpoints.next(0.001));
+/// ```
+#[derive(Debug, Clone)]
+pub struct SetpointGenerator<'a> {
+    sampler: Sampler<'a>,
+    time: f32,
+    start: f32,
+    end: f32,
+    stop_at_end: bool,
+}
+
+impl<'a> SetpointGenerator<'a> {
+    /// A generator along the spline given by `coefficients` and the knots
+    /// `xs`, extrapolating beyond the last knot according to `extrapolate`.
+    pub fn new(
+        coefficients: &'a [(f32, f32, f32, f32)],
+        xs: &'a [f32],
+        extrapolate: Extrapolate,
+    ) -> Result<Self, Error> {
+        let stop_at_end = extrapolate == Extrapolate::Error;
+        let extrapolate = if stop_at_end {
+            Extrapolate::ClampToEndValue
+        } else {
+            extrapolate
+        };
+        let sampler = Sampler::new(coefficients, xs, extrapolate)?;
+        Ok(Self {
+            sampler,
+            time: xs[0],
+            start: xs[0],
+            end: xs[xs.len() - 1],
+            stop_at_end,
+        })
+    }
+
+    /// Saturate every setpoint to `[y_min, y_max]` as
+    /// [`eval_clamped`](crate::eval_clamped) does. Fails with
+    /// [`Error::InvalidRange`] unless `y_min` is below `y_max`.
+    pub fn with_output_range(mut self, y_min: f32, y_max: f32) -> Result<Self, Error> {
+        self.sampler = self.sampler.with_output_range(y_min, y_max)?;
+        Ok(self)
+    }
+
+    /// Advance the clock by `dt` and return the setpoint at the new time. A
+    /// NaN `dt` leaves the clock as it is.
+    pub fn next(&mut self, dt: f32) -> f32 {
+        if !dt.is_nan() {
+            self.time += dt;
+        }
+        if self.stop_at_end && self.time > self.end {
+            self.time = self.end;
+        }
+        // Extrapolation never fails but with Extrapolate::Error, ruled out
+        self.sampler.sample(self.time).unwrap_or(f32::NAN)
+    }
+
+    /// The current time of the clock.
+    pub fn time(&self) -> f32 {
+        self.time
+    }
+
+    /// Set the clock to `time`, e.g. back to the start to repeat the profile.
+    pub fn seek(&mut self, time: f32) {
+        self.time = time;
+    }
+
+    /// Set the clock back to the first knot.
+    pub fn reset(&mut self) {
+        self.time = self.start;
+    }
+
+    /// Whether the clock has reached the last knot.
+    pub fn finished(&self) -> bool {
+        self.time >= self.end
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::eval;
+
+    use crate::fixture::natural;
+
+    // Starting at 1, so that the clock does not start at zero
+    const XS: [f32; 4] = [1f32, 2.0, 2.5, 4.0];
+
+    fn spline() -> [(f32, f32, f32, f32); 3] {
+        natural(&XS, &[0f32, 2.0, 1.0, 3.0])
+    }
+
+    #[test]
+    fn follows_spline_over_time() {
+        let coeffs = spline();
+        let mut setpoints =
+            SetpointGenerator::new(&coeffs, &XS, Extrapolate::LinearFromEndSlope).unwrap();
+        assert_eq!(1.0, setpoints.time());
+        for tick in 1..=40 {
+            let y = setpoints.next(0.1);
+            let x = setpoints.time();
+            assert!((x - (1f32 + tick as f32 * 0.1)).abs() < 1e-5);
+            let expected = eval(&coeffs, &XS, x, Extrapolate::LinearFromEndSlope).unwrap();
+            assert_eq!(expected, y);
+        }
+        // Keeps going beyond the end along the tangent
+        assert!(setpoints.finished());
+        assert!(setpoints.next(0.1) > 3.0);
+        assert!(setpoints.time() > 4.0);
+
+        setpoints.reset();
+        assert_eq!(2.0, setpoints.next(1.0));
+        setpoints.seek(2.5);
+        assert_eq!(2.5, setpoints.time());
+        assert_eq!(1.0, setpoints.next(f32::NAN));
+    }
+
+    #[test]
+    fn holds_and_saturates() {
+        let coeffs = spline();
+        let mut setpoints = SetpointGenerator::new(&coeffs, &XS, Extrapolate::Error)
+            .unwrap()
+            .with_output_range(0.5, 2.5)
+            .unwrap();
+        assert_eq!(0.5, setpoints.next(0.0));
+        assert_eq!(2.5, setpoints.next(10.0));
+        assert_eq!(4.0, setpoints.time());
+        assert!(setpoints.finished());
+        assert_eq!(
+            Err(Error::InvalidRange),
+            SetpointGenerator::new(&coeffs, &XS, Extrapolate::Error)
+                .unwrap()
+                .with_output_range(1.0, 1.0)
+                .map(|_| ())
+        );
+        assert_eq!(
+            Err(Error::InvalidSliceLength),
+            SetpointGenerator::new(&coeffs, &XS[..3], Extrapolate::Error).map(|_| ())
+        );
+    }
+}
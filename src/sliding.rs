@@ -0,0 +1,217 @@
+use crate::extrapolate::locate;
+use crate::{check_increasing, eval_segment, Error, Extrapolate};
+
+/// A natural spline through the last `N` points of a stream.
+///
+/// Every [`push`](SlidingSpline::push) drops the oldest point once the window
+/// is full. Instead of assembling and solving the whole system from scratch,
+/// the system entries of the retained points are shifted and only the entries
+/// involving the new point are computed. If the segment lengths in the window
+/// are exactly the same as for the previous solve, as with samples taken at a
+/// fixed period on an integer time base, the factorization of the tridiagonal
+/// matrix is reused as well and only the right hand side is swept.
+#[derive(Debug, Clone)]
+pub struct SlidingSpline<const N: usize> {
+    xs: [f32; N],
+    ys: [f32; N],
+    len: usize,
+    /// Segment lengths h_i
+    h: [f32; N],
+    /// Divided differences (y_{i+1} - y_i) / h_i
+    slopes: [f32; N],
+    /// Segment lengths the factorization below was computed for
+    factor_h: [f32; N],
+    /// Eliminated main diagonal of the interior system
+    factor_main: [f32; N],
+    /// Elimination multipliers of the interior system
+    factor_lower: [f32; N],
+    factored: bool,
+    coefficients: [(f32, f32, f32, f32); N],
+}
+
+impl<const N: usize> Default for SlidingSpline<N> {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl<const N: usize> SlidingSpline<N> {
+    /// An empty window. `N` must be at least 3.
+    pub fn new() -> Self {
+        Self {
+            xs: [0f32; N],
+            ys: [0f32; N],
+            len: 0,
+            h: [0f32; N],
+            slopes: [0f32; N],
+            factor_h: [0f32; N],
+            factor_main: [0f32; N],
+            factor_lower: [0f32; N],
+            factored: false,
+            coefficients: [(0f32, 0f32, 0f32, 0f32); N],
+        }
+    }
+
+    /// Append a point, dropping the oldest one if the window is full. x must
+    /// be greater than the x of the previous point. Once the window is full,
+    /// the spline is updated on every push.
+    pub fn push(&mut self, x: f32, y: f32) -> Result<(), Error> {
+        if N < 3 {
+            return Err(Error::InvalidSliceLength);
+        }
+        if self.len > 0 && check_increasing(&[self.xs[self.len - 1], x]).is_err() {
+            return Err(Error::NonIncreasingKnots { index: self.len });
+        }
+        if self.len == N {
+            self.xs.copy_within(1.., 0);
+            self.ys.copy_within(1.., 0);
+            self.h.copy_within(1..N - 1, 0);
+            self.slopes.copy_within(1..N - 1, 0);
+            self.len -= 1;
+        }
+        self.xs[self.len] = x;
+        self.ys[self.len] = y;
+        if self.len > 0 {
+            let i = self.len - 1;
+            self.h[i] = self.xs[i + 1] - self.xs[i];
+            self.slopes[i] = (self.ys[i + 1] - self.ys[i]) / self.h[i];
+        }
+        self.len += 1;
+        if self.len == N {
+            self.solve();
+        }
+        Ok(())
+    }
+
+    /// Whether the window is full and the spline is available.
+    pub fn is_ready(&self) -> bool {
+        self.len == N
+    }
+
+    /// The knots currently in the window, oldest first.
+    pub fn xs(&self) -> &[f32] {
+        &self.xs[..self.len]
+    }
+
+    /// The coefficients of the N-1 segments, once the window is full.
+    pub fn coefficients(&self) -> Option<&[(f32, f32, f32, f32)]> {
+        if self.is_ready() {
+            Some(&self.coefficients[..N - 1])
+        } else {
+            None
+        }
+    }
+
+    /// Evaluate the spline at x, once the window is full. Positions outside
+    /// of the window are evaluated on the first or last polynomial.
+    pub fn eval(&self, x: f32) -> Option<f32> {
+        self.eval_extrapolated(x, Extrapolate::ExtendCubic).ok()
+    }
+
+    /// Like [`eval`](SlidingSpline::eval), but positions outside of the window
+    /// are extrapolated according to `extrapolate`. Fails with
+    /// [`Error::EmptyInput`] until the window is full.
+    pub fn eval_extrapolated(&self, x: f32, extrapolate: Extrapolate) -> Result<f32, Error> {
+        let coefficients = self.coefficients().ok_or(Error::EmptyInput)?;
+        let (polynomial, t) = locate(|segment| coefficients[segment], &self.xs, x, extrapolate)?;
+        Ok(eval_segment(polynomial, t))
+    }
+
+    /// Factorize the interior system for the current segment lengths.
+    fn factorize(&mut self) {
+        let n = N - 2;
+        self.factor_main[0] = 2f32 * (self.h[0] + self.h[1]);
+        for k in 1..n {
+            let sub = self.h[k];
+            self.factor_lower[k] = sub / self.factor_main[k - 1];
+            self.factor_main[k] = 2f32 * (self.h[k] + self.h[k + 1]) - self.factor_lower[k] * sub;
+        }
+        self.factor_h = self.h;
+        self.factored = true;
+    }
+
+    fn solve(&mut self) {
+        if !self.factored || self.factor_h[..N - 1] != self.h[..N - 1] {
+            self.factorize();
+        }
+        let n = N - 2;
+        // Forward sweep of the right hand side, stored in c[1..=n]
+        let mut c = [0f32; N];
+        c[1] = 3f32 * (self.slopes[1] - self.slopes[0]);
+        for k in 1..n {
+            let r = 3f32 * (self.slopes[k + 1] - self.slopes[k]);
+            c[k + 1] = r - self.factor_lower[k] * c[k];
+        }
+        // Back substitution
+        c[n] /= self.factor_main[n - 1];
+        for k in (0..n - 1).rev() {
+            c[k + 1] = (c[k + 1] - self.h[k + 1] * c[k + 2]) / self.factor_main[k];
+        }
+        for i in 0..N - 1 {
+            let h = self.h[i];
+            let b = self.slopes[i] - (2f32 * c[i] + c[i + 1]) / 3f32 * h;
+            let d = (c[i + 1] - c[i]) / (3f32 * h);
+            self.coefficients[i] = (self.ys[i], b, c[i], d);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use core::convert::TryInto;
+
+    #[test]
+    fn sliding_matches_full_solve() {
+        let xs = [
+            0.5f32, 1f32, 2f32, 3f32, 4.5f32, 5f32, 6f32, 7f32, 8f32, 9f32, 10f32, 11.5f32,
+        ];
+        let ys = [
+            0f32, 0f32, 1f32, 2f32, 4f32, 7f32, 9f32, 10f32, 8f32, 6f32, 3f32, 2f32,
+        ];
+        let mut sliding = SlidingSpline::<8>::new();
+        for (i, (x, y)) in xs.iter().zip(&ys).enumerate() {
+            sliding.push(*x, *y).unwrap();
+            if i < 7 {
+                assert!(sliding.coefficients().is_none());
+                continue;
+            }
+            let start = i - 7;
+            let window_xs: [f32; 8] = xs[start..=i].try_into().unwrap();
+            let window_ys: [f32; 8] = ys[start..=i].try_into().unwrap();
+            let mut expected = [(0f32, 0f32, 0f32, 0f32); 7];
+            crate::splinterpol::<8>(&window_xs, &window_ys, &mut expected).unwrap();
+            assert_eq!(&window_xs, sliding.xs());
+            for (c, e) in sliding.coefficients().unwrap().iter().zip(&expected) {
+                assert!((c.1 - e.1).abs() < 0.0001);
+                assert!((c.2 - e.2).abs() < 0.0001);
+                assert!((c.3 - e.3).abs() < 0.0001);
+            }
+        }
+    }
+
+    #[test]
+    fn sliding_reuses_uniform_factorization() {
+        let mut sliding = SlidingSpline::<5>::new();
+        for tick in 0..5 {
+            sliding.push(tick as f32, (tick * tick) as f32).unwrap();
+        }
+        assert!((sliding.eval(4.0).unwrap() - 16.0).abs() < 0.0001);
+
+        // Equal spacing: the (deliberately tampered) factorization is kept
+        sliding.factor_main[2] += 1.0;
+        let tampered = sliding.factor_main;
+        sliding.push(5.0, 25.0).unwrap();
+        assert_eq!(tampered, sliding.factor_main);
+
+        // Different spacing: the factorization is recomputed
+        sliding.push(7.5, 56.25).unwrap();
+        assert_ne!(tampered, sliding.factor_main);
+        assert!((sliding.eval(7.5).unwrap() - 56.25).abs() < 0.0001);
+
+        assert_eq!(
+            Err(Error::NonIncreasingKnots { index: 5 }),
+            sliding.push(7.5, 0.0)
+        );
+    }
+}
@@ -0,0 +1,433 @@
+use crate::{check_increasing, eval_segment, Error};
+
+/// Smallest weight [`robust_smoothing_spline`] assigns, standing in for zero,
+/// which [`smoothing_spline`] does not accept.
+const MIN_ROBUST_WEIGHT: f32 = 1e-6;
+
+/// Iterations of [`robust_smoothing_spline`] stop early once no weight
+/// changes by more than this.
+const ROBUST_WEIGHT_TOLERANCE: f32 = 1e-3;
+
+/// Scales the median absolute deviation to the standard deviation of normally
+/// distributed residuals.
+const MAD_TO_SIGMA: f32 = 1.4826;
+
+/// Weighting of residuals in [`robust_smoothing_spline`]. Thresholds are in
+/// units of the robust standard deviation of the residuals, estimated from
+/// their median absolute deviation.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum RobustLoss {
+    /// Residuals up to `k` have full weight, larger ones are down-weighted in
+    /// inverse proportion to their size. `k = 1.345` is the usual choice.
+    Huber {
+        /// Threshold
+        k: f32,
+    },
+    /// Residuals beyond `c` are ignored, smaller ones are smoothly
+    /// down-weighted as they approach `c`. `c = 4.685` is the usual choice.
+    Tukey {
+        /// Threshold
+        c: f32,
+    },
+}
+
+impl RobustLoss {
+    /// Weight of a residual, given the robust scale of all residuals.
+    fn weight(&self, residual: f32, scale: f32) -> f32 {
+        let weight = match *self {
+            RobustLoss::Huber { k } => {
+                let limit = k * scale;
+                if residual.abs() <= limit {
+                    1f32
+                } else {
+                    limit / residual.abs()
+                }
+            }
+            RobustLoss::Tukey { c } => {
+                let u = residual / (c * scale);
+                if u.abs() < 1f32 {
+                    let v = 1f32 - u * u;
+                    v * v
+                } else {
+                    0f32
+                }
+            }
+        };
+        if weight.is_nan() {
+            // Zero residual at zero scale
+            1f32
+        } else {
+            weight.max(MIN_ROBUST_WEIGHT)
+        }
+    }
+}
+
+/// Calculate the coefficients of the cubic smoothing spline through xs and ys
+/// of same length N, which need not pass through the points exactly.
+///
+/// Among all functions f with continuous second derivative, the smoothing
+/// spline minimizes
+///
+/// `sum(weights[i] * (ys[i] - f(xs[i]))^2) + lambda * integral(f''(x)^2)`
+///
+/// so it trades closeness to the data against roughness. With `lambda` zero,
+/// it is the natural interpolating spline, and as `lambda` grows, it tends to
+/// the weighted least-squares line through the points.
+///
+/// `weights` default to 1 for every point. Points of higher weight are
+/// followed more closely, and a weight of `f32::INFINITY` pins the spline to
+/// its point, e.g. for a trusted calibration point among noisy ones. Weights
+/// must be strictly positive.
+///
+/// The values at the knots are found via a symmetric pentadiagonal system in
+/// the second derivatives at the interior knots (Reinsch's algorithm), which is
+/// solved by an LDL^T factorization in time linear in N.
+///
+/// ```
+/// use cubic_splinterpol::smoothing_spline;
+///
+/// let xs = [0f32, 1.0, 2.0, 3.0, 4.0];
+/// let noisy = [0.1f32, 0.9, 2.2, 2.9, 4.1];
+/// let weights = [f32::INFINITY, 1.0, 1.0, 1.0, 1.0];
+/// let mut coefficients = [(0f32, 0f32, 0f32, 0f32); 4];
+/// smoothing_spline(&xs, &noisy, Some(&weights), 10.0, &mut coefficients).unwrap();
+/// // Pinned to the first point
+/// assert_eq!(0.1, coefficients[0].0);
+/// ```
+pub fn smoothing_spline<const N: usize>(
+    xs: &[f32; N],
+    ys: &[f32; N],
+    weights: Option<&[f32; N]>,
+    lambda: f32,
+    coefficients: &mut [(f32, f32, f32, f32)],
+) -> Result<(), Error> {
+    if N < 3 || coefficients.len() < N - 1 {
+        return Err(Error::InvalidSliceLength);
+    }
+    check_increasing(xs)?;
+    if lambda.is_nan() || lambda < 0f32 {
+        return Err(Error::InvalidLimit);
+    }
+    // Reciprocal weights sigma = 1/w
+    let mut sigma = [1f32; N];
+    if let Some(weights) = weights {
+        for (sigma, weight) in sigma.iter_mut().zip(weights) {
+            if weight.is_nan() || *weight <= 0f32 {
+                return Err(Error::InvalidLimit);
+            }
+            *sigma = 1f32 / weight;
+        }
+    }
+
+    let h = |i: usize| xs[i + 1] - xs[i];
+    let inv_h = |i: usize| 1f32 / h(i);
+    let slope = |i: usize| (ys[i + 1] - ys[i]) * inv_h(i);
+
+    // Interior unknowns gamma_1..gamma_{N-2}, stored at 0..N-2, of the system
+    // (R + lambda * Q^T W^-1 Q) gamma = Q^T y in its three upper diagonals
+    let m = N - 2;
+    let mut main = [0f32; N];
+    let mut first = [0f32; N];
+    let mut second = [0f32; N];
+    let mut r = [0f32; N];
+    for k in 0..m {
+        let j = k + 1;
+        let outer = inv_h(j - 1) + inv_h(j);
+        main[k] = (h(j - 1) + h(j)) / 3f32
+            + lambda
+                * (sigma[j - 1] * inv_h(j - 1) * inv_h(j - 1)
+                    + sigma[j] * outer * outer
+                    + sigma[j + 1] * inv_h(j) * inv_h(j));
+        if k + 1 < m {
+            let next_outer = inv_h(j) + inv_h(j + 1);
+            first[k] =
+                h(j) / 6f32 - lambda * inv_h(j) * (sigma[j] * outer + sigma[j + 1] * next_outer);
+        }
+        if k + 2 < m {
+            second[k] = lambda * sigma[j + 1] * inv_h(j) * inv_h(j + 1);
+        }
+        r[k] = slope(j) - slope(j - 1);
+    }
+
+    let mut gamma = [0f32; N];
+    pentadiagonal_symmetric(
+        &mut main[..m],
+        &mut first[..m],
+        &mut second[..m],
+        &mut r[..m],
+        &mut gamma[1..N - 1],
+    )?;
+
+    // Smoothed values g = y - lambda * W^-1 Q gamma
+    let mut g = [0f32; N];
+    for i in 0..N {
+        let mut q_gamma = 0f32;
+        if i > 0 {
+            q_gamma += gamma[i - 1] * inv_h(i - 1);
+            q_gamma -= gamma[i] * inv_h(i - 1);
+        }
+        if i < N - 1 {
+            q_gamma -= gamma[i] * inv_h(i);
+            q_gamma += gamma[i + 1] * inv_h(i);
+        }
+        g[i] = ys[i] - lambda * sigma[i] * q_gamma;
+    }
+
+    for i in 0..N - 1 {
+        coefficients[i] = (
+            g[i],
+            (g[i + 1] - g[i]) * inv_h(i) - h(i) * (2f32 * gamma[i] + gamma[i + 1]) / 6f32,
+            gamma[i] / 2f32,
+            (gamma[i + 1] - gamma[i]) * inv_h(i) / 6f32,
+        );
+    }
+    Ok(())
+}
+
+/// Like [`smoothing_spline`], but with weights found by iteratively
+/// reweighted least squares, so that outliers, e.g. a single corrupted sample
+/// in a calibration run, barely influence the curve.
+///
+/// Starting from equal weights, every iteration fits the smoothing spline,
+/// estimates the scale of the residuals from their median absolute deviation,
+/// and weights every point according to `loss`. This stops after at most
+/// `max_iterations` refits, or once the weights settle, whichever comes
+/// first, so the run time is bounded. `lambda` must be strictly positive, as an
+/// interpolating spline leaves no residuals to judge the points by.
+///
+/// The final weights are written to `weights`, where outliers stand out with
+/// weights near zero. Returns the number of refits done.
+pub fn robust_smoothing_spline<const N: usize>(
+    xs: &[f32; N],
+    ys: &[f32; N],
+    lambda: f32,
+    loss: RobustLoss,
+    max_iterations: usize,
+    coefficients: &mut [(f32, f32, f32, f32)],
+    weights: &mut [f32; N],
+) -> Result<usize, Error> {
+    if lambda.is_nan() || lambda <= 0f32 {
+        return Err(Error::InvalidLimit);
+    }
+    let threshold = match loss {
+        RobustLoss::Huber { k } => k,
+        RobustLoss::Tukey { c } => c,
+    };
+    if threshold.is_nan() || threshold <= 0f32 {
+        return Err(Error::InvalidLimit);
+    }
+    *weights = [1f32; N];
+    smoothing_spline(xs, ys, None, lambda, coefficients)?;
+    for iteration in 0..max_iterations {
+        let mut residuals = [0f32; N];
+        for i in 0..N - 1 {
+            residuals[i] = ys[i] - coefficients[i].0;
+        }
+        residuals[N - 1] = ys[N - 1] - eval_segment(coefficients[N - 2], xs[N - 1] - xs[N - 2]);
+
+        let mut deviations = residuals;
+        for deviation in deviations.iter_mut() {
+            *deviation = deviation.abs();
+        }
+        deviations.sort_unstable_by(|a, b| a.partial_cmp(b).unwrap_or(core::cmp::Ordering::Equal));
+        let median = if N % 2 == 1 {
+            deviations[N / 2]
+        } else {
+            (deviations[N / 2 - 1] + deviations[N / 2]) / 2f32
+        };
+        let scale = MAD_TO_SIGMA * median;
+
+        let mut change = 0f32;
+        for (weight, residual) in weights.iter_mut().zip(&residuals) {
+            let new = loss.weight(*residual, scale);
+            change = change.max((new - *weight).abs());
+            *weight = new;
+        }
+        if change <= ROBUST_WEIGHT_TOLERANCE {
+            return Ok(iteration);
+        }
+        smoothing_spline(xs, ys, Some(weights), lambda, coefficients)?;
+    }
+    Ok(max_iterations)
+}
+
+/// Solve the symmetric positive definite pentadiagonal system with diagonals
+/// `main`, `first` and `second` (the latter two above the main diagonal, and
+/// padded at the end) and right hand side `r` by LDL^T factorization. The
+/// diagonals and `r` are overwritten.
+fn pentadiagonal_symmetric(
+    main: &mut [f32],
+    first: &mut [f32],
+    second: &mut [f32],
+    r: &mut [f32],
+    x: &mut [f32],
+) -> Result<(), Error> {
+    let n = main.len();
+    // main becomes D, first and second become the multipliers of L
+    for i in 0..n {
+        if i >= 1 {
+            main[i] -= first[i - 1] * first[i - 1] * main[i - 1];
+        }
+        if i >= 2 {
+            main[i] -= second[i - 2] * second[i - 2] * main[i - 2];
+        }
+        if main[i].is_nan() || main[i] < f32::MIN_POSITIVE {
+            return Err(Error::SingularSystem { row: i });
+        }
+        if i >= 1 {
+            first[i] -= first[i - 1] * second[i - 1] * main[i - 1];
+        }
+        first[i] /= main[i];
+        second[i] /= main[i];
+    }
+    for i in 0..n {
+        if i >= 1 {
+            r[i] -= first[i - 1] * r[i - 1];
+        }
+        if i >= 2 {
+            r[i] -= second[i - 2] * r[i - 2];
+        }
+    }
+    for i in (0..n).rev() {
+        x[i] = r[i] / main[i];
+        if i + 1 < n {
+            x[i] -= first[i] * x[i + 1];
+        }
+        if i + 2 < n {
+            x[i] -= second[i] * x[i + 2];
+        }
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const XS: [f32; 6] = [0f32, 1.0, 2.5, 3.0, 4.0, 5.5];
+    const YS: [f32; 6] = [1f32, 2.0, 0.5, 3.0, 2.0, 2.5];
+
+    #[test]
+    fn zero_lambda_interpolates() {
+        let mut smooth = [(0f32, 0f32, 0f32, 0f32); 5];
+        smoothing_spline(&XS, &YS, None, 0.0, &mut smooth).unwrap();
+        let mut natural = [(0f32, 0f32, 0f32, 0f32); 5];
+        crate::splinterpol(&XS, &YS, &mut natural).unwrap();
+        for (s, n) in smooth.iter().zip(&natural) {
+            for (s, n) in [(s.0, n.0), (s.1, n.1), (s.2, n.2), (s.3, n.3)] {
+                assert!((s - n).abs() < 1e-4, "{:?} vs {:?}", smooth, natural);
+            }
+        }
+    }
+
+    #[test]
+    fn large_lambda_tends_to_regression_line() {
+        let xs = [0f32, 1.0, 2.0, 3.0, 4.0];
+        let ys = [0f32, 2.0, 1.0, 3.0, 4.0];
+        let mut coeffs = [(0f32, 0f32, 0f32, 0f32); 4];
+        smoothing_spline(&xs, &ys, None, 1e5, &mut coeffs).unwrap();
+        // Least-squares line y = 0.2 + 0.9 x
+        for (x, segment) in xs.iter().zip(&coeffs) {
+            assert!((segment.0 - (0.2 + 0.9 * x)).abs() < 1e-2);
+            assert!((segment.1 - 0.9).abs() < 1e-2);
+            assert!(segment.2.abs() < 1e-3);
+        }
+    }
+
+    #[test]
+    fn weights_pin_points() {
+        let mut weights = [1f32; 6];
+        weights[2] = f32::INFINITY;
+        let mut coeffs = [(0f32, 0f32, 0f32, 0f32); 5];
+        smoothing_spline(&XS, &YS, Some(&weights), 1.0, &mut coeffs).unwrap();
+        assert_eq!(YS[2], coeffs[2].0);
+        assert!((coeffs[1].0 - YS[1]).abs() > 0.01);
+
+        // Smoothing reduces the curvature at the knots
+        let mut natural = [(0f32, 0f32, 0f32, 0f32); 5];
+        crate::splinterpol(&XS, &YS, &mut natural).unwrap();
+        let roughness = |c: &[(f32, f32, f32, f32)]| c.iter().map(|s| s.2 * s.2).sum::<f32>();
+        assert!(roughness(&coeffs) < roughness(&natural));
+        // and the result is still twice continuously differentiable
+        assert_eq!(
+            Ok(()),
+            crate::verify(
+                &coeffs,
+                &XS,
+                &[
+                    coeffs[0].0,
+                    coeffs[1].0,
+                    coeffs[2].0,
+                    coeffs[3].0,
+                    coeffs[4].0,
+                    crate::eval_segment(coeffs[4], 1.5),
+                ],
+                1e-4
+            )
+        );
+    }
+
+    #[test]
+    fn rejects_invalid_parameters() {
+        let mut coeffs = [(0f32, 0f32, 0f32, 0f32); 5];
+        assert_eq!(
+            Err(Error::InvalidLimit),
+            smoothing_spline(&XS, &YS, None, -1.0, &mut coeffs)
+        );
+        let weights = [1f32, 1.0, 0.0, 1.0, 1.0, 1.0];
+        assert_eq!(
+            Err(Error::InvalidLimit),
+            smoothing_spline(&XS, &YS, Some(&weights), 1.0, &mut coeffs)
+        );
+    }
+
+    #[test]
+    fn robust_fit_ignores_outlier() {
+        let xs = [0f32, 1.0, 2.0, 3.0, 4.0, 5.0, 6.0, 7.0, 8.0];
+        let mut ys = [0f32; 9];
+        for (i, (y, x)) in ys.iter_mut().zip(&xs).enumerate() {
+            // Line with a little alternating noise
+            *y = 0.5 * x + if i % 2 == 0 { 0.05 } else { -0.05 };
+        }
+        ys[4] = 10.0;
+
+        let mut plain = [(0f32, 0f32, 0f32, 0f32); 8];
+        smoothing_spline(&xs, &ys, None, 0.5, &mut plain).unwrap();
+        for loss in [
+            RobustLoss::Huber { k: 1.345 },
+            RobustLoss::Tukey { c: 4.685 },
+        ] {
+            let mut robust = [(0f32, 0f32, 0f32, 0f32); 8];
+            let mut weights = [0f32; 9];
+            let iterations =
+                robust_smoothing_spline(&xs, &ys, 0.5, loss, 20, &mut robust, &mut weights)
+                    .unwrap();
+            assert!(iterations <= 20);
+            assert!(weights[4] < 0.1, "{:?}", weights);
+            assert!(weights[0] > 0.5);
+            // Bent towards the outlier much less than the plain fit
+            let plain_error = (plain[4].0 - 2.0).abs();
+            let robust_error = (robust[4].0 - 2.0).abs();
+            assert!(
+                robust_error < plain_error / 4f32,
+                "{} vs {}",
+                robust_error,
+                plain_error
+            );
+            assert!((robust[1].0 - 0.5).abs() < 0.2);
+        }
+
+        let mut coeffs = [(0f32, 0f32, 0f32, 0f32); 8];
+        let mut weights = [0f32; 9];
+        let loss = RobustLoss::Huber { k: 1.345 };
+        assert_eq!(
+            Ok(0),
+            robust_smoothing_spline(&xs, &ys, 0.5, loss, 0, &mut coeffs, &mut weights)
+        );
+        assert_eq!(plain, coeffs);
+        assert_eq!(
+            Err(Error::InvalidLimit),
+            robust_smoothing_spline(&xs, &ys, 0.0, loss, 5, &mut coeffs, &mut weights)
+        );
+    }
+}
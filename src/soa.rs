@@ -0,0 +1,184 @@
+use crate::batch::eval_batch_with;
+use crate::extrapolate::locate;
+use crate::{eval_segment, Error, Extrapolate};
+
+/// Spline coefficients of `M` segments in struct-of-arrays layout: all `a`
+/// coefficients contiguous, then all `b`, and so on.
+///
+/// Compared to a slice of `(a, b, c, d)` tuples, this keeps the coefficients
+/// of neighbouring segments next to each other, which suits vectorized
+/// evaluation and plotting long buffers.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct CoefficientsSoA<const M: usize> {
+    /// Constant coefficients
+    pub a: [f32; M],
+    /// Linear coefficients
+    pub b: [f32; M],
+    /// Quadratic coefficients
+    pub c: [f32; M],
+    /// Cubic coefficients
+    pub d: [f32; M],
+}
+
+impl<const M: usize> Default for CoefficientsSoA<M> {
+    fn default() -> Self {
+        Self {
+            a: [0f32; M],
+            b: [0f32; M],
+            c: [0f32; M],
+            d: [0f32; M],
+        }
+    }
+}
+
+impl<const M: usize> CoefficientsSoA<M> {
+    /// Convert from the tuple layout. `coefficients` must hold exactly `M`
+    /// segments.
+    pub fn from_tuples(coefficients: &[(f32, f32, f32, f32)]) -> Result<Self, Error> {
+        if coefficients.len() != M {
+            return Err(Error::InvalidSliceLength);
+        }
+        Ok(Self::from_tuples_unchecked(coefficients))
+    }
+
+    /// Convert to the tuple layout. `coefficients` must hold exactly `M`
+    /// segments.
+    pub fn to_tuples(&self, coefficients: &mut [(f32, f32, f32, f32)]) -> Result<(), Error> {
+        if coefficients.len() != M {
+            return Err(Error::InvalidSliceLength);
+        }
+        for (i, elem) in coefficients.iter_mut().enumerate() {
+            *elem = self.segment(i);
+        }
+        Ok(())
+    }
+
+    /// The coefficients of segment `i` as a tuple. Panics if `i >= M`.
+    pub fn segment(&self, i: usize) -> (f32, f32, f32, f32) {
+        (self.a[i], self.b[i], self.c[i], self.d[i])
+    }
+
+    /// Evaluate the spline with knots `xs` at global position x. Positions
+    /// outside of the knots are evaluated on the first or last polynomial.
+    pub fn eval(&self, xs: &[f32], x: f32) -> Result<f32, Error> {
+        self.eval_extrapolated(xs, x, Extrapolate::ExtendCubic)
+    }
+
+    /// Like [`eval`](CoefficientsSoA::eval), but positions outside of the
+    /// knots are extrapolated according to `extrapolate`.
+    pub fn eval_extrapolated(
+        &self,
+        xs: &[f32],
+        x: f32,
+        extrapolate: Extrapolate,
+    ) -> Result<f32, Error> {
+        self.check_knots(xs)?;
+        let (polynomial, t) = locate(|segment| self.segment(segment), xs, x, extrapolate)?;
+        Ok(eval_segment(polynomial, t))
+    }
+
+    /// Evaluate the spline with knots `xs` at every position in `queries`,
+    /// like [`eval_batch`](crate::eval_batch).
+    pub fn eval_batch(&self, xs: &[f32], queries: &[f32], out: &mut [f32]) -> Result<(), Error> {
+        self.eval_batch_extrapolated(xs, queries, out, Extrapolate::ExtendCubic)
+    }
+
+    /// Like [`eval_batch`](CoefficientsSoA::eval_batch), but positions outside
+    /// of the knots are extrapolated according to `extrapolate`.
+    pub fn eval_batch_extrapolated(
+        &self,
+        xs: &[f32],
+        queries: &[f32],
+        out: &mut [f32],
+        extrapolate: Extrapolate,
+    ) -> Result<(), Error> {
+        self.check_knots(xs)?;
+        eval_batch_with(
+            |segment| self.segment(segment),
+            xs,
+            queries,
+            out,
+            extrapolate,
+        )
+    }
+
+    fn from_tuples_unchecked(coefficients: &[(f32, f32, f32, f32)]) -> Self {
+        let mut soa = Self::default();
+        for (i, (a, b, c, d)) in coefficients.iter().enumerate() {
+            soa.a[i] = *a;
+            soa.b[i] = *b;
+            soa.c[i] = *c;
+            soa.d[i] = *d;
+        }
+        soa
+    }
+
+    fn check_knots(&self, xs: &[f32]) -> Result<(), Error> {
+        if M == 0 {
+            return Err(Error::EmptyInput);
+        }
+        if xs.len() != M + 1 {
+            return Err(Error::InvalidSliceLength);
+        }
+        Ok(())
+    }
+}
+
+impl<const M: usize> From<&[(f32, f32, f32, f32); M]> for CoefficientsSoA<M> {
+    fn from(coefficients: &[(f32, f32, f32, f32); M]) -> Self {
+        Self::from_tuples_unchecked(coefficients)
+    }
+}
+
+impl<const M: usize> From<&CoefficientsSoA<M>> for [(f32, f32, f32, f32); M] {
+    fn from(soa: &CoefficientsSoA<M>) -> Self {
+        let mut coefficients = [(0f32, 0f32, 0f32, 0f32); M];
+        for (i, elem) in coefficients.iter_mut().enumerate() {
+            *elem = soa.segment(i);
+        }
+        coefficients
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn soa_round_trip() {
+        let xs = [0.5f32, 1f32, 2f32, 3f32, 4.5f32, 5f32, 6f32, 7f32];
+        let ys = [0f32, 0f32, 1f32, 2f32, 4f32, 7f32, 9f32, 10f32];
+        let mut coeffs = [(0f32, 0f32, 0f32, 0f32); 7];
+        crate::splinterpol::<8>(&xs, &ys, &mut coeffs).unwrap();
+
+        let soa = CoefficientsSoA::from(&coeffs);
+        assert_eq!(soa, CoefficientsSoA::<7>::from_tuples(&coeffs).unwrap());
+        assert_eq!(coeffs, <[(f32, f32, f32, f32); 7]>::from(&soa));
+        let mut back = [(0f32, 0f32, 0f32, 0f32); 7];
+        soa.to_tuples(&mut back).unwrap();
+        assert_eq!(coeffs, back);
+        assert_eq!(
+            Err(Error::InvalidSliceLength),
+            CoefficientsSoA::<6>::from_tuples(&coeffs)
+        );
+    }
+
+    #[test]
+    fn soa_eval_matches_tuples() {
+        let xs = [0.5f32, 1f32, 2f32, 3f32, 4.5f32, 5f32, 6f32, 7f32];
+        let ys = [0f32, 0f32, 1f32, 2f32, 4f32, 7f32, 9f32, 10f32];
+        let mut coeffs = [(0f32, 0f32, 0f32, 0f32); 7];
+        crate::splinterpol::<8>(&xs, &ys, &mut coeffs).unwrap();
+        let soa = CoefficientsSoA::from(&coeffs);
+
+        let queries = [0.5f32, 0.7, 1.0, 2.5, 4.6, 6.9, 7.0, 3.3, 1.1];
+        let mut expected = [0f32; 9];
+        crate::eval_batch(&coeffs, &xs, &queries, &mut expected).unwrap();
+        let mut out = [0f32; 9];
+        soa.eval_batch(&xs, &queries, &mut out).unwrap();
+        assert_eq!(expected, out);
+        for (x, y) in queries.iter().zip(&expected) {
+            assert_eq!(*y, soa.eval(&xs, *x).unwrap());
+        }
+    }
+}
@@ -0,0 +1,210 @@
+//! Battery state of charge from the open-circuit voltage, the most common use
+//! of interpolation in battery-powered devices.
+//!
+//! The discharge curve is interpolated by a monotone cubic through the
+//! calibration points, so a higher voltage never reads as a lower state of
+//! charge and the result never leaves the calibrated range, unlike with the C2
+//! spline, which overshoots on the flat plateau of e.g. a Li-ion curve.
+
+use crate::monotone::monotone_slices;
+use crate::{eval_segment, find_segment, Error};
+
+/// A state of charge curve through `N` calibration points.
+///
+/// ```
+/// use cubic_splinterpol::soc::{SocCurve, TemperatureCompensation};
+///
+/// // Li-ion open-circuit voltage in V against state of charge in %
+/// let curve = SocCurve::new(&[
+///     (3.0f32, 0f32),
+///     (3.45, 5.0),
+///     (3.68, 20.0),
+///     (3.8, 50.0),
+///     (3.95, 75.0),
+///     (4.2, 100.0),
+/// ])
+/// .unwrap();
+/// assert_eq!(50.0, curve.soc(3.8));
+/// assert_eq!(100.0, curve.soc(4.3));
+/// assert_eq!(0.0, curve.soc(2.5));
+///
+/// // In the cold, the same charge shows a lower voltage
+/// let compensation =
+///     TemperatureCompensation::new(&[(-20f32, 0.12f32), (0.0, 0.04), (25.0, 0.0)]).unwrap();
+/// assert!((curve.soc_compensated(3.76, 0.0, &compensation) - 50.0).abs() < 1e-3);
+/// ```
+#[derive(Debug, Clone)]
+pub struct SocCurve<const N: usize> {
+    voltages: [f32; N],
+    socs: [f32; N],
+    /// N-1 of N used
+    coefficients: [(f32, f32, f32, f32); N],
+}
+
+impl<const N: usize> SocCurve<N> {
+    /// A curve through the calibration points `(voltage, soc)`, with strictly
+    /// increasing voltage and non-decreasing state of charge, in any unit
+    /// such as percent or a fraction. At least 2 points are needed.
+    ///
+    /// Fails with [`Error::InvalidRange`] if the state of charge decreases.
+    pub fn new(points: &[(f32, f32); N]) -> Result<Self, Error> {
+        if N < 2 {
+            return Err(Error::InvalidSliceLength);
+        }
+        let mut voltages = [0f32; N];
+        let mut socs = [0f32; N];
+        for (i, (voltage, soc)) in points.iter().enumerate() {
+            voltages[i] = *voltage;
+            socs[i] = *soc;
+        }
+        if socs
+            .windows(2)
+            .any(|pair| pair[0].is_nan() || pair[1].is_nan() || pair[1] < pair[0])
+        {
+            return Err(Error::InvalidRange);
+        }
+        let mut coefficients = [(0f32, 0f32, 0f32, 0f32); N];
+        monotone_slices(&voltages, &socs, &mut coefficients[..N - 1])?;
+        Ok(Self {
+            voltages,
+            socs,
+            coefficients,
+        })
+    }
+
+    /// State of charge at `voltage`, clamped to the first and last
+    /// calibration point. A NaN reading gives the lowest state of charge.
+    pub fn soc(&self, voltage: f32) -> f32 {
+        eval_clamped(&self.voltages, &self.socs, &self.coefficients, voltage)
+    }
+
+    /// State of charge at `voltage` measured at `temperature`, after adding
+    /// the voltage offset of `compensation` at that temperature.
+    pub fn soc_compensated<const M: usize>(
+        &self,
+        voltage: f32,
+        temperature: f32,
+        compensation: &TemperatureCompensation<M>,
+    ) -> f32 {
+        self.soc(voltage + compensation.offset(temperature))
+    }
+}
+
+/// A voltage offset against temperature through `M` points, to map a reading
+/// at any temperature to the reading at the temperature a [`SocCurve`] was
+/// calibrated at.
+#[derive(Debug, Clone)]
+pub struct TemperatureCompensation<const M: usize> {
+    temperatures: [f32; M],
+    offsets: [f32; M],
+    /// M-1 of M used
+    coefficients: [(f32, f32, f32, f32); M],
+}
+
+impl<const M: usize> TemperatureCompensation<M> {
+    /// A compensation through the points `(temperature, offset)`, with
+    /// strictly increasing temperature. At least 2 points are needed. The
+    /// offsets are interpolated by a monotone cubic as well, so they stay
+    /// within the given ones.
+    pub fn new(points: &[(f32, f32); M]) -> Result<Self, Error> {
+        if M < 2 {
+            return Err(Error::InvalidSliceLength);
+        }
+        let mut temperatures = [0f32; M];
+        let mut offsets = [0f32; M];
+        for (i, (temperature, offset)) in points.iter().enumerate() {
+            temperatures[i] = *temperature;
+            offsets[i] = *offset;
+        }
+        let mut coefficients = [(0f32, 0f32, 0f32, 0f32); M];
+        monotone_slices(&temperatures, &offsets, &mut coefficients[..M - 1])?;
+        Ok(Self {
+            temperatures,
+            offsets,
+            coefficients,
+        })
+    }
+
+    /// Voltage offset at `temperature`, clamped to the first and last point.
+    pub fn offset(&self, temperature: f32) -> f32 {
+        eval_clamped(
+            &self.temperatures,
+            &self.offsets,
+            &self.coefficients,
+            temperature,
+        )
+    }
+}
+
+/// Evaluate at x, with the end values outside of the knots.
+fn eval_clamped(xs: &[f32], ys: &[f32], coefficients: &[(f32, f32, f32, f32)], x: f32) -> f32 {
+    let last = xs.len() - 1;
+    if x.is_nan() || x <= xs[0] {
+        return ys[0];
+    }
+    if x >= xs[last] {
+        return ys[last];
+    }
+    let segment = find_segment(xs, x);
+    eval_segment(coefficients[segment], x - xs[segment])
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const POINTS: [(f32, f32); 7] = [
+        (3.0, 0.0),
+        (3.3, 2.0),
+        (3.6, 10.0),
+        (3.7, 40.0),
+        (3.8, 60.0),
+        (3.9, 70.0),
+        (4.2, 100.0),
+    ];
+
+    #[test]
+    fn monotone_and_clamped() {
+        let curve = SocCurve::new(&POINTS).unwrap();
+        for (voltage, soc) in POINTS {
+            assert!((curve.soc(voltage) - soc).abs() < 1e-4);
+        }
+        let mut previous = 0f32;
+        for i in 0..=150 {
+            let soc = curve.soc(2.9 + i as f32 / 100f32);
+            assert!(soc >= previous && soc <= 100.0);
+            previous = soc;
+        }
+        assert_eq!(0.0, curve.soc(f32::NAN));
+        assert_eq!(100.0, curve.soc(f32::INFINITY));
+    }
+
+    #[test]
+    fn temperature_compensation() {
+        let curve = SocCurve::new(&POINTS).unwrap();
+        let compensation = TemperatureCompensation::new(&[(-10f32, 0.1f32), (25.0, 0.0)]).unwrap();
+        assert_eq!(0.1, compensation.offset(-30.0));
+        assert!((compensation.offset(7.5) - 0.05).abs() < 1e-6);
+        assert!((curve.soc_compensated(3.6, -10.0, &compensation) - 40.0).abs() < 1e-3);
+        assert_eq!(
+            curve.soc(3.7),
+            curve.soc_compensated(3.7, 40.0, &compensation)
+        );
+    }
+
+    #[test]
+    fn rejects_invalid_points() {
+        assert_eq!(
+            Err(Error::InvalidRange),
+            SocCurve::new(&[(3f32, 0f32), (3.5, 50.0), (4.0, 40.0)]).map(|_| ())
+        );
+        assert_eq!(
+            Err(Error::NonIncreasingKnots { index: 2 }),
+            SocCurve::new(&[(3f32, 0f32), (3.5, 50.0), (3.5, 60.0)]).map(|_| ())
+        );
+        assert_eq!(
+            Err(Error::InvalidSliceLength),
+            SocCurve::new(&[(3f32, 0f32)]).map(|_| ())
+        );
+    }
+}
@@ -0,0 +1,539 @@
+use crate::{
+    check_range, check_spline, eval_segment, eval_segment_derivative,
+    eval_segment_second_derivative, find_segment, hermite, rebase, Error,
+};
+
+/// Smoothness that [`concat`] enforces at the junction of two splines.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Continuity {
+    /// Continuous value, bridged by a straight line
+    C0,
+    /// Continuous value and slope, bridged by a cubic
+    C1,
+    /// Continuous value, slope and curvature, bridged by three cubics
+    C2,
+}
+
+/// Split the spline given by `coefficients` and the knots `xs` at x, strictly
+/// between the first and last knot, into the spline left and the spline right
+/// of x, e.g. to replace the rest of a trajectory while it is running.
+///
+/// The parts are written to the front of the `left_` and `right_` buffers,
+/// which fail with [`Error::CapacityExceeded`] if too small; buffers as long
+/// as the input always suffice. Returns the number of segments of both parts.
+/// If x is not a knot already, the segment containing it is split in two,
+/// exactly up to rounding, so together the parts are the original curve.
+///
+/// ```
+/// use cubic_splinterpol::{eval, split_at, Extrapolate};
+///
+/// let xs = [0f32, 1.0, 2.0];
+/// let coefficients = [(0f32, 1f32, 0f32, 0f32), (1.0, 1.0, 0.5, 0.0)];
+/// let (mut left, mut left_xs) = ([(0f32, 0f32, 0f32, 0f32); 2], [0f32; 3]);
+/// let (mut right, mut right_xs) = ([(0f32, 0f32, 0f32, 0f32); 2], [0f32; 3]);
+/// let segments = split_at(
+///     &coefficients, &xs, 1.5, &mut left, &mut left_xs, &mut right, &mut right_xs,
+/// )
+/// .unwrap();
+/// assert_eq!((2, 1), segments);
+/// assert_eq!([0.0, 1.0, 1.5], left_xs);
+/// assert_eq!([1.5, 2.0], right_xs[..2]);
+/// let y = eval(&right[..1], &right_xs[..2], 1.75, Extrapolate::Error).unwrap();
+/// assert_eq!(eval(&coefficients, &xs, 1.75, Extrapolate::Error).unwrap(), y);
+/// ```
+pub fn split_at(
+    coefficients: &[(f32, f32, f32, f32)],
+    xs: &[f32],
+    x: f32,
+    left_coefficients: &mut [(f32, f32, f32, f32)],
+    left_xs: &mut [f32],
+    right_coefficients: &mut [(f32, f32, f32, f32)],
+    right_xs: &mut [f32],
+) -> Result<(usize, usize), Error> {
+    check_spline(coefficients, xs)?;
+    let last = xs[xs.len() - 1];
+    if x.is_nan() || x <= xs[0] || x >= last {
+        return Err(Error::OutOfRange);
+    }
+    let left = restrict(coefficients, xs, xs[0], x, left_coefficients, left_xs)?;
+    let right = restrict(coefficients, xs, x, last, right_coefficients, right_xs)?;
+    Ok((left, right))
+}
+
+/// Restrict the spline given by `coefficients` and the knots `xs` to
+/// `[from, to]`, within the first and last knot, e.g. to plot or integrate
+/// only part of it with the functions taking a whole spline.
+///
+/// The restricted spline is written to the front of `out_coefficients` and
+/// `out_xs`, which fail with [`Error::CapacityExceeded`] if too small; buffers
+/// as long as the input always suffice. Returns its number of segments. Its
+/// first knot is `from`, with the first segment expanded around it, and its
+/// last knot is `to`; the curve is unchanged in between.
+///
+/// ```
+/// use cubic_splinterpol::trim;
+///
+/// let xs = [0f32, 1.0, 2.0, 3.0];
+/// let coefficients = [(0f32, 1f32, 0f32, 0f32), (1.0, 1.0, 0.0, 0.0), (2.0, 1.0, 0.0, 0.0)];
+/// let mut out = [(0f32, 0f32, 0f32, 0f32); 3];
+/// let mut out_xs = [0f32; 4];
+/// assert_eq!(Ok(2), trim(&coefficients, &xs, 0.5, 1.5, &mut out, &mut out_xs));
+/// assert_eq!([0.5, 1.0, 1.5], out_xs[..3]);
+/// assert_eq!((0.5, 1.0, 0.0, 0.0), out[0]);
+/// ```
+pub fn trim(
+    coefficients: &[(f32, f32, f32, f32)],
+    xs: &[f32],
+    from: f32,
+    to: f32,
+    out_coefficients: &mut [(f32, f32, f32, f32)],
+    out_xs: &mut [f32],
+) -> Result<usize, Error> {
+    check_spline(coefficients, xs)?;
+    check_range(from, to)?;
+    if from < xs[0] || to > xs[xs.len() - 1] {
+        return Err(Error::OutOfRange);
+    }
+    restrict(coefficients, xs, from, to, out_coefficients, out_xs)
+}
+
+/// Join the spline `second` to the end of the spline `first`, e.g. to chain
+/// independently planned motion segments into one profile.
+///
+/// The knots of `second` are shifted so that it starts `blend` after the last
+/// knot of `first`. With a `blend` of zero, the segments of both are just
+/// joined, so the splines should meet already. Otherwise, blend segments
+/// spanning the gap are inserted which connect the end of `first` to the start
+/// of `second` with the given `continuity`.
+///
+/// The result is written to the front of `out_coefficients` and `out_xs`,
+/// which fail with [`Error::CapacityExceeded`] if too small. Returns its number
+/// of segments: those of both splines, plus one blend segment for
+/// [`Continuity::C0`] and [`Continuity::C1`] or three for [`Continuity::C2`].
+/// A C2 blend so short that its cubic coefficients do not fit into `f32`
+/// fails with [`Error::Overflow`].
+///
+/// ```
+/// use cubic_splinterpol::{concat, Continuity};
+///
+/// // Ramp up, then hold at a different level
+/// let ramp = [(0f32, 1f32, 0f32, 0f32)];
+/// let hold = [(2f32, 0f32, 0f32, 0f32)];
+/// let mut out = [(0f32, 0f32, 0f32, 0f32); 3];
+/// let mut out_xs = [0f32; 4];
+/// let segments = concat(
+///     &ramp, &[0.0, 1.0], &hold, &[0.0, 5.0], Continuity::C1, 0.5, &mut out, &mut out_xs,
+/// )
+/// .unwrap();
+/// assert_eq!(3, segments);
+/// assert_eq!([0.0, 1.0, 1.5, 6.5], out_xs);
+/// assert_eq!(1.0, out[1].0);
+/// assert_eq!(1.0, out[1].1);
+/// ```
+#[allow(clippy::too_many_arguments)]
+pub fn concat(
+    first_coefficients: &[(f32, f32, f32, f32)],
+    first_xs: &[f32],
+    second_coefficients: &[(f32, f32, f32, f32)],
+    second_xs: &[f32],
+    continuity: Continuity,
+    blend: f32,
+    out_coefficients: &mut [(f32, f32, f32, f32)],
+    out_xs: &mut [f32],
+) -> Result<usize, Error> {
+    check_spline(first_coefficients, first_xs)?;
+    check_spline(second_coefficients, second_xs)?;
+    if blend.is_nan() || blend < 0f32 {
+        return Err(Error::InvalidLimit);
+    }
+    let blends = match (blend > 0f32, continuity) {
+        (false, _) => 0,
+        (true, Continuity::C0 | Continuity::C1) => 1,
+        (true, Continuity::C2) => 3,
+    };
+    let first = first_coefficients.len();
+    let second = second_coefficients.len();
+    let segments = first + blends + second;
+    if out_coefficients.len() < segments || out_xs.len() < segments + 1 {
+        return Err(Error::CapacityExceeded);
+    }
+
+    out_coefficients[..first].copy_from_slice(first_coefficients);
+    out_xs[..=first].copy_from_slice(first_xs);
+    let end = first_xs[first];
+    let last = first_coefficients[first - 1];
+    let h = end - first_xs[first - 1];
+    let from = (
+        eval_segment(last, h),
+        eval_segment_derivative(last, h),
+        eval_segment_second_derivative(last, h),
+    );
+    let (a, b, c, _) = second_coefficients[0];
+    let to = (a, b, 2f32 * c);
+    let blend_coefficients = &mut out_coefficients[first..first + blends];
+    match (blends, continuity) {
+        (0, _) => {}
+        (_, Continuity::C0) => {
+            blend_coefficients[0] = (from.0, (to.0 - from.0) / blend, 0f32, 0f32);
+        }
+        (_, Continuity::C1) => {
+            blend_coefficients[0] = hermite(from.0, to.0, from.1, to.1, blend);
+        }
+        (_, Continuity::C2) => bridge_c2(from, to, blend / 3f32, blend_coefficients)?,
+    }
+    for i in 1..blends {
+        out_xs[first + i] = end + blend * i as f32 / blends as f32;
+    }
+
+    let offset = end + blend - second_xs[0];
+    out_coefficients[first + blends..segments].copy_from_slice(second_coefficients);
+    for (out, x) in out_xs[first + blends..=segments].iter_mut().zip(second_xs) {
+        *out = x + offset;
+    }
+    // Avoid a knot off from the blend by rounding
+    out_xs[first + blends] = end + blend;
+    Ok(segments)
+}
+
+/// Coefficients of three cubics of width w each, joined C2, from the value,
+/// slope and curvature `from` to those `to`.
+///
+/// Solved in units of w: with slope times w, curvature times w² and cubic
+/// coefficients e = d w³, every segment advances the state by a fixed linear
+/// map, so the system for the three e is a constant matrix with the inverse
+/// below, and stays as well conditioned for small blends as for large ones.
+fn bridge_c2(
+    from: (f32, f32, f32),
+    to: (f32, f32, f32),
+    w: f32,
+    coefficients: &mut [(f32, f32, f32, f32)],
+) -> Result<(), Error> {
+    // Value, scaled slope and scaled curvature after a segment with e
+    let advance =
+        |(y, m, k): (f32, f32, f32), e: f32| (y + m + k / 2f32 + e, m + k + 3f32 * e, k + 6f32 * e);
+    let start = (from.0, from.1 * w, from.2 * w * w);
+    let base = advance(advance(advance(start, 0f32), 0f32), 0f32);
+    let rhs = [to.0 - base.0, to.1 * w - base.1, to.2 * w * w - base.2];
+    // Inverse of the matrix with the columns (19, 15, 6), (7, 9, 6) and
+    // (1, 3, 6), the effects of the e of the first, second and third segment
+    let inverse = [
+        [1f32 / 6f32, -1f32 / 6f32, 1f32 / 18f32],
+        [-1f32 / 3f32, 1f32 / 2f32, -7f32 / 36f32],
+        [1f32 / 6f32, -1f32 / 3f32, 11f32 / 36f32],
+    ];
+    let mut state = start;
+    for (coefficients, row) in coefficients.iter_mut().zip(inverse) {
+        let e = row[0] * rhs[0] + row[1] * rhs[1] + row[2] * rhs[2];
+        let d = e / (w * w * w);
+        *coefficients = (state.0, state.1 / w, state.2 / (2f32 * w * w), d);
+        if !d.is_finite() {
+            return Err(Error::Overflow);
+        }
+        state = advance(state, e);
+    }
+    Ok(())
+}
+
+/// [`trim`] without the checks.
+fn restrict(
+    coefficients: &[(f32, f32, f32, f32)],
+    xs: &[f32],
+    from: f32,
+    to: f32,
+    out_coefficients: &mut [(f32, f32, f32, f32)],
+    out_xs: &mut [f32],
+) -> Result<usize, Error> {
+    let first = find_segment(xs, from);
+    let mut last = find_segment(xs, to);
+    // A part ending on a knot ends with the segment before it
+    if last > first && xs[last] == to {
+        last -= 1;
+    }
+    let segments = last - first + 1;
+    if out_coefficients.len() < segments || out_xs.len() < segments + 1 {
+        return Err(Error::CapacityExceeded);
+    }
+    out_coefficients[..segments].copy_from_slice(&coefficients[first..=last]);
+    out_coefficients[0] = rebase(coefficients[first], from - xs[first]);
+    out_xs[..segments + 1].copy_from_slice(&xs[first..=last + 1]);
+    out_xs[0] = from;
+    out_xs[segments] = to;
+    Ok(segments)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::fixture::{spline, XS};
+    use crate::{eval, Extrapolate};
+
+    #[test]
+    fn split_within_segment_and_at_knot() {
+        let coeffs = spline();
+        let mut left = [(0f32, 0f32, 0f32, 0f32); 4];
+        let mut right = [(0f32, 0f32, 0f32, 0f32); 4];
+        let mut left_xs = [0f32; 5];
+        let mut right_xs = [0f32; 5];
+        for x in [1.7f32, 2.5] {
+            let (l, r) = split_at(
+                &coeffs,
+                &XS,
+                x,
+                &mut left,
+                &mut left_xs,
+                &mut right,
+                &mut right_xs,
+            )
+            .unwrap();
+            assert_eq!(x, left_xs[l]);
+            assert_eq!(x, right_xs[0]);
+            assert_eq!(4.0, right_xs[r]);
+            for i in 0..=40 {
+                let p = i as f32 / 10f32;
+                let expected = eval(&coeffs, &XS, p, Extrapolate::Error).unwrap();
+                let y = if p <= x {
+                    eval(&left[..l], &left_xs[..=l], p, Extrapolate::Error)
+                } else {
+                    eval(&right[..r], &right_xs[..=r], p, Extrapolate::Error)
+                };
+                assert!((y.unwrap() - expected).abs() < 1e-5);
+            }
+        }
+        // At the knot 2.5, no segment is split
+        assert_eq!([0.0, 1.0, 2.5], left_xs[..3]);
+        assert_eq!([2.5, 3.0, 4.0], right_xs[..3]);
+        assert_eq!(coeffs[2..], right[..2]);
+    }
+
+    #[test]
+    fn split_rejects_ends_and_small_buffers() {
+        let coeffs = spline();
+        let mut left = [(0f32, 0f32, 0f32, 0f32); 4];
+        let mut right = [(0f32, 0f32, 0f32, 0f32); 1];
+        let mut left_xs = [0f32; 5];
+        let mut right_xs = [0f32; 2];
+        for x in [0f32, 4.0, f32::NAN] {
+            assert_eq!(
+                Err(Error::OutOfRange),
+                split_at(
+                    &coeffs,
+                    &XS,
+                    x,
+                    &mut left,
+                    &mut left_xs,
+                    &mut right,
+                    &mut right_xs
+                )
+            );
+        }
+        assert_eq!(
+            Err(Error::CapacityExceeded),
+            split_at(
+                &coeffs,
+                &XS,
+                0.5,
+                &mut left,
+                &mut left_xs,
+                &mut right,
+                &mut right_xs
+            )
+        );
+        assert_eq!(
+            Ok((4, 1)),
+            split_at(
+                &coeffs,
+                &XS,
+                3.5,
+                &mut left,
+                &mut left_xs,
+                &mut right,
+                &mut right_xs
+            )
+        );
+    }
+
+    #[test]
+    fn trim_to_subinterval() {
+        let coeffs = spline();
+        let mut out = [(0f32, 0f32, 0f32, 0f32); 4];
+        let mut out_xs = [0f32; 5];
+        // Within one segment
+        assert_eq!(Ok(1), trim(&coeffs, &XS, 1.2, 2.0, &mut out, &mut out_xs));
+        assert_eq!([1.2, 2.0], out_xs[..2]);
+        // From knot to knot, unchanged
+        assert_eq!(Ok(2), trim(&coeffs, &XS, 1.0, 3.0, &mut out, &mut out_xs));
+        assert_eq!([1.0, 2.5, 3.0], out_xs[..3]);
+        assert_eq!(coeffs[1..3], out[..2]);
+        // Whole spline
+        assert_eq!(Ok(4), trim(&coeffs, &XS, 0.0, 4.0, &mut out, &mut out_xs));
+        assert_eq!(XS, out_xs);
+        assert_eq!(coeffs, out);
+
+        assert_eq!(Ok(4), trim(&coeffs, &XS, 0.5, 3.5, &mut out, &mut out_xs));
+        for i in 5..=35 {
+            let x = i as f32 / 10f32;
+            let expected = eval(&coeffs, &XS, x, Extrapolate::Error).unwrap();
+            let y = eval(&out, &out_xs, x, Extrapolate::Error).unwrap();
+            assert!((y - expected).abs() < 1e-5);
+        }
+
+        assert_eq!(
+            Err(Error::InvalidRange),
+            trim(&coeffs, &XS, 2.0, 2.0, &mut out, &mut out_xs)
+        );
+        assert_eq!(
+            Err(Error::OutOfRange),
+            trim(&coeffs, &XS, -0.5, 2.0, &mut out, &mut out_xs)
+        );
+        assert_eq!(
+            Err(Error::CapacityExceeded),
+            trim(&coeffs, &XS, 0.5, 3.5, &mut out[..3], &mut out_xs)
+        );
+    }
+
+    #[test]
+    fn concat_with_continuity() {
+        let coeffs = spline();
+        let other = [(5f32, -1f32, 0.5f32, 0.1f32), (4.0, 0.0, 0.0, 0.0)];
+        let other_xs = [10f32, 11.0, 12.0];
+        let mut out = [(0f32, 0f32, 0f32, 0f32); 9];
+        let mut out_xs = [0f32; 10];
+        let end = coeffs[3];
+        let (y, m, k) = (
+            eval_segment(end, 1.0),
+            eval_segment_derivative(end, 1.0),
+            eval_segment_second_derivative(end, 1.0),
+        );
+        for (continuity, blends, gap) in [
+            (Continuity::C0, 1, 0.6),
+            (Continuity::C1, 1, 0.6),
+            (Continuity::C2, 3, 0.6),
+            (Continuity::C1, 1, 0.01),
+            (Continuity::C2, 3, 0.01),
+            (Continuity::C2, 3, 0.001),
+        ] {
+            let segments = concat(
+                &coeffs,
+                &XS,
+                &other,
+                &other_xs,
+                continuity,
+                gap,
+                &mut out,
+                &mut out_xs,
+            )
+            .unwrap();
+            assert_eq!(6 + blends, segments);
+            assert_eq!(coeffs, out[..4]);
+            assert_eq!(other, out[4 + blends..segments]);
+            assert_eq!(4.0 + gap, out_xs[4 + blends]);
+            assert!((out_xs[segments] - (6.0 + gap)).abs() < 1e-6);
+            assert_eq!(Ok(()), crate::check_increasing(&out_xs[..=segments]));
+            // Values, and derivatives up to the order of continuity, match
+            // at both ends of the blend
+            // At the width of the blend segments, rather than that of the
+            // knots, which are rounded to the ulp of x
+            let blend = gap / blends as f32;
+            let last = out[4 + blends - 1];
+            let order = match continuity {
+                Continuity::C0 => 0,
+                Continuity::C1 => 1,
+                Continuity::C2 => 2,
+            };
+            let start = [out[4].0, out[4].1, 2f32 * out[4].2];
+            let finish = [
+                eval_segment(last, blend),
+                eval_segment_derivative(last, blend),
+                eval_segment_second_derivative(last, blend),
+            ];
+            for (derivative, (lhs, rhs)) in [(y, 5.0), (m, -1.0), (k, 1.0)].iter().enumerate() {
+                if derivative <= order {
+                    // Rounding grows with the derivative, like 1 / blend^order
+                    let tolerance = 1e-5 * libm::powf(blend.min(1f32), -(derivative as f32));
+                    assert!((start[derivative] - lhs).abs() < 1e-4, "{:?}", continuity);
+                    let error = (finish[derivative] - rhs).abs();
+                    assert!(error < tolerance, "{:?} {} {}", continuity, gap, error);
+                }
+            }
+        }
+
+        // Short C2 blends of the spline to itself, at the start of the copy
+        let mut out = [(0f32, 0f32, 0f32, 0f32); 11];
+        let mut out_xs = [0f32; 12];
+        for gap in [0.01f32, 0.001, 0.0001] {
+            concat(
+                &coeffs,
+                &XS,
+                &coeffs,
+                &XS,
+                Continuity::C2,
+                gap,
+                &mut out,
+                &mut out_xs,
+            )
+            .unwrap();
+            let w = gap / 3f32;
+            let last = out[6];
+            assert!((eval_segment(last, w) - 1.0).abs() < 1e-5, "{}", gap);
+            let slope = eval_segment_derivative(last, w) - coeffs[0].1;
+            assert!(slope.abs() < 1e-5 / w, "{} {}", gap, slope);
+            let curvature = eval_segment_second_derivative(last, w);
+            assert!(curvature.abs() < 1e-5 / (w * w), "{} {}", gap, curvature);
+        }
+        assert_eq!(
+            Err(Error::Overflow),
+            concat(
+                &coeffs,
+                &XS,
+                &coeffs,
+                &XS,
+                Continuity::C2,
+                1e-30,
+                &mut out,
+                &mut out_xs,
+            )
+        );
+        let mut out = [(0f32, 0f32, 0f32, 0f32); 9];
+        let mut out_xs = [0f32; 10];
+
+        // Joined directly
+        let segments = concat(
+            &coeffs,
+            &XS,
+            &other,
+            &other_xs,
+            Continuity::C2,
+            0.0,
+            &mut out,
+            &mut out_xs,
+        );
+        assert_eq!(Ok(6), segments);
+        assert_eq!([4.0, 5.0, 6.0], out_xs[4..7]);
+        assert_eq!(
+            Err(Error::InvalidLimit),
+            concat(
+                &coeffs,
+                &XS,
+                &other,
+                &other_xs,
+                Continuity::C0,
+                -1.0,
+                &mut out,
+                &mut out_xs
+            )
+        );
+        assert_eq!(
+            Err(Error::CapacityExceeded),
+            concat(
+                &coeffs,
+                &XS,
+                &other,
+                &other_xs,
+                Continuity::C2,
+                1.0,
+                &mut out[..8],
+                &mut out_xs
+            )
+        );
+    }
+}
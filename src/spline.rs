@@ -0,0 +1,103 @@
+use crate::in_place::splinterpol_in_place_slices;
+use crate::plot_spline::sample_uniform;
+use crate::{eval_segment, find_segment, Error};
+
+/// Natural spline owning its knots and coefficients, for host-side tooling
+/// and tests which do not need to avoid the heap.
+///
+/// ```
+/// use cubic_splinterpol::Spline;
+///
+/// let spline = Spline::new(&[(2.0, 1.0), (0.0, 0.0), (1.0, 2.0), (3.0, 3.0)]).unwrap();
+/// assert_eq!(2.0, spline.eval(1.0));
+/// let samples = spline.sample(7);
+/// assert_eq!(7, samples.len());
+/// assert_eq!(3.0, samples[6]);
+/// ```
+#[derive(Debug, Clone, PartialEq)]
+pub struct Spline {
+    xs: Vec<f32>,
+    coefficients: Vec<(f32, f32, f32, f32)>,
+}
+
+impl Spline {
+    /// The natural spline through `points`, in any order and converted to
+    /// f32. Needs at least three points, and fails with
+    /// [`Error::NonIncreasingKnots`] for two with the same x.
+    pub fn new(points: &[(f64, f64)]) -> Result<Self, Error> {
+        let mut points: Vec<(f32, f32)> =
+            points.iter().map(|(x, y)| (*x as f32, *y as f32)).collect();
+        points.sort_by(|a, b| a.0.total_cmp(&b.0));
+        let xs: Vec<f32> = points.iter().map(|(x, _)| *x).collect();
+        let mut ys: Vec<f32> = points.iter().map(|(_, y)| *y).collect();
+        let mut coefficients = vec![(0f32, 0f32, 0f32, 0f32); xs.len().saturating_sub(1)];
+        splinterpol_in_place_slices(&xs, &mut ys, &mut coefficients)?;
+        Ok(Self { xs, coefficients })
+    }
+
+    /// The knots, sorted.
+    pub fn xs(&self) -> &[f32] {
+        &self.xs
+    }
+
+    /// The coefficients of all segments, for the slice-based functions of
+    /// this crate.
+    pub fn coefficients(&self) -> &[(f32, f32, f32, f32)] {
+        &self.coefficients
+    }
+
+    /// Evaluate the spline at global position x, continuing the first and
+    /// last polynomial outside of the knots.
+    pub fn eval(&self, x: f32) -> f32 {
+        let segment = find_segment(&self.xs, x);
+        eval_segment(self.coefficients[segment], x - self.xs[segment])
+    }
+
+    /// `count` samples from the first to the last knot inclusive, at the same
+    /// positions as [`sample_uniform_into`](crate::sample_uniform_into).
+    pub fn sample(&self, count: usize) -> Vec<f32> {
+        let mut samples = vec![0f32; count];
+        sample_uniform(&self.coefficients, &self.xs, count, |index, y| {
+            samples[index] = y
+        });
+        samples
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn matches_splinterpol() {
+        let xs = [0f32, 1.0, 2.5, 3.0, 4.0];
+        let ys = [1f32, -1.0, 0.5, 2.0, 0.0];
+        let mut expected = [(0f32, 0f32, 0f32, 0f32); 4];
+        crate::splinterpol(&xs, &ys, &mut expected).unwrap();
+        let points: Vec<(f64, f64)> = xs
+            .iter()
+            .zip(&ys)
+            .rev()
+            .map(|(x, y)| (*x as f64, *y as f64))
+            .collect();
+        let spline = Spline::new(&points).unwrap();
+        assert_eq!(xs, spline.xs());
+        assert_eq!(expected, spline.coefficients());
+
+        let mut samples = [0f32; 9];
+        crate::sample_uniform_into(&mut samples, &expected, &xs).unwrap();
+        assert_eq!(samples.to_vec(), spline.sample(9));
+    }
+
+    #[test]
+    fn rejects_invalid_points() {
+        assert_eq!(
+            Err(Error::InvalidSliceLength),
+            Spline::new(&[(0.0, 0.0), (1.0, 1.0)])
+        );
+        assert_eq!(
+            Err(Error::NonIncreasingKnots { index: 2 }),
+            Spline::new(&[(1.0, 0.0), (0.0, 0.0), (1.0, 1.0)])
+        );
+    }
+}
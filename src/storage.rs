@@ -0,0 +1,364 @@
+//! Compact binary format of coefficient tables and knots, e.g. for storage in
+//! MCU flash or EEPROM.
+//!
+//! The layout is fixed and little-endian:
+//!
+//! | offset       | size       | content                                  |
+//! |--------------|------------|------------------------------------------|
+//! | 0            | 1          | format version, [`FORMAT_VERSION`]       |
+//! | 1            | 3          | reserved, zero                           |
+//! | 4            | 4          | number of segments m as `u32`            |
+//! | 8            | 4 (m + 1)  | knots as `f32`                           |
+//! | 8 + 4 (m + 1)| 16 m       | coefficients `a, b, c, d` as `f32`       |
+//! | 12 + 20 m    | 4          | CRC-32 of all preceding bytes as `u32`   |
+//!
+//! All `f32` are 4-byte aligned relative to the start, but none of the
+//! functions here require any alignment of the bytes.
+//!
+//! The CRC-32 is the common IEEE 802.3 one (reflected polynomial
+//! `0xEDB88320`, as in zlib), so tables can be produced by host tooling.
+//! [`load_checked`] verifies it together with the knots before a table from
+//! external flash is trusted, while [`SplineView::new`] only checks the header
+//! and length, for memory that is known to be intact.
+
+use core::convert::TryFrom;
+
+use crate::extrapolate::locate_by;
+use crate::{check_spline, eval_segment, Error, Extrapolate};
+
+/// Version of the format written by [`to_bytes`].
+pub const FORMAT_VERSION: u8 = 2;
+
+/// Size of the header preceding the knots.
+const HEADER_LEN: usize = 8;
+
+/// Size of the CRC following the coefficients.
+const CRC_LEN: usize = 4;
+
+/// Number of bytes [`to_bytes`] writes for a spline of `segments` segments.
+pub const fn serialized_len(segments: usize) -> usize {
+    HEADER_LEN + 4 * (segments + 1) + 16 * segments + CRC_LEN
+}
+
+/// Write the spline given by `coefficients` and the knots `xs` to the front of
+/// `buffer`. Returns the number of bytes written, see [`serialized_len`].
+pub fn to_bytes(
+    coefficients: &[(f32, f32, f32, f32)],
+    xs: &[f32],
+    buffer: &mut [u8],
+) -> Result<usize, Error> {
+    check_spline(coefficients, xs)?;
+    let segments = coefficients.len();
+    let len = serialized_len(segments);
+    if buffer.len() < len {
+        return Err(Error::CapacityExceeded);
+    }
+    let count = u32::try_from(segments).map_err(|_| Error::Overflow)?;
+    buffer[0] = FORMAT_VERSION;
+    buffer[1..4].fill(0);
+    buffer[4..8].copy_from_slice(&count.to_le_bytes());
+    let words = buffer[HEADER_LEN..len - CRC_LEN].chunks_exact_mut(4);
+    let values = xs.iter().copied().chain(
+        coefficients
+            .iter()
+            .flat_map(|(a, b, c, d)| [*a, *b, *c, *d]),
+    );
+    for (word, value) in words.zip(values) {
+        word.copy_from_slice(&value.to_le_bytes());
+    }
+    let crc = crc32(&buffer[..len - CRC_LEN]);
+    buffer[len - CRC_LEN..len].copy_from_slice(&crc.to_le_bytes());
+    Ok(len)
+}
+
+/// Read a spline written by [`to_bytes`], copying its knots and coefficients
+/// to the front of `xs` and `coefficients`. Returns the number of segments.
+///
+/// The bytes are validated like in [`load_checked`].
+pub fn from_bytes(
+    bytes: &[u8],
+    coefficients: &mut [(f32, f32, f32, f32)],
+    xs: &mut [f32],
+) -> Result<usize, Error> {
+    let view = load_checked(bytes)?;
+    let segments = view.segments();
+    if coefficients.len() < segments || xs.len() < segments + 1 {
+        return Err(Error::CapacityExceeded);
+    }
+    for (i, x) in xs[..segments + 1].iter_mut().enumerate() {
+        *x = view.knot(i);
+    }
+    for (i, segment) in coefficients[..segments].iter_mut().enumerate() {
+        *segment = view.segment(i);
+    }
+    Ok(segments)
+}
+
+/// Validate a spline written by [`to_bytes`] and view it in place.
+///
+/// In addition to the header and length checked by [`SplineView::new`], this
+/// verifies the CRC, failing with [`Error::ChecksumMismatch`], and that the
+/// knots are strictly increasing, failing with [`Error::NonIncreasingKnots`].
+///
+/// ```
+/// use cubic_splinterpol::storage::{load_checked, serialized_len, to_bytes};
+/// use cubic_splinterpol::Error;
+///
+/// let xs = [0f32, 1.0, 2.0];
+/// let coefficients = [(0f32, 1f32, 0f32, 0f32), (1.0, 1.0, 0.0, 0.0)];
+/// let mut flash = [0u8; serialized_len(2)];
+/// to_bytes(&coefficients, &xs, &mut flash).unwrap();
+/// assert!(load_checked(&flash).is_ok());
+///
+/// // A flipped bit somewhere in the table
+/// flash[20] ^= 0x04;
+/// assert_eq!(Err(Error::ChecksumMismatch), load_checked(&flash).map(|_| ()));
+/// ```
+pub fn load_checked(bytes: &[u8]) -> Result<SplineView<'_>, Error> {
+    let view = SplineView::new(bytes)?;
+    let (data, crc) = view.bytes.split_at(view.bytes.len() - CRC_LEN);
+    if crc32(data) != u32::from_le_bytes([crc[0], crc[1], crc[2], crc[3]]) {
+        return Err(Error::ChecksumMismatch);
+    }
+    for i in 1..=view.segments {
+        if !matches!(
+            view.knot(i - 1).partial_cmp(&view.knot(i)),
+            Some(core::cmp::Ordering::Less)
+        ) {
+            return Err(Error::NonIncreasingKnots { index: i });
+        }
+    }
+    Ok(view)
+}
+
+/// CRC-32 (IEEE 802.3) of `bytes`, bitwise to avoid a table in flash.
+fn crc32(bytes: &[u8]) -> u32 {
+    let mut crc = !0u32;
+    for byte in bytes {
+        crc ^= u32::from(*byte);
+        for _ in 0..8 {
+            let mask = (crc & 1).wrapping_neg();
+            crc = (crc >> 1) ^ (0xEDB8_8320 & mask);
+        }
+    }
+    !crc
+}
+
+/// A spline read in place from bytes in the format of [`to_bytes`], e.g.
+/// in memory-mapped flash, without copying it to RAM.
+///
+/// ```
+/// use cubic_splinterpol::storage::{serialized_len, to_bytes, SplineView};
+/// use cubic_splinterpol::{splinterpol, Extrapolate};
+///
+/// let xs = [0f32, 1.0, 2.0];
+/// let ys = [0f32, 1.0, 0.0];
+/// let mut coefficients = [(0f32, 0f32, 0f32, 0f32); 2];
+/// splinterpol(&xs, &ys, &mut coefficients).unwrap();
+///
+/// let mut flash = [0u8; serialized_len(2)];
+/// to_bytes(&coefficients, &xs, &mut flash).unwrap();
+///
+/// let view = SplineView::new(&flash).unwrap();
+/// assert_eq!(Ok(1.0), view.eval(1.0, Extrapolate::Error));
+/// ```
+#[derive(Debug, Clone, Copy)]
+pub struct SplineView<'a> {
+    bytes: &'a [u8],
+    segments: usize,
+}
+
+impl<'a> SplineView<'a> {
+    /// Check the header and length of `bytes`, but not the CRC, see
+    /// [`load_checked`]. Trailing bytes beyond the spline are ignored.
+    pub fn new(bytes: &'a [u8]) -> Result<Self, Error> {
+        if bytes.len() < HEADER_LEN || bytes[0] != FORMAT_VERSION {
+            return Err(Error::InvalidFormat);
+        }
+        let count = u32::from_le_bytes([bytes[4], bytes[5], bytes[6], bytes[7]]);
+        let segments = usize::try_from(count).map_err(|_| Error::InvalidFormat)?;
+        if segments == 0 {
+            return Err(Error::EmptyInput);
+        }
+        // Guard the length computation against counts from corrupted bytes
+        if segments > (bytes.len() - HEADER_LEN) / 20 {
+            return Err(Error::InvalidFormat);
+        }
+        let len = serialized_len(segments);
+        if bytes.len() < len {
+            return Err(Error::InvalidFormat);
+        }
+        Ok(Self {
+            bytes: &bytes[..len],
+            segments,
+        })
+    }
+
+    /// The bytes of the spline including the CRC, without trailing bytes.
+    pub fn as_bytes(&self) -> &'a [u8] {
+        self.bytes
+    }
+
+    /// Number of segments.
+    pub fn segments(&self) -> usize {
+        self.segments
+    }
+
+    /// Knot i. Panics if `i > segments()`.
+    pub fn knot(&self, i: usize) -> f32 {
+        assert!(i <= self.segments);
+        self.word(HEADER_LEN + 4 * i)
+    }
+
+    /// Coefficients of segment i. Panics if `i >= segments()`.
+    pub fn segment(&self, i: usize) -> (f32, f32, f32, f32) {
+        assert!(i < self.segments);
+        let offset = HEADER_LEN + 4 * (self.segments + 1) + 16 * i;
+        (
+            self.word(offset),
+            self.word(offset + 4),
+            self.word(offset + 8),
+            self.word(offset + 12),
+        )
+    }
+
+    /// Evaluate the spline at global position x, extrapolating outside of the
+    /// knots according to `extrapolate`.
+    pub fn eval(&self, x: f32, extrapolate: Extrapolate) -> Result<f32, Error> {
+        let (polynomial, t) = locate_by(
+            |i| self.segment(i),
+            self.segments + 1,
+            |i| self.knot(i),
+            x,
+            extrapolate,
+        )?;
+        Ok(eval_segment(polynomial, t))
+    }
+
+    fn word(&self, offset: usize) -> f32 {
+        let bytes = &self.bytes[offset..offset + 4];
+        f32::from_le_bytes([bytes[0], bytes[1], bytes[2], bytes[3]])
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::eval;
+    use crate::fixture::{spline, XS};
+
+    #[test]
+    fn roundtrip() {
+        let coeffs = spline();
+        let mut buffer = [0xffu8; 128];
+        assert_eq!(Ok(serialized_len(4)), to_bytes(&coeffs, &XS, &mut buffer));
+        assert_eq!(96, serialized_len(4));
+        assert_eq!([2, 0, 0, 0, 4, 0, 0, 0], buffer[..8]);
+        assert_eq!(1f32.to_le_bytes(), buffer[12..16]);
+
+        let mut xs = [0f32; 8];
+        let mut out = [(0f32, 0f32, 0f32, 0f32); 8];
+        assert_eq!(Ok(4), from_bytes(&buffer, &mut out, &mut xs));
+        assert_eq!(XS, xs[..5]);
+        assert_eq!(coeffs, out[..4]);
+
+        let view = load_checked(&buffer).unwrap();
+        assert_eq!(96, view.as_bytes().len());
+        for i in 0..=50 {
+            let x = i as f32 / 10f32 - 0.5;
+            let extrapolate = Extrapolate::LinearFromEndSlope;
+            assert_eq!(
+                eval(&coeffs, &XS, x, extrapolate),
+                view.eval(x, extrapolate)
+            );
+        }
+    }
+
+    #[test]
+    fn rejects_malformed_bytes() {
+        let coeffs = spline();
+        let mut buffer = [0u8; 96];
+        assert_eq!(
+            Err(Error::CapacityExceeded),
+            to_bytes(&coeffs, &XS, &mut buffer[..95])
+        );
+        to_bytes(&coeffs, &XS, &mut buffer).unwrap();
+        assert!(matches!(
+            SplineView::new(&buffer[..95]),
+            Err(Error::InvalidFormat)
+        ));
+        let mut xs = [0f32; 4];
+        let mut out = [(0f32, 0f32, 0f32, 0f32); 4];
+        assert_eq!(
+            Err(Error::CapacityExceeded),
+            from_bytes(&buffer, &mut out, &mut xs)
+        );
+
+        let mut corrupted = buffer;
+        corrupted[0] = 1;
+        assert!(matches!(
+            SplineView::new(&corrupted),
+            Err(Error::InvalidFormat)
+        ));
+        let mut corrupted = buffer;
+        corrupted[7] = 0xff;
+        assert!(matches!(
+            SplineView::new(&corrupted),
+            Err(Error::InvalidFormat)
+        ));
+        let mut corrupted = buffer;
+        corrupted[4] = 0;
+        assert!(matches!(
+            SplineView::new(&corrupted),
+            Err(Error::EmptyInput)
+        ));
+    }
+
+    #[test]
+    fn crc_of_check_string() {
+        assert_eq!(0xCBF4_3926, crc32(b"123456789"));
+        assert_eq!(0, crc32(&[]));
+    }
+
+    #[test]
+    fn load_checked_detects_corruption() {
+        let coeffs = spline();
+        let mut buffer = [0u8; 96];
+        to_bytes(&coeffs, &XS, &mut buffer).unwrap();
+        assert!(load_checked(&buffer).is_ok());
+
+        for byte in 0..92 {
+            let mut corrupted = buffer;
+            corrupted[byte] ^= 0x10;
+            assert!(load_checked(&corrupted).is_err(), "byte {}", byte);
+        }
+        let mut corrupted = buffer;
+        corrupted[40] ^= 0x01;
+        assert!(SplineView::new(&corrupted).is_ok());
+        assert_eq!(
+            Err(Error::ChecksumMismatch),
+            load_checked(&corrupted).map(|_| ())
+        );
+        let mut xs = [0f32; 5];
+        let mut out = [(0f32, 0f32, 0f32, 0f32); 4];
+        assert_eq!(
+            Err(Error::ChecksumMismatch),
+            from_bytes(&corrupted, &mut out, &mut xs)
+        );
+    }
+
+    #[test]
+    fn load_checked_rejects_unsorted_knots() {
+        // Knots swapped with a valid CRC, e.g. from a buggy table generator
+        let coeffs = spline();
+        let mut buffer = [0u8; 96];
+        to_bytes(&coeffs, &XS, &mut buffer).unwrap();
+        buffer[16..20].copy_from_slice(&0.5f32.to_le_bytes());
+        let crc = crc32(&buffer[..92]);
+        buffer[92..].copy_from_slice(&crc.to_le_bytes());
+        assert_eq!(
+            Err(Error::NonIncreasingKnots { index: 2 }),
+            load_checked(&buffer).map(|_| ())
+        );
+    }
+}
@@ -0,0 +1,340 @@
+//! Temperature profiles of ramps and soaks, e.g. for reflow ovens, kilns or
+//! sous-vide cookers.
+//!
+//! A [`ThermalProfile`] is built from a list of [`Phase`]s, each ending at a
+//! temperature after a duration. The temperature setpoint between the ends
+//! of the phases is a monotone cubic, so it changes smoothly, stays exactly
+//! flat during a soak and never overshoots the temperature a phase ends at.
+
+use crate::monotone::monotone_slices;
+use crate::{eval_segment, eval_segment_derivative, find_segment, Error};
+
+/// A phase of a [`ThermalProfile`]. Durations are in seconds, or whatever
+/// unit the profile is evaluated in, and must be strictly positive.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum Phase {
+    /// Heat or cool to `to` within `duration`
+    Ramp {
+        /// Temperature at the end
+        to: f32,
+        /// Duration of the phase
+        duration: f32,
+    },
+    /// Hold the temperature for `duration`
+    Soak {
+        /// Duration of the phase
+        duration: f32,
+    },
+    /// Heat to `peak` in the middle of `duration` and return to the
+    /// temperature at the start of the phase at its end, e.g. for the time
+    /// above liquidus of a solder
+    Reflow {
+        /// Temperature in the middle
+        peak: f32,
+        /// Duration of the phase
+        duration: f32,
+    },
+    /// Cool to `to` within `duration`, the same as a ramp
+    Cool {
+        /// Temperature at the end
+        to: f32,
+        /// Duration of the phase
+        duration: f32,
+    },
+}
+
+/// The earliest time a [`ThermalProfile`] heats or cools faster than allowed.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct SlopeViolation {
+    /// Time of the steepest point
+    pub t: f32,
+    /// Rate of the temperature change there, negative for cooling
+    pub slope: f32,
+}
+
+/// A temperature profile through up to `N` setpoints, which the phases end at.
+///
+/// The profile starts at time 0. Before that, it holds the start temperature,
+/// and after its end, it holds the last one.
+///
+/// ```
+/// use cubic_splinterpol::thermal::{Phase, ThermalProfile};
+///
+/// // Lead-free reflow
+/// let profile = ThermalProfile::<8>::new(
+///     25.0,
+///     &[
+///         Phase::Ramp { to: 150.0, duration: 90.0 },
+///         Phase::Ramp { to: 180.0, duration: 90.0 },
+///         Phase::Reflow { peak: 245.0, duration: 60.0 },
+///         Phase::Cool { to: 50.0, duration: 60.0 },
+///     ],
+/// )
+/// .unwrap();
+/// assert_eq!(300.0, profile.duration());
+/// assert_eq!(245.0, profile.temperature(210.0));
+/// assert_eq!(Some(2), profile.phase(200.0));
+/// // Heats at just over 3 °C/s on the way to the peak
+/// assert!(profile.check_slope(3.5, 6.0).is_ok());
+/// let violation = profile.check_slope(3.0, 6.0).unwrap_err();
+/// assert_eq!(Some(2), profile.phase(violation.t));
+/// ```
+#[derive(Debug, Clone)]
+pub struct ThermalProfile<const N: usize> {
+    ts: [f32; N],
+    temperatures: [f32; N],
+    /// len-1 of N used
+    coefficients: [(f32, f32, f32, f32); N],
+    /// Index of the setpoint every phase ends at, as many as there are phases
+    phase_ends: [usize; N],
+    len: usize,
+    phases: usize,
+}
+
+impl<const N: usize> ThermalProfile<N> {
+    /// A profile starting at `start`, through the `phases` in order. Fails
+    /// with [`Error::EmptyInput`] without phases, [`Error::InvalidLimit`] for
+    /// a duration which is not strictly positive or a temperature of NaN, and
+    /// [`Error::CapacityExceeded`] if the phases end at more than `N - 1`
+    /// setpoints, where every reflow counts twice.
+    pub fn new(start: f32, phases: &[Phase]) -> Result<Self, Error> {
+        if phases.is_empty() {
+            return Err(Error::EmptyInput);
+        }
+        let mut profile = Self {
+            ts: [0f32; N],
+            temperatures: [0f32; N],
+            coefficients: [(0f32, 0f32, 0f32, 0f32); N],
+            phase_ends: [0; N],
+            len: 0,
+            phases: 0,
+        };
+        profile.push(0f32, start)?;
+        for phase in phases {
+            let (t, temperature) = (
+                profile.ts[profile.len - 1],
+                profile.temperatures[profile.len - 1],
+            );
+            match *phase {
+                Phase::Ramp { to, duration } | Phase::Cool { to, duration } => {
+                    check_duration(duration)?;
+                    profile.push(t + duration, to)?;
+                }
+                Phase::Soak { duration } => {
+                    check_duration(duration)?;
+                    profile.push(t + duration, temperature)?;
+                }
+                Phase::Reflow { peak, duration } => {
+                    check_duration(duration)?;
+                    profile.push(t + 0.5 * duration, peak)?;
+                    profile.push(t + duration, temperature)?;
+                }
+            }
+            profile.phase_ends[profile.phases] = profile.len - 1;
+            profile.phases += 1;
+        }
+        let len = profile.len;
+        monotone_slices(
+            &profile.ts[..len],
+            &profile.temperatures[..len],
+            &mut profile.coefficients[..len - 1],
+        )?;
+        Ok(profile)
+    }
+
+    fn push(&mut self, t: f32, temperature: f32) -> Result<(), Error> {
+        if temperature.is_nan() {
+            return Err(Error::InvalidLimit);
+        }
+        if self.len == N {
+            return Err(Error::CapacityExceeded);
+        }
+        self.ts[self.len] = t;
+        self.temperatures[self.len] = temperature;
+        self.len += 1;
+        Ok(())
+    }
+
+    /// The times of the setpoints.
+    pub fn ts(&self) -> &[f32] {
+        &self.ts[..self.len]
+    }
+
+    /// The temperatures at the setpoints.
+    pub fn temperatures(&self) -> &[f32] {
+        &self.temperatures[..self.len]
+    }
+
+    /// The coefficients of the segments between the setpoints.
+    pub fn coefficients(&self) -> &[(f32, f32, f32, f32)] {
+        &self.coefficients[..self.len - 1]
+    }
+
+    /// Time from the start to the end of the last phase.
+    pub fn duration(&self) -> f32 {
+        self.ts[self.len - 1]
+    }
+
+    /// Index of the phase running at time t, or `None` before the start and
+    /// after the end.
+    pub fn phase(&self, t: f32) -> Option<usize> {
+        if t.is_nan() || t < 0f32 || t > self.duration() {
+            return None;
+        }
+        self.phase_ends[..self.phases]
+            .iter()
+            .position(|end| t <= self.ts[*end])
+    }
+
+    /// Temperature setpoint at time t, e.g. the wall-clock time since the
+    /// start of the oven program.
+    pub fn temperature(&self, t: f32) -> f32 {
+        let last = self.len - 1;
+        match self.locate(t) {
+            Some((segment, t)) => eval_segment(self.coefficients[segment], t),
+            None if t < 0f32 => self.temperatures[0],
+            None => self.temperatures[last],
+        }
+    }
+
+    /// Rate of change of the temperature setpoint at time t, zero outside of
+    /// the profile.
+    pub fn slope(&self, t: f32) -> f32 {
+        match self.locate(t) {
+            Some((segment, t)) => eval_segment_derivative(self.coefficients[segment], t),
+            None => 0f32,
+        }
+    }
+
+    /// Check that the profile never heats faster than `max_heating` or cools
+    /// faster than `max_cooling`, both given as positive rates, e.g. to avoid
+    /// thermal shock to components.
+    ///
+    /// The slope is quadratic within each segment, so it peaks at a setpoint
+    /// or where its own derivative vanishes, which are checked instead of
+    /// sampling.
+    pub fn check_slope(&self, max_heating: f32, max_cooling: f32) -> Result<(), SlopeViolation> {
+        for (segment, coefficients) in self.coefficients().iter().enumerate() {
+            let h = self.ts[segment + 1] - self.ts[segment];
+            let (_, _, c, d) = *coefficients;
+            let turn = -c / (3f32 * d);
+            let turn = if turn > 0f32 && turn < h { turn } else { h };
+            for t in [0f32, turn, h] {
+                let slope = eval_segment_derivative(*coefficients, t);
+                if slope > max_heating || -slope > max_cooling {
+                    return Err(SlopeViolation {
+                        t: self.ts[segment] + t,
+                        slope,
+                    });
+                }
+            }
+        }
+        Ok(())
+    }
+
+    /// Segment and time relative to its start, or `None` outside of the
+    /// profile.
+    fn locate(&self, t: f32) -> Option<(usize, f32)> {
+        if t.is_nan() || t < 0f32 || t > self.duration() {
+            return None;
+        }
+        let segment = find_segment(self.ts(), t);
+        Some((segment, t - self.ts[segment]))
+    }
+}
+
+fn check_duration(duration: f32) -> Result<(), Error> {
+    if duration.is_nan() || duration <= 0f32 {
+        return Err(Error::InvalidLimit);
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const REFLOW: [Phase; 4] = [
+        Phase::Ramp {
+            to: 150.0,
+            duration: 60.0,
+        },
+        Phase::Soak { duration: 90.0 },
+        Phase::Reflow {
+            peak: 240.0,
+            duration: 40.0,
+        },
+        Phase::Cool {
+            to: 40.0,
+            duration: 50.0,
+        },
+    ];
+
+    #[test]
+    fn phases_end_at_setpoints() {
+        let profile = ThermalProfile::<6>::new(20.0, &REFLOW).unwrap();
+        assert_eq!(&[0.0, 60.0, 150.0, 170.0, 190.0, 240.0], profile.ts());
+        assert_eq!(
+            &[20.0, 150.0, 150.0, 240.0, 150.0, 40.0],
+            profile.temperatures()
+        );
+        // Flat soak, no overshoot anywhere
+        for i in 0..=240 {
+            let t = i as f32;
+            let temperature = profile.temperature(t);
+            assert!((20f32..=240.0).contains(&temperature));
+            if (60.0..=150.0).contains(&t) {
+                assert_eq!(150.0, temperature);
+            }
+        }
+        assert_eq!(20.0, profile.temperature(-5.0));
+        assert_eq!(40.0, profile.temperature(300.0));
+        assert_eq!(0.0, profile.slope(300.0));
+        assert_eq!(Some(0), profile.phase(0.0));
+        assert_eq!(Some(1), profile.phase(100.0));
+        assert_eq!(Some(3), profile.phase(240.0));
+        assert_eq!(None, profile.phase(241.0));
+    }
+
+    #[test]
+    fn check_slope_finds_steepest_point() {
+        let profile = ThermalProfile::<6>::new(20.0, &REFLOW).unwrap();
+        let mut heating = 0f32;
+        let mut cooling = 0f32;
+        for i in 0..=24000 {
+            let slope = profile.slope(i as f32 / 100f32);
+            heating = heating.max(slope);
+            cooling = cooling.max(-slope);
+        }
+        assert_eq!(Ok(()), profile.check_slope(heating + 0.01, cooling + 0.01));
+        let violation = profile.check_slope(heating - 0.01, cooling + 0.01);
+        assert!(violation.unwrap_err().slope > 0.0);
+        let violation = profile.check_slope(heating + 0.01, cooling - 0.01);
+        let violation = violation.unwrap_err();
+        assert!(violation.slope < 0.0 && violation.t > 170.0);
+    }
+
+    #[test]
+    fn rejects_invalid_phases() {
+        assert_eq!(
+            Err(Error::CapacityExceeded),
+            ThermalProfile::<5>::new(20.0, &REFLOW).map(|_| ())
+        );
+        assert_eq!(
+            Err(Error::EmptyInput),
+            ThermalProfile::<5>::new(20.0, &[]).map(|_| ())
+        );
+        assert_eq!(
+            Err(Error::InvalidLimit),
+            ThermalProfile::<5>::new(20.0, &[Phase::Soak { duration: 0.0 }]).map(|_| ())
+        );
+        let nan = Phase::Ramp {
+            to: f32::NAN,
+            duration: 1.0,
+        };
+        assert_eq!(
+            Err(Error::InvalidLimit),
+            ThermalProfile::<5>::new(20.0, &[nan]).map(|_| ())
+        );
+    }
+}
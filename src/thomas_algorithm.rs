@@ -1,20 +1,51 @@
+//! Solvers for tridiagonal linear systems, as they arise when computing the
+//! coefficients of cubic splines.
+
 #![deny(unsafe_code)]
 #![deny(missing_docs)]
 
 use crate::Error;
 
+/// Pivots with an absolute value at or below this threshold are treated as
+/// zero by the solvers which do not take an explicit epsilon.
+pub const DEFAULT_PIVOT_EPSILON: f32 = f32::MIN_POSITIVE;
+
+pub(crate) fn check_pivot(pivot: f32, row: usize, epsilon: f32) -> Result<(), Error> {
+    // Written this way round so that a NaN pivot is rejected as well
+    if pivot.abs() > epsilon {
+        Ok(())
+    } else {
+        Err(Error::SingularSystem { row })
+    }
+}
+
 /// Solve Ax = r for A being tridiagonal. That is, A must have non-zero values
 /// only on the main diagonal and the upper and lower sub-diagonals.
-#[allow(dead_code)]
+///
+/// `main` and `r` are used as scratch space and are overwritten.
 pub fn thomas_algorithm(
     lower: &[f32],
     main: &mut [f32],
     upper: &[f32],
     r: &mut [f32],
     x: &mut [f32],
+) -> Result<(), Error> {
+    thomas_algorithm_with_epsilon(lower, main, upper, r, x, DEFAULT_PIVOT_EPSILON)
+}
+
+/// Solve Ax = r for A being tridiagonal, like [`thomas_algorithm`], rejecting
+/// the system with [`Error::SingularSystem`] as soon as a pivot with an
+/// absolute value at or below `epsilon` is encountered.
+pub fn thomas_algorithm_with_epsilon(
+    lower: &[f32],
+    main: &mut [f32],
+    upper: &[f32],
+    r: &mut [f32],
+    x: &mut [f32],
+    epsilon: f32,
 ) -> Result<(), Error> {
     let n = main.len();
-    if n < 4 {
+    if n == 0 {
         return Err(Error::InvalidSliceLength);
     }
     if lower.len() != n - 1 {
@@ -30,13 +61,15 @@ pub fn thomas_algorithm(
         return Err(Error::InvalidSliceLength);
     }
     for i in 1..n {
+        check_pivot(main[i - 1], i - 1, epsilon)?;
         let mc = lower[i - 1] / main[i - 1];
         main[i] -= mc * upper[i - 1];
         r[i] -= mc * r[i - 1];
     }
+    check_pivot(main[n - 1], n - 1, epsilon)?;
     x[n - 1] = r[n - 1] / main[n - 1];
 
-    for i in (0..=(n - 2)).rev() {
+    for i in (0..n - 1).rev() {
         x[i] = (r[i] - upper[i] * x[i + 1]) / main[i];
     }
     Ok(())
@@ -45,14 +78,30 @@ pub fn thomas_algorithm(
 /// Solve Ax = r for A being tridiagonal and symmetric. That is, A must have
 /// non-zero values only on the main diagonal and the upper and lower
 /// sub-diagonals, and the values on the sub-diagonals must be equal.
+///
+/// `main` and `r` are used as scratch space and are overwritten.
 pub fn thomas_algorithm_symmetric(
     sub_diagonal: &[f32],
     main: &mut [f32],
     r: &mut [f32],
     x: &mut [f32],
+) -> Result<(), Error> {
+    thomas_algorithm_symmetric_with_epsilon(sub_diagonal, main, r, x, DEFAULT_PIVOT_EPSILON)
+}
+
+/// Solve Ax = r for A being tridiagonal and symmetric, like
+/// [`thomas_algorithm_symmetric`], rejecting the system with
+/// [`Error::SingularSystem`] as soon as a pivot with an absolute value at or
+/// below `epsilon` is encountered.
+pub fn thomas_algorithm_symmetric_with_epsilon(
+    sub_diagonal: &[f32],
+    main: &mut [f32],
+    r: &mut [f32],
+    x: &mut [f32],
+    epsilon: f32,
 ) -> Result<(), Error> {
     let n = main.len();
-    if n < 4 {
+    if n == 0 {
         return Err(Error::InvalidSliceLength);
     }
     if sub_diagonal.len() != n - 1 {
@@ -65,18 +114,354 @@ pub fn thomas_algorithm_symmetric(
         return Err(Error::InvalidSliceLength);
     }
     for i in 1..n {
+        check_pivot(main[i - 1], i - 1, epsilon)?;
         let mc = sub_diagonal[i - 1] / main[i - 1];
         main[i] -= mc * sub_diagonal[i - 1];
         r[i] -= mc * r[i - 1];
     }
+    check_pivot(main[n - 1], n - 1, epsilon)?;
     x[n - 1] = r[n - 1] / main[n - 1];
 
-    for i in (0..=(n - 2)).rev() {
+    for i in (0..n - 1).rev() {
         x[i] = (r[i] - sub_diagonal[i] * x[i + 1]) / main[i];
     }
     Ok(())
 }
 
+/// Solve Ax = r for A being tridiagonal, like [`thomas_algorithm`], but
+/// without modifying any of the inputs.
+///
+/// `workspace` must have the same length as `main`. It receives the
+/// eliminated main diagonal, while `x` doubles as storage for the eliminated
+/// right hand side.
+pub fn thomas_algorithm_with_workspace(
+    lower: &[f32],
+    main: &[f32],
+    upper: &[f32],
+    r: &[f32],
+    x: &mut [f32],
+    workspace: &mut [f32],
+) -> Result<(), Error> {
+    let n = main.len();
+    if n == 0 {
+        return Err(Error::InvalidSliceLength);
+    }
+    if lower.len() != n - 1 {
+        return Err(Error::InvalidSliceLength);
+    }
+    if upper.len() != n - 1 {
+        return Err(Error::InvalidSliceLength);
+    }
+    if r.len() != n {
+        return Err(Error::InvalidSliceLength);
+    }
+    if x.len() != n {
+        return Err(Error::InvalidSliceLength);
+    }
+    if workspace.len() != n {
+        return Err(Error::InvalidSliceLength);
+    }
+    workspace.copy_from_slice(main);
+    x.copy_from_slice(r);
+    for i in 1..n {
+        check_pivot(workspace[i - 1], i - 1, DEFAULT_PIVOT_EPSILON)?;
+        let mc = lower[i - 1] / workspace[i - 1];
+        workspace[i] -= mc * upper[i - 1];
+        x[i] -= mc * x[i - 1];
+    }
+    check_pivot(workspace[n - 1], n - 1, DEFAULT_PIVOT_EPSILON)?;
+    x[n - 1] /= workspace[n - 1];
+
+    for i in (0..n - 1).rev() {
+        x[i] = (x[i] - upper[i] * x[i + 1]) / workspace[i];
+    }
+    Ok(())
+}
+
+/// Solve Ax = r for A being tridiagonal and symmetric, like
+/// [`thomas_algorithm_symmetric`], but without modifying any of the inputs.
+///
+/// `workspace` must have the same length as `main`. It receives the
+/// eliminated main diagonal, while `x` doubles as storage for the eliminated
+/// right hand side.
+pub fn thomas_algorithm_symmetric_with_workspace(
+    sub_diagonal: &[f32],
+    main: &[f32],
+    r: &[f32],
+    x: &mut [f32],
+    workspace: &mut [f32],
+) -> Result<(), Error> {
+    thomas_algorithm_with_workspace(sub_diagonal, main, sub_diagonal, r, x, workspace)
+}
+
+/// Solve Ax = r for A being tridiagonal, copying `main` and `r` into internal
+/// scratch buffers of const size `N` so that the inputs are left untouched.
+pub fn thomas_algorithm_copied<const N: usize>(
+    lower: &[f32],
+    main: &[f32; N],
+    upper: &[f32],
+    r: &[f32; N],
+    x: &mut [f32; N],
+) -> Result<(), Error> {
+    let mut workspace = [0f32; N];
+    thomas_algorithm_with_workspace(lower, main, upper, r, x, &mut workspace)
+}
+
+/// Elimination of a symmetric tridiagonal matrix of at most `N` rows, from
+/// [`factorize`], for solving systems with many right hand sides by [`solve`].
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Factorization<const N: usize> {
+    sub_diagonal: [f32; N],
+    // Main diagonal after elimination
+    main: [f32; N],
+    multipliers: [f32; N],
+    len: usize,
+}
+
+impl<const N: usize> Factorization<N> {
+    /// Number of rows of the matrix.
+    pub fn len(&self) -> usize {
+        self.len
+    }
+
+    /// Whether the matrix has no rows, which [`factorize`] never returns.
+    pub fn is_empty(&self) -> bool {
+        self.len == 0
+    }
+}
+
+/// Eliminate the symmetric tridiagonal matrix given by `sub_diagonal` and
+/// `main` once, so that [`solve`] can solve Ax = r for any number of right
+/// hand sides without repeating the work, e.g. for many channels or frames
+/// interpolated on the same knots.
+///
+/// `main` must hold between one and `N` elements. Fails with
+/// [`Error::SingularSystem`] like [`thomas_algorithm_symmetric`].
+///
+/// ```
+/// use cubic_splinterpol::thomas_algorithm::{factorize, solve};
+///
+/// let factorization = factorize::<3>(&[1.0, 1.0], &[4.0, 4.0, 4.0]).unwrap();
+/// let mut x = [0f32; 3];
+/// for r in [[5f32, 6.0, 5.0], [4.0, 1.0, 0.0]] {
+///     solve(&factorization, &r, &mut x).unwrap();
+///     assert!((x[0] * 4.0 + x[1] - r[0]).abs() < 1e-6);
+/// }
+/// ```
+pub fn factorize<const N: usize>(
+    sub_diagonal: &[f32],
+    main: &[f32],
+) -> Result<Factorization<N>, Error> {
+    let n = main.len();
+    if n == 0 || n > N || sub_diagonal.len() != n - 1 {
+        return Err(Error::InvalidSliceLength);
+    }
+    let mut factorization = Factorization {
+        sub_diagonal: [0f32; N],
+        main: [0f32; N],
+        multipliers: [0f32; N],
+        len: n,
+    };
+    factorization.sub_diagonal[..n - 1].copy_from_slice(sub_diagonal);
+    factorization.main[..n].copy_from_slice(main);
+    for i in 1..n {
+        check_pivot(factorization.main[i - 1], i - 1, DEFAULT_PIVOT_EPSILON)?;
+        let mc = sub_diagonal[i - 1] / factorization.main[i - 1];
+        factorization.multipliers[i] = mc;
+        factorization.main[i] -= mc * sub_diagonal[i - 1];
+    }
+    check_pivot(factorization.main[n - 1], n - 1, DEFAULT_PIVOT_EPSILON)?;
+    Ok(factorization)
+}
+
+/// Solve Ax = r for the matrix A eliminated by [`factorize`], with the same
+/// result as [`thomas_algorithm_symmetric`]. `r` and `x` must have as many
+/// elements as A has rows.
+pub fn solve<const N: usize>(
+    factorization: &Factorization<N>,
+    r: &[f32],
+    x: &mut [f32],
+) -> Result<(), Error> {
+    let n = factorization.len;
+    if r.len() != n || x.len() != n {
+        return Err(Error::InvalidSliceLength);
+    }
+    let Factorization {
+        sub_diagonal,
+        main,
+        multipliers,
+        ..
+    } = factorization;
+    x.copy_from_slice(r);
+    for i in 1..n {
+        x[i] -= multipliers[i] * x[i - 1];
+    }
+    x[n - 1] /= main[n - 1];
+    for i in (0..n - 1).rev() {
+        x[i] = (x[i] - sub_diagonal[i] * x[i + 1]) / main[i];
+    }
+    Ok(())
+}
+
+/// Solve Ax = r for A being tridiagonal, with the sub-diagonals given as
+/// functions of the row instead of slices, and the solution written over the
+/// right hand side in `rx`. Same arithmetic as [`thomas_algorithm`], so the
+/// spline pipeline needs no arrays for entries it can compute from the knots.
+///
+/// `main` is used as scratch space and is overwritten.
+pub(crate) fn thomas_algorithm_in_place(
+    lower: impl Fn(usize) -> f32,
+    main: &mut [f32],
+    upper: impl Fn(usize) -> f32,
+    rx: &mut [f32],
+) -> Result<(), Error> {
+    let n = main.len();
+    if n == 0 || rx.len() != n {
+        return Err(Error::InvalidSliceLength);
+    }
+    for i in 1..n {
+        check_pivot(main[i - 1], i - 1, DEFAULT_PIVOT_EPSILON)?;
+        let mc = lower(i - 1) / main[i - 1];
+        main[i] -= mc * upper(i - 1);
+        rx[i] -= mc * rx[i - 1];
+    }
+    check_pivot(main[n - 1], n - 1, DEFAULT_PIVOT_EPSILON)?;
+    rx[n - 1] /= main[n - 1];
+
+    for i in (0..n - 1).rev() {
+        rx[i] = (rx[i] - upper(i) * rx[i + 1]) / main[i];
+    }
+    Ok(())
+}
+
+/// Selects the algorithm used to solve the tridiagonal spline system.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum Solver {
+    /// Plain Thomas elimination without pivoting. Fastest, and accurate for
+    /// diagonally dominant systems.
+    #[default]
+    Thomas,
+    /// Gaussian elimination with partial pivoting, see
+    /// [`tridiagonal_partial_pivoting`]. Slower, but more robust for
+    /// ill-conditioned systems.
+    PartialPivoting,
+}
+
+/// Solve Ax = r for A being tridiagonal using Gaussian elimination with
+/// partial pivoting. Whenever the sub-diagonal entry is larger in magnitude
+/// than the current pivot, the two rows are interchanged, which keeps the
+/// elimination factors bounded by one.
+///
+/// Row interchanges introduce a second super-diagonal, which is stored in
+/// `lower`. `lower`, `main`, `upper` and `r` are used as scratch space and are
+/// overwritten.
+pub fn tridiagonal_partial_pivoting(
+    lower: &mut [f32],
+    main: &mut [f32],
+    upper: &mut [f32],
+    r: &mut [f32],
+    x: &mut [f32],
+) -> Result<(), Error> {
+    let n = main.len();
+    if n == 0 {
+        return Err(Error::InvalidSliceLength);
+    }
+    if lower.len() != n - 1 {
+        return Err(Error::InvalidSliceLength);
+    }
+    if upper.len() != n - 1 {
+        return Err(Error::InvalidSliceLength);
+    }
+    if r.len() != n {
+        return Err(Error::InvalidSliceLength);
+    }
+    if x.len() != n {
+        return Err(Error::InvalidSliceLength);
+    }
+    for i in 0..n - 1 {
+        if main[i].abs() >= lower[i].abs() {
+            // No row interchange required
+            check_pivot(main[i], i, DEFAULT_PIVOT_EPSILON)?;
+            let fact = lower[i] / main[i];
+            main[i + 1] -= fact * upper[i];
+            r[i + 1] -= fact * r[i];
+            lower[i] = 0f32;
+        } else {
+            // Interchange rows i and i + 1
+            let fact = main[i] / lower[i];
+            main[i] = lower[i];
+            let temp = main[i + 1];
+            main[i + 1] = upper[i] - fact * temp;
+            if i < n - 2 {
+                lower[i] = upper[i + 1];
+                upper[i + 1] = -fact * lower[i];
+            } else {
+                lower[i] = 0f32;
+            }
+            upper[i] = temp;
+            let temp = r[i];
+            r[i] = r[i + 1];
+            r[i + 1] = temp - fact * r[i + 1];
+        }
+    }
+    check_pivot(main[n - 1], n - 1, DEFAULT_PIVOT_EPSILON)?;
+
+    x[n - 1] = r[n - 1] / main[n - 1];
+    if n >= 2 {
+        x[n - 2] = (r[n - 2] - upper[n - 2] * x[n - 1]) / main[n - 2];
+    }
+    for i in (0..n.saturating_sub(2)).rev() {
+        x[i] = (r[i] - upper[i] * x[i + 1] - lower[i] * x[i + 2]) / main[i];
+    }
+    Ok(())
+}
+
+/// Solve Ax = r for A being cyclic tridiagonal: tridiagonal, plus the two
+/// corner entries `corners = (A[n-1][0], A[0][n-1])`. Such systems arise for
+/// periodic splines.
+///
+/// The system is solved as a tridiagonal system with a rank one correction
+/// (Sherman-Morrison), which takes two tridiagonal solves. `main` is used as
+/// scratch space and is overwritten, `scratch` must hold `3 * n` elements.
+pub fn thomas_algorithm_cyclic(
+    lower: &[f32],
+    main: &mut [f32],
+    upper: &[f32],
+    corners: (f32, f32),
+    r: &[f32],
+    x: &mut [f32],
+    scratch: &mut [f32],
+) -> Result<(), Error> {
+    let n = main.len();
+    if n < 3 {
+        return Err(Error::InvalidSliceLength);
+    }
+    if scratch.len() != 3 * n {
+        return Err(Error::InvalidSliceLength);
+    }
+    let (alpha, beta) = corners;
+    check_pivot(main[0], 0, DEFAULT_PIVOT_EPSILON)?;
+    let gamma = -main[0];
+    main[0] -= gamma;
+    main[n - 1] -= alpha * beta / gamma;
+
+    let (u, rest) = scratch.split_at_mut(n);
+    let (z, workspace) = rest.split_at_mut(n);
+    thomas_algorithm_with_workspace(lower, main, upper, r, x, workspace)?;
+
+    // Correction vector u = (gamma, 0, ..., 0, alpha)
+    u.iter_mut().for_each(|elem| *elem = 0f32);
+    u[0] = gamma;
+    u[n - 1] = alpha;
+    thomas_algorithm_with_workspace(lower, main, upper, u, z, workspace)?;
+
+    let fact = (x[0] + beta * x[n - 1] / gamma) / (1f32 + z[0] + beta * z[n - 1] / gamma);
+    for (x, z) in x.iter_mut().zip(z.iter()) {
+        *x -= fact * z;
+    }
+    Ok(())
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -187,4 +572,200 @@ mod tests {
         ];
         assert_eq!(expected, x);
     }
+
+    #[test]
+    fn thomas_algorithm_with_workspace_preserves_inputs() {
+        let lower = [3f32, 1f32, 3f32];
+        let main = [10f32, 10f32, 7f32, 4f32];
+        let upper = [2f32, 4f32, 5f32];
+        let r = [3f32, 4f32, 5f32, 6f32];
+
+        let mut x = [0f32; 4];
+        let mut workspace = [0f32; 4];
+        thomas_algorithm_with_workspace(&lower, &main, &upper, &r, &mut x, &mut workspace).unwrap();
+
+        let expected = [0.14877588, 0.7561206, -1.0018834, 2.2514126];
+        assert_eq!(expected, x);
+        assert_eq!([10f32, 10f32, 7f32, 4f32], main);
+        assert_eq!([3f32, 4f32, 5f32, 6f32], r);
+
+        let mut y = [0f32; 4];
+        thomas_algorithm_copied(&lower, &main, &upper, &r, &mut y).unwrap();
+        assert_eq!(x, y);
+    }
+
+    #[test]
+    fn thomas_algorithm_symmetric_with_workspace_matches_destructive() {
+        let main = [
+            3.0f32, 4.0f32, 5.0f32, 4.0f32, 3.0f32, 4.0f32, 4.0f32, 4.0f32, 4.0f32, 5.0f32, 4.0f32,
+            3.0f32, 4.0f32, 4.0f32,
+        ];
+        let sub = [
+            1.0f32, 1.0f32, 1.5f32, 0.5f32, 1.0f32, 1.0f32, 1.0f32, 1.0f32, 1.0f32, 1.5f32, 0.5f32,
+            1.0f32, 1.0f32,
+        ];
+        let r = [
+            3f32, 0f32, 1f32, 14f32, -12f32, -3f32, -9f32, 0f32, -3f32, 7f32, 2f32, -3f32, 3f32,
+            -3f32,
+        ];
+
+        let mut x = [0f32; 14];
+        let mut workspace = [0f32; 14];
+        thomas_algorithm_symmetric_with_workspace(&sub, &main, &r, &mut x, &mut workspace).unwrap();
+
+        let mut main_copy = main;
+        let mut r_copy = r;
+        let mut expected = [0f32; 14];
+        thomas_algorithm_symmetric(&sub, &mut main_copy, &mut r_copy, &mut expected).unwrap();
+        assert_eq!(expected, x);
+    }
+
+    #[test]
+    fn thomas_algorithm_with_workspace_checks_workspace_length() {
+        let lower = [3f32, 1f32, 3f32];
+        let main = [10f32, 10f32, 7f32, 4f32];
+        let upper = [2f32, 4f32, 5f32];
+        let r = [3f32, 4f32, 5f32, 6f32];
+        let mut x = [0f32; 4];
+        let mut workspace = [0f32; 3];
+        assert_eq!(
+            Err(Error::InvalidSliceLength),
+            thomas_algorithm_with_workspace(&lower, &main, &upper, &r, &mut x, &mut workspace)
+        );
+    }
+
+    #[test]
+    fn thomas_algorithm_detects_zero_pivot() {
+        let lower = [1f32, 1f32, 1f32];
+        let mut main = [1f32, 1f32, 2f32, 2f32];
+        let upper = [1f32, 1f32, 1f32];
+        let mut r = [1f32, 2f32, 3f32, 4f32];
+        let mut x = [0f32; 4];
+        assert_eq!(
+            Err(Error::SingularSystem { row: 1 }),
+            thomas_algorithm(&lower, &mut main, &upper, &mut r, &mut x)
+        );
+    }
+
+    #[test]
+    fn thomas_algorithm_symmetric_respects_epsilon() {
+        let sub = [1f32, 1f32, 1f32];
+        let mut main = [1f32, 1.001f32, 2f32, 2f32];
+        let mut r = [1f32, 2f32, 3f32, 4f32];
+        let mut x = [0f32; 4];
+        assert!(
+            thomas_algorithm_symmetric(&sub, &mut main.clone(), &mut r.clone(), &mut x).is_ok()
+        );
+        assert_eq!(
+            Err(Error::SingularSystem { row: 1 }),
+            thomas_algorithm_symmetric_with_epsilon(&sub, &mut main, &mut r, &mut x, 0.01)
+        );
+    }
+
+    #[test]
+    fn partial_pivoting_matches_thomas() {
+        let mut lower = [3f32, 1f32, 3f32];
+        let mut main = [10f32, 10f32, 7f32, 4f32];
+        let mut upper = [2f32, 4f32, 5f32];
+        let mut r = [3f32, 4f32, 5f32, 6f32];
+
+        let mut x = [0f32; 4];
+        tridiagonal_partial_pivoting(&mut lower, &mut main, &mut upper, &mut r, &mut x).unwrap();
+
+        let expected = [0.14877588, 0.7561206, -1.0018834, 2.2514126];
+        for (x, expected) in x.iter().zip(&expected) {
+            assert!((x - expected).abs() < 0.00001);
+        }
+    }
+
+    #[test]
+    fn partial_pivoting_solves_system_with_zero_leading_pivot() {
+        // Plain Thomas elimination fails immediately on this system
+        let lower = [1f32, 1f32, 1f32];
+        let main = [0f32, 1f32, 2f32, 1f32];
+        let upper = [1f32, 1f32, 1f32];
+        let r = [1f32, 3f32, 4f32, 2f32];
+
+        let mut x = [0f32; 4];
+        assert_eq!(
+            Err(Error::SingularSystem { row: 0 }),
+            thomas_algorithm(&lower, &mut main.clone(), &upper, &mut r.clone(), &mut x)
+        );
+
+        let (mut l, mut m, mut u, mut rr) = (lower, main, upper, r);
+        tridiagonal_partial_pivoting(&mut l, &mut m, &mut u, &mut rr, &mut x).unwrap();
+        let expected = [1f32, 1f32, 1f32, 1f32];
+        for (x, expected) in x.iter().zip(&expected) {
+            assert!((x - expected).abs() < 0.00001);
+        }
+    }
+
+    #[test]
+    fn cyclic_solves_periodic_system() {
+        let lower = [1f32, 1f32, 1f32];
+        let mut main = [4f32, 4f32, 4f32, 4f32];
+        let upper = [1f32, 1f32, 1f32];
+        // A x for x = (1, 2, 3, 4), with both corners 1
+        let r = [
+            4f32 + 2f32 + 4f32,
+            1f32 + 8f32 + 3f32,
+            2f32 + 12f32 + 4f32,
+            3f32 + 16f32 + 1f32,
+        ];
+        let mut x = [0f32; 4];
+        let mut scratch = [0f32; 12];
+        thomas_algorithm_cyclic(
+            &lower,
+            &mut main,
+            &upper,
+            (1f32, 1f32),
+            &r,
+            &mut x,
+            &mut scratch,
+        )
+        .unwrap();
+        let expected = [1f32, 2f32, 3f32, 4f32];
+        for (x, expected) in x.iter().zip(&expected) {
+            assert!((x - expected).abs() < 0.00001);
+        }
+    }
+
+    #[test]
+    fn factorization_matches_symmetric() {
+        let main = [
+            3.0f32, 4.0f32, 5.0f32, 4.0f32, 3.0f32, 4.0f32, 4.0f32, 4.0f32, 4.0f32, 5.0f32, 4.0f32,
+            3.0f32, 4.0f32, 4.0f32,
+        ];
+        let sub = [
+            1.0f32, 1.0f32, 1.5f32, 0.5f32, 1.0f32, 1.0f32, 1.0f32, 1.0f32, 1.0f32, 1.5f32, 0.5f32,
+            1.0f32, 1.0f32,
+        ];
+        let factorization = factorize::<16>(&sub, &main).unwrap();
+        assert_eq!(14, factorization.len());
+        let r = [
+            3f32, 0f32, 1f32, 14f32, -12f32, -3f32, -9f32, 0f32, -3f32, 7f32, 2f32, -3f32, 3f32,
+            -3f32,
+        ];
+        for scale in [1f32, -2.5] {
+            let mut scaled = r;
+            scaled.iter_mut().for_each(|elem| *elem *= scale);
+            let mut x = [0f32; 14];
+            solve(&factorization, &scaled, &mut x).unwrap();
+
+            let mut main_copy = main;
+            let mut expected = [0f32; 14];
+            thomas_algorithm_symmetric(&sub, &mut main_copy, &mut scaled, &mut expected).unwrap();
+            assert_eq!(expected, x);
+        }
+        let mut x = [0f32; 13];
+        assert_eq!(
+            Err(Error::InvalidSliceLength),
+            solve(&factorization, &r[..13], &mut x)
+        );
+        assert_eq!(Err(Error::InvalidSliceLength), factorize::<13>(&sub, &main));
+        assert_eq!(
+            Err(Error::SingularSystem { row: 1 }),
+            factorize::<3>(&[1.0, 1.0], &[1.0, 1.0, 1.0])
+        );
+    }
 }
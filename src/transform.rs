@@ -0,0 +1,225 @@
+use crate::{check_increasing, check_spline, rebase, Error};
+
+/// Shift the spline given by the knots `xs` by `dx` along x in place, e.g. to
+/// make a stored profile start now. The coefficients are relative to the
+/// start of their segments and stay as they are.
+///
+/// Fails with [`Error::NonIncreasingKnots`] and leaves `xs` untouched if
+/// rounding would collapse knots, for a shift far larger than their spacing.
+///
+/// ```
+/// use cubic_splinterpol::shift_x;
+///
+/// let mut xs = [0f32, 1.0, 3.0];
+/// shift_x(&mut xs, 10.0).unwrap();
+/// assert_eq!([10.0, 11.0, 13.0], xs);
+/// ```
+pub fn shift_x(xs: &mut [f32], dx: f32) -> Result<(), Error> {
+    map_knots(xs, |x| x + dx)
+}
+
+/// Stretch the spline given by `coefficients` and the knots `xs` in place by
+/// `factor` along x, about x = 0, so that it takes at `factor * x` the value
+/// it took at `x`, e.g. to play a motion profile at a different speed.
+///
+/// Fails with [`Error::InvalidLimit`] for a factor which is not strictly
+/// positive, see [`reverse`] to mirror a spline.
+///
+/// ```
+/// use cubic_splinterpol::{eval, scale_x, splinterpol, Extrapolate};
+///
+/// let mut xs = [0f32, 1.0, 2.0];
+/// let ys = [0f32, 1.0, 0.0];
+/// let mut coefficients = [(0f32, 0f32, 0f32, 0f32); 2];
+/// splinterpol(&xs, &ys, &mut coefficients).unwrap();
+/// let before = eval(&coefficients, &xs, 0.5, Extrapolate::Error).unwrap();
+///
+/// scale_x(&mut coefficients, &mut xs, 2.0).unwrap();
+/// assert_eq!([0.0, 2.0, 4.0], xs);
+/// let after = eval(&coefficients, &xs, 1.0, Extrapolate::Error).unwrap();
+/// assert!((before - after).abs() < 1e-6);
+/// ```
+pub fn scale_x(
+    coefficients: &mut [(f32, f32, f32, f32)],
+    xs: &mut [f32],
+    factor: f32,
+) -> Result<(), Error> {
+    check_spline(coefficients, xs)?;
+    if factor.is_nan() || factor <= 0f32 {
+        return Err(Error::InvalidLimit);
+    }
+    map_knots(xs, |x| x * factor)?;
+    // s(t / factor) within every segment
+    let inverse = 1f32 / factor;
+    for (_, b, c, d) in coefficients.iter_mut() {
+        *b *= inverse;
+        *c *= inverse * inverse;
+        *d *= inverse * inverse * inverse;
+    }
+    Ok(())
+}
+
+/// Shift the spline given by `coefficients` by `dy` along y in place.
+///
+/// ```
+/// use cubic_splinterpol::shift_y;
+///
+/// let mut coefficients = [(1f32, 2f32, 3f32, 4f32)];
+/// shift_y(&mut coefficients, -1.0).unwrap();
+/// assert_eq!([(0.0, 2.0, 3.0, 4.0)], coefficients);
+/// ```
+pub fn shift_y(coefficients: &mut [(f32, f32, f32, f32)], dy: f32) -> Result<(), Error> {
+    if coefficients.is_empty() {
+        return Err(Error::EmptyInput);
+    }
+    for (a, _, _, _) in coefficients.iter_mut() {
+        *a += dy;
+    }
+    Ok(())
+}
+
+/// Scale the spline given by `coefficients` by `factor` along y in place, e.g.
+/// to convert a stored profile to other units. A negative factor mirrors it.
+///
+/// ```
+/// use cubic_splinterpol::scale_y;
+///
+/// let mut coefficients = [(1f32, 2f32, 3f32, 4f32)];
+/// scale_y(&mut coefficients, -0.5).unwrap();
+/// assert_eq!([(-0.5, -1.0, -1.5, -2.0)], coefficients);
+/// ```
+pub fn scale_y(coefficients: &mut [(f32, f32, f32, f32)], factor: f32) -> Result<(), Error> {
+    if coefficients.is_empty() {
+        return Err(Error::EmptyInput);
+    }
+    for (a, b, c, d) in coefficients.iter_mut() {
+        *a *= factor;
+        *b *= factor;
+        *c *= factor;
+        *d *= factor;
+    }
+    Ok(())
+}
+
+/// Reverse the spline given by `coefficients` and the knots `xs` in place on
+/// the same interval, so that it runs from its end value to its start value,
+/// e.g. to retrace a motion profile. The first and last knot stay exactly as
+/// they are.
+///
+/// ```
+/// use cubic_splinterpol::{eval, reverse, splinterpol, Extrapolate};
+///
+/// let mut xs = [0f32, 1.0, 3.0];
+/// let ys = [0f32, 2.0, 1.0];
+/// let mut coefficients = [(0f32, 0f32, 0f32, 0f32); 2];
+/// splinterpol(&xs, &ys, &mut coefficients).unwrap();
+/// let before = eval(&coefficients, &xs, 0.5, Extrapolate::Error).unwrap();
+///
+/// reverse(&mut coefficients, &mut xs).unwrap();
+/// assert_eq!([0.0, 2.0, 3.0], xs);
+/// assert!((coefficients[0].0 - 1.0).abs() < 1e-6);
+/// let after = eval(&coefficients, &xs, 2.5, Extrapolate::Error).unwrap();
+/// assert!((before - after).abs() < 1e-6);
+/// ```
+pub fn reverse(coefficients: &mut [(f32, f32, f32, f32)], xs: &mut [f32]) -> Result<(), Error> {
+    check_spline(coefficients, xs)?;
+    let (start, end) = (xs[0], xs[xs.len() - 1]);
+    coefficients.reverse();
+    // Widths of the segments in their new order
+    for (polynomial, pair) in coefficients.iter_mut().zip(xs.windows(2).rev()) {
+        // p(h - t) from the expansion around the old end of the segment
+        let (a, b, c, d) = rebase(*polynomial, pair[1] - pair[0]);
+        *polynomial = (a, -b, c, -d);
+    }
+    xs.reverse();
+    for x in xs.iter_mut() {
+        *x = start + end - *x;
+    }
+    let last = xs.len() - 1;
+    xs[0] = start;
+    xs[last] = end;
+    Ok(())
+}
+
+/// Apply `f` to all knots, unless the result would not be strictly increasing.
+fn map_knots(xs: &mut [f32], f: impl Fn(f32) -> f32) -> Result<(), Error> {
+    for (i, pair) in xs.windows(2).enumerate() {
+        check_increasing(&[f(pair[0]), f(pair[1])])
+            .map_err(|_| Error::NonIncreasingKnots { index: i + 1 })?;
+    }
+    for x in xs.iter_mut() {
+        *x = f(*x);
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{eval, Extrapolate};
+
+    use crate::fixture::natural;
+
+    // Knots on both sides of zero, which scaling x has to keep in order
+    const XS: [f32; 5] = [-1f32, 0.0, 1.5, 2.0, 4.0];
+    const YS: [f32; 5] = [1f32, 3.0, 2.0, -1.0, 0.5];
+
+    fn spline() -> [(f32, f32, f32, f32); 4] {
+        natural(&XS, &YS)
+    }
+
+    #[test]
+    fn transforms_match_original() {
+        let original = spline();
+        let at = |coeffs: &[(f32, f32, f32, f32)], xs: &[f32], x: f32| {
+            eval(coeffs, xs, x, Extrapolate::Error).unwrap()
+        };
+        let (mut coeffs, mut xs) = (original, XS);
+        scale_x(&mut coeffs, &mut xs, 0.5).unwrap();
+        shift_x(&mut xs, 3.0).unwrap();
+        scale_y(&mut coeffs, 2.0).unwrap();
+        shift_y(&mut coeffs, 1.0).unwrap();
+        reverse(&mut coeffs, &mut xs).unwrap();
+        assert_eq!([2.5, 3.5, 3.75, 4.5, 5.0], xs);
+        for i in 0..=50 {
+            let x = -1f32 + i as f32 / 10f32;
+            // Reversed on [2.5, 5] after x / 2 + 3
+            let transformed = at(&coeffs, &xs, 7.5 - (x / 2f32 + 3f32));
+            let expected = 2f32 * at(&original, &XS, x) + 1f32;
+            assert!(
+                (transformed - expected).abs() < 1e-4,
+                "{} at {}",
+                transformed,
+                x
+            );
+        }
+        // Reversing twice is the identity
+        let (mut twice, mut twice_xs) = (original, XS);
+        reverse(&mut twice, &mut twice_xs).unwrap();
+        reverse(&mut twice, &mut twice_xs).unwrap();
+        assert_eq!(XS, twice_xs);
+        for (lhs, rhs) in original.iter().zip(&twice) {
+            assert!((lhs.0 - rhs.0).abs() < 1e-5);
+            assert!((lhs.1 - rhs.1).abs() < 1e-4);
+        }
+    }
+
+    #[test]
+    fn rejects_invalid_input() {
+        let (mut coeffs, mut xs) = (spline(), XS);
+        assert_eq!(
+            Err(Error::InvalidLimit),
+            scale_x(&mut coeffs, &mut xs, -1.0)
+        );
+        assert_eq!(
+            Err(Error::NonIncreasingKnots { index: 1 }),
+            shift_x(&mut xs, 1e9)
+        );
+        assert_eq!(XS, xs);
+        assert_eq!(
+            Err(Error::InvalidSliceLength),
+            reverse(&mut coeffs, &mut xs[..4])
+        );
+        assert_eq!(Err(Error::EmptyInput), shift_y(&mut [], 1.0));
+    }
+}
@@ -0,0 +1,173 @@
+use crate::{
+    check_spline, eval_segment, eval_segment_derivative, eval_segment_second_derivative, Error,
+};
+
+/// Ways in which a coefficient table fails [`verify`].
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum VerifyError {
+    /// Coefficients, knots and values do not fit together
+    Invalid(Error),
+    /// The spline misses the value at the given knot
+    Value {
+        /// Index of the knot
+        knot: usize,
+    },
+    /// Value or one of the first two derivatives jumps at the given interior
+    /// knot
+    Discontinuity {
+        /// Index of the knot
+        knot: usize,
+        /// Order of the derivative which jumps, 0 for the value itself
+        derivative: usize,
+    },
+}
+
+impl From<Error> for VerifyError {
+    fn from(error: Error) -> Self {
+        VerifyError::Invalid(error)
+    }
+}
+
+impl core::fmt::Display for VerifyError {
+    fn fmt(&self, f: &mut core::fmt::Formatter<'_>) -> core::fmt::Result {
+        match self {
+            VerifyError::Invalid(error) => write!(f, "invalid spline: {}", error),
+            VerifyError::Value { knot } => write!(f, "value at knot {} is off", knot),
+            VerifyError::Discontinuity { knot, derivative } => {
+                write!(f, "derivative {} jumps at knot {}", derivative, knot)
+            }
+        }
+    }
+}
+
+#[cfg(feature = "std")]
+impl std::error::Error for VerifyError {}
+
+/// Check that the spline given by `coefficients` and the knots `xs` passes
+/// through `ys`, and that value, slope and curvature of adjacent segments
+/// agree at every interior knot, all within `tol`. Meant as a cheap self-test
+/// of coefficient tables loaded from external memory.
+///
+/// Failures are reported for the first knot, in order, which does not pass.
+///
+/// ```
+/// use cubic_splinterpol::{splinterpol, verify, VerifyError};
+///
+/// let xs = [0f32, 1.0, 2.0, 3.0];
+/// let ys = [0f32, 1.0, 0.0, 2.0];
+/// let mut coefficients = [(0f32, 0f32, 0f32, 0f32); 3];
+/// splinterpol(&xs, &ys, &mut coefficients).unwrap();
+/// assert_eq!(Ok(()), verify(&coefficients, &xs, &ys, 1e-5));
+///
+/// // A flipped bit in the table
+/// coefficients[1].2 = -coefficients[1].2;
+/// assert!(matches!(
+///     verify(&coefficients, &xs, &ys, 1e-5),
+///     Err(VerifyError::Discontinuity { knot: 1, .. })
+/// ));
+/// ```
+pub fn verify(
+    coefficients: &[(f32, f32, f32, f32)],
+    xs: &[f32],
+    ys: &[f32],
+    tol: f32,
+) -> Result<(), VerifyError> {
+    check_spline(coefficients, xs)?;
+    if ys.len() != xs.len() {
+        return Err(Error::InvalidSliceLength.into());
+    }
+    if tol.is_nan() || tol < 0f32 {
+        return Err(Error::InvalidLimit.into());
+    }
+    // NaN never passes
+    let within = |lhs: f32, rhs: f32| (lhs - rhs).abs() <= tol;
+    for (knot, polynomial) in coefficients.iter().enumerate() {
+        if !within(polynomial.0, ys[knot]) {
+            return Err(VerifyError::Value { knot });
+        }
+        let h = xs[knot + 1] - xs[knot];
+        let end = (
+            eval_segment(*polynomial, h),
+            eval_segment_derivative(*polynomial, h),
+            eval_segment_second_derivative(*polynomial, h),
+        );
+        let knot = knot + 1;
+        match coefficients.get(knot) {
+            Some((a, b, c, _)) => {
+                let next = [*a, *b, 2f32 * c];
+                for (derivative, (end, next)) in [end.0, end.1, end.2].iter().zip(next).enumerate()
+                {
+                    if !within(*end, next) {
+                        return Err(VerifyError::Discontinuity { knot, derivative });
+                    }
+                }
+            }
+            None => {
+                if !within(end.0, ys[knot]) {
+                    return Err(VerifyError::Value { knot });
+                }
+            }
+        }
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::fixture::{spline, XS, YS};
+
+    #[test]
+    fn verify_accepts_computed_spline() {
+        assert_eq!(Ok(()), verify(&spline(), &XS, &YS, 1e-4));
+    }
+
+    #[test]
+    fn verify_detects_corruption() {
+        let mut coeffs = spline();
+        coeffs[3].3 += 0.5;
+        assert_eq!(
+            Err(VerifyError::Value { knot: 4 }),
+            verify(&coeffs, &XS, &YS, 1e-4)
+        );
+
+        let mut coeffs = spline();
+        coeffs[2].0 += 0.01;
+        assert_eq!(
+            Err(VerifyError::Discontinuity {
+                knot: 2,
+                derivative: 0
+            }),
+            verify(&coeffs, &XS, &YS, 1e-3)
+        );
+        // Consistent on its own, but not with the values
+        let mut ys = YS;
+        ys[0] = 0.0;
+        assert_eq!(
+            Err(VerifyError::Value { knot: 0 }),
+            verify(&spline(), &XS, &ys, 1e-4)
+        );
+
+        let mut coeffs = spline();
+        coeffs[1].1 = f32::NAN;
+        assert_eq!(
+            Err(VerifyError::Discontinuity {
+                knot: 1,
+                derivative: 1
+            }),
+            verify(&coeffs, &XS, &YS, 1e-4)
+        );
+    }
+
+    #[test]
+    fn verify_rejects_invalid_input() {
+        assert_eq!(
+            Err(VerifyError::Invalid(Error::InvalidSliceLength)),
+            verify(&spline(), &XS, &YS[..4], 1e-4)
+        );
+        assert_eq!(
+            Err(VerifyError::Invalid(Error::EmptyInput)),
+            verify(&[], &[], &[], 1e-4)
+        );
+    }
+}